@@ -1,924 +1,14785 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+// The debug snapshot's json! literal outgrew the default macro recursion limit
+#![recursion_limit = "256"]
 
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::Duration;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, broadcast, mpsc};
 use tokio::time::interval;
 use tauri::{Manager, State};
 use serde::{Deserialize, Serialize};
-use rustfft::{FftPlanner, num_complex::Complex};
-use lsl::{StreamInlet, resolve_streams, StreamInfo, Pullable};
+use rustfft::{Fft, FftPlanner, num_complex::Complex};
+use realfft::{RealFftPlanner, RealToComplex};
+use rayon::prelude::*;
+use lsl::{StreamInlet, StreamOutlet, resolve_streams, resolve_byprop, StreamInfo, Pullable, Pushable, ChannelFormat};
 use rand::{Rng, SeedableRng};
 use rand::seq::SliceRandom;
 
+// All event timestamps (eeg_sample, filtered_eeg_sample, frequency_bands) are seconds on the
+// clock selected by set_timestamp_source: the LSL sample clock by default, or seconds since
+// start_eeg_processing when "local" is chosen.
+// sample_index counts samples pulled from the stream since connect (starting at 0). It is not
+// derived from the LSL timestamps, so samples lost before the pull leave no jump in it; compare
+// timestamps against the nominal rate to find those. Interpolated samples repeat the index of
+// the last pulled sample before the gap.
+// `interpolated` (only serialized when true) marks samples synthesized by gap interpolation.
+// `lsl_timestamp` (with set_emit_lsl_timestamp) is the inlet's timestamp as pulled, before the
+// local clock or the timestamp guard replaced it; interpolated and CSV samples have none.
 #[derive(Debug, Serialize, Clone)]
 struct EEGSample {
     timestamp: f64,
+    sample_index: u64,
     channels: Vec<f32>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    interpolated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lsl_timestamp: Option<f64>,
 }
 
+// One sample of a cf_string stream (annotations, labels), emitted as `string_sample`
+// instead of going through the filters and FFT
 #[derive(Debug, Serialize, Clone)]
-struct FilteredEEGSample {
+struct StringSample {
     timestamp: f64,
-    channels: Vec<f32>,
+    sample_index: u64,
+    channels: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Clone)]
-struct FrequencyBands {
+struct FilteredEEGSample {
     timestamp: f64,
-    channel: usize,
-    alpha: f32,    // 8-12 Hz
-    beta: f32,     // 13-30 Hz
-    theta: f32,    // 4-8 Hz
-    delta: f32,    // 0.5-4 Hz
-    gamma: f32,    // 30-100 Hz
+    sample_index: u64,
+    channels: Vec<f32>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    interpolated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lsl_timestamp: Option<f64>,
 }
 
+// Compact form of the display stream, emitted as `eeg_sample_delta` when delta encoding is on.
+// Exactly one of `keyframe` (full values) or `deltas` (change per channel in units of
+// `quantization`) is set. To reconstruct: on a keyframe take its values, otherwise add
+// `deltas[i] * quantization` to the previous value of channel i. The encoder tracks the decoded
+// values, so rounding never accumulates; a delta clamped to the i16 range is caught up over
+// the following frames, and a keyframe every DELTA_KEYFRAME_INTERVAL frames lets late
+// listeners resync.
 #[derive(Debug, Serialize, Clone)]
-struct LSLStreamInfo {
-    name: String,
-    channel_count: i32,
-    sample_rate: f64,
-    is_connected: bool,
-    metadata: String,
-    stream_type: String,
-    source_id: String,
-    channel_names: Vec<String>,
-    manufacturer: String,
-    device_model: String,
+struct EEGSampleDelta {
+    timestamp: f64,
+    sample_index: u64,
+    quantization: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keyframe: Option<Vec<f32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deltas: Option<Vec<i16>>,
 }
 
-#[derive(Debug, Deserialize)]
-struct LSLConfig {
-    stream_name: String,
-    use_real_data: bool,
-}
+// Frames between keyframes (about 4 s at the 125 Hz display cadence)
+const DELTA_KEYFRAME_INTERVAL: u32 = 500;
+const DEFAULT_DELTA_QUANTIZATION: f32 = 0.01; // µV per delta step
 
-// Digital filter structures for real-time processing
 #[derive(Debug, Clone)]
-struct ButterworthFilter {
-    order: usize,
-    a: Vec<f64>,
-    b: Vec<f64>,
-    x_history: Vec<Vec<f64>>, // Input history for each channel
-    y_history: Vec<Vec<f64>>, // Output history for each channel
+struct DeltaEncoder {
+    quantization: f32,
+    decoded: Vec<f32>, // what a listener holds after the previous frame; empty forces a keyframe
+    since_keyframe: u32,
 }
 
-impl ButterworthFilter {
-    fn new(order: usize, channel_count: usize) -> Self {
-        // 4th order Butterworth bandpass 1-40 Hz at 250 Hz sampling rate
-        // Coefficients calculated for 1-40 Hz bandpass
-        let b = vec![0.0067, 0.0, -0.0134, 0.0, 0.0067];
-        let a = vec![1.0, -3.1806, 3.8612, -2.1122, 0.4383];
-        
-        Self {
-            order,
-            a,
-            b,
-            x_history: vec![vec![0.0; order + 1]; channel_count],
-            y_history: vec![vec![0.0; order + 1]; channel_count],
-        }
+impl DeltaEncoder {
+    fn new(quantization: f32) -> Self {
+        Self { quantization, decoded: Vec::new(), since_keyframe: 0 }
+    }
+    
+    fn reset(&mut self) {
+        self.decoded.clear();
     }
     
-    fn process(&mut self, input: &[f32]) -> Vec<f32> {
-        let mut output = Vec::with_capacity(input.len());
+    fn encode(&mut self, sample: &FilteredEEGSample) -> EEGSampleDelta {
+        let mut frame = EEGSampleDelta {
+            timestamp: sample.timestamp,
+            sample_index: sample.sample_index,
+            quantization: self.quantization,
+            keyframe: None,
+            deltas: None,
+        };
         
-        for (ch, &sample) in input.iter().enumerate() {
-            if ch >= self.x_history.len() {
-                output.push(sample);
-                continue;
-            }
-            
-            // Shift history
-            for i in (1..self.x_history[ch].len()).rev() {
-                self.x_history[ch][i] = self.x_history[ch][i - 1];
-                self.y_history[ch][i] = self.y_history[ch][i - 1];
-            }
-            
-            self.x_history[ch][0] = sample as f64;
-            
-            // Apply filter equation
-            let mut y = 0.0;
-            for i in 0..self.b.len() {
-                if i < self.x_history[ch].len() {
-                    y += self.b[i] * self.x_history[ch][i];
-                }
-            }
-            for i in 1..self.a.len() {
-                if i < self.y_history[ch].len() {
-                    y -= self.a[i] * self.y_history[ch][i];
-                }
-            }
-            
-            self.y_history[ch][0] = y;
-            output.push(y as f32);
+        let finite = sample.channels.iter().all(|v| v.is_finite());
+        if self.decoded.len() != sample.channels.len() || self.since_keyframe >= DELTA_KEYFRAME_INTERVAL || !finite {
+            self.decoded = sample.channels.clone();
+            self.since_keyframe = 0;
+            frame.keyframe = Some(sample.channels.clone());
+            return frame;
         }
         
-        output
+        let deltas = sample.channels.iter().zip(self.decoded.iter_mut())
+            .map(|(value, decoded)| {
+                let steps = ((value - *decoded) / self.quantization).round()
+                    .clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+                *decoded += steps as f32 * self.quantization;
+                steps
+            })
+            .collect();
+        self.since_keyframe += 1;
+        frame.deltas = Some(deltas);
+        frame
+    }
+}
+
+// Everything the engine produces for one accepted sample, broadcast to subscribe() receivers.
+// `bands` is only set on the samples that completed an analysis cycle.
+#[derive(Debug, Serialize, Clone)]
+struct ProcessedFrame {
+    raw: EEGSample,
+    filtered: FilteredEEGSample,
+    bands: Option<Vec<FrequencyBands>>,
+}
+
+// With set_sample_batch_size(n > 1), every raw sample (not every 2nd) goes out in order in
+// `eeg_sample_batch` events of n samples instead of as single `eeg_sample` events
+#[derive(Debug, Serialize, Clone)]
+struct EEGSampleBatch {
+    samples: Vec<EEGSample>,
+}
+
+const MAX_SAMPLE_BATCH_SIZE: usize = 250;
+
+// Emitted as `eeg_frame` once per analysis cycle: the band powers together with the feedback
+// thresholds they should be judged against, so every view renders the same feedback
+#[derive(Debug, Serialize, Clone)]
+struct EegFrame<'a> {
+    timestamp: f64,
+    bands: &'a [FrequencyBands],
+    thresholds: &'a HashMap<String, BandThreshold>,
+}
+
+// Frames a slow subscriber may fall behind before it starts missing them (about 4 s at 250 Hz)
+const FRAME_CHANNEL_CAPACITY: usize = 1024;
+
+// Band values are keyed by the active BandProfile's names and serialized inline, so the
+// classic profile produces the same `alpha`/`beta`/... fields as before
+#[derive(Debug, Serialize, Clone)]
+struct FrequencyBands {
+    timestamp: f64,     // timestamp of the newest sample in the analysis window
+    channel: usize,
+    #[serde(flatten)]
+    bands: HashMap<String, f32>,
+    // set_passband_only: bands lying wholly outside the channel's bandpass, left out of `bands`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    unavailable: Vec<String>,
+}
+
+impl FrequencyBands {
+    fn get(&self, band: &str) -> Option<f32> {
+        self.bands.get(band).copied()
     }
 }
 
+// One named band, [low, high) in Hz
+#[derive(Debug, Clone, PartialEq)]
+struct BandDefinition {
+    name: String,
+    low: f32,
+    high: f32,
+}
+
 #[derive(Debug, Clone)]
-struct NotchFilter {
-    // 50 Hz notch filter coefficients for 250 Hz sampling rate
-    b: Vec<f64>,
-    a: Vec<f64>,
-    x_history: Vec<Vec<f64>>,
-    y_history: Vec<Vec<f64>>,
+struct BandProfile {
+    bands: Vec<BandDefinition>, // sorted by low edge
 }
 
-impl NotchFilter {
-    fn new(channel_count: usize) -> Self {
-        // 50 Hz notch filter coefficients (Q=30)
-        let b = vec![0.9565, -1.9131, 0.9565];
-        let a = vec![1.0, -1.9131, 0.9131];
-        
+impl Default for BandProfile {
+    // Classic five bands; note the 12-13 Hz gap between alpha and beta
+    fn default() -> Self {
+        let band = |name: &str, low: f32, high: f32| BandDefinition { name: name.to_string(), low, high };
         Self {
-            b,
-            a,
-            x_history: vec![vec![0.0; 3]; channel_count],
-            y_history: vec![vec![0.0; 3]; channel_count],
+            bands: vec![
+                band("delta", 0.5, 4.0),
+                band("theta", 4.0, 8.0),
+                band("alpha", 8.0, 12.0),
+                band("beta", 13.0, 30.0),
+                band("gamma", 30.0, 100.0),
+            ],
         }
     }
+}
+
+impl BandProfile {
+    // JSON object of band name -> [low, high] in Hz, e.g. {"theta": [4, 7], "low_beta": [13, 20]}
+    fn from_json(contents: &str) -> Result<Self, String> {
+        let edges: HashMap<String, [f32; 2]> = serde_json::from_str(contents)
+            .map_err(|e| format!("❌ Invalid band profile: {} (expected {{\"name\": [low, high], ...}})", e))?;
+        Self::from_edges(edges)
+    }
     
-    fn process(&mut self, input: &[f32]) -> Vec<f32> {
-        let mut output = Vec::with_capacity(input.len());
+    fn from_edges(edges: HashMap<String, [f32; 2]>) -> Result<Self, String> {
+        if edges.is_empty() {
+            return Err("❌ Band profile defines no bands".to_string());
+        }
         
-        for (ch, &sample) in input.iter().enumerate() {
-            if ch >= self.x_history.len() {
-                output.push(sample);
-                continue;
-            }
-            
-            // Shift history
-            for i in (1..3).rev() {
-                self.x_history[ch][i] = self.x_history[ch][i - 1];
-                self.y_history[ch][i] = self.y_history[ch][i - 1];
-            }
-            
-            self.x_history[ch][0] = sample as f64;
-            
-            // Apply filter
-            let mut y = 0.0;
-            for i in 0..self.b.len() {
-                y += self.b[i] * self.x_history[ch][i];
+        let mut bands = Vec::with_capacity(edges.len());
+        for (name, [low, high]) in edges {
+            if name.trim().is_empty() {
+                return Err("❌ Band profile contains a band with an empty name".to_string());
             }
-            for i in 1..self.a.len() {
-                y -= self.a[i] * self.y_history[ch][i];
+            if !(low >= 0.0 && low < high && high.is_finite()) {
+                return Err(format!("❌ Invalid edges for band '{}': [{}, {}] must satisfy 0 <= low < high", name, low, high));
             }
-            
-            self.y_history[ch][0] = y;
-            output.push(y as f32);
+            bands.push(BandDefinition { name, low, high });
         }
+        bands.sort_by(|a, b| a.low.total_cmp(&b.low).then_with(|| a.name.cmp(&b.name)));
         
-        output
+        Ok(Self { bands })
+    }
+    
+    fn names(&self) -> Vec<String> {
+        self.bands.iter().map(|b| b.name.clone()).collect()
+    }
+    
+    // The from_json form
+    fn edges(&self) -> HashMap<String, [f32; 2]> {
+        self.bands.iter().map(|b| (b.name.clone(), [b.low, b.high])).collect()
+    }
+    
+    // Copy with every band clipped to [low, high), plus the names of bands with nothing left
+    fn within(&self, low: f32, high: f32) -> (Self, Vec<String>) {
+        let mut outside = Vec::new();
+        let bands = self.bands.iter()
+            .filter_map(|band| {
+                let (clipped_low, clipped_high) = (band.low.max(low), band.high.min(high));
+                if clipped_low < clipped_high {
+                    Some(BandDefinition { name: band.name.clone(), low: clipped_low, high: clipped_high })
+                } else {
+                    outside.push(band.name.clone());
+                    None
+                }
+            })
+            .collect();
+        (Self { bands }, outside)
+    }
+    
+    // Copy with theta ending and alpha starting at `hz`; None unless the profile has both and
+    // the boundary leaves each of them a non-empty range
+    fn with_theta_alpha_boundary(&self, hz: f32) -> Option<Self> {
+        let theta = self.bands.iter().position(|b| b.name == "theta")?;
+        let alpha = self.bands.iter().position(|b| b.name == "alpha")?;
+        if !(hz > self.bands[theta].low && hz < self.bands[alpha].high) {
+            return None;
+        }
+        let mut profile = self.clone();
+        profile.bands[theta].high = hz;
+        profile.bands[alpha].low = hz;
+        Some(profile)
     }
 }
 
-// Thread-safe LSL connection
-#[derive(Debug, Clone)]
-struct LSLConnection {
-    stream_info: Option<LSLStreamInfo>,
-    channel_count: usize,
-    is_real_connection: bool,
-    stream_name: Option<String>,
+// Theta/alpha transition frequency of one channel, emitted as `taf`: the power minimum between
+// theta and the alpha peak (Klimesch), from the raw spectrum
+#[derive(Debug, Serialize, Clone)]
+struct Taf {
+    channel: usize,
+    hz: f32,
 }
 
-impl LSLConnection {
-    fn new() -> Self {
-        Self {
-            stream_info: None,
-            channel_count: 8,
-            is_real_connection: false,
-            stream_name: None,
-        }
+const TAF_ALPHA_RANGE_HZ: (f32, f32) = (7.0, 13.0); // where the alpha peak is looked for
+const TAF_LOWEST_HZ: f32 = 4.0;
+
+// None without a clear alpha peak (a maximum at the range edge) or a trough below it
+fn transition_frequency(power: &[f32], freq_resolution: f32) -> Option<f32> {
+    let bin = |hz: f32| (hz / freq_resolution).round() as usize;
+    let (alpha_low, alpha_high, lowest) = (bin(TAF_ALPHA_RANGE_HZ.0), bin(TAF_ALPHA_RANGE_HZ.1), bin(TAF_LOWEST_HZ));
+    if lowest == 0 || alpha_high + 1 >= power.len() {
+        return None;
+    }
+    // 3-bin moving average, so a single noisy bin doesn't set the minimum
+    let smoothed: Vec<f32> = (0..power.len())
+        .map(|i| power[i.saturating_sub(1)..(i + 2).min(power.len())].iter().sum::<f32>() / 3.0)
+        .collect();
+    
+    let peak = (alpha_low..=alpha_high).max_by(|&a, &b| smoothed[a].total_cmp(&smoothed[b]))?;
+    if peak == alpha_low || peak == alpha_high {
+        return None;
     }
+    let trough = (lowest..peak).min_by(|&a, &b| smoothed[a].total_cmp(&smoothed[b]))?;
+    (trough > lowest && smoothed[trough] < smoothed[peak]).then_some(trough as f32 * freq_resolution)
 }
 
-struct EEGProcessor {
-    sample_rate: f32,
+// Hjorth time-domain descriptors for one channel, emitted as `hjorth`
+#[derive(Debug, Serialize, Clone)]
+struct HjorthParameters {
+    timestamp: f64,
+    channel: usize,
+    activity: f64,      // variance of the signal
+    mobility: f64,      // sqrt(var(d1) / var(signal))
+    complexity: f64,    // mobility of d1 relative to mobility of the signal
+}
+
+// Pearson correlation between the filtered channels over the analysis buffer, emitted as
+// `correlation_matrix`; `values` is row-major size x size, rows/columns follow `channels`
+#[derive(Debug, Serialize, Clone)]
+struct CorrelationMatrix {
+    timestamp: f64,
+    channels: Vec<usize>,
+    size: usize,
+    values: Vec<f32>,
+}
+
+// set_xcorr_pair: lag of the strongest normalized cross-correlation between two filtered
+// channels over the analysis buffer, emitted as `xcorr_lag`. A positive lag means the second
+// channel of the pair trails the first; `peak` keeps its sign, so a negative peak is an
+// inverted copy.
+#[derive(Debug, Serialize, Clone)]
+struct XcorrLag {
+    timestamp: f64,
+    pair: (usize, usize),
+    lag_ms: f32,
+    peak: f32,
+}
+
+#[derive(Debug, Serialize, Clone, Copy)]
+struct XcorrConfig {
+    pair: (usize, usize),
+    max_lag_ms: f32,
+}
+
+const MAX_XCORR_LAG_MS: f32 = 1000.0;
+
+// Lag (in samples, b relative to a) and value of the largest |r| within ±max_lag, normalized by
+// the full-window norms; None for a flat channel
+fn xcorr_peak(a: &[f64], b: &[f64], max_lag: usize) -> Option<(i64, f64)> {
+    let n = a.len().min(b.len());
+    let center = |x: &[f64]| {
+        let mean = x[..n].iter().sum::<f64>() / n as f64;
+        x[..n].iter().map(|v| v - mean).collect::<Vec<f64>>()
+    };
+    let (a, b) = (center(a), center(b));
+    let norm = (a.iter().map(|v| v * v).sum::<f64>() * b.iter().map(|v| v * v).sum::<f64>()).sqrt();
+    if n == 0 || norm <= 0.0 {
+        return None;
+    }
+    
+    let max_lag = max_lag.min(n - 1) as i64;
+    (-max_lag..=max_lag)
+        .map(|lag| {
+            let sum: f64 = (0..n as i64)
+                .filter(|&t| (0..n as i64).contains(&(t + lag)))
+                .map(|t| a[t as usize] * b[(t + lag) as usize])
+                .sum();
+            (lag, sum / norm)
+        })
+        .max_by(|x, y| x.1.abs().total_cmp(&y.1.abs()))
+}
+
+// Emitted as `settling` while the first samples after connect only prime the filters;
+// the last event has remaining = 0 and the next sample is the first one emitted
+#[derive(Debug, Serialize, Clone)]
+struct SettlingStatus {
+    remaining: usize,
+    total: usize,
+}
+
+// Phase (radians, -π..π, cosine reference) at the newest buffered sample for the FFT bin nearest
+// the requested frequency, emitted as `target_phase` while set_target_phase is active
+#[derive(Debug, Serialize, Clone)]
+struct TargetPhase {
+    timestamp: f64,
+    frequency: f32,   // centre of the bin actually used
+    phases: Vec<f32>, // per EEG channel
+}
+
+// Instantaneous amplitude (µV) of the set_envelope_band band from the analytic signal, emitted
+// as a list of these (one per EEG channel) in `band_envelope` at the display cadence
+#[derive(Debug, Serialize, Clone)]
+struct BandEnvelope {
+    timestamp: f64,
+    channel: usize,
+    band: String,
+    amplitude: f32,
+}
+
+// The envelope is read this fraction of the window before the newest sample, where the
+// wrap-around of the FFT's circular band-pass no longer distorts it (~256 ms at 512/250 Hz)
+const ENVELOPE_GUARD_FRACTION: usize = 8;
+
+// Emitted as `band_resolution_warning`, once per rate/window/profile combination, for each band
+// that spans fewer than set_min_band_bins FFT bins: its power then rests on a handful of bins
+#[derive(Debug, Serialize, Clone)]
+struct BandResolutionWarning {
+    band: String,
+    low: f32,
+    high: f32,
+    bins: usize,
+    min_bins: usize,
+    resolution_hz: f32,
+    suggested_buffer_size: Option<usize>, // smallest window giving min_bins, if within MAX_FFT_SIZE
+}
+
+// Emitted as `band_passband_warning`, once per filter/profile combination, for each band reaching
+// outside the bandpass of the listed channels: power there is roll-off residue, not signal
+#[derive(Debug, Serialize, Clone, PartialEq)]
+struct BandPassbandWarning {
+    band: String,
+    low: f32,
+    high: f32,
+    passband: (f64, f64),
+    channels: Vec<usize>,
+    unavailable: bool, // wholly outside the passband, not just past one edge
+    restricted: bool,  // set_passband_only is on: the band is clipped, or reported unavailable
+}
+
+// Everything the band_resolution_warning outcome depends on
+#[derive(Debug, Clone, PartialEq)]
+struct ResolutionCheck {
+    rate: f32,
     buffer_size: usize,
-    channel_buffers: Arc<Mutex<Vec<Vec<f32>>>>,
-    filtered_buffers: Arc<Mutex<Vec<Vec<f32>>>>,
-    lsl_connection: Arc<Mutex<LSLConnection>>,
-    bandpass_filter: Arc<Mutex<Option<ButterworthFilter>>>,
-    notch_filter: Arc<Mutex<Option<NotchFilter>>>,
+    min_bins: usize,
+    excluded_bins: usize,
+    bands: Vec<BandDefinition>,
 }
 
-impl EEGProcessor {
-    fn new() -> Self {
-        println!("🔧 [DEBUG] Creating new EEGProcessor instance");
-        Self {
-            sample_rate: 250.0,
-            buffer_size: 512,
-            channel_buffers: Arc::new(Mutex::new(vec![Vec::new(); 8])),
-            filtered_buffers: Arc::new(Mutex::new(vec![Vec::new(); 8])),
-            lsl_connection: Arc::new(Mutex::new(LSLConnection::new())),
-            bandpass_filter: Arc::new(Mutex::new(None)),
-            notch_filter: Arc::new(Mutex::new(None)),
+// Delta (0.5-4 Hz) at 512 samples / 250 Hz has 7 bins, so the default flags exactly that case
+const DEFAULT_MIN_BAND_BINS: usize = 8;
+
+// Warm-up progress of the analysis buffers, emitted as `buffer_fill_progress` until full
+#[derive(Debug, Serialize, Clone)]
+struct BufferFillProgress {
+    fraction: f32, // fill of the emptiest EEG channel, 0..1
+}
+
+// Per-channel setup quality, emitted as `snr`: in-band (1-40 Hz) power over everything else
+// below Nyquist (drift below 1 Hz, EMG and mains above 40 Hz). It comes from the spectrum the
+// band analysis computes, so on filtered buffers it measures what the bandpass and notch left
+// outside the band.
+#[derive(Debug, Serialize, Clone)]
+struct ChannelSnr {
+    channel: usize,
+    db: f32,
+}
+
+const SNR_BAND_HZ: (f32, f32) = (1.0, 40.0);
+
+// Aperiodic (1/f) component of one channel's raw spectrum, emitted as `aperiodic`: the line
+// log10(power) = offset + slope * log10(freq) fitted over the configured range. Steeper
+// (more negative) slopes go with lower arousal.
+#[derive(Debug, Serialize, Clone)]
+struct Aperiodic {
+    channel: usize,
+    slope: f32,
+    offset: f32,
+}
+
+const DEFAULT_APERIODIC_RANGE_HZ: (f32, f32) = (2.0, 40.0);
+// Bins this close to 50 or 60 Hz are left out of the fit (and of the muscle artifact
+// ratio), whatever the notch setting
+const MAINS_EXCLUSION_HZ: f32 = 2.0;
+// Keeps empty bins (e.g. fully notched) out of log10(0), here and in the spectral entropy
+const APERIODIC_POWER_FLOOR: f32 = 1e-10;
+
+// Shannon entropy of one channel's normalized raw power spectrum over the configured range,
+// emitted as `spectral_entropy`. Divided by ln(bins), so 1 is a flat (white) spectrum and
+// values near 0 mean the power sits in a few bins.
+#[derive(Debug, Serialize, Clone)]
+struct SpectralEntropy {
+    channel: usize,
+    value: f32,
+}
+
+const DEFAULT_ENTROPY_RANGE_HZ: (f32, f32) = (1.0, 40.0);
+
+// Shannon entropy of the power distribution over ln(bins): 1 for a flat spectrum, 0 for all
+// power in one bin; None under two bins
+fn normalized_entropy(powers: &[f64]) -> Option<f32> {
+    if powers.len() < 2 {
+        return None;
+    }
+    
+    let total: f64 = powers.iter().sum();
+    let entropy: f64 = powers.iter()
+        .map(|&power| power / total)
+        .filter(|&p| p > 0.0)
+        .map(|p| -p * p.ln())
+        .sum();
+    Some((entropy / (powers.len() as f64).ln()) as f32)
+}
+
+// get_dominant_frequency searches the channel-averaged spectrum here, past the 1/f rise at
+// DC and below mains
+const DOMINANT_FREQUENCY_RANGE_HZ: (f32, f32) = (1.0, 40.0);
+
+// measure_thd: power of harmonics 2..=THD_MAX_HARMONIC below Nyquist over the fundamental's
+// power. Each peak is summed over ±THD_PEAK_BINS bins, the Hann main lobe.
+const THD_MAX_HARMONIC: usize = 10;
+const THD_PEAK_BINS: usize = 2;
+
+// Harmonic-to-fundamental power ratio from one power spectrum; None without fundamental power
+fn harmonic_distortion(power: &[f32], fundamental_bin: f32) -> Option<f32> {
+    let peak_power = |center: usize| -> f32 {
+        power[center.saturating_sub(THD_PEAK_BINS)..(center + THD_PEAK_BINS + 1).min(power.len())].iter().sum()
+    };
+    let fundamental = peak_power(fundamental_bin.round() as usize);
+    if fundamental <= 0.0 {
+        return None;
+    }
+    
+    let harmonics: f32 = (2..=THD_MAX_HARMONIC)
+        .map(|k| (k as f32 * fundamental_bin).round() as usize)
+        .take_while(|&bin| bin + THD_PEAK_BINS < power.len())
+        .map(peak_power)
+        .sum();
+    Some(harmonics / fundamental)
+}
+
+// get_ssvep_response: power at each stimulation frequency and its harmonics, from the Hann
+// spectrum of the raw window (the bandpass would cut the upper harmonics) as unscaled |X|².
+// Each peak sums ±SSVEP_PEAK_BINS bins, narrower than THD's so neighbouring stimulation
+// frequencies stay apart. Its SNR is the peak's mean bin power over the mean of the
+// SSVEP_NOISE_BINS bins on either side.
+const DEFAULT_SSVEP_HARMONICS: usize = 3; // the fundamental and two harmonics
+const MAX_SSVEP_HARMONICS: usize = 8;
+const MAX_SSVEP_FREQUENCIES: usize = 32;
+const SSVEP_PEAK_BINS: usize = 1;
+const SSVEP_NOISE_BINS: usize = 5;
+
+#[derive(Debug, Serialize, Clone)]
+struct HarmonicResponse {
+    harmonic: usize, // 1 = the stimulation frequency itself
+    frequency: f32,
+    power: f32,
+    snr: f32,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct FreqResponse {
+    frequency: f32,
+    total_power: f32,                 // over the harmonics below Nyquist
+    harmonics: Vec<HarmonicResponse>, // harmonics past Nyquist are left out
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct ChannelFreqResponse {
+    channel: usize,
+    responses: Vec<FreqResponse>, // in the order requested
+}
+
+// (peak power, SNR) around `center`; SNR is NaN when no neighbouring bin carries power
+fn ssvep_peak(power: &[f32], center: usize) -> (f32, f32) {
+    let peak_range = center.saturating_sub(SSVEP_PEAK_BINS)..(center + SSVEP_PEAK_BINS + 1).min(power.len());
+    let peak: f32 = power[peak_range.clone()].iter().sum();
+    
+    let below = peak_range.start.saturating_sub(SSVEP_NOISE_BINS)..peak_range.start;
+    let above = peak_range.end..(peak_range.end + SSVEP_NOISE_BINS).min(power.len());
+    let noise: Vec<f32> = power[below].iter().chain(&power[above]).copied().collect();
+    let noise_mean = noise.iter().sum::<f32>() / noise.len().max(1) as f32;
+    let snr = if noise_mean > 0.0 { peak / peak_range.len() as f32 / noise_mean } else { f32::NAN };
+    (peak, snr)
+}
+
+// set_variance_trend: running variance of each raw channel over a long window, a slow quality
+// indicator for drift and drying electrodes (raw, because the highpass removes exactly that
+// drift). Samples go into one Welford accumulator per channel per VARIANCE_BLOCK_S of stream
+// time; the window merges the last window_s of those blocks, so one summary per second is kept
+// instead of every sample. Emitted as `channel_variance_trend` whenever a block completes.
+const DEFAULT_VARIANCE_WINDOW_S: f64 = 30.0;
+const MIN_VARIANCE_WINDOW_S: f64 = 2.0;
+const MAX_VARIANCE_WINDOW_S: f64 = 600.0;
+const VARIANCE_BLOCK_S: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Welford {
+    count: u64,
+    mean: f64,
+    m2: f64, // sum of squared deviations from the mean
+}
+
+impl Welford {
+    fn push(&mut self, x: f64) {
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+    
+    // Chan et al.'s pairwise combination: the same result as pushing both sets into one
+    fn merge(&self, other: &Welford) -> Welford {
+        let count = self.count + other.count;
+        if count == 0 {
+            return Welford::default();
+        }
+        let delta = other.mean - self.mean;
+        Welford {
+            count,
+            mean: self.mean + delta * other.count as f64 / count as f64,
+            m2: self.m2 + other.m2 + delta * delta * self.count as f64 * other.count as f64 / count as f64,
         }
     }
+    
+    // Sample variance (n - 1); NaN below two samples
+    fn variance(&self) -> f64 {
+        if self.count < 2 { f64::NAN } else { self.m2 / (self.count - 1) as f64 }
+    }
+}
 
-    async fn connect_to_lsl(&self, stream_name: &str) -> Result<LSLStreamInfo, String> {
-        println!("🚀 [DEBUG] ===== STARTING LSL CONNECTION PROCESS =====");
-        println!("🔍 [DEBUG] Target stream name: '{}'", stream_name);
-        println!("🔍 [DEBUG] Current thread: {:?}", std::thread::current().id());
+#[derive(Debug)]
+struct VarianceTrend {
+    window_s: f64,
+    block_start: Option<f64>,
+    current: Vec<Welford>,
+    blocks: VecDeque<Vec<Welford>>, // completed blocks, oldest first
+}
+
+impl VarianceTrend {
+    fn new(window_s: f64) -> Self {
+        Self { window_s, block_start: None, current: Vec::new(), blocks: VecDeque::new() }
+    }
+    
+    // Some when this sample starts a new block, reporting the window up to it
+    fn push(&mut self, timestamp: f64, channels: &[f32]) -> Option<ChannelVarianceTrend> {
+        // A different channel count is a different stream: start over
+        if self.current.len() != channels.len() {
+            *self = Self::new(self.window_s);
+            self.current = vec![Welford::default(); channels.len()];
+        }
+        let block_start = *self.block_start.get_or_insert(timestamp);
         
-        // Use blocking task to handle LSL operations
-        let stream_name_clone = stream_name.to_string();
-        let result = tokio::task::spawn_blocking(move || {
-            println!("🔍 [DEBUG] Entered blocking task for LSL operations");
-            println!("🔍 [DEBUG] Resolving LSL streams with 15 second timeout...");
-            
-            // MAXIMUM TIMEOUT: Give plenty of time for stream discovery
-            match resolve_streams(15.0) {
-                Ok(streams) => {
-                    println!("📡 [DEBUG] ===== LSL STREAM DISCOVERY RESULTS =====");
-                    println!("📡 [DEBUG] Total streams found: {}", streams.len());
-                    
-                    if streams.is_empty() {
-                        println!("❌ [DEBUG] NO LSL STREAMS FOUND AT ALL!");
-                        println!("❌ [DEBUG] This means:");
+        let mut report = None;
+        if timestamp - block_start >= VARIANCE_BLOCK_S {
+            let finished = std::mem::replace(&mut self.current, vec![Welford::default(); channels.len()]);
+            self.blocks.push_back(finished);
+            let max_blocks = (self.window_s / VARIANCE_BLOCK_S).round() as usize;
+            while self.blocks.len() > max_blocks {
+                self.blocks.pop_front();
+            }
+            self.block_start = Some(timestamp);
+            report = Some(self.report(timestamp));
+        }
+        for (stats, &value) in self.current.iter_mut().zip(channels) {
+            stats.push(value as f64);
+        }
+        report
+    }
+    
+    fn report(&self, timestamp: f64) -> ChannelVarianceTrend {
+        let window: Vec<Welford> = (0..self.current.len())
+            .map(|ch| self.blocks.iter().fold(Welford::default(), |total, block| total.merge(&block[ch])))
+            .collect();
+        ChannelVarianceTrend {
+            timestamp,
+            window_s: self.blocks.len() as f64 * VARIANCE_BLOCK_S,
+            samples: window.first().map_or(0, |stats| stats.count),
+            variance: window.iter().map(|stats| stats.variance() as f32).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct ChannelVarianceTrend {
+    timestamp: f64,
+    window_s: f64, // covered so far; below the configured window until it has filled
+    samples: u64,
+    variance: Vec<f32>, // per channel, µV²
+}
+
+// set_muscle_artifact: a channel's window counts as muscle-contaminated when the share of its
+// raw power above MUSCLE_CUTOFF_HZ exceeds the threshold. Bins within MAINS_EXCLUSION_HZ of
+// 50/60 Hz and their harmonics are left out of both sums, so line noise isn't read as EMG.
+#[derive(Debug, Serialize, Clone, Copy)]
+struct MuscleArtifactConfig {
+    threshold: f32,              // 0-1, share of 1 Hz..Nyquist power
+    exclude_from_averages: bool, // flagged channels skip the heartbeat and session band averages
+}
+
+// Emitted as `muscle_artifact` in cycles where at least one channel is flagged
+#[derive(Debug, Serialize, Clone)]
+struct MuscleArtifact {
+    timestamp: f64,
+    channels: Vec<MuscleArtifactChannel>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct MuscleArtifactChannel {
+    channel: usize,
+    high_frequency_ratio: f32,
+}
+
+const MUSCLE_CUTOFF_HZ: f32 = 40.0;
+const DEFAULT_MUSCLE_THRESHOLD: f32 = 0.3;
+
+// set_gate_bad_channels: each analysis cycle the EEG channels are checked over the analysis
+// window, and flagged ones are left out of the averaged outputs (regional bands, the average
+// engagement index, get_dominant_frequency). A raw channel pinned at its window extreme for
+// RAILED_FRACTION of the samples is railed; a filtered signal below FLAT_CHANNEL_UV RMS is
+// disconnected, above NOISY_CHANNEL_UV RMS noisy.
+const RAILED_FRACTION: f32 = 0.2;
+const FLAT_CHANNEL_UV: f32 = 0.5;
+const NOISY_CHANNEL_UV: f32 = 100.0;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum BadChannelReason {
+    Railed,
+    Disconnected,
+    Noisy,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+struct GatedChannel {
+    channel: usize,
+    reason: BadChannelReason,
+}
+
+// Emitted as `channel_gate` whenever the set of excluded channels changes (an empty list when
+// every channel is back)
+#[derive(Debug, Serialize, Clone)]
+struct ChannelGate {
+    timestamp: f64,
+    excluded: Vec<GatedChannel>,
+}
+
+fn bad_channel_reason(raw: &[f32], filtered: &[f32]) -> Option<BadChannelReason> {
+    let (min, max) = raw.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &x| (lo.min(x), hi.max(x)));
+    let pinned = raw.iter().filter(|&&x| x == min).count().max(raw.iter().filter(|&&x| x == max).count());
+    if !raw.is_empty() && pinned as f32 >= RAILED_FRACTION * raw.len() as f32 {
+        return Some(BadChannelReason::Railed);
+    }
+    let n = filtered.len().max(1) as f32;
+    let mean = filtered.iter().sum::<f32>() / n;
+    let rms = (filtered.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / n).sqrt();
+    if rms < FLAT_CHANNEL_UV {
+        Some(BadChannelReason::Disconnected)
+    } else if rms > NOISY_CHANNEL_UV {
+        Some(BadChannelReason::Noisy)
+    } else {
+        None
+    }
+}
+
+// Output of one registered EegMetric for one channel, emitted as `custom_metric`
+#[derive(Debug, Serialize, Clone)]
+struct CustomMetricResult {
+    timestamp: f64,
+    metric: String,
+    channel: usize,
+    value: serde_json::Value,
+}
+
+// Wide layout of one analysis cycle: each band is an array indexed like `channels`
+#[derive(Debug, Serialize, Clone)]
+struct BandedFrequencyBands {
+    timestamp: f64,
+    channels: Vec<usize>,
+    #[serde(flatten)]
+    bands: HashMap<String, Vec<f32>>,
+}
+
+impl BandedFrequencyBands {
+    fn from_bands(timestamp: f64, bands: &[FrequencyBands]) -> Self {
+        let mut banded: HashMap<String, Vec<f32>> = HashMap::new();
+        for name in bands.iter().flat_map(|b| b.bands.keys()) {
+            banded.entry(name.clone())
+                .or_insert_with(|| bands.iter().map(|b| b.get(name).unwrap_or(0.0)).collect());
+        }
+        
+        Self {
+            timestamp,
+            channels: bands.iter().map(|b| b.channel).collect(),
+            bands: banded,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct LSLStreamInfo {
+    name: String,
+    channel_count: i32,
+    sample_rate: f64,
+    is_connected: bool,
+    metadata: String,
+    stream_type: String,
+    source_id: String,
+    channel_names: Vec<String>,
+    manufacturer: String,
+    device_model: String,
+    channel_format: String, // LSL channel format, e.g. "float32" or "string"
+    units: Vec<String>,     // per channel from <channels>/<channel>/<unit>; "" when not declared
+}
+
+// get_detected_device: what connect decided about the device, for the UI to confirm or correct
+#[derive(Debug, Serialize, Clone)]
+struct DeviceProfile {
+    manufacturer: String,
+    model: String,
+    montage: Vec<String>,           // channel names as assigned at connect
+    matched_keyword: Option<String>, // found in the source ID or stream name; None when unknown
+    is_known: bool,
+}
+
+// Keyword looked for in the lowercased source ID and stream name, manufacturer, model
+const KNOWN_DEVICES: [(&str, &str, &str); 5] = [
+    ("unicorn", "g.tec medical engineering GmbH", "Unicorn Hybrid Black"),
+    ("openbci", "OpenBCI", "Cyton Board"),
+    ("emotiv", "Emotiv Inc.", "EPOC+"),
+    ("neurosky", "NeuroSky", "MindWave"),
+    ("muse", "InteraXon", "Muse Headband"),
+];
+
+// First known device matching either string. A stream named exactly "123" is a Unicorn (its
+// default LSL name), reported with "123" as the keyword.
+fn detect_device(source_id: &str, stream_name: &str) -> Option<(&'static str, &'static str, &'static str)> {
+    let source_id = source_id.to_lowercase();
+    let stream_name = stream_name.to_lowercase();
+    
+    if stream_name == "123" && !source_id.contains("unicorn") && !stream_name.contains("unicorn") {
+        let (_, manufacturer, model) = KNOWN_DEVICES[0];
+        return Some(("123", manufacturer, model));
+    }
+    KNOWN_DEVICES.iter()
+        .find(|(keyword, _, _)| source_id.contains(keyword) || stream_name.contains(keyword))
+        .copied()
+}
+
+// One-shot look at a stream from probe_stream, without connecting to it
+#[derive(Debug, Serialize, Clone)]
+struct StreamProbe {
+    name: String,
+    channel_format: String,
+    sample_rate: f64,
+    channel_count: i32,
+    samples: usize, // pulled during the burst; 0 for string streams, which aren't read
+    channels: Vec<ChannelRange>,
+    // "uV", "V", "counts" or "unknown" from the value ranges; declared units are in `channels`
+    unit_guess: String,
+    suggested_scale: Option<f32>, // set_channel_scale factor to µV, when the guess implies one
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct ChannelRange {
+    channel: usize,
+    name: String,
+    unit: String, // declared in the stream metadata; "" when not declared
+    min: f32,
+    max: f32,
+    mean: f32,
+    std: f32,
+}
+
+const PROBE_BURST_S: f64 = 1.0;
+const PROBE_MAX_SAMPLES: usize = 1000;
+
+// Judged on the median channel's spread (std), so a flat or railed channel doesn't decide it.
+// Scalp EEG is tens of µV: in volts that's ~1e-5, in µV 1-1000, and integer-valued readings
+// with a spread in the thousands look like raw ADC counts (scale unknown).
+fn guess_amplitude_unit(channels: &[ChannelRange], integral: bool) -> (&'static str, Option<f32>) {
+    let mut spreads: Vec<f32> = channels.iter().map(|c| c.std).filter(|s| *s > 0.0).collect();
+    if spreads.is_empty() {
+        return ("unknown", None);
+    }
+    spreads.sort_by(|a, b| a.total_cmp(b));
+    let spread = spreads[spreads.len() / 2];
+    match spread {
+        s if s < 1e-2 => ("V", Some(1e6)),
+        _ if integral && spread > 1e3 => ("counts", None),
+        s if (0.1..=1e4).contains(&s) => ("uV", None),
+        _ => ("unknown", None),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LSLConfig {
+    stream_name: String,
+    use_real_data: bool,
+}
+
+// auto_normalize_display leaves channels below this RMS (µV) at gain 1 instead of blowing up noise
+const AUTO_NORMALIZE_MIN_RMS: f32 = 1e-3;
+
+// Digital filter structures for real-time processing
+#[derive(Debug, Clone)]
+struct ButterworthFilter {
+    order: usize,
+    a: Vec<f64>,
+    b: Vec<f64>,
+    x_history: Vec<Vec<f64>>, // Input history for each channel
+    y_history: Vec<Vec<f64>>, // Output history for each channel
+}
+
+impl ButterworthFilter {
+    fn new(order: usize, highpass: f64, lowpass: f64, sample_rate: f64, channel_count: usize) -> Self {
+        // Butterworth bandpass designed from the cutoffs (bilinear transform)
+        // order 4 with 1-40 Hz at 250 Hz gives the classic EEG bandpass
+        let (b, a) = Self::design_bandpass(order, highpass, lowpass, sample_rate);
+        
+        Self {
+            order,
+            a,
+            b,
+            x_history: vec![vec![0.0; order + 1]; channel_count],
+            y_history: vec![vec![0.0; order + 1]; channel_count],
+        }
+    }
+    
+    // Analog Butterworth prototype -> bandpass -> bilinear transform, expanded to b/a polynomials
+    fn design_bandpass(order: usize, highpass: f64, lowpass: f64, sample_rate: f64) -> (Vec<f64>, Vec<f64>) {
+        let n = (order / 2).max(1);
+        let fs2 = 2.0 * sample_rate;
+        let (analog_poles, bw) = Self::analog_bandpass_poles(order, highpass, lowpass, sample_rate);
+        let digital_poles = Self::bandpass_poles(order, highpass, lowpass, sample_rate);
+        
+        // n zeros at DC (z = 1) and n at Nyquist (z = -1)
+        let mut zeros = vec![Complex::new(1.0, 0.0); n];
+        zeros.extend(vec![Complex::new(-1.0, 0.0); n]);
+        
+        let denom: Complex<f64> = analog_poles.iter().map(|&p| fs2 - p).product();
+        let gain = (bw * fs2).powi(n as i32) / denom.re;
+        
+        let b = Self::expand_polynomial(&zeros).into_iter().map(|c| c * gain).collect();
+        let a = Self::expand_polynomial(&digital_poles);
+        (b, a)
+    }
+    
+    // Pre-warped analog bandpass poles and the bandwidth in rad/s
+    fn analog_bandpass_poles(order: usize, highpass: f64, lowpass: f64, sample_rate: f64) -> (Vec<Complex<f64>>, f64) {
+        use std::f64::consts::PI;
+        
+        let n = (order / 2).max(1);
+        let fs2 = 2.0 * sample_rate;
+        
+        // Pre-warp the cutoffs
+        let w1 = fs2 * (PI * highpass / sample_rate).tan();
+        let w2 = fs2 * (PI * lowpass / sample_rate).tan();
+        let bw = w2 - w1;
+        let w0_sq = w1 * w2;
+        
+        // Each prototype pole maps to a pair of bandpass poles
+        let mut analog_poles = Vec::with_capacity(2 * n);
+        for k in 0..n {
+            let theta = PI * (2 * k + 1 + n) as f64 / (2 * n) as f64;
+            let p = Complex::new(theta.cos(), theta.sin()) * (bw / 2.0);
+            let disc = (p * p - w0_sq).sqrt();
+            analog_poles.push(p + disc);
+            analog_poles.push(p - disc);
+        }
+        (analog_poles, bw)
+    }
+    
+    fn bandpass_poles(order: usize, highpass: f64, lowpass: f64, sample_rate: f64) -> Vec<Complex<f64>> {
+        let fs2 = 2.0 * sample_rate;
+        Self::analog_bandpass_poles(order, highpass, lowpass, sample_rate).0.iter()
+            .map(|&p| (fs2 + p) / (fs2 - p))
+            .collect()
+    }
+    
+    // Real coefficients of prod(1 - r z^-1) over the given roots
+    fn expand_polynomial(roots: &[Complex<f64>]) -> Vec<f64> {
+        let mut coeffs = vec![Complex::new(1.0, 0.0)];
+        for &root in roots {
+            let mut next = vec![Complex::new(0.0, 0.0); coeffs.len() + 1];
+            for (i, &c) in coeffs.iter().enumerate() {
+                next[i] += c;
+                next[i + 1] -= c * root;
+            }
+            coeffs = next;
+        }
+        coeffs.iter().map(|c| c.re).collect()
+    }
+    
+    fn process_sample(&mut self, ch: usize, sample: f32) -> f32 {
+        if ch >= self.x_history.len() {
+            return sample;
+        }
+        
+        // Shift history
+        for i in (1..self.x_history[ch].len()).rev() {
+            self.x_history[ch][i] = self.x_history[ch][i - 1];
+            self.y_history[ch][i] = self.y_history[ch][i - 1];
+        }
+        
+        self.x_history[ch][0] = sample as f64;
+        
+        // Apply filter equation
+        let mut y = 0.0;
+        for i in 0..self.b.len() {
+            if i < self.x_history[ch].len() {
+                y += self.b[i] * self.x_history[ch][i];
+            }
+        }
+        for i in 1..self.a.len() {
+            if i < self.y_history[ch].len() {
+                y -= self.a[i] * self.y_history[ch][i];
+            }
+        }
+        
+        self.y_history[ch][0] = y;
+        y as f32
+    }
+}
+
+#[derive(Debug, Clone)]
+struct NotchFilter {
+    // Second-order IIR notch designed for the given frequency, Q and sampling rate
+    b: Vec<f64>,
+    a: Vec<f64>,
+    x_history: Vec<Vec<f64>>,
+    y_history: Vec<Vec<f64>>,
+    frequency: f64,
+}
+
+impl NotchFilter {
+    fn new(frequency: f64, q: f64, sample_rate: f64, channel_count: usize) -> Self {
+        // Notch with -3 dB bandwidth of frequency / Q (e.g. 50 Hz, Q=30)
+        let w0 = 2.0 * std::f64::consts::PI * frequency / sample_rate;
+        let bandwidth = w0 / q;
+        let gain = 1.0 / (1.0 + (bandwidth / 2.0).tan());
+        let b = vec![gain, -2.0 * gain * w0.cos(), gain];
+        let a = vec![1.0, -2.0 * gain * w0.cos(), 2.0 * gain - 1.0];
+        
+        Self {
+            b,
+            a,
+            x_history: vec![vec![0.0; 3]; channel_count],
+            y_history: vec![vec![0.0; 3]; channel_count],
+            frequency,
+        }
+    }
+    
+    fn process_sample(&mut self, ch: usize, sample: f32) -> f32 {
+        if ch >= self.x_history.len() {
+            return sample;
+        }
+        
+        // Shift history
+        for i in (1..3).rev() {
+            self.x_history[ch][i] = self.x_history[ch][i - 1];
+            self.y_history[ch][i] = self.y_history[ch][i - 1];
+        }
+        
+        self.x_history[ch][0] = sample as f64;
+        
+        // Apply filter
+        let mut y = 0.0;
+        for i in 0..self.b.len() {
+            y += self.b[i] * self.x_history[ch][i];
+        }
+        for i in 1..self.a.len() {
+            y -= self.a[i] * self.y_history[ch][i];
+        }
+        
+        self.y_history[ch][0] = y;
+        y as f32
+    }
+}
+
+// Amplitude limit for artifact clipping (µV)
+const ARTIFACT_CLIP_UV: f32 = 300.0;
+
+fn clip_artifact(value: f32) -> f32 {
+    if value.abs() > ARTIFACT_CLIP_UV {
+        value.signum() * ARTIFACT_CLIP_UV
+    } else {
+        value
+    }
+}
+
+// Savitzky-Golay smoother for the displayed waveform: fits a polynomial of `order` over a centered
+// window and evaluates it at the center, so peaks survive better than with a moving average.
+// Output lags by (window - 1) / 2 samples and carries the timestamp of the center sample.
+#[derive(Debug, Clone)]
+struct SavitzkyGolayFilter {
+    window: usize,
+    order: usize,
+    coefficients: Vec<f64>,
+    history: Vec<VecDeque<f32>>,
+    timestamps: VecDeque<(f64, u64, bool, Option<f64>)>, // (timestamp, sample_index, interpolated, lsl_timestamp)
+}
+
+impl SavitzkyGolayFilter {
+    fn new(window: usize, order: usize) -> Result<Self, String> {
+        if window.is_multiple_of(2) || window <= order {
+            return Err(format!(
+                "❌ Invalid Savitzky-Golay window {} / order {}: window must be odd and greater than order",
+                window, order
+            ));
+        }
+        
+        Ok(Self {
+            window,
+            order,
+            coefficients: Self::coefficients(window, order),
+            history: Vec::new(),
+            timestamps: VecDeque::with_capacity(window),
+        })
+    }
+    
+    // Least-squares weights c = A (AᵀA)⁻¹ e₀ for A[i][j] = zᵢʲ, z = -m..m
+    fn coefficients(window: usize, order: usize) -> Vec<f64> {
+        let half = (window / 2) as f64;
+        let positions: Vec<f64> = (0..window).map(|i| i as f64 - half).collect();
+        let terms = order + 1;
+        
+        // Normal equations (AᵀA) x = e₀, with e₀ appended as the augmented column
+        let mut system = vec![vec![0.0; terms + 1]; terms];
+        for (j, row) in system.iter_mut().enumerate() {
+            for (k, cell) in row.iter_mut().take(terms).enumerate() {
+                *cell = positions.iter().map(|z| z.powi((j + k) as i32)).sum();
+            }
+            row[terms] = if j == 0 { 1.0 } else { 0.0 };
+        }
+        
+        // Gauss-Jordan elimination with partial pivoting
+        for col in 0..terms {
+            let pivot = (col..terms)
+                .max_by(|&a, &b| system[a][col].abs().total_cmp(&system[b][col].abs()))
+                .unwrap_or(col);
+            system.swap(col, pivot);
+            
+            let divisor = system[col][col];
+            for value in system[col].iter_mut() {
+                *value /= divisor;
+            }
+            let pivot_row = system[col].clone();
+            for (r, row) in system.iter_mut().enumerate() {
+                if r != col {
+                    let factor = row[col];
+                    for (value, pivot_value) in row.iter_mut().zip(pivot_row.iter()) {
+                        *value -= factor * pivot_value;
+                    }
+                }
+            }
+        }
+        let x: Vec<f64> = system.iter().map(|row| row[terms]).collect();
+        
+        positions.iter()
+            .map(|z| x.iter().enumerate().map(|(j, xj)| xj * z.powi(j as i32)).sum())
+            .collect()
+    }
+    
+    fn process(&mut self, sample: &FilteredEEGSample) -> Option<FilteredEEGSample> {
+        if self.history.len() != sample.channels.len() {
+            self.history = vec![VecDeque::with_capacity(self.window); sample.channels.len()];
+            self.timestamps.clear();
+        }
+        
+        if self.timestamps.len() == self.window {
+            self.timestamps.pop_front();
+        }
+        self.timestamps.push_back((sample.timestamp, sample.sample_index, sample.interpolated, sample.lsl_timestamp));
+        for (history, &value) in self.history.iter_mut().zip(sample.channels.iter()) {
+            if history.len() == self.window {
+                history.pop_front();
+            }
+            history.push_back(value);
+        }
+        
+        if self.timestamps.len() < self.window {
+            return None;
+        }
+        
+        let (timestamp, sample_index, interpolated, lsl_timestamp) = self.timestamps[self.window / 2];
+        Some(FilteredEEGSample {
+            timestamp,
+            sample_index,
+            interpolated,
+            lsl_timestamp,
+            channels: self.history.iter()
+                .map(|history| {
+                    history.iter()
+                        .zip(self.coefficients.iter())
+                        .map(|(&x, c)| x as f64 * c)
+                        .sum::<f64>() as f32
+                })
+                .collect(),
+        })
+    }
+}
+
+// Extension point for user-defined real-time metrics. Registered metrics run once per
+// analysis cycle on every channel's full filtered buffer; results go out as `custom_metric`.
+trait EegMetric: Send {
+    fn name(&self) -> &str;
+    fn compute(&mut self, channel: usize, buffer: &[f32], rate: f32) -> serde_json::Value;
+}
+
+// Hjorth activity (variance), mobility and complexity from the signal and its differences
+fn hjorth_parameters(buffer: &[f32]) -> (f64, f64, f64) {
+    fn variance(values: &[f64]) -> f64 {
+        if values.is_empty() {
+            return 0.0;
+        }
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64
+    }
+    fn diff(values: &[f64]) -> Vec<f64> {
+        values.windows(2).map(|w| w[1] - w[0]).collect()
+    }
+    
+    let signal: Vec<f64> = buffer.iter().map(|&v| v as f64).collect();
+    let first = diff(&signal);
+    let second = diff(&first);
+    
+    let activity = variance(&signal);
+    let (var_d1, var_d2) = (variance(&first), variance(&second));
+    let mobility = if activity > 0.0 { (var_d1 / activity).sqrt() } else { 0.0 };
+    let complexity = if var_d1 > 0.0 && mobility > 0.0 {
+        (var_d2 / var_d1).sqrt() / mobility
+    } else {
+        0.0
+    };
+    
+    (activity, mobility, complexity)
+}
+
+struct HjorthMetric;
+
+impl EegMetric for HjorthMetric {
+    fn name(&self) -> &str {
+        "hjorth"
+    }
+    
+    fn compute(&mut self, _channel: usize, buffer: &[f32], _rate: f32) -> serde_json::Value {
+        let (activity, mobility, complexity) = hjorth_parameters(buffer);
+        serde_json::json!({
+            "activity": activity,
+            "mobility": mobility,
+            "complexity": complexity,
+        })
+    }
+}
+
+// Filter settings for one channel; every channel starts from the global config
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct ChannelFilterConfig {
+    highpass: f64,       // Hz
+    lowpass: f64,        // Hz
+    notch: Option<f64>,  // Hz, None disables the notch
+    notch_q: f64,        // notch frequency / -3 dB bandwidth
+    median_despike: usize, // odd window of the median run before the bandpass; 1 disables it
+    #[serde(default)]
+    mains_method: MainsMethod, // how the `notch` frequency is removed
+}
+
+impl Default for ChannelFilterConfig {
+    fn default() -> Self {
+        Self {
+            highpass: 1.0,
+            lowpass: 40.0,
+            notch: Some(50.0),
+            notch_q: 30.0,
+            median_despike: 1,
+            mains_method: MainsMethod::Notch,
+        }
+    }
+}
+
+// set_mains_method: the IIR notch, or an LMS canceller subtracting a fitted mains sine
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+enum MainsMethod {
+    #[default]
+    Notch,
+    Adaptive,
+}
+
+impl std::str::FromStr for MainsMethod {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "notch" => Ok(Self::Notch),
+            "adaptive" => Ok(Self::Adaptive),
+            other => Err(format!("❌ Unknown mains method '{}': expected \"notch\" or \"adaptive\"", other)),
+        }
+    }
+}
+
+// Adaptive interference canceller (Widrow): a synthesized sine/cosine pair at the mains
+// frequency is weighted by LMS to match the interference and subtracted each sample. With the
+// unit-amplitude reference its rejection band is about 2·MAINS_LMS_STEP rad/sample wide
+// (0.8 Hz at 250 Hz, against 1.7 Hz for the Q 30 notch) and it adapts with a time constant of
+// 1/MAINS_LMS_STEP samples.
+const MAINS_LMS_STEP: f64 = 0.01;
+// Converged: five time constants in, with the amplitude estimate within 10% (or 0.1 µV) of its
+// average over the last five time constants
+const MAINS_CONVERGED_TIME_CONSTANTS: f64 = 5.0;
+const MAINS_CONVERGED_TOLERANCE: (f64, f64) = (0.1, 0.1);
+
+#[derive(Debug, Clone)]
+struct MainsCanceller {
+    frequency: f64,
+    phase: f64,      // of the reference, radians
+    increment: f64,  // phase step per sample
+    weights: [f64; 2], // sine and cosine amplitude, µV
+    samples: u64,    // since the canceller was built
+    mean_amplitude: f64,
+}
+
+impl MainsCanceller {
+    fn new(frequency: f64, sample_rate: f64) -> Self {
+        Self {
+            frequency,
+            phase: 0.0,
+            increment: 2.0 * std::f64::consts::PI * frequency / sample_rate,
+            weights: [0.0; 2],
+            samples: 0,
+            mean_amplitude: 0.0,
+        }
+    }
+    
+    // Weights and phase carry over, so a small retune doesn't restart the adaptation
+    fn retune(&mut self, frequency: f64, sample_rate: f64) {
+        self.frequency = frequency;
+        self.increment = 2.0 * std::f64::consts::PI * frequency / sample_rate;
+    }
+    
+    fn process(&mut self, sample: f32) -> f32 {
+        let (sin, cos) = self.phase.sin_cos();
+        let error = sample as f64 - (self.weights[0] * sin + self.weights[1] * cos);
+        self.weights[0] += 2.0 * MAINS_LMS_STEP * error * sin;
+        self.weights[1] += 2.0 * MAINS_LMS_STEP * error * cos;
+        self.phase = (self.phase + self.increment).rem_euclid(2.0 * std::f64::consts::PI);
+        
+        self.samples += 1;
+        let smoothing = MAINS_LMS_STEP / MAINS_CONVERGED_TIME_CONSTANTS;
+        self.mean_amplitude += smoothing * (self.amplitude() - self.mean_amplitude);
+        error as f32
+    }
+    
+    // Estimated mains amplitude being removed, µV
+    fn amplitude(&self) -> f64 {
+        self.weights[0].hypot(self.weights[1])
+    }
+    
+    fn converged(&self) -> bool {
+        let (relative, absolute) = MAINS_CONVERGED_TOLERANCE;
+        self.samples as f64 * MAINS_LMS_STEP >= MAINS_CONVERGED_TIME_CONSTANTS
+            && (self.amplitude() - self.mean_amplitude).abs() <= relative * self.mean_amplitude + absolute
+    }
+}
+
+// get_mains_canceller_status: one entry per channel running the adaptive canceller
+#[derive(Debug, Serialize, Clone)]
+struct MainsCancellerStatus {
+    channel: usize,
+    frequency: f64,
+    amplitude: f64, // µV
+    converged: bool,
+}
+
+impl ChannelFilterConfig {
+    fn validate(&self, sample_rate: f64) -> Result<(), String> {
+        MedianFilter::validate_len(self.median_despike)?;
+        let nyquist = sample_rate / 2.0;
+        if !(self.highpass > 0.0 && self.highpass < self.lowpass && self.lowpass < nyquist) {
+            return Err(format!(
+                "❌ Invalid bandpass {}-{} Hz: need 0 < highpass < lowpass < {:.1} Hz (Nyquist)",
+                self.highpass, self.lowpass, nyquist
+            ));
+        }
+        if let Some(notch) = self.notch {
+            if !(notch > 0.0 && notch < nyquist) {
+                return Err(format!("❌ Invalid notch frequency {} Hz: must be between 0 and {:.1} Hz", notch, nyquist));
+            }
+        }
+        if !(self.notch_q > 0.0 && self.notch_q.is_finite()) {
+            return Err(format!("❌ Invalid notch Q {}: must be a positive number", self.notch_q));
+        }
+        
+        // In range is not enough: cutoffs squeezed against 0 Hz or Nyquist put poles on the unit
+        // circle once rounded, and the filter output grows until it is NaN
+        let poles = ButterworthFilter::bandpass_poles(4, self.highpass, self.lowpass, sample_rate);
+        if let Some(magnitude) = unstable_pole(&poles) {
+            return Err(format!(
+                "❌ Unstable bandpass {}-{} Hz at {} Hz: pole magnitude {} (must be below 1), move the cutoffs away from 0 Hz and Nyquist",
+                self.highpass, self.lowpass, sample_rate, magnitude
+            ));
+        }
+        if let Some(notch) = self.notch {
+            let a = NotchFilter::new(notch, self.notch_q, sample_rate, 0).a;
+            // Roots of z^2 + a1 z + a2
+            let disc = Complex::new(a[1] * a[1] - 4.0 * a[2], 0.0).sqrt();
+            let poles = [(-a[1] + disc) / 2.0, (-a[1] - disc) / 2.0];
+            if let Some(magnitude) = unstable_pole(&poles) {
+                return Err(format!(
+                    "❌ Unstable notch at {} Hz with Q {}: pole magnitude {} (must be below 1)",
+                    notch, self.notch_q, magnitude
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Magnitude of the largest pole when it is on or outside the unit circle (infinite for NaN poles)
+fn unstable_pole(poles: &[Complex<f64>]) -> Option<f64> {
+    let magnitude = poles.iter()
+        .map(|pole| if pole.is_finite() { pole.norm() } else { f64::INFINITY })
+        .fold(0.0, f64::max);
+    (magnitude >= 1.0).then_some(magnitude)
+}
+
+// What a channel records, for set_channel_signal_type. Each type maps to a filter preset:
+//   eeg  the global filter config (1-40 Hz with the 50 Hz notch by default)
+//   emg  20-250 Hz with the global notch
+//   eog  0.1-10 Hz, no notch (mains is far above the passband)
+//   ecg  0.5-40 Hz with the global notch
+// Upper cutoffs are capped at 0.9 × Nyquist, so emg at 250 Hz sampling gets 20-112.5 Hz.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum SignalType {
+    Eeg,
+    Emg,
+    Eog,
+    Ecg,
+}
+
+impl std::str::FromStr for SignalType {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "eeg" => Ok(Self::Eeg),
+            "emg" => Ok(Self::Emg),
+            "eog" => Ok(Self::Eog),
+            "ecg" => Ok(Self::Ecg),
+            other => Err(format!("❌ Unknown signal type '{}': expected \"eeg\", \"emg\", \"eog\" or \"ecg\"", other)),
+        }
+    }
+}
+
+impl SignalType {
+    fn filter_preset(self, global: &ChannelFilterConfig, sample_rate: f64) -> ChannelFilterConfig {
+        let (highpass, lowpass, notch): (f64, f64, Option<f64>) = match self {
+            Self::Eeg => return global.clone(),
+            Self::Emg => (20.0, 250.0, global.notch),
+            Self::Eog => (0.1, 10.0, None),
+            Self::Ecg => (0.5, 40.0, global.notch),
+        };
+        ChannelFilterConfig {
+            highpass,
+            lowpass: lowpass.min(0.45 * sample_rate),
+            notch,
+            ..global.clone()
+        }
+    }
+}
+
+// Longest despike window accepted; longer medians start flattening real waveform peaks
+const MAX_MEDIAN_DESPIKE: usize = 15;
+
+// Rolling N-point median over the newest samples. A single-sample spike is never the median of
+// three or more points, so it is dropped entirely instead of ringing through the IIR stages.
+// Output lags the input by (N - 1) / 2 samples; until N samples are in, input passes straight through.
+#[derive(Debug, Clone)]
+struct MedianFilter {
+    len: usize,
+    window: VecDeque<f32>,
+    sorted: Vec<f32>, // reused scratch for the selection
+}
+
+impl MedianFilter {
+    fn validate_len(len: usize) -> Result<(), String> {
+        if len % 2 != 1 || len > MAX_MEDIAN_DESPIKE {
+            return Err(format!("❌ Invalid median despike length {}: must be odd, 1-{} (1 disables)", len, MAX_MEDIAN_DESPIKE));
+        }
+        Ok(())
+    }
+    
+    fn new(len: usize) -> Self {
+        Self {
+            len,
+            window: VecDeque::with_capacity(len),
+            sorted: Vec::with_capacity(len),
+        }
+    }
+    
+    fn process(&mut self, sample: f32) -> f32 {
+        if self.window.len() == self.len {
+            self.window.pop_front();
+        }
+        self.window.push_back(sample);
+        if self.window.len() < self.len {
+            return sample;
+        }
+        
+        self.sorted.clear();
+        self.sorted.extend(self.window.iter());
+        let mid = self.len / 2;
+        *self.sorted.select_nth_unstable_by(mid, f32::total_cmp).1
+    }
+}
+
+// Slow per-channel amplitude tracking for set_auto_gain: an exponential mean of the squared
+// filtered signal with the configured time constant (a plain running mean until that much
+// signal has been seen, so the first estimate isn't dominated by one sample)
+#[derive(Debug, Clone)]
+struct AutoGain {
+    target_rms: f32,
+    time_constant_s: f32,
+    mean_square: Vec<f64>,
+    samples_seen: u64,
+}
+
+// Accepted time constants; shorter ones would follow the very rhythms the feedback reports
+const MIN_AUTO_GAIN_TIME_CONSTANT_S: f32 = 1.0;
+const MAX_AUTO_GAIN_TIME_CONSTANT_S: f32 = 600.0;
+
+impl AutoGain {
+    fn new(target_rms: f32, time_constant_s: f32) -> Self {
+        Self { target_rms, time_constant_s, mean_square: Vec::new(), samples_seen: 0 }
+    }
+    
+    fn reset(&mut self) {
+        self.mean_square.clear();
+        self.samples_seen = 0;
+    }
+    
+    fn update(&mut self, channels: &[f32], rate: f32) {
+        if self.mean_square.len() != channels.len() {
+            self.mean_square = vec![0.0; channels.len()];
+            self.samples_seen = 0;
+        }
+        self.samples_seen += 1;
+        let alpha = (1.0 / self.samples_seen as f64).max(1.0 - (-1.0 / (self.time_constant_s as f64 * rate as f64)).exp());
+        for (mean_square, &value) in self.mean_square.iter_mut().zip(channels) {
+            if value.is_finite() {
+                *mean_square += alpha * ((value as f64).powi(2) - *mean_square);
+            }
+        }
+    }
+    
+    // Power factor (amplitude gain squared) that brings channel `ch` to the target RMS
+    fn power_gain(&self, ch: usize) -> f32 {
+        match self.mean_square.get(ch) {
+            Some(&mean_square) if mean_square > 1e-12 => (self.target_rms as f64).powi(2) as f32 / mean_square as f32,
+            _ => 1.0,
+        }
+    }
+}
+
+// set_band_time_constant: an exponential moving average per band and channel over the emitted
+// band powers, weighted by the time between analysis cycles. Only the reported values are
+// smoothed; the spectrum, the raw/filtered buffers and everything else computed from them are not.
+#[derive(Debug, Default)]
+struct BandSmoothing {
+    time_constants: HashMap<String, f32>, // seconds, by band name
+    state: HashMap<(usize, String), (f32, f64)>, // (channel, band) -> (smoothed power, timestamp)
+}
+
+impl BandSmoothing {
+    fn apply(&mut self, bands: &mut [FrequencyBands]) {
+        if self.time_constants.is_empty() {
+            return;
+        }
+        for channel_bands in bands.iter_mut() {
+            let timestamp = channel_bands.timestamp;
+            for (band, power) in channel_bands.bands.iter_mut() {
+                let Some(&tau) = self.time_constants.get(band) else {
+                    continue;
+                };
+                let key = (channel_bands.channel, band.clone());
+                // The first value (and any timestamp step backwards) starts the average over
+                let smoothed = match self.state.get(&key) {
+                    Some(&(previous, last)) if timestamp > last => {
+                        let alpha = 1.0 - (-((timestamp - last) as f32) / tau).exp();
+                        previous + alpha * (*power - previous)
+                    }
+                    _ => *power,
+                };
+                self.state.insert(key, (smoothed, timestamp));
+                *power = smoothed;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.state.clear();
+    }
+}
+
+const MAX_BAND_TIME_CONSTANT_S: f32 = 60.0;
+
+// Independent bandpass + notch chain for a single channel
+#[derive(Debug, Clone)]
+struct ChannelFilter {
+    config: ChannelFilterConfig,
+    despike: Option<MedianFilter>,
+    bandpass: ButterworthFilter,
+    notch: Option<NotchFilter>,
+    canceller: Option<MainsCanceller>, // instead of the notch with MainsMethod::Adaptive
+    last_output: f32,
+}
+
+impl ChannelFilter {
+    fn new(config: ChannelFilterConfig, sample_rate: f64) -> Self {
+        let bandpass = ButterworthFilter::new(4, config.highpass, config.lowpass, sample_rate, 1);
+        let despike = (config.median_despike > 1).then(|| MedianFilter::new(config.median_despike));
+        let (notch, canceller) = Self::mains_stage(&config, sample_rate);
+        
+        Self {
+            config,
+            despike,
+            bandpass,
+            notch,
+            canceller,
+            last_output: 0.0,
+        }
+    }
+    
+    fn mains_stage(config: &ChannelFilterConfig, sample_rate: f64) -> (Option<NotchFilter>, Option<MainsCanceller>) {
+        match (config.notch, config.mains_method) {
+            (Some(freq), MainsMethod::Notch) => (Some(NotchFilter::new(freq, config.notch_q, sample_rate, 1)), None),
+            (Some(freq), MainsMethod::Adaptive) => (None, Some(MainsCanceller::new(freq, sample_rate))),
+            (None, _) => (None, None),
+        }
+    }
+    
+    // Redesign only the notch stage, leaving the bandpass history untouched
+    fn set_notch_q(&mut self, q: f64, sample_rate: f64) {
+        self.config.notch_q = q;
+        if self.config.mains_method == MainsMethod::Notch {
+            self.notch = self.config.notch.map(|freq| NotchFilter::new(freq, q, sample_rate, 1));
+        }
+    }
+    
+    // Swaps the mains stage; a new canceller starts adapting from zero
+    fn set_mains_method(&mut self, method: MainsMethod, sample_rate: f64) {
+        if self.config.mains_method != method {
+            self.config.mains_method = method;
+            (self.notch, self.canceller) = Self::mains_stage(&self.config, sample_rate);
+        }
+    }
+    
+    // Frequency the notch or canceller currently removes
+    fn mains_frequency(&self) -> Option<f64> {
+        self.notch.as_ref().map(|notch| notch.frequency)
+            .or(self.canceller.as_ref().map(|canceller| canceller.frequency))
+    }
+    
+    fn set_median_despike(&mut self, len: usize) {
+        self.config.median_despike = len;
+        self.despike = (len > 1).then(|| MedianFilter::new(len));
+    }
+    
+    fn process(&mut self, sample: f32) -> f32 {
+        let sample = match self.despike.as_mut() {
+            Some(despike) => despike.process(sample),
+            None => sample,
+        };
+        let bandpassed = self.bandpass.process_sample(0, sample);
+        self.last_output = match (self.notch.as_mut(), self.canceller.as_mut()) {
+            (Some(notch), _) => notch.process_sample(0, bandpassed),
+            (None, Some(canceller)) => canceller.process(bandpassed),
+            (None, None) => bandpassed,
+        };
+        self.last_output
+    }
+    
+    // Same cutoffs designed for a new rate. The order and therefore the state length are
+    // unchanged, so the previous input/output history carries over and there is no restart.
+    fn redesigned(&self, sample_rate: f64) -> Self {
+        let mut filter = Self::new(self.config.clone(), sample_rate);
+        filter.despike.clone_from(&self.despike);
+        filter.bandpass.x_history.clone_from(&self.bandpass.x_history);
+        filter.bandpass.y_history.clone_from(&self.bandpass.y_history);
+        if let (Some(new), Some(old)) = (filter.notch.as_mut(), self.notch.as_ref()) {
+            new.x_history.clone_from(&old.x_history);
+            new.y_history.clone_from(&old.y_history);
+        }
+        if let Some(old) = self.canceller.as_ref() {
+            let mut canceller = old.clone();
+            canceller.retune(old.frequency, sample_rate);
+            filter.canceller = Some(canceller);
+        }
+        filter.last_output = self.last_output;
+        filter
+    }
+    
+    // Repeat the previous output without advancing the filter state
+    fn hold(&self) -> f32 {
+        self.last_output
+    }
+    
+    // Move the notch centre (adaptive notch) keeping its history; config.notch stays the nominal
+    fn retune_notch(&mut self, frequency: f64, sample_rate: f64) {
+        if let Some(old) = self.notch.as_ref() {
+            let mut notch = NotchFilter::new(frequency, self.config.notch_q, sample_rate, 1);
+            notch.x_history.clone_from(&old.x_history);
+            notch.y_history.clone_from(&old.y_history);
+            self.notch = Some(notch);
+        }
+        if let Some(canceller) = self.canceller.as_mut() {
+            canceller.retune(frequency, sample_rate);
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum TimestampSource {
+    Lsl,   // timestamps reported by the LSL inlet
+    Local, // seconds since start_eeg_processing
+}
+
+impl std::str::FromStr for TimestampSource {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "lsl" => Ok(Self::Lsl),
+            "local" => Ok(Self::Local),
+            other => Err(format!("❌ Unknown timestamp source '{}': expected \"lsl\" or \"local\"", other)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum BandFormat {
+    PerChannel, // Vec<FrequencyBands>, one struct per channel
+    Banded,     // BandedFrequencyBands, one array per band
+}
+
+impl std::str::FromStr for BandFormat {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "per_channel" => Ok(Self::PerChannel),
+            "banded" => Ok(Self::Banded),
+            other => Err(format!("❌ Unknown band format '{}': expected \"per_channel\" or \"banded\"", other)),
+        }
+    }
+}
+
+// Lowest bins of each analysis window left out of every band. Bin 0 is the window mean, so any
+// residual DC offset would otherwise land in delta; bins 1-2 still carry its leakage and drift.
+const DEFAULT_DC_EXCLUSION_BINS: usize = 1;
+const MAX_DC_EXCLUSION_BINS: usize = 3;
+
+// Spectrum shaping applied by compute_channel_bands
+#[derive(Debug, Clone, Copy, Default)]
+struct BandOptions {
+    gamma_preemphasis: bool,
+    whitening: Option<usize>, // half-width in bins of the whitening smoothing
+    excluded_bins: usize,     // in bins of the unpadded window
+}
+
+// How band powers are computed. Goertzel evaluates only a few frequencies per band
+// (set_analysis_method's points_per_band), each costing one pass over the window. That is not
+// cheaper than rustfft's plan in practice: for the classic profile, 512 samples and 32 channels
+// it measured about 2.5x slower at 1 point per band and 8x at 4. Its band power is the mean power at those
+// frequencies times the band's bin count: exact for a flat spectrum, but a narrow peak between
+// points is under- or overestimated, and whitening, gamma pre-emphasis and multi-resolution
+// (which need the full spectrum) are skipped.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum AnalysisMethod {
+    Fft,
+    Goertzel,
+}
+
+impl std::str::FromStr for AnalysisMethod {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "fft" => Ok(Self::Fft),
+            "goertzel" => Ok(Self::Goertzel),
+            other => Err(format!("❌ Unknown analysis method '{}': expected \"fft\" or \"goertzel\"", other)),
+        }
+    }
+}
+
+const DEFAULT_GOERTZEL_POINTS: usize = 4;
+const MAX_GOERTZEL_POINTS: usize = 64;
+
+// Buffers the band analysis (frequency_bands and everything derived from it) reads. Raw skips
+// the bandpass and notch; it holds only buffer_size samples, so multi-resolution analysis falls
+// back to the standard window.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum AnalysisSource {
+    Filtered,
+    Raw,
+}
+
+impl std::str::FromStr for AnalysisSource {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "filtered" => Ok(Self::Filtered),
+            "raw" => Ok(Self::Raw),
+            other => Err(format!("❌ Unknown analysis source '{}': expected \"filtered\" or \"raw\"", other)),
+        }
+    }
+}
+
+// Where the ±ARTIFACT_CLIP_UV artifact check runs. Filtered clips the filter output (the IIR
+// still sees the transient and rings afterwards); raw checks the input before the bandpass and,
+// for an out-of-range sample, holds the previous output and keeps the sample out of the filter
+// history. Channels past the EEG channel count are never checked in either domain.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum ArtifactDomain {
+    Raw,
+    Filtered,
+}
+
+impl std::str::FromStr for ArtifactDomain {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "raw" => Ok(Self::Raw),
+            "filtered" => Ok(Self::Filtered),
+            other => Err(format!("❌ Unknown artifact domain '{}': expected \"raw\" or \"filtered\"", other)),
+        }
+    }
+}
+
+// What a threshold trigger watches on its channel
+#[derive(Debug, Clone, PartialEq)]
+enum TriggerMetric {
+    Band(String),    // a band of the active BandProfile
+    ThetaBetaRatio,
+    Rms,             // RMS of the filtered analysis buffer
+}
+
+impl TriggerMetric {
+    fn parse(metric: &str, band: Option<&str>, known_bands: &[String]) -> Result<Self, String> {
+        let metric = metric.to_lowercase();
+        match metric.as_str() {
+            "theta_beta_ratio" => Ok(Self::ThetaBetaRatio),
+            "rms" => Ok(Self::Rms),
+            "band" | "band_power" => {
+                let band = band.ok_or_else(|| format!("❌ Trigger metric '{}' needs a band", metric))?;
+                match known_bands.iter().find(|b| b.eq_ignore_ascii_case(band)) {
+                    Some(known) => Ok(Self::Band(known.clone())),
+                    None => Err(format!("❌ Unknown band '{}': expected one of {:?}", band, known_bands)),
+                }
+            }
+            band if known_bands.iter().any(|b| b.eq_ignore_ascii_case(band)) => {
+                Ok(Self::Band(known_bands.iter().find(|b| b.eq_ignore_ascii_case(band)).cloned().unwrap_or_default()))
+            }
+            other => Err(format!(
+                "❌ Unknown trigger metric '{}': expected a band name, \"band\", \"theta_beta_ratio\" or \"rms\"",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TriggerOp {
+    Above,
+    AtLeast,
+    Below,
+    AtMost,
+}
+
+impl std::str::FromStr for TriggerOp {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            ">" | "above" => Ok(Self::Above),
+            ">=" => Ok(Self::AtLeast),
+            "<" | "below" => Ok(Self::Below),
+            "<=" => Ok(Self::AtMost),
+            other => Err(format!("❌ Unknown trigger operator '{}': expected \">\", \">=\", \"<\" or \"<=\"", other)),
+        }
+    }
+}
+
+// Fires once per crossing: after firing it stays silent until the value falls back past the
+// threshold by TRIGGER_HYSTERESIS (relative), and never fires twice within TRIGGER_DEBOUNCE_S
+const TRIGGER_HYSTERESIS: f32 = 0.05;
+const TRIGGER_DEBOUNCE_S: f64 = 1.0;
+
+#[derive(Debug, Clone)]
+struct Trigger {
+    id: u64,
+    metric: TriggerMetric,
+    channel: usize,
+    op: TriggerOp,
+    threshold: f32,
+    armed: bool,
+    last_fired: Option<f64>,
+}
+
+impl Trigger {
+    // Returns true when the trigger fires for this value
+    fn evaluate(&mut self, value: f32, timestamp: f64) -> bool {
+        let margin = self.threshold.abs() * TRIGGER_HYSTERESIS;
+        let (crossed, released) = match self.op {
+            TriggerOp::Above => (value > self.threshold, value < self.threshold - margin),
+            TriggerOp::AtLeast => (value >= self.threshold, value < self.threshold - margin),
+            TriggerOp::Below => (value < self.threshold, value > self.threshold + margin),
+            TriggerOp::AtMost => (value <= self.threshold, value > self.threshold + margin),
+        };
+        
+        if !self.armed {
+            self.armed = released;
+            return false;
+        }
+        
+        let debounced = self.last_fired.is_some_and(|t| (timestamp - t).abs() < TRIGGER_DEBOUNCE_S);
+        if crossed && !debounced {
+            self.armed = false;
+            self.last_fired = Some(timestamp);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct TriggerFired {
+    id: u64,
+    value: f32,
+    timestamp: f64,
+}
+
+// Which StreamInfo property the stream name given to connect is compared against (case-insensitive)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum MatchField {
+    Auto,     // exact name, then source_id, hostname, type; then the legacy partial/Unicorn match
+    Name,
+    SourceId,
+    Type,
+    Hostname,
+}
+
+impl std::str::FromStr for MatchField {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "name" => Ok(Self::Name),
+            "source_id" => Ok(Self::SourceId),
+            "type" => Ok(Self::Type),
+            "hostname" => Ok(Self::Hostname),
+            other => Err(format!(
+                "❌ Unknown match field '{}': expected \"name\", \"source_id\", \"type\", \"hostname\" or \"auto\"",
+                other
+            )),
+        }
+    }
+}
+
+impl MatchField {
+    const AUTO_ORDER: [MatchField; 4] = [Self::Name, Self::SourceId, Self::Hostname, Self::Type];
+    
+    fn property(self, stream: &StreamInfo) -> Option<String> {
+        match self {
+            Self::Auto => None,
+            Self::Name => Some(stream.stream_name()),
+            Self::SourceId => Some(stream.source_id()),
+            Self::Type => Some(stream.stream_type()),
+            Self::Hostname => Some(stream.hostname()),
+        }
+    }
+}
+
+// What the processing loop gives up when it can't keep up with its 4 ms tick
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum OverloadPolicy {
+    None,         // run everything, late ticks are caught up in a burst
+    DropOldest,   // skip late ticks and the analysis cycle, so streaming stays on fresh samples
+    DropAnalysis, // keep every tick but run the analysis cycle once per second instead of 250 ms
+}
+
+impl std::str::FromStr for OverloadPolicy {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "drop_oldest" => Ok(Self::DropOldest),
+            "drop_analysis" => Ok(Self::DropAnalysis),
+            other => Err(format!(
+                "❌ Unknown overload policy '{}': expected \"none\", \"drop_oldest\" or \"drop_analysis\"",
+                other
+            )),
+        }
+    }
+}
+
+impl OverloadPolicy {
+    // Set on the processing loop's interval when it starts and whenever the policy changes
+    fn missed_tick_behavior(self) -> tokio::time::MissedTickBehavior {
+        match self {
+            Self::DropOldest => tokio::time::MissedTickBehavior::Skip,
+            _ => tokio::time::MissedTickBehavior::Burst,
+        }
+    }
+}
+
+// One active stage of the per-sample pipeline, in processing order, for `processing_chain`
+#[derive(Debug, Serialize, Clone, PartialEq)]
+struct ProcessingStage {
+    stage: String,
+    params: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+struct ProcessingChain {
+    stages: Vec<ProcessingStage>,
+}
+
+// With set_emit_processing_chain on, the chain is rebuilt at most this often and emitted on change
+const PROCESSING_CHAIN_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+struct ProcessingChainWatch {
+    last: Option<ProcessingChain>,
+    checked: Option<std::time::Instant>,
+}
+
+// Where set_channel_polarity flips a channel's sign. Before the filters, the raw buffers and
+// raw_* replay columns are flipped too; after, only filtered data is. The filters are linear, so
+// filtered output and band powers are the same either way.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum PolarityStage {
+    BeforeFilter,
+    AfterFilter,
+}
+
+impl std::str::FromStr for PolarityStage {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "before_filter" => Ok(Self::BeforeFilter),
+            "after_filter" => Ok(Self::AfterFilter),
+            other => Err(format!("❌ Unknown polarity stage '{}': expected \"before_filter\" or \"after_filter\"", other)),
+        }
+    }
+}
+
+// What accept_sample does with a sample whose timestamp is not after the previous one
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum TimestampGuard {
+    Drop,    // discard the sample
+    Restamp, // keep it, one nominal sample period after the previous timestamp
+}
+
+impl std::str::FromStr for TimestampGuard {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "drop" => Ok(Self::Drop),
+            "restamp" => Ok(Self::Restamp),
+            other => Err(format!("❌ Unknown timestamp guard '{}': expected \"drop\" or \"restamp\"", other)),
+        }
+    }
+}
+
+// Non-monotonic timestamps seen since connect, returned by get_timestamp_stats
+#[derive(Debug, Serialize, Clone, Default)]
+struct TimestampStats {
+    duplicates: u64,   // same timestamp as the previous sample
+    out_of_order: u64, // earlier than the previous sample (or not finite)
+    dropped: u64,
+    restamped: u64,
+}
+
+// Per-channel band powers summed over analysis cycles, for heartbeats and session summaries
+#[derive(Debug, Default)]
+struct BandAverage {
+    sums: Vec<HashMap<String, f32>>, // per channel, summed over `cycles` analysis cycles
+    cycles: usize,
+}
+
+impl BandAverage {
+    fn add(&mut self, bands: &[FrequencyBands]) {
+        for channel_bands in bands {
+            if self.sums.len() <= channel_bands.channel {
+                self.sums.resize(channel_bands.channel + 1, HashMap::new());
+            }
+            let sums = &mut self.sums[channel_bands.channel];
+            for (band, power) in &channel_bands.bands {
+                *sums.entry(band.clone()).or_insert(0.0) += power;
+            }
+        }
+        self.cycles += 1;
+    }
+
+    fn mean(self, timestamp: f64) -> Vec<FrequencyBands> {
+        let cycles = self.cycles.max(1) as f32;
+        self.sums.into_iter()
+            .enumerate()
+            .filter(|(_, sums)| !sums.is_empty())
+            .map(|(channel, sums)| FrequencyBands {
+                timestamp,
+                channel,
+                bands: sums.into_iter().map(|(band, sum)| (band, sum / cycles)).collect(),
+                unavailable: Vec::new(),
+            })
+            .collect()
+    }
+}
+
+// set_quiet_mode state: aggregates of the current heartbeat interval
+#[derive(Debug)]
+struct QuietMode {
+    interval_s: f64,
+    started: std::time::Instant,
+    samples: u64,
+    bands: BandAverage,
+    snr: Vec<ChannelSnr>, // newest
+}
+
+impl QuietMode {
+    fn new(interval_s: f64) -> Self {
+        Self {
+            interval_s,
+            started: std::time::Instant::now(),
+            samples: 0,
+            bands: BandAverage::default(),
+            snr: Vec::new(),
+        }
+    }
+}
+
+// start_timed_session state; the processing loop stops itself once `duration_s` has passed
+#[derive(Debug)]
+struct TimedSession {
+    duration_s: f64,
+    started: std::time::Instant,
+    samples: u64,
+    bands: BandAverage,
+    with_quote: bool,
+}
+
+// Emitted once as `session_complete` when a timed session runs out (not when it is stopped)
+#[derive(Debug, Serialize, Clone)]
+struct SessionSummary {
+    timestamp: f64,
+    duration_s: f64,
+    samples: u64,
+    mean_bands: Vec<FrequencyBands>, // averaged over the session's analysis cycles
+    crossings: HashMap<String, u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quote: Option<String>,
+}
+
+// set_band_crossing_threshold: upward crossings of a band's channel-mean power since processing
+// started. Like a trigger, it re-arms only once the power falls TRIGGER_HYSTERESIS below.
+#[derive(Debug, Serialize, Clone, Copy)]
+struct BandCrossing {
+    threshold: f32,
+    count: u64,
+    #[serde(skip)]
+    above: bool,
+}
+
+impl BandCrossing {
+    fn update(&mut self, power: f32) {
+        if self.above {
+            self.above = power >= self.threshold - self.threshold.abs() * TRIGGER_HYSTERESIS;
+        } else if power > self.threshold {
+            self.above = true;
+            self.count += 1;
+        }
+    }
+}
+
+// get_session_stats: running counts for the current start_eeg_processing run
+#[derive(Debug, Serialize, Clone)]
+struct SessionStats {
+    crossings: HashMap<String, u64>,
+}
+
+const MAX_TIMED_SESSION_S: f64 = 4.0 * 3600.0;
+
+// The only event in quiet mode, every heartbeat interval: what the suppressed events would have shown
+#[derive(Debug, Serialize, Clone)]
+struct Heartbeat {
+    timestamp: f64,
+    interval_s: f64,
+    samples: u64,
+    effective_rate: Option<f64>, // smoothed, as in connection_status
+    mean_bands: Vec<FrequencyBands>, // averaged over the interval's analysis cycles
+    snr: Vec<ChannelSnr>,
+}
+
+const DEFAULT_HEARTBEAT_INTERVAL_S: f64 = 1.0;
+
+// Smoothed share of the 4 ms tick spent processing; emitted once per second as `processing_load`
+#[derive(Debug, Serialize, Clone)]
+struct ProcessingLoad {
+    load: f32,
+    overloaded: bool,
+    policy: OverloadPolicy,
+    skipped_analyses: u64,
+}
+
+// Wall-clock time per processed sample (filter + buffers + analysis when one ran) over the last
+// second, emitted with processing_load as `processing_latency_ms`; 4 ms is the per-sample budget
+#[derive(Debug, Serialize, Clone)]
+struct ProcessingLatency {
+    average: f32,
+    max: f32,
+    cycles: u64,
+    overloaded: bool,
+    policy: OverloadPolicy,
+}
+
+// How late each 4 ms tick fired against its schedule over the last second, emitted with
+// processing_load as `loop_jitter`. With the burst policy a stall leaves the ticks after it late
+// as well, so a mean that stays high means the machine can't sustain the tick rate.
+#[derive(Debug, Serialize, Clone)]
+struct LoopJitter {
+    mean_ms: f32,
+    max_ms: f32,
+    ticks: u64,
+}
+
+// ERD/ERS: percent change of band power in the activity window after a marker relative to
+// the baseline window before it. Negative = desynchronization (ERD), positive = ERS.
+#[derive(Debug, Clone, Copy)]
+struct ErdWindows {
+    baseline_s: f64,
+    activity_s: f64,
+}
+
+impl Default for ErdWindows {
+    fn default() -> Self {
+        Self { baseline_s: 2.0, activity_s: 2.0 }
+    }
+}
+
+// Baseline powers below this are treated as this, so near-silent channels don't explode the ratio
+const ERD_POWER_FLOOR: f32 = 1e-6;
+
+#[derive(Debug, Clone)]
+struct EventMarker {
+    timestamp: f64,
+    label: Option<String>,
+}
+
+// Synthetic markers for exercising the marker features on a CSV replay without hardware. Each
+// adds an N1/P3-like deflection to every EEG channel of the samples that follow it, so
+// the same file and marker times always give the same data.
+const TEST_ERP_COMPONENTS: [(f64, f64, f32); 2] = [(0.1, 0.025, -5.0), (0.3, 0.06, 10.0)]; // latency s, width s, amplitude
+const TEST_ERP_DURATION_S: f64 = 0.8;
+const MIN_AUTO_MARKER_INTERVAL_MS: u64 = 1000; // keeps consecutive deflections from overlapping
+const MAX_AUTO_MARKER_INTERVAL_MS: u64 = 60_000;
+const AUTO_MARKER_CODE: &str = "auto";
+
+#[derive(Debug, Default)]
+struct TestMarkers {
+    auto_interval_s: Option<f64>,
+    next_auto: Option<f64>,
+    onsets: Vec<f64>, // markers whose deflection hasn't finished
+}
+
+impl TestMarkers {
+    fn deflection(&self, timestamp: f64) -> f32 {
+        self.onsets.iter()
+            .map(|onset| timestamp - onset)
+            .filter(|latency| (0.0..TEST_ERP_DURATION_S).contains(latency))
+            .map(|latency| {
+                TEST_ERP_COMPONENTS.iter()
+                    .map(|&(peak, width, amplitude)| amplitude * (-0.5 * ((latency - peak) / width).powi(2)).exp() as f32)
+                    .sum::<f32>()
+            })
+            .sum()
+    }
+}
+
+// compute_itpc: inter-trial phase coherence over the epochs that follow the markers still in
+// the replay buffer. Per channel and frequency bin, the length of the mean unit phase vector
+// across epochs: 1 = every epoch has the same phase there, ~1/sqrt(epochs) for random phase.
+const DEFAULT_ITPC_EPOCH_MS: u64 = 1000;
+const MIN_ITPC_EPOCH_MS: u64 = 100;
+const MAX_ITPC_EPOCH_MS: u64 = 4000;
+const MIN_ITPC_EPOCHS: usize = 2;
+const MAX_EPOCH_MARKERS: usize = 500; // markers kept for epoching; older ones have left the replay buffer anyway
+
+#[derive(Debug, Serialize, Clone)]
+struct ChannelItpc {
+    channel: usize,
+    itpc: Vec<f32>, // aligned with Itpc::frequencies
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct Itpc {
+    epochs: usize,
+    epoch_ms: u64,
+    skipped_markers: usize, // matching markers whose epoch isn't (fully) in the replay buffer
+    frequencies: Vec<f32>,
+    channels: Vec<ChannelItpc>,
+}
+
+// Spectra of one channel's epochs, all the same length; the result is per bin up to Nyquist.
+// A bin with no power in an epoch has no phase and counts as a zero vector.
+fn inter_trial_coherence(spectra: &[Vec<Complex<f32>>]) -> Vec<f32> {
+    let bins = spectra.first().map_or(0, |spectrum| spectrum.len() / 2 + 1);
+    (0..bins)
+        .map(|bin| {
+            let sum: Complex<f32> = spectra.iter()
+                .map(|spectrum| spectrum[bin])
+                .filter(|value| value.norm() > 0.0)
+                .map(|value| value / value.norm())
+                .sum();
+            sum.norm() / spectra.len() as f32
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct ErdErs {
+    band: String,
+    channel: usize,
+    percent_change: f32,
+    marker_timestamp: f64,
+    label: Option<String>,
+}
+
+// Single 0-100 neurofeedback number from band weights, emitted as `calm_score`. The raw value
+// is the weighted sum of relative band powers (averaged over the set_score_channels channels,
+// weights scaled so their absolute values sum to 1), i.e. roughly -1..1. Without a baseline it maps linearly
+// to 50 ± 50; after calibrate_calm_score it is the z-score against the baseline squashed
+// through tanh, so 50 = baseline level.
+#[derive(Debug, Clone)]
+struct CalmScoreState {
+    weights: HashMap<String, f32>,
+    baseline: Option<(f32, f32)>,                  // mean, standard deviation of the raw score
+    calibration: Option<(f64, Option<f64>, Vec<f32>)>, // duration, start timestamp, raw scores so far
+}
+
+impl Default for CalmScoreState {
+    fn default() -> Self {
+        Self {
+            weights: HashMap::from([
+                ("alpha".to_string(), 1.0),
+                ("theta".to_string(), 0.5),
+                ("beta".to_string(), -1.0),
+            ]),
+            baseline: None,
+            calibration: None,
+        }
+    }
+}
+
+// set_band_normalization: the unit of the published band values (frequency_bands, eeg_frame and
+// the WebSocket frames), per band. Triggers, thresholds checks, scores and logs keep working on
+// the absolute values. Relative is the band's share of the channel's total band power; zscore
+// and db_baseline compare the band power with its calibrate_band_baseline mean (null until a
+// baseline exists), with the baseline floored at ERD_POWER_FLOOR.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum BandNormalization {
+    Absolute,
+    Relative,
+    Zscore,
+    DbBaseline,
+}
+
+impl std::str::FromStr for BandNormalization {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "absolute" => Ok(Self::Absolute),
+            "relative" => Ok(Self::Relative),
+            "zscore" => Ok(Self::Zscore),
+            "db_baseline" => Ok(Self::DbBaseline),
+            other => Err(format!(
+                "❌ Unknown band normalization '{}': expected \"absolute\", \"relative\", \"zscore\" or \"db_baseline\"",
+                other
+            )),
+        }
+    }
+}
+
+// `amplitude` is a band value as computed, `total_power` the channel's summed band power and
+// `baseline` the (mean, standard deviation) of this band's power during calibration
+fn normalize_band_value(mode: BandNormalization, amplitude: f32, total_power: f32, baseline: Option<(f32, f32)>) -> f32 {
+    let power = amplitude * amplitude;
+    match (mode, baseline) {
+        (BandNormalization::Absolute, _) => amplitude,
+        (BandNormalization::Relative, _) => if total_power > 0.0 { power / total_power } else { 0.0 },
+        (BandNormalization::Zscore, Some((mean, sd))) => (power - mean) / sd.max(ERD_POWER_FLOOR),
+        (BandNormalization::DbBaseline, Some((mean, _))) => 10.0 * (power.max(ERD_POWER_FLOOR) / mean.max(ERD_POWER_FLOOR)).log10(),
+        (_, None) => f32::NAN,
+    }
+}
+
+#[derive(Debug, Clone)]
+struct BandNormalizationState {
+    mode: BandNormalization,
+    per_band: HashMap<String, BandNormalization>, // overrides of `mode`
+    baseline: HashMap<(usize, String), (f32, f32)>, // (channel, band) -> mean, sd of the power
+    calibration: Option<BandCalibration>,
+}
+
+#[derive(Debug, Clone)]
+struct BandCalibration {
+    duration_s: f64,
+    start: Option<f64>, // timestamp of the first cycle collected
+    powers: HashMap<(usize, String), Vec<f32>>,
+}
+
+impl Default for BandNormalizationState {
+    fn default() -> Self {
+        Self { mode: BandNormalization::Absolute, per_band: HashMap::new(), baseline: HashMap::new(), calibration: None }
+    }
+}
+
+impl BandNormalizationState {
+    // Feeds a running calibration, then returns the bands in their configured units
+    fn apply(&mut self, timestamp: f64, bands: &[FrequencyBands]) -> Vec<FrequencyBands> {
+        if let Some(calibration) = self.calibration.as_mut() {
+            let start = *calibration.start.get_or_insert(timestamp);
+            for b in bands {
+                for (band, amplitude) in &b.bands {
+                    calibration.powers.entry((b.channel, band.clone())).or_default().push(amplitude * amplitude);
+                }
+            }
+            if timestamp - start >= calibration.duration_s {
+                self.baseline = calibration.powers.iter()
+                    .map(|(key, values)| {
+                        let mean = values.iter().sum::<f32>() / values.len() as f32;
+                        let variance = values.iter().map(|p| (p - mean).powi(2)).sum::<f32>() / values.len() as f32;
+                        (key.clone(), (mean, variance.sqrt()))
+                    })
+                    .collect();
+                println!("📐 [DEBUG] Band baseline calibrated over {:.1} s ({} channel bands)", timestamp - start, self.baseline.len());
+                self.calibration = None;
+            }
+        }
+        
+        if self.mode == BandNormalization::Absolute && self.per_band.values().all(|mode| *mode == BandNormalization::Absolute) {
+            return bands.to_vec();
+        }
+        bands.iter()
+            .map(|b| {
+                let total_power: f32 = b.bands.values().map(|v| v * v).sum();
+                let mut normalized = b.clone();
+                for (band, value) in normalized.bands.iter_mut() {
+                    let mode = self.per_band.get(band).copied().unwrap_or(self.mode);
+                    *value = normalize_band_value(mode, *value, total_power, self.baseline.get(&(b.channel, band.clone())).copied());
+                }
+                normalized
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct CalmScore {
+    timestamp: f64,
+    score: f32,
+    raw: f32,
+    calibrated: bool,
+    calibrating: bool,
+}
+
+// Meditation index, emitted as `meditation_index` each analysis cycle: the weighted
+// (alpha + theta) / beta ratio of the band values, averaged over the set_score_channels channels
+// left after set_gate_bad_channels. After calibrate_meditation_index it is also reported over its baseline
+// mean (1 = baseline level), and the tier of that picks the quote of a timed session; without a
+// baseline quotes are drawn from all of them.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+struct MeditationWeights {
+    alpha: f32,
+    theta: f32,
+    beta: f32,
+}
+
+impl Default for MeditationWeights {
+    fn default() -> Self {
+        Self { alpha: 1.0, theta: 1.0, beta: 1.0 }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct MeditationIndexState {
+    weights: MeditationWeights,
+    baseline: Option<f32>,                             // mean index during calibration
+    calibration: Option<(f64, Option<f64>, Vec<f32>)>, // duration, start timestamp, indices so far
+}
+
+// Normalized index below MEDITATION_STEADY_RATIO is settling, from MEDITATION_DEEP_RATIO on deep
+const MEDITATION_STEADY_RATIO: f32 = 0.9;
+const MEDITATION_DEEP_RATIO: f32 = 1.2;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum MeditationTier {
+    Settling,
+    Steady,
+    Deep,
+}
+
+impl MeditationTier {
+    fn from_normalized(normalized: f32) -> Self {
+        if normalized >= MEDITATION_DEEP_RATIO {
+            Self::Deep
+        } else if normalized >= MEDITATION_STEADY_RATIO {
+            Self::Steady
+        } else {
+            Self::Settling
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct MeditationIndex {
+    timestamp: f64,
+    index: f32,
+    normalized: Option<f32>, // index / baseline mean, once calibrated
+    tier: Option<MeditationTier>,
+    calibrating: bool,
+}
+
+// None unless the channel reports alpha, theta and beta
+fn meditation_index(bands: &FrequencyBands, weights: MeditationWeights) -> Option<f32> {
+    let (alpha, theta, beta) = (bands.get("alpha")?, bands.get("theta")?, bands.get("beta")?);
+    Some((weights.alpha * alpha + weights.theta * theta) / (weights.beta * beta).max(ENGAGEMENT_POWER_FLOOR))
+}
+
+// run_alpha_blocking_test: eyes closed, then eyes open, each for `phase_s`. Each phase begins
+// with an `alpha_blocking_prompt`; the first ALPHA_BLOCKING_SETTLE_S of it are skipped while the
+// user follows the prompt, the rest of its analysis cycles are averaged per channel. Alpha values
+// are band amplitudes, so the reduction is computed on their squares (power).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum AlphaBlockingPhase {
+    EyesClosed,
+    EyesOpen,
+}
+
+#[derive(Debug, Clone)]
+struct AlphaBlockingTest {
+    phase_s: f64,
+    phase: AlphaBlockingPhase,
+    phase_start: Option<f64>, // set by the first analysis cycle of the phase
+    eyes_closed: HashMap<usize, Vec<f32>>, // channel -> alpha power per cycle
+    eyes_open: HashMap<usize, Vec<f32>>,
+}
+
+const DEFAULT_ALPHA_BLOCKING_PHASE_S: f64 = 20.0;
+const ALPHA_BLOCKING_SETTLE_S: f64 = 2.0;
+// Percent drop in mean alpha power that counts as the effect being detected
+const ALPHA_BLOCKING_MIN_REDUCTION_PCT: f32 = 25.0;
+
+#[derive(Debug, Serialize, Clone)]
+struct AlphaBlockingPrompt {
+    phase: AlphaBlockingPhase,
+    duration_s: f64,
+    instruction: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct ChannelAlphaBlocking {
+    channel: usize,
+    eyes_closed: f32,       // mean alpha power
+    eyes_open: f32,
+    percent_reduction: f32, // 100 * (1 - open / closed); negative when alpha rose
+}
+
+// Emitted as `alpha_blocking_result` when the eyes-open phase ends. The overall values are the
+// channel means; None when a phase collected no cycles.
+#[derive(Debug, Serialize, Clone)]
+struct AlphaBlockingResult {
+    timestamp: f64,
+    eyes_closed: Option<f32>,
+    eyes_open: Option<f32>,
+    percent_reduction: Option<f32>,
+    detected: bool, // percent_reduction reached ALPHA_BLOCKING_MIN_REDUCTION_PCT
+    channels: Vec<ChannelAlphaBlocking>,
+}
+
+// Blinks show as a large slow deflection, strongest at the frontopolar electrodes. The detector
+// follows the mean filtered signal of the detection channels (Fp*, else AF*, else the frontal
+// region, else channel 0): a sample beyond BLINK_THRESHOLD_UV starts a blink, its peak is the
+// largest excursion until BLINK_POST_S have passed without a larger one, and the next blink can
+// start BLINK_REFRACTORY_S after that.
+const BLINK_THRESHOLD_UV: f32 = 75.0;
+const BLINK_PRE_S: f64 = 0.2;  // template window before the peak
+const BLINK_POST_S: f64 = 0.3; // and after it
+const BLINK_REFRACTORY_S: f64 = 0.3;
+const MIN_BLINK_CALIBRATION_COUNT: usize = 3;
+const MAX_BLINK_CALIBRATION_COUNT: usize = 30;
+const BLINK_CALIBRATION_S_PER_BLINK: f64 = 3.0; // time allowed per requested blink
+// Largest live template scale applied; bigger deflections are more than a blink
+const MAX_BLINK_SCALE: f32 = 3.0;
+
+#[derive(Debug, Clone)]
+struct BlinkDetector {
+    post_len: usize,
+    refractory_len: usize,
+    peak: Option<(f32, usize)>, // |peak| so far and samples since it
+    cooldown: usize,
+}
+
+impl BlinkDetector {
+    fn new(post_len: usize, refractory_len: usize) -> Self {
+        Self { post_len, refractory_len, peak: None, cooldown: 0 }
+    }
+    
+    // Once post_len samples have followed a blink's peak, Some(post_len): the peak's age
+    fn push(&mut self, value: f32) -> Option<usize> {
+        let magnitude = value.abs();
+        if let Some((peak, age)) = self.peak.as_mut() {
+            if magnitude > *peak {
+                (*peak, *age) = (magnitude, 0);
+            } else {
+                *age += 1;
+            }
+            if *age < self.post_len {
+                return None;
+            }
+            self.peak = None;
+            self.cooldown = self.refractory_len;
+            return Some(self.post_len);
+        }
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+        } else if magnitude > BLINK_THRESHOLD_UV {
+            self.peak = Some((magnitude, 0));
+        }
+        None
+    }
+}
+
+// Frontopolar channels first, as blinks are largest there
+fn blink_channels(names: &[String]) -> Vec<usize> {
+    let with_prefix = |prefix: &str| -> Vec<usize> {
+        names.iter()
+            .enumerate()
+            .filter(|(_, name)| canonical_electrode_label(name).is_some_and(|label| {
+                let lower = label.to_lowercase();
+                lower.starts_with(prefix) && lower[prefix.len()..].starts_with(|c: char| c.is_ascii_digit() || c == 'z')
+            }))
+            .map(|(channel, _)| channel)
+            .collect()
+    };
+    let frontal: Vec<usize> = names.iter()
+        .enumerate()
+        .filter(|(_, name)| electrode_region(name) == Some("frontal"))
+        .map(|(channel, _)| channel)
+        .collect();
+    [with_prefix("fp"), with_prefix("af"), frontal].into_iter()
+        .find(|channels| !channels.is_empty())
+        .unwrap_or_else(|| vec![0])
+}
+
+// Window of every channel around a peak `age` samples back from the newest sample; None while the
+// buffers don't reach back far enough
+fn blink_window(buffers: &[Vec<f32>], age: usize, pre_len: usize) -> Option<Vec<Vec<f32>>> {
+    buffers.iter()
+        .map(|buffer| {
+            let peak = buffer.len().checked_sub(age + 1)?;
+            let start = peak.checked_sub(pre_len)?;
+            Some(buffer[start..=peak + age].to_vec())
+        })
+        .collect()
+}
+
+// Least-squares scale of the template onto the window, over the detection channels
+fn blink_scale(window: &[Vec<f32>], template: &[Vec<f32>], channels: &[usize]) -> Option<f32> {
+    let (mut cross, mut energy) = (0.0f64, 0.0f64);
+    for &channel in channels {
+        for (&x, &t) in window.get(channel)?.iter().zip(template.get(channel)?) {
+            cross += x as f64 * t as f64;
+            energy += t as f64 * t as f64;
+        }
+    }
+    (energy > 0.0).then(|| (cross / energy) as f32)
+}
+
+// calibrate_blinks: the user is asked to blink `target` times; each detected blink's window is
+// kept until there are enough, then the windows are averaged per channel into the template
+#[derive(Debug, Clone)]
+struct BlinkCalibration {
+    target: usize,
+    started: Option<f64>, // timestamp of the first sample after the request
+    windows: Vec<Vec<Vec<f32>>>,
+}
+
+// Emitted as `blink_template` when a calibration ends: per channel the mean filtered signal (µV)
+// from pre_s before to post_s after the blink peak. With set_blink_correction each live blink is
+// fitted to it on the detection channels and the scaled template subtracted from the filtered
+// buffers the band analysis reads. The emitted sample events keep the uncorrected signal, and
+// with set_analysis_source("raw") the correction has no effect.
+#[derive(Debug, Serialize, Clone)]
+struct BlinkTemplate {
+    timestamp: f64,
+    blinks: usize,
+    sample_rate: f64,
+    pre_s: f64,
+    post_s: f64,
+    detection_channels: Vec<usize>,
+    channels: Vec<Vec<f32>>,
+}
+
+// `blink_calibration`: status is "started" (with the instruction), "blink" for each one detected,
+// "done" when the template is built, or "timed_out" when too few blinks came in time
+#[derive(Debug, Serialize, Clone)]
+struct BlinkCalibrationProgress {
+    timestamp: f64,
+    status: String,
+    detected: usize,
+    target: usize,
+    instruction: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct BlinkState {
+    detector: Option<BlinkDetector>, // Some while calibrating or correcting
+    channels: Vec<usize>,            // detection channels
+    pre_len: usize,
+    sample_rate: f64,
+    calibration: Option<BlinkCalibration>,
+    template: Option<BlinkTemplate>,
+    correction: bool,
+    corrected: u64, // live blinks subtracted since correction was enabled
+}
+
+// Emitted as `engagement_index` every analysis cycle: beta / (alpha + theta) of the reported
+// band values, with the denominator floored at ENGAGEMENT_POWER_FLOOR. set_engagement_mode picks
+// the per-channel values or their mean over the EEG channels.
+#[derive(Debug, Serialize, Clone)]
+struct EngagementIndex {
+    timestamp: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channels: Option<Vec<ChannelEngagement>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    average: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct ChannelEngagement {
+    channel: usize,
+    value: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum EngagementMode {
+    PerChannel,
+    Average,
+}
+
+impl std::str::FromStr for EngagementMode {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "per_channel" => Ok(Self::PerChannel),
+            "average" => Ok(Self::Average),
+            other => Err(format!("❌ Unknown engagement mode '{}': expected \"per_channel\" or \"average\"", other)),
+        }
+    }
+}
+
+const ENGAGEMENT_POWER_FLOOR: f32 = 1e-6;
+
+// None unless the channel reports beta, alpha and theta
+fn engagement_index(bands: &FrequencyBands) -> Option<f32> {
+    let (beta, alpha, theta) = (bands.get("beta")?, bands.get("alpha")?, bands.get("theta")?);
+    Some(beta / (alpha + theta).max(ENGAGEMENT_POWER_FLOOR))
+}
+
+// Unit-impulse responses of one channel's filter stages (fresh instances, so the live filter state
+// is untouched) and the window the band FFT multiplies each analysis buffer by
+#[derive(Debug, Serialize, Clone)]
+struct ImpulseResponses {
+    channel: usize,
+    sample_rate: f64,
+    bandpass_ir: Vec<f64>,
+    notch_ir: Option<Vec<f64>>,
+    window: Vec<f32>,
+}
+
+const DEFAULT_IMPULSE_RESPONSE_LEN: usize = 512;
+const MAX_IMPULSE_RESPONSE_LEN: usize = 8192;
+
+// Exact coefficients and design parameters of one channel's filter chain
+#[derive(Debug, Serialize, Clone)]
+struct FilterCoeffs {
+    channel: usize,
+    order: usize,
+    bandpass_b: Vec<f64>,
+    bandpass_a: Vec<f64>,
+    notch_b: Option<Vec<f64>>,
+    notch_a: Option<Vec<f64>>,
+    sample_rate: f64,
+    cutoffs: ChannelFilterConfig,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct SelfTestCheck {
+    name: String,
+    passed: bool,
+    measured_db: f32,    // output / input amplitude of the component
+    requirement: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct SelfTestReport {
+    passed: bool,
+    sample_rate: f64,
+    config: ChannelFilterConfig,
+    checks: Vec<SelfTestCheck>,
+}
+
+// run_end_to_end_test: a temporary LSL outlet plays one tone per channel, a private processor
+// connects to it by name and runs the live pull -> filter -> buffer -> FFT path on what arrives
+const E2E_TONES: [(&str, f64); 3] = [("theta", 6.0), ("alpha", 10.0), ("beta", 20.0)];
+const E2E_AMPLITUDE: f64 = 20.0; // µV
+const E2E_SAMPLE_RATE: f64 = 250.0;
+const E2E_BUFFER_SIZE: usize = 128; // short window so the test finishes quickly
+const E2E_TIMEOUT_S: u64 = 60;      // for filling the window, after connecting
+
+#[derive(Debug, Serialize, Clone)]
+struct E2EChannel {
+    channel: usize,
+    tone_hz: f64,
+    expected_band: String,
+    dominant_band: Option<String>,
+    passed: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct E2EReport {
+    passed: bool,
+    stream_name: String,
+    connected: bool,
+    samples_received: u64,
+    effective_rate: Option<f64>, // from the received timestamps; well below nominal means samples are being missed
+    channels: Vec<E2EChannel>,
+    error: Option<String>,       // the stage that failed, if any
+}
+
+// Rolling raw + filtered history, kept separately from the FFT buffers so it can span much longer
+#[derive(Debug, Clone)]
+struct ReplayFrame {
+    timestamp: f64,
+    raw: Vec<f32>,
+    filtered: Vec<f32>,
+}
+
+// get_dual_snapshot: the newest samples of both domains, channel-major and aligned by index
+// with `timestamps`, so a side-by-side view needs no matching of two event streams
+#[derive(Debug, Serialize, Clone)]
+struct DualSnapshot {
+    raw: Vec<Vec<f32>>,
+    filtered: Vec<Vec<f32>>,
+    timestamps: Vec<f64>,
+}
+
+// get_frame_at: what every metric read at one analysis cycle, kept for the last
+// `capacity` cycles so a scrubbed or hovered point in time can be shown coherently
+#[derive(Debug, Serialize, Clone)]
+struct CombinedFrame {
+    timestamp: f64,
+    raw: Vec<f32>,      // the sample that completed the cycle
+    filtered: Vec<f32>,
+    bands: Vec<FrequencyBands>,
+    calm_score: Option<CalmScore>,
+    engagement: Option<EngagementIndex>,
+    snr: Vec<ChannelSnr>,
+    excluded_channels: Vec<GatedChannel>, // set_gate_bad_channels, as of this cycle
+}
+
+const DEFAULT_FRAME_HISTORY: usize = 300;
+const MAX_FRAME_HISTORY: usize = 10_000;
+
+#[derive(Debug)]
+struct FrameHistory {
+    capacity: usize, // 0 = not kept
+    frames: VecDeque<CombinedFrame>,
+}
+
+impl FrameHistory {
+    fn push(&mut self, frame: CombinedFrame) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.frames.len() >= self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+    }
+    
+    // Frames are in timestamp order, so only the two around `timestamp` can be nearest
+    fn nearest(&self, timestamp: f64) -> Option<&CombinedFrame> {
+        let after = self.frames.partition_point(|frame| frame.timestamp < timestamp);
+        let before = after.checked_sub(1).and_then(|i| self.frames.get(i));
+        match (before, self.frames.get(after)) {
+            (Some(a), Some(b)) => Some(if timestamp - a.timestamp <= b.timestamp - timestamp { a } else { b }),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+// render_spectrogram_png: how power maps onto the colormap
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum SpectrogramScale {
+    Linear,
+    Db,
+}
+
+impl std::str::FromStr for SpectrogramScale {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "linear" => Ok(Self::Linear),
+            "db" => Ok(Self::Db),
+            other => Err(format!("❌ Unknown spectrogram scale '{}': expected \"linear\" or \"db\"", other)),
+        }
+    }
+}
+
+const DEFAULT_SPECTROGRAM_MAX_SIZE: (u32, u32) = (1600, 800);
+const MAX_SPECTROGRAM_SIDE: u32 = 8192;
+// Automatic dB range: from this far below the loudest cell up to it
+const SPECTROGRAM_DB_SPAN: f32 = 60.0;
+// Viridis at evenly spaced stops, interpolated linearly between them
+const SPECTROGRAM_COLORMAP: [[u8; 3]; 5] = [[68, 1, 84], [59, 82, 139], [33, 145, 140], [94, 201, 98], [253, 231, 37]];
+
+fn spectrogram_color(t: f32) -> image::Rgb<u8> {
+    let position = t.clamp(0.0, 1.0) * (SPECTROGRAM_COLORMAP.len() - 1) as f32;
+    let stop = (position as usize).min(SPECTROGRAM_COLORMAP.len() - 2);
+    let frac = position - stop as f32;
+    let (a, b) = (SPECTROGRAM_COLORMAP[stop], SPECTROGRAM_COLORMAP[stop + 1]);
+    image::Rgb([0, 1, 2].map(|i| (a[i] as f32 + (b[i] as f32 - a[i] as f32) * frac).round() as u8))
+}
+
+// Averages consecutive values down to `len` (no-op when already that short)
+fn average_down(values: &[f32], len: usize) -> Vec<f32> {
+    if values.len() <= len {
+        return values.to_vec();
+    }
+    (0..len)
+        .map(|j| {
+            let group = &values[j * values.len() / len..(j + 1) * values.len() / len];
+            group.iter().sum::<f32>() / group.len() as f32
+        })
+        .collect()
+}
+
+// What render_spectrogram_png wrote
+#[derive(Debug, Serialize, Clone)]
+struct SpectrogramImage {
+    path: String,
+    width: u32,
+    height: u32,
+    windows: usize,  // STFT windows covered; several share a column when they exceed the width
+    max_hz: f32,     // frequency of the top row
+    scale: SpectrogramScale,
+    range: (f32, f32), // power (µV² per bin, or dB of it) mapped to the ends of the colormap
+}
+
+#[derive(Debug)]
+struct ReplayBuffer {
+    seconds: f64,
+    capacity: usize,
+    frames: VecDeque<ReplayFrame>,
+}
+
+impl ReplayBuffer {
+    fn new(seconds: f64, sample_rate: f32) -> Self {
+        let capacity = (seconds * sample_rate as f64).ceil() as usize;
+        Self {
+            seconds,
+            capacity,
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+    
+    fn push(&mut self, frame: ReplayFrame) {
+        while self.frames.len() >= self.capacity.max(1) {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+    }
+    
+    fn resize(&mut self, seconds: f64, sample_rate: f32) {
+        self.seconds = seconds;
+        self.capacity = (seconds * sample_rate as f64).ceil() as usize;
+        while self.frames.len() > self.capacity {
+            self.frames.pop_front();
+        }
+    }
+}
+
+// set_max_event_rate: one token bucket shared by every streamed event (the processing loop, the
+// sample forwarder and the fixed-rate emitter), holding one second of budget. Events over the
+// budget are dropped and counted; the counts go out once a second as `events_dropped`. One-off
+// replies to commands (config_reset, stream_warning, ...) are not counted.
+const DEFAULT_MAX_EVENT_RATE: f64 = 1000.0;
+const MIN_MAX_EVENT_RATE: f64 = 10.0;
+const MAX_MAX_EVENT_RATE: f64 = 100_000.0;
+
+#[derive(Debug, Serialize, Clone)]
+struct EventsDropped {
+    dropped: u64,
+    by_event: HashMap<String, u64>,
+    max_event_rate: f64,
+}
+
+#[derive(Debug)]
+struct EventRateLimiter {
+    per_second: Option<f64>, // None = unlimited
+    tokens: f64,
+    refilled: std::time::Instant,
+    dropped: HashMap<String, u64>, // since the last report
+    total_dropped: u64,
+}
+
+impl EventRateLimiter {
+    fn new(per_second: Option<f64>) -> Self {
+        Self {
+            per_second,
+            tokens: per_second.unwrap_or(0.0),
+            refilled: std::time::Instant::now(),
+            dropped: HashMap::new(),
+            total_dropped: 0,
+        }
+    }
+    
+    fn admit(&mut self, event: &str) -> bool {
+        let Some(rate) = self.per_second else { return true };
+        let now = std::time::Instant::now();
+        self.tokens = (self.tokens + now.duration_since(self.refilled).as_secs_f64() * rate).min(rate);
+        self.refilled = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return true;
+        }
+        
+        match self.dropped.get_mut(event) {
+            Some(count) => *count += 1,
+            None => { self.dropped.insert(event.to_string(), 1); }
+        }
+        self.total_dropped += 1;
+        false
+    }
+    
+    fn take_report(&mut self) -> Option<EventsDropped> {
+        if self.dropped.is_empty() {
+            return None;
+        }
+        let by_event = std::mem::take(&mut self.dropped);
+        Some(EventsDropped {
+            dropped: by_event.values().sum(),
+            by_event,
+            max_event_rate: self.per_second.unwrap_or(0.0),
+        })
+    }
+}
+
+fn emit_limited<S: Serialize + Clone>(
+    app_handle: &tauri::AppHandle,
+    limiter: &std::sync::Mutex<EventRateLimiter>,
+    event: &str,
+    payload: S,
+) -> tauri::Result<()> {
+    if !limiter.lock().unwrap_or_else(std::sync::PoisonError::into_inner).admit(event) {
+        return Ok(());
+    }
+    app_handle.emit_all(event, payload)
+}
+
+// set_event_logging: the most recent events the processing loop sent, with a one-line summary
+// instead of the payload, for attaching to bug reports
+#[derive(Debug, Serialize, Clone)]
+struct EventLogEntry {
+    timestamp: String, // wall clock, RFC 3339
+    event: String,
+    summary: String,
+}
+
+#[derive(Debug)]
+struct EventLog {
+    capacity: usize,
+    entries: VecDeque<EventLogEntry>,
+}
+
+const DEFAULT_EVENT_LOG_CAPACITY: usize = 1000;
+const MAX_EVENT_LOG_CAPACITY: usize = 100_000;
+const EVENT_SUMMARY_MAX_CHARS: usize = 120;
+
+impl EventLog {
+    fn record<S: Serialize>(&mut self, event: &str, payload: &S) {
+        let summary = serde_json::to_value(payload).map_or_else(|e| format!("unserializable: {}", e), |v| event_summary(&v));
+        self.entries.push_back(EventLogEntry {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            event: event.to_string(),
+            summary,
+        });
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+    }
+}
+
+// Arrays by length, objects by their scalar fields (nested ones by size), cut at EVENT_SUMMARY_MAX_CHARS
+fn event_summary(payload: &serde_json::Value) -> String {
+    let short = |value: &serde_json::Value| match value {
+        serde_json::Value::Array(items) => format!("[{}]", items.len()),
+        serde_json::Value::Object(fields) => format!("{{{}}}", fields.len()),
+        scalar => scalar.to_string(),
+    };
+    let summary = match payload {
+        serde_json::Value::Array(items) => format!("{} items", items.len()),
+        serde_json::Value::Object(fields) => fields.iter()
+            .map(|(name, value)| format!("{}={}", name, short(value)))
+            .collect::<Vec<_>>()
+            .join(", "),
+        scalar => short(scalar),
+    };
+    match summary.char_indices().nth(EVENT_SUMMARY_MAX_CHARS) {
+        Some((cut, _)) => format!("{}…", &summary[..cut]),
+        None => summary,
+    }
+}
+
+// Open start_band_logging file: one row per channel per analysis cycle. Columns are fixed from
+// the band profile at start; a band missing from a later cycle leaves its field empty
+struct BandLog {
+    path: String,
+    writer: BandLogWriter,
+    bands: Vec<String>,
+    theta_beta: bool, // theta_beta_ratio column, when the profile has both bands
+    rows: usize,
+    last_flush: std::time::Instant,
+}
+
+// Gzip output is compressed as it is written, so memory stays bounded however long the log runs.
+// Appending to an existing .csv.gz adds a gzip member, which gzip -d and MultiGzDecoder read through.
+enum BandLogWriter {
+    Plain(std::io::BufWriter<std::fs::File>),
+    Gzip(flate2::write::GzEncoder<std::io::BufWriter<std::fs::File>>),
+}
+
+impl Write for BandLogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            BandLogWriter::Plain(writer) => writer.write(buf),
+            BandLogWriter::Gzip(writer) => writer.write(buf),
+        }
+    }
+    
+    // A gzip flush ends the current deflate block, so the rows so far can be decompressed
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            BandLogWriter::Plain(writer) => writer.flush(),
+            BandLogWriter::Gzip(writer) => writer.flush(),
+        }
+    }
+}
+
+impl BandLogWriter {
+    // Writes the gzip trailer; without it most tools report the file as truncated
+    fn finish(self) -> std::io::Result<()> {
+        match self {
+            BandLogWriter::Plain(mut writer) => writer.flush(),
+            BandLogWriter::Gzip(writer) => writer.finish()?.flush(),
+        }
+    }
+}
+
+const BAND_LOG_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+impl BandLog {
+    fn write_cycle(&mut self, bands: &[FrequencyBands]) -> std::io::Result<()> {
+        let field = |value: Option<f32>| value.map(|v| v.to_string()).unwrap_or_default();
+        for channel_bands in bands {
+            let mut values: Vec<String> = self.bands.iter().map(|band| field(channel_bands.get(band))).collect();
+            if self.theta_beta {
+                let ratio = match (channel_bands.get("theta"), channel_bands.get("beta")) {
+                    (Some(theta), Some(beta)) if beta > 0.0 => Some(theta / beta),
+                    _ => None,
+                };
+                values.push(field(ratio));
+            }
+            writeln!(self.writer, "{:.6},{},{}", channel_bands.timestamp, channel_bands.channel, values.join(","))?;
+            self.rows += 1;
+        }
+        if self.last_flush.elapsed() >= BAND_LOG_FLUSH_INTERVAL {
+            self.writer.flush()?;
+            self.last_flush = std::time::Instant::now();
+        }
+        Ok(())
+    }
+}
+
+// Keeps every `factor`th sample of a stream. With anti_alias it yields the mean of each block of
+// `factor` samples (and of their timestamps) instead of the block's last sample: a moving-average
+// lowpass with nulls at multiples of the new rate, which keeps most content above the new
+// Nyquist from folding back, at the cost of some attenuation just below it.
+struct Decimator {
+    factor: usize,
+    anti_alias: bool,
+    count: usize,
+    timestamp_sum: f64,
+    sums: Vec<f64>,
+}
+
+impl Decimator {
+    fn new(factor: usize, anti_alias: bool) -> Self {
+        Self { factor: factor.max(1), anti_alias, count: 0, timestamp_sum: 0.0, sums: Vec::new() }
+    }
+    
+    // Some once the block is complete; a change of width starts a new block
+    fn push(&mut self, timestamp: f64, values: &[f32]) -> Option<(f64, Vec<f32>)> {
+        if self.sums.len() != values.len() {
+            self.sums = vec![0.0; values.len()];
+            self.count = 0;
+            self.timestamp_sum = 0.0;
+        }
+        self.count += 1;
+        if self.anti_alias {
+            self.timestamp_sum += timestamp;
+            for (sum, &value) in self.sums.iter_mut().zip(values) {
+                *sum += value as f64;
+            }
+        }
+        if self.count < self.factor {
+            return None;
+        }
+        
+        let n = self.count as f64;
+        let out = if self.anti_alias {
+            (self.timestamp_sum / n, self.sums.iter().map(|sum| (sum / n) as f32).collect())
+        } else {
+            (timestamp, values.to_vec())
+        };
+        self.count = 0;
+        self.timestamp_sum = 0.0;
+        self.sums.fill(0.0);
+        Some(out)
+    }
+}
+
+const MAX_RECORDING_DECIMATION: usize = 100;
+
+// Open start_recording file, in dump_replay_buffer's layout (timestamp, raw_*, filtered_*) so
+// read_recording, compare_sessions and CSV sources take it. A decimation above 1 is lossy for the
+// raw signal: only rate / decimation samples per second are kept, so content above half that rate
+// is gone (and, without anti_alias, aliased onto lower frequencies). Use it for slow signals or
+// band-power work, not for raw EEG review.
+struct SampleRecording {
+    path: String,
+    writer: BandLogWriter,
+    decimator: Decimator,
+    channel_count: usize,
+    rows: usize,
+    last_flush: std::time::Instant,
+}
+
+impl SampleRecording {
+    fn write_sample(&mut self, sample: &EEGSample, filtered: &FilteredEEGSample) -> std::io::Result<()> {
+        if sample.channels.len() != self.channel_count || filtered.channels.len() != self.channel_count {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("channel count changed from {} to {}", self.channel_count, sample.channels.len()),
+            ));
+        }
+        let values: Vec<f32> = sample.channels.iter().chain(filtered.channels.iter()).copied().collect();
+        let Some((timestamp, values)) = self.decimator.push(sample.timestamp, &values) else {
+            return Ok(());
+        };
+        let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        writeln!(self.writer, "{:.6},{}", timestamp, values.join(","))?;
+        self.rows += 1;
+        if self.last_flush.elapsed() >= BAND_LOG_FLUSH_INTERVAL {
+            self.writer.flush()?;
+            self.last_flush = std::time::Instant::now();
+        }
+        Ok(())
+    }
+}
+
+// CSV recordings: an optional block of `# key: value` comment lines (recording_header), a header
+// row, then data rows. Columns are found by name, so columns added by newer recording formats
+// are ignored; rows that don't parse are skipped and counted instead of failing the file.
+// `.gz` files are decompressed on the fly. Spreadsheet exports are read too: a leading BOM,
+// `;` as the delimiter and quoted fields.
+struct CsvLayout {
+    header: Vec<String>,
+    timestamp_column: bool,
+    delimiter: char,
+}
+
+// Skipped rows are logged up to this many per file; the rest are only counted
+const CSV_SKIP_LOG_LIMIT: usize = 10;
+
+impl CsvLayout {
+    fn parse(line: &str) -> Result<Self, String> {
+        let line = strip_bom(line);
+        // Locales with a decimal comma export `;`-separated files
+        let delimiter = if line.contains(';') && !line.contains(',') { ';' } else { ',' };
+        let header: Vec<String> = line.trim().split(delimiter).map(|c| unquote(c).to_string()).collect();
+        let timestamp_column = header.first()
+            .is_some_and(|c| c.eq_ignore_ascii_case("timestamp") || c.eq_ignore_ascii_case("time"));
+        if header.len() <= usize::from(timestamp_column) || header.iter().any(String::is_empty) {
+            return Err(format!("❌ Invalid CSV header '{}': expected comma-separated channel names", line.trim()));
+        }
+        Ok(Self { header, timestamp_column, delimiter })
+    }
+    
+    // The columns named `prefix`* (one half of a dump_replay_buffer file), with the prefix
+    // dropped from the names; every column after the timestamp when none is
+    fn channels(&self, prefix: &str) -> (Vec<usize>, Vec<String>) {
+        let prefixed: Vec<usize> = (0..self.header.len()).filter(|&i| self.header[i].starts_with(prefix)).collect();
+        let columns: Vec<usize> = if prefixed.is_empty() {
+            (usize::from(self.timestamp_column)..self.header.len()).collect()
+        } else {
+            prefixed
+        };
+        let names = columns.iter()
+            .map(|&i| self.header[i].strip_prefix(prefix).unwrap_or(&self.header[i]).to_string())
+            .collect();
+        (columns, names)
+    }
+    
+    // The row's timestamp (when the file has them) and the values of `columns`. Fields past the
+    // header are ignored; a short row or a value that isn't a number is an error.
+    fn parse_row(&self, row: &str, columns: &[usize]) -> Result<(Option<f64>, Vec<f32>), String> {
+        let fields: Vec<&str> = row.split(self.delimiter).map(unquote).collect();
+        if fields.len() < self.header.len() {
+            return Err(format!("expected {} fields, found {}", self.header.len(), fields.len()));
+        }
+        let timestamp = if self.timestamp_column {
+            Some(fields[0].parse::<f64>().map_err(|_| format!("invalid timestamp '{}'", fields[0]))?)
+        } else {
+            None
+        };
+        let values = columns.iter()
+            .map(|&column| fields[column].parse::<f32>().map_err(|_| format!("invalid value '{}'", fields[column])))
+            .collect::<Result<Vec<f32>, String>>()?;
+        Ok((timestamp, values))
+    }
+}
+
+fn strip_bom(line: &str) -> &str {
+    line.strip_prefix('\u{feff}').unwrap_or(line)
+}
+
+fn unquote(field: &str) -> &str {
+    let field = field.trim();
+    field.strip_prefix('"').and_then(|f| f.strip_suffix('"')).unwrap_or(field).trim()
+}
+
+// `# key: value`; other comment lines carry no metadata
+fn parse_metadata_line(line: &str) -> Option<(String, String)> {
+    let (key, value) = line.strip_prefix('#')?.split_once(':')?;
+    let key = key.trim();
+    (!key.is_empty()).then(|| (key.to_string(), value.trim().to_string()))
+}
+
+fn open_recording(path: &str) -> std::io::Result<Box<dyn std::io::BufRead + Send>> {
+    let file = std::fs::File::open(path)?;
+    Ok(if path.ends_with(".gz") {
+        Box::new(std::io::BufReader::new(flate2::read::MultiGzDecoder::new(file)))
+    } else {
+        Box::new(std::io::BufReader::new(file))
+    })
+}
+
+// Channels of a recording for compare_sessions: the filtered_* columns of a dump_replay_buffer
+// file, or every column after an optional leading timestamp in a plain CSV
+struct Recording {
+    names: Vec<String>,
+    channels: Vec<Vec<f32>>,
+    rate: Option<f32>, // implied by the timestamps (median step), if the file has them
+    metadata: Vec<(String, String)>, // the `# key: value` lines before the header
+    skipped_rows: usize,
+}
+
+impl Recording {
+    // The rate the recording app ran at, from the config line of the recording_header block
+    // (divided by a start_recording decimation); the fallback when the file has no timestamps
+    fn header_rate(&self) -> Option<f32> {
+        let (_, config) = self.metadata.iter().find(|(key, _)| key == "config")?;
+        let config: serde_json::Value = serde_json::from_str(config).ok()?;
+        let decimation = self.metadata.iter()
+            .find(|(key, _)| key == "decimation")
+            .and_then(|(_, value)| value.parse::<f32>().ok())
+            .filter(|factor| *factor >= 1.0)
+            .unwrap_or(1.0);
+        config.get("filter_rate")?.as_f64().map(|rate| rate as f32 / decimation)
+    }
+}
+
+fn read_recording(path: &str) -> Result<Recording, String> {
+    use std::io::BufRead;
+    
+    let reader = open_recording(path).map_err(|e| format!("❌ Failed to read recording '{}': {}", path, e))?;
+    let mut metadata = Vec::new();
+    let mut layout: Option<(CsvLayout, Vec<usize>, Vec<String>)> = None;
+    let mut channels: Vec<Vec<f32>> = Vec::new();
+    let mut timestamps = Vec::new();
+    let mut skipped_rows = 0;
+    for (index, line) in reader.lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) if layout.is_none() => return Err(format!("❌ Failed to read recording '{}': {}", path, e)),
+            // A truncated or damaged tail (e.g. an unfinished gzip member) keeps what came before it
+            Err(e) => {
+                println!("⚠️ [DEBUG] Recording '{}' unreadable from line {} on, stopping there: {}", path, index + 1, e);
+                break;
+            }
+        };
+        let row = strip_bom(&line).trim();
+        if row.is_empty() {
+            continue;
+        }
+        if row.starts_with('#') {
+            if layout.is_none() {
+                metadata.extend(parse_metadata_line(row));
+            }
+            continue;
+        }
+        let Some((csv, columns, _)) = layout.as_ref() else {
+            let csv = CsvLayout::parse(row).map_err(|e| format!("{} in recording '{}'", e, path))?;
+            let (columns, names) = csv.channels("filtered_");
+            channels = vec![Vec::new(); columns.len()];
+            layout = Some((csv, columns, names));
+            continue;
+        };
+        
+        match csv.parse_row(row, columns) {
+            Ok((timestamp, values)) => {
+                timestamps.extend(timestamp);
+                for (channel, value) in channels.iter_mut().zip(values) {
+                    channel.push(value);
+                }
+            }
+            Err(e) => {
+                skipped_rows += 1;
+                if skipped_rows <= CSV_SKIP_LOG_LIMIT {
+                    println!("⚠️ [DEBUG] Recording '{}' line {} skipped: {}", path, index + 1, e);
+                }
+            }
+        }
+    }
+    let (_, _, names) = layout.ok_or_else(|| format!("❌ Recording '{}' is empty", path))?;
+    if skipped_rows > 0 {
+        println!("⚠️ [DEBUG] Recording '{}': {} malformed rows skipped", path, skipped_rows);
+    }
+    
+    let mut steps: Vec<f64> = timestamps.windows(2).map(|w| w[1] - w[0]).filter(|step| *step > 0.0).collect();
+    steps.sort_by(f64::total_cmp);
+    let rate = steps.get(steps.len() / 2).map(|step| (1.0 / step) as f32);
+    Ok(Recording { names, channels, rate, metadata, skipped_rows })
+}
+
+// Thread-safe LSL connection
+#[derive(Debug, Clone)]
+struct LSLConnection {
+    stream_info: Option<LSLStreamInfo>,
+    channel_count: usize,
+    is_real_connection: bool,
+    stream_name: Option<String>,
+    channel_order: Option<Vec<usize>>, // channel_order[i] = device channel shown at position i
+    next_sample_index: u64,
+    eeg_channel_count: Option<usize>, // only the first k channels are EEG; the rest pass through
+    irregular_rate: bool,              // stream advertises nominal_srate() == 0
+    recent_timestamps: VecDeque<f64>,  // arrival stamps used to estimate the effective rate
+    string_channels: bool,             // cf_string stream: pulled as text, never filtered
+    last_sample: Option<EEGSample>,    // previous pulled sample, the left end of a gap interpolation
+    pending_samples: VecDeque<EEGSample>, // filled gap + the sample after it, handed out one per call
+    csv_source: bool,                  // samples come from connect_to_csv_stream, not LSL
+    reconnect: ReconnectBackoff,       // paces re-resolves while the stream is missing
+    timestamp_stats: TimestampStats,
+    host: Option<String>,              // connect_by_address: resolve only this machine's streams
+    rate_window: Option<(f64, usize)>, // start timestamp and samples of the current report window
+    smoothed_rate: Option<f64>,
+    rate_report_due: bool,             // a window closed since the loop last emitted the rate
+    overflow_warned: bool,             // a too-wide sample was already reported for this connection
+}
+
+impl LSLConnection {
+    // source_id of the connected LSL stream, when it declared one
+    fn pinned_source(&self) -> Option<String> {
+        if self.csv_source {
+            return None;
+        }
+        self.stream_info.as_ref().map(|info| info.source_id.clone()).filter(|id| !id.is_empty())
+    }
+    
+    fn new() -> Self {
+        Self {
+            stream_info: None,
+            channel_count: 8,
+            is_real_connection: false,
+            stream_name: None,
+            channel_order: None,
+            next_sample_index: 0,
+            eeg_channel_count: None,
+            irregular_rate: false,
+            recent_timestamps: VecDeque::new(),
+            string_channels: false,
+            last_sample: None,
+            pending_samples: VecDeque::new(),
+            csv_source: false,
+            reconnect: ReconnectBackoff::default(),
+            timestamp_stats: TimestampStats::default(),
+            host: None,
+            rate_window: None,
+            smoothed_rate: None,
+            rate_report_due: false,
+            overflow_warned: false,
+        }
+    }
+    
+    fn reset_rate_report(&mut self) {
+        self.rate_window = None;
+        self.smoothed_rate = None;
+        self.rate_report_due = false;
+    }
+    
+    fn update_rate_report(&mut self, timestamp: f64, alpha: f64) {
+        let (start, count) = self.rate_window.get_or_insert((timestamp, 0));
+        *count += 1;
+        let elapsed = timestamp - *start;
+        if elapsed < RATE_REPORT_WINDOW_S {
+            return;
+        }
+        // The closing sample starts the next window, so it isn't counted in this one
+        let rate = (*count - 1) as f64 / elapsed;
+        self.rate_window = Some((timestamp, 1));
+        self.smoothed_rate = Some(match self.smoothed_rate {
+            Some(previous) => alpha * rate + (1.0 - alpha) * previous,
+            None => rate,
+        });
+        self.rate_report_due = true;
+    }
+}
+
+// The sample pulls resolve the stream on every tick; once it stops showing up, further
+// resolves wait out an exponentially growing, jittered delay instead of hammering the network.
+// The first resolve that finds it again resets the backoff.
+#[derive(Debug, Clone, Default)]
+struct ReconnectBackoff {
+    failures: u32,
+    next_attempt: Option<std::time::Instant>,
+}
+
+const RECONNECT_BASE_DELAY_S: f32 = 0.25;
+const DEFAULT_MAX_RECONNECT_INTERVAL_S: f32 = 30.0;
+const MAX_RECONNECT_INTERVAL_S: f32 = 300.0;
+
+impl ReconnectBackoff {
+    fn due(&self) -> bool {
+        match self.next_attempt {
+            Some(at) => std::time::Instant::now() >= at,
+            None => true,
+        }
+    }
+    
+    // Schedules the next attempt and returns its delay in seconds. The delay is drawn from the
+    // upper half of the doubled window, so several apps that lost the same stream spread out.
+    fn failed(&mut self, max_interval_s: f32) -> f32 {
+        let window = (RECONNECT_BASE_DELAY_S * 2f32.powi(self.failures.min(16) as i32)).min(max_interval_s);
+        let delay = with_rng(|rng| rng.gen_range(window / 2.0..=window));
+        self.failures += 1;
+        self.next_attempt = Some(std::time::Instant::now() + Duration::from_secs_f32(delay));
+        delay
+    }
+    
+    // Returns how many attempts had failed before this success
+    fn succeeded(&mut self) -> u32 {
+        self.next_attempt = None;
+        std::mem::take(&mut self.failures)
+    }
+}
+
+// LSL reports nominal_srate() == 0 for streams without a fixed sampling rate
+const IRREGULAR_RATE: f64 = 0.0;
+fn valid_rate(rate: f32) -> bool {
+    rate.is_finite() && rate > 0.0
+}
+
+// How long one pull_sample waits for data. Short timeouts keep latency low but make the 4 ms
+// loop poll the inlet almost continuously; longer ones hold a blocking-pool thread (and the
+// processor lock) for up to that long per tick, which lowers CPU use but delays everything
+// else in the loop, so keep it well under the 250 ms analysis cadence.
+const DEFAULT_PULL_TIMEOUT_S: f64 = 0.001;
+const MAX_PULL_TIMEOUT_S: f64 = 0.1;
+
+// What every pull re-resolves against: connect's stream name and match rule, plus the source_id
+// of the stream connect picked. A device that comes back under a new source_id is only taken
+// with set_reconnect_to_any_match; otherwise the pull waits for the original source.
+#[derive(Debug, Clone)]
+struct StreamTarget {
+    name: String,
+    match_field: MatchField,
+    max_streams: usize,
+    source_id: Option<String>, // None when the connected stream declared none: any match is taken
+    any_match: bool,
+}
+
+impl StreamTarget {
+    fn select(&self, streams: &[StreamInfo]) -> Option<StreamInfo> {
+        let streams = &streams[..streams.len().min(self.max_streams)];
+        if let Some(source_id) = self.source_id.as_deref() {
+            let same_source: Vec<StreamInfo> = streams.iter().filter(|s| s.source_id() == source_id).cloned().collect();
+            if let Some(stream) = EEGProcessor::select_stream(&same_source, &self.name, self.match_field, usize::MAX) {
+                return Some(stream.clone());
+            }
+            if !self.any_match {
+                return None;
+            }
+        }
+        EEGProcessor::select_stream(streams, &self.name, self.match_field, usize::MAX).cloned()
+    }
+    
+    // The selected stream's (source_id, stream name) when it isn't the pinned source
+    fn switched(&self, stream: &StreamInfo) -> Option<(String, String)> {
+        let source_id = stream.source_id();
+        self.source_id.as_ref()
+            .filter(|pinned| **pinned != source_id)
+            .map(|_| (source_id, stream.stream_name()))
+    }
+}
+
+// set_pull_mode. Poll (the default) resolves the stream and opens an inlet on every tick, then
+// waits up to pull_timeout for one sample: nothing is queued between the device and the loop, at
+// the cost of a resolve per tick and a busy blocking pool. Blocking keeps one inlet open in a
+// dedicated thread that sleeps inside pull_sample until data arrives and queues the samples for
+// the loop; CPU use drops, but samples can wait in the queue for up to a tick (more after a
+// stall, when the queue is drained one sample per tick).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum PullMode {
+    Poll,
+    Blocking,
+}
+
+impl std::str::FromStr for PullMode {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "poll" => Ok(Self::Poll),
+            "blocking" => Ok(Self::Blocking),
+            other => Err(format!("❌ Unknown pull mode '{}': expected \"poll\" or \"blocking\"", other)),
+        }
+    }
+}
+
+// From the blocking-mode inlet thread to the processing loop, in the order they happened
+enum InletMessage {
+    Resolved(bool), // whether the stream was found, for the reconnect status
+    Switched(String, String), // reconnected to another source: source_id, stream name
+    Sample(EEGSample),
+}
+
+// Longest single wait inside pull_sample, so the thread notices a dropped receiver
+const BLOCKING_PULL_TIMEOUT_S: f64 = 0.5;
+// Pause before resolving again when the stream is missing or was lost
+const INLET_WORKER_RETRY_S: f64 = 1.0;
+
+// Redesign the filters once the measured rate is this far (relative) from their design rate;
+// well above the jitter of a one-window estimate so bursty transports don't cause churn
+const FILTER_REDESIGN_THRESHOLD: f64 = 0.05;
+
+#[derive(Debug, Serialize, Clone)]
+struct FilterRedesign {
+    previous_rate: f64,
+    new_rate: f64,
+}
+
+// set_adaptive_notch state, also emitted as `adaptive_notch` on every retune. Each analysis cycle
+// the strongest narrowband peak within ADAPTIVE_NOTCH_RANGE_HZ of the configured notch, in the raw
+// spectrum summed over EEG channels, is located to a fraction of a bin and smoothed into `tracked`;
+// the notches move once it is ADAPTIVE_NOTCH_STEP_HZ away from where they sit.
+#[derive(Debug, Serialize, Clone)]
+struct AdaptiveNotch {
+    nominal: f32,   // the configured notch frequency
+    tracked: f32,   // smoothed estimate of the mains frequency
+    frequency: f32, // where the notches currently sit
+}
+
+const ADAPTIVE_NOTCH_RANGE_HZ: f32 = 2.0;
+const ADAPTIVE_NOTCH_STEP_HZ: f32 = 0.05;
+const ADAPTIVE_NOTCH_SMOOTHING: f32 = 0.3; // EMA weight of each new estimate
+// A peak counts as mains only this far above the median of the search range; else `tracked` holds
+const ADAPTIVE_NOTCH_PROMINENCE: f32 = 10.0;
+
+impl AdaptiveNotch {
+    fn new(nominal: f32) -> Self {
+        Self { nominal, tracked: nominal, frequency: nominal }
+    }
+    
+    // Folds in one estimate; true when the notches should move to `frequency`
+    fn update(&mut self, estimate: Option<f32>) -> bool {
+        if let Some(estimate) = estimate {
+            self.tracked += ADAPTIVE_NOTCH_SMOOTHING * (estimate - self.tracked);
+        }
+        if (self.tracked - self.frequency).abs() < ADAPTIVE_NOTCH_STEP_HZ {
+            return false;
+        }
+        self.frequency = self.tracked;
+        true
+    }
+}
+
+// Peak of a one-sided power spectrum within ±ADAPTIVE_NOTCH_RANGE_HZ of `nominal`, refined by
+// Gaussian interpolation over its neighbours (near exact for a Hann-windowed tone)
+fn estimate_mains_peak(power: &[f32], freq_resolution: f32, nominal: f32) -> Option<f32> {
+    let first = ((nominal - ADAPTIVE_NOTCH_RANGE_HZ) / freq_resolution).ceil().max(1.0) as usize;
+    let last = (((nominal + ADAPTIVE_NOTCH_RANGE_HZ) / freq_resolution).floor() as usize).min(power.len().checked_sub(2)?);
+    if last < first + 2 {
+        return None;
+    }
+    
+    let range = &power[first..=last];
+    let (offset, &peak) = range.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1))?;
+    let mut sorted = range.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    if peak <= ADAPTIVE_NOTCH_PROMINENCE * sorted[sorted.len() / 2] {
+        return None;
+    }
+    
+    let i = first + offset;
+    let ln = |p: f32| p.max(APERIODIC_POWER_FLOOR).ln();
+    let (left, centre, right) = (ln(power[i - 1]), ln(power[i]), ln(power[i + 1]));
+    let curvature = left - 2.0 * centre + right;
+    let delta = if curvature < 0.0 { (0.5 * (left - right) / curvature).clamp(-0.5, 0.5) } else { 0.0 };
+    Some((i as f32 + delta) * freq_resolution)
+}
+
+// Longer dropouts are real artifacts; a straight line over them would only hide the problem
+const MAX_GAP_INTERPOLATION: usize = 25;
+
+// Rows buffered between the CSV reader thread and the processing loop (~4 s at 250 Hz)
+const CSV_CHANNEL_CAPACITY: usize = 1024;
+const CSV_HEADER_TIMEOUT_S: f64 = 15.0;
+
+// Plenty for a lab; beyond this, connect would mostly spend its time enumerating other people's streams
+const DEFAULT_MAX_STREAMS: usize = 64;
+
+// Timestamps kept for the effective-rate estimate of irregular streams
+const RATE_ESTIMATE_WINDOW: usize = 256;
+
+// The reported effective rate counts samples over windows of this many seconds of stream time,
+// then smooths them (EMA weight of the newest window) so Bluetooth bursts don't swing the number
+const RATE_REPORT_WINDOW_S: f64 = 1.0;
+const DEFAULT_RATE_SMOOTHING: f64 = 0.3;
+
+// Pick items so that position i holds items[positions[i]]
+fn reorder<T: Clone>(items: &[T], positions: &[usize]) -> Vec<T> {
+    positions.iter().map(|&p| items[p].clone()).collect()
+}
+
+// Two-window band analysis: bands ending at or below MULTIRES_SPLIT_HZ (classic profile: delta,
+// theta, alpha) come from the long window for finer frequency resolution, the rest (beta, gamma)
+// from the short one, which reacts faster. Both are scaled to the standard buffer_size window so
+// values stay comparable with single-resolution output.
+const MULTIRES_SPLIT_HZ: f32 = 13.0;
+
+#[derive(Clone)]
+struct MultiResolution {
+    short_len: usize,
+    long_len: usize,
+    short_plan: Arc<dyn RealToComplex<f32>>,
+    long_plan: Arc<dyn RealToComplex<f32>>,
+}
+
+impl MultiResolution {
+    fn new(short_len: usize, long_len: usize) -> Result<Self, String> {
+        if !(16..=8192).contains(&short_len) || !(16..=8192).contains(&long_len) || short_len >= long_len {
+            return Err(format!(
+                "❌ Invalid multi-resolution windows {}/{}: need 16 <= short_len < long_len <= 8192",
+                short_len, long_len
+            ));
+        }
+        
+        let mut planner = RealFftPlanner::new();
+        Ok(Self {
+            short_len,
+            long_len,
+            short_plan: planner.plan_fft_forward(short_len),
+            long_plan: planner.plan_fft_forward(long_len),
+        })
+    }
+    
+    fn merge(
+        profile: &BandProfile,
+        long: &HashMap<String, f32>,
+        short: &HashMap<String, f32>,
+        long_scale: f32,
+        short_scale: f32,
+    ) -> HashMap<String, f32> {
+        profile.bands.iter()
+            .map(|band| {
+                let value = if band.high <= MULTIRES_SPLIT_HZ {
+                    long.get(&band.name).copied().unwrap_or(0.0) * long_scale
+                } else {
+                    short.get(&band.name).copied().unwrap_or(0.0) * short_scale
+                };
+                (band.name.clone(), value)
+            })
+            .collect()
+    }
+}
+
+// Electrode labels of the 10-20 system and its 10-10 extension, plus ear/mastoid references
+const ELECTRODE_LABELS: [&str; 91] = [
+    "Nz", "Fp1", "Fpz", "Fp2", "AF9", "AF7", "AF5", "AF3", "AF1", "AFz", "AF2", "AF4", "AF6", "AF8", "AF10",
+    "F9", "F7", "F5", "F3", "F1", "Fz", "F2", "F4", "F6", "F8", "F10",
+    "FT9", "FT7", "FC5", "FC3", "FC1", "FCz", "FC2", "FC4", "FC6", "FT8", "FT10",
+    "T9", "T7", "C5", "C3", "C1", "Cz", "C2", "C4", "C6", "T8", "T10",
+    "TP9", "TP7", "CP5", "CP3", "CP1", "CPz", "CP2", "CP4", "CP6", "TP8", "TP10",
+    "P9", "P7", "P5", "P3", "P1", "Pz", "P2", "P4", "P6", "P8", "P10",
+    "PO9", "PO7", "PO5", "PO3", "PO1", "POz", "PO2", "PO4", "PO6", "PO8", "PO10",
+    "O1", "Oz", "O2", "O9", "O10", "Iz", "A1", "A2", "M1", "M2",
+];
+
+// Old 10-20 names that were renamed in 10-10; still seen on many caps
+const LEGACY_ELECTRODE_LABELS: [(&str, &str); 4] = [("T3", "T7"), ("T4", "T8"), ("T5", "P7"), ("T6", "P8")];
+
+// Label of channels past a known device's montage, e.g. ones added by newer firmware. The first
+// such channel ends the EEG channels on connect, so they stay out of filtering and analysis.
+fn aux_label(n: usize) -> String {
+    format!("AUX{}", n)
+}
+
+fn first_aux_channel(names: &[String]) -> Option<usize> {
+    names.iter().position(|name| {
+        name.strip_prefix("AUX").is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+    })
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct MontageIssue {
+    index: usize,
+    name: String,
+    kind: String,               // "unknown", "ambiguous" or "duplicate"
+    suggestion: Option<String>,
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+// Unknown labels get the closest valid label as a suggestion (if within 2 edits); labels that
+// only match with different case or use legacy 10-20 names are reported as ambiguous
+fn check_montage(names: &[String]) -> Vec<MontageIssue> {
+    let mut issues = Vec::new();
+    let mut seen: Vec<String> = Vec::new();
+    
+    for (index, name) in names.iter().enumerate() {
+        let trimmed = name.trim();
+        let mut issue = |kind: &str, suggestion: Option<&str>| issues.push(MontageIssue {
+            index,
+            name: name.clone(),
+            kind: kind.to_string(),
+            suggestion: suggestion.map(str::to_string),
+        });
+        
+        if seen.iter().any(|s| s.eq_ignore_ascii_case(trimmed)) {
+            issue("duplicate", None);
+        } else if ELECTRODE_LABELS.contains(&trimmed) {
+            // valid as written
+        } else if let Some(label) = ELECTRODE_LABELS.iter().find(|l| l.eq_ignore_ascii_case(trimmed)) {
+            issue("ambiguous", Some(label));
+        } else if let Some((_, modern)) = LEGACY_ELECTRODE_LABELS.iter().find(|(old, _)| old.eq_ignore_ascii_case(trimmed)) {
+            issue("ambiguous", Some(modern));
+        } else {
+            let lowered = trimmed.to_lowercase();
+            let closest = ELECTRODE_LABELS.iter()
+                .map(|label| (edit_distance(&lowered, &label.to_lowercase()), *label))
+                .min_by_key(|(distance, _)| *distance)
+                .filter(|(distance, _)| *distance <= 2)
+                .map(|(_, label)| label);
+            issue("unknown", closest);
+        }
+        seen.push(trimmed.to_string());
+    }
+    
+    issues
+}
+
+// The ELECTRODE_LABELS spelling of a channel name: any case, legacy T3-T6 mapped to T7/T8/P7/P8
+fn canonical_electrode_label(name: &str) -> Option<&'static str> {
+    let trimmed = name.trim();
+    let modern = LEGACY_ELECTRODE_LABELS.iter()
+        .find(|(old, _)| old.eq_ignore_ascii_case(trimmed))
+        .map_or(trimmed, |(_, modern)| *modern);
+    ELECTRODE_LABELS.iter().find(|l| l.eq_ignore_ascii_case(modern)).copied()
+}
+
+// Right-hemisphere counterpart of a left (odd-numbered) label, e.g. F3 -> F4, Fp1 -> Fp2
+fn mirror_electrode_label(label: &str) -> Option<&'static str> {
+    let split = label.find(|c: char| c.is_ascii_digit())?;
+    let number: u32 = label[split..].parse().ok()?;
+    if number % 2 != 1 {
+        return None;
+    }
+    canonical_electrode_label(&format!("{}{}", &label[..split], number + 1))
+}
+
+// Scalp position of a 10-10 label (legacy T3-T6 accepted, any case) on the unit head:
+// azimuthal equidistant projection with Cz at the origin, x towards the right ear and y towards
+// the nose. The Nz-T9-Iz ring has radius 1, the Fpz-T7-Oz ring (the usual head outline) 0.8.
+// Intermediate positions are spaced evenly between the midline and that ring, and the
+// ear/mastoid references sit just outside it, so the layout is schematic, not digitized.
+fn electrode_position(label: &str) -> Option<(f32, f32)> {
+    let label = canonical_electrode_label(label)?;
+    
+    // theta: degrees from Cz; azimuth: degrees clockwise from the nose
+    let polar = |theta: f32, azimuth: f32| {
+        let (sin, cos) = azimuth.to_radians().sin_cos();
+        (theta / 90.0 * sin, theta / 90.0 * cos)
+    };
+    
+    let lower = label.to_lowercase();
+    match lower.as_str() {
+        "nz" => return Some(polar(90.0, 0.0)),
+        "iz" => return Some(polar(90.0, 180.0)),
+        "a1" => return Some(polar(100.0, -90.0)),
+        "a2" => return Some(polar(100.0, 90.0)),
+        "m1" => return Some(polar(100.0, -115.0)),
+        "m2" => return Some(polar(100.0, 115.0)),
+        _ => {}
+    }
+    
+    let split = lower.find(|c: char| c.is_ascii_digit() || c == 'z')?;
+    let (prefix, suffix) = lower.split_at(split);
+    // Each row: midline electrode (theta, front or back) and the azimuth where it meets the rings
+    let (midline_theta, ring_azimuth) = match prefix {
+        "fp" => (72.0, 18.0),
+        "af" => (54.0, 36.0),
+        "f" => (36.0, 54.0),
+        "fc" | "ft" => (18.0, 72.0),
+        "c" | "t" => (0.0, 90.0),
+        "cp" | "tp" => (-18.0, 108.0),
+        "p" => (-36.0, 126.0),
+        "po" => (-54.0, 144.0),
+        "o" => (-72.0, 162.0),
+        _ => return None,
+    };
+    let midline = (0.0, midline_theta / 90.0);
+    if suffix == "z" {
+        return Some(midline);
+    }
+    
+    let number: u32 = suffix.parse().ok()?;
+    let side = if number % 2 == 1 { -1.0 } else { 1.0 };
+    // Steps out from the midline: 1/2 -> 1 ... 7/8 -> 4 (Fpz-T7-Oz ring), 9/10 -> 5 (Nz-T9-Iz ring).
+    // Fp1/Fp2 and O1/O2 already sit on the 0.8 ring.
+    let mut step = number.div_ceil(2);
+    if matches!(prefix, "fp" | "o") && step == 1 {
+        step = 4;
+    }
+    let (x, y) = match step {
+        5 => polar(90.0, ring_azimuth),
+        _ => {
+            let ring = polar(72.0, ring_azimuth);
+            let t = step as f32 / 4.0;
+            (ring.0 * t, midline.1 + (ring.1 - midline.1) * t)
+        }
+    };
+    Some((x * side, y))
+}
+
+// One channel of get_topomap_layout; x/y follow electrode_position and are None for labels
+// outside the 10-10 system (e.g. "Ch1", "AUX")
+#[derive(Debug, Serialize, Clone)]
+struct TopoPoint {
+    name: String,
+    x: Option<f32>,
+    y: Option<f32>,
+    is_eeg: bool, // false past set_eeg_channel_count
+}
+
+// Emitted as `hemisphere_difference` each analysis cycle for every symmetric electrode pair in
+// the montage. Per band: ln(right power) - ln(left power), the usual asymmetry index
+// (0 = symmetric, positive = more power on the right; frontal alpha asymmetry is the F4/F3 alpha value).
+#[derive(Debug, Serialize, Clone)]
+struct HemispherePair {
+    left: String,
+    right: String,
+    left_channel: usize,
+    right_channel: usize,
+    #[serde(flatten)]
+    bands: HashMap<String, f32>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct HemisphereDifference {
+    timestamp: f64,
+    pairs: Vec<HemispherePair>,
+}
+
+// set_channel_groups: named sets of channels whose band powers are averaged each analysis
+// cycle and emitted as `regional_bands`. Without custom groups, channels with 10-10 labels are
+// grouped by scalp region; streams without such labels get no default groups.
+const MAX_CHANNEL_GROUPS: usize = 32;
+
+#[derive(Debug, Serialize, Clone)]
+struct ChannelGroup {
+    name: String,
+    channels: Vec<usize>,
+}
+
+// Region of a 10-10 label by its row; references (A1/A2, M1/M2) and Nz/Iz belong to none
+fn electrode_region(label: &str) -> Option<&'static str> {
+    let lower = canonical_electrode_label(label)?.to_lowercase();
+    let split = lower.find(|c: char| c.is_ascii_digit() || c == 'z')?;
+    match &lower[..split] {
+        "fp" | "af" | "f" => Some("frontal"),
+        "fc" | "c" => Some("central"),
+        "ft" | "t" | "tp" => Some("temporal"),
+        "cp" | "p" => Some("parietal"),
+        "po" | "o" => Some("occipital"),
+        _ => None,
+    }
+}
+
+// Default channels of calm_score and meditation_index: the parietal and occipital ones, where
+// relaxed alpha is strongest; empty when the montage names none
+fn posterior_channels(names: &[String]) -> Vec<usize> {
+    names.iter()
+        .enumerate()
+        .filter(|(_, name)| matches!(electrode_region(name), Some("parietal" | "occipital")))
+        .map(|(channel, _)| channel)
+        .collect()
+}
+
+fn default_channel_groups(names: &[String]) -> Vec<ChannelGroup> {
+    ["frontal", "central", "temporal", "parietal", "occipital"].iter()
+        .map(|&region| ChannelGroup {
+            name: region.to_string(),
+            channels: names.iter()
+                .enumerate()
+                .filter(|(_, name)| electrode_region(name) == Some(region))
+                .map(|(channel, _)| channel)
+                .collect(),
+        })
+        .filter(|group| !group.channels.is_empty())
+        .collect()
+}
+
+// Per band, the mean over the group's channels that have that band this cycle. Groups with no
+// analyzed channel are left out.
+fn regional_band_means(groups: &[ChannelGroup], bands: &[FrequencyBands]) -> Vec<RegionBands> {
+    groups.iter()
+        .filter_map(|group| {
+            let members: Vec<&FrequencyBands> = bands.iter().filter(|b| group.channels.contains(&b.channel)).collect();
+            if members.is_empty() {
+                return None;
+            }
+            let mut sums: HashMap<String, (f32, usize)> = HashMap::new();
+            for member in &members {
+                for (band, &power) in &member.bands {
+                    let entry = sums.entry(band.clone()).or_default();
+                    entry.0 += power;
+                    entry.1 += 1;
+                }
+            }
+            Some(RegionBands {
+                name: group.name.clone(),
+                channels: members.iter().map(|b| b.channel).collect(),
+                bands: sums.into_iter().map(|(band, (sum, count))| (band, sum / count as f32)).collect(),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct RegionBands {
+    name: String,
+    channels: Vec<usize>, // the members analyzed this cycle
+    #[serde(flatten)]
+    bands: HashMap<String, f32>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct RegionalBands {
+    timestamp: f64,
+    regions: Vec<RegionBands>,
+}
+
+// One channel of compare_sessions: mean band powers of each recording (same analysis as the live
+// bands, averaged over non-overlapping buffer_size windows), b - a and the change relative to a
+#[derive(Debug, Serialize, Clone)]
+struct ChannelComparison {
+    channel: String,
+    a: HashMap<String, f32>,
+    b: HashMap<String, f32>,
+    difference: HashMap<String, f32>,
+    percent_change: HashMap<String, f32>, // bands with zero power in a are left out
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct SessionComparison {
+    windows_a: usize,
+    windows_b: usize,
+    skipped_rows_a: usize, // malformed rows left out of each recording
+    skipped_rows_b: usize,
+    channels: Vec<ChannelComparison>, // channels present in both recordings, in a's order
+    unmatched: Vec<String>,           // channels found in only one of them
+}
+
+// Last successfully connected stream, persisted in the app data dir for auto_connect_last
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct LastStream {
+    stream_name: String,
+    source_id: String,
+    auto_connect: bool, // opt-in, off until set_auto_connect(true)
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct ConnectionStatus {
+    // "disabled", "no_saved_stream", "not_found", "connecting", "connected" or "failed" from
+    // auto_connect_last; "reconnecting" / "reconnected" while the live stream is missing, and
+    // "streaming" once a second with the effective rate
+    status: String,
+    stream_name: Option<String>,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    effective_rate: Option<f64>, // smoothed Hz, on the once-a-second "streaming" status
+}
+
+// `channel_layout`: set_auto_expand_channels grew the layout to fit a sample wider than the
+// stream announced. Added channels get default names, gains, scales and filters.
+#[derive(Debug, Serialize, Clone)]
+struct ChannelLayoutChange {
+    previous_count: usize,
+    channel_count: usize,
+    channel_names: Vec<String>,
+}
+
+fn app_data_path(app_handle: &tauri::AppHandle, file_name: &str) -> Result<std::path::PathBuf, String> {
+    app_handle.path_resolver()
+        .app_data_dir()
+        .map(|dir| dir.join(file_name))
+        .ok_or_else(|| "❌ Could not resolve the app data directory".to_string())
+}
+
+fn last_stream_path(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    app_data_path(app_handle, "last_stream.json")
+}
+
+fn load_last_stream(app_handle: &tauri::AppHandle) -> Result<Option<LastStream>, String> {
+    let path = last_stream_path(app_handle)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("❌ Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&contents)
+        .map(Some)
+        .map_err(|e| format!("❌ Failed to parse {}: {}", path.display(), e))
+}
+
+fn save_last_stream(app_handle: &tauri::AppHandle, last_stream: &LastStream) -> Result<(), String> {
+    let path = last_stream_path(app_handle)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("❌ Failed to create {}: {}", dir.display(), e))?;
+    }
+    
+    let contents = serde_json::to_string_pretty(last_stream).map_err(|e| e.to_string())?;
+    std::fs::write(&path, contents).map_err(|e| format!("❌ Failed to write {}: {}", path.display(), e))
+}
+
+// check_lsl_environment: whether liblsl loads and discovery works on this machine, so a broken
+// install or blocked multicast shows up as a diagnostic instead of a failed connect. Run at
+// startup (emitted as `lsl_environment`) unless turned off with set_startup_lsl_check.
+#[derive(Debug, Serialize, Clone)]
+struct LslEnvReport {
+    library_loaded: bool,
+    library_version: Option<String>, // e.g. "1.16"
+    protocol_version: Option<i32>,
+    library_info: Option<String>,
+    streams_found: usize,
+    resolve_s: f64,
+    error: Option<String>,
+    hint: Option<String>,
+}
+
+const LSL_CHECK_RESOLVE_S: f64 = 1.0;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct StartupSettings {
+    #[serde(default = "default_startup_lsl_check")]
+    check_lsl_environment: bool,
+}
+
+fn default_startup_lsl_check() -> bool {
+    true
+}
+
+impl Default for StartupSettings {
+    fn default() -> Self {
+        Self { check_lsl_environment: default_startup_lsl_check() }
+    }
+}
+
+fn load_startup_settings(app_handle: &tauri::AppHandle) -> Result<StartupSettings, String> {
+    let path = app_data_path(app_handle, "startup_settings.json")?;
+    if !path.exists() {
+        return Ok(StartupSettings::default());
+    }
+    
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("❌ Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("❌ Failed to parse {}: {}", path.display(), e))
+}
+
+fn save_startup_settings(app_handle: &tauri::AppHandle, settings: &StartupSettings) -> Result<(), String> {
+    let path = app_data_path(app_handle, "startup_settings.json")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("❌ Failed to create {}: {}", dir.display(), e))?;
+    }
+    
+    let contents = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    std::fs::write(&path, contents).map_err(|e| format!("❌ Failed to write {}: {}", path.display(), e))
+}
+
+// The startup check's result, for a frontend that subscribed after it was emitted
+static STARTUP_LSL_REPORT: std::sync::Mutex<Option<LslEnvReport>> = std::sync::Mutex::new(None);
+
+// save_preset / load_preset: the tunables a user sets up per device or protocol, stored by name
+// in presets.json. Stream-, session- and calibration-specific state (triggers, baselines, DC
+// offset, markers) is left out. Per-channel lists apply to as many channels as both have.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct ConfigPreset {
+    filter: ChannelFilterConfig,
+    band_profile: HashMap<String, [f32; 2]>,
+    active_band_profile: String,
+    band_time_constants: HashMap<String, f32>,
+    analysis_method: AnalysisMethod,
+    goertzel_points: usize,
+    analysis_source: AnalysisSource,
+    artifact_domain: ArtifactDomain,
+    band_format: BandFormat,
+    engagement_mode: EngagementMode,
+    band_normalization: BandNormalization,
+    band_normalization_per_band: HashMap<String, BandNormalization>,
+    gamma_preemphasis: bool,
+    whitening_bins: Option<usize>,
+    dc_exclusion_bins: usize,
+    zero_pad_factor: usize,
+    passband_only: bool,
+    timestamp_source: TimestampSource,
+    timestamp_guard: TimestampGuard,
+    overload_policy: OverloadPolicy,
+    match_field: MatchField,
+    max_streams: usize,
+    pull_mode: PullMode,
+    pull_timeout_s: f64,
+    reconnect_any_match: bool,
+    #[serde(default)]
+    auto_expand_channels: bool,
+    max_reconnect_interval: f32,
+    gap_interpolation: usize,
+    settle_samples: usize,
+    polarity_stage: PolarityStage,
+    channel_polarity: Vec<bool>,
+    display_gains: Vec<f32>,
+    channel_scales: Vec<f32>,
+    channel_signal_types: Vec<SignalType>,
+}
+
+fn load_presets(app_handle: &tauri::AppHandle) -> Result<HashMap<String, ConfigPreset>, String> {
+    let path = app_data_path(app_handle, "presets.json")?;
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("❌ Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("❌ Failed to parse {}: {}", path.display(), e))
+}
+
+fn save_presets(app_handle: &tauri::AppHandle, presets: &HashMap<String, ConfigPreset>) -> Result<(), String> {
+    let path = app_data_path(app_handle, "presets.json")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("❌ Failed to create {}: {}", dir.display(), e))?;
+    }
+    
+    let contents = serde_json::to_string_pretty(presets).map_err(|e| e.to_string())?;
+    std::fs::write(&path, contents).map_err(|e| format!("❌ Failed to write {}: {}", path.display(), e))
+}
+
+// A panic inside the liblsl calls (e.g. the library failing to load) comes back here as a
+// join error rather than taking the app down
+async fn run_lsl_environment_check() -> LslEnvReport {
+    let mut report = LslEnvReport {
+        library_loaded: false,
+        library_version: None,
+        protocol_version: None,
+        library_info: None,
+        streams_found: 0,
+        resolve_s: 0.0,
+        error: None,
+        hint: None,
+    };
+    let versions = spawn_lsl_blocking(Duration::ZERO, || {
+        let version = lsl::library_version();
+        (format!("{}.{}", version / 100, version % 100), lsl::protocol_version(), lsl::library_info())
+    })
+    .await;
+    match versions {
+        Ok((library_version, protocol_version, library_info)) => {
+            report.library_loaded = true;
+            report.library_version = Some(library_version);
+            report.protocol_version = Some(protocol_version);
+            report.library_info = Some(library_info);
+        }
+        Err(LslTaskError::Join(e)) => {
+            report.error = Some(format!("LSL library failed to load: {}", e));
+            report.hint = Some("Reinstall the app or place a liblsl matching this build next to the executable".to_string());
+        }
+        Err(e) => report.error = Some(format!("LSL check did not complete: {}", e)),
+    }
+    
+    if report.library_loaded {
+        let started = std::time::Instant::now();
+        let resolved = spawn_lsl_blocking(Duration::from_secs_f64(LSL_CHECK_RESOLVE_S), || {
+            resolve_streams(LSL_CHECK_RESOLVE_S).map(|streams| streams.len())
+        })
+        .await;
+        report.resolve_s = started.elapsed().as_secs_f64();
+        match resolved {
+            Ok(Ok(0)) => report.hint = Some(
+                "No LSL streams found - check that the device software is streaming, and that a firewall or VPN \
+                 isn't blocking multicast (UDP 16571-16604); otherwise add the host to KnownPeers in lsl_api.cfg".to_string()
+            ),
+            Ok(Ok(streams)) => report.streams_found = streams,
+            Ok(Err(e)) => {
+                report.error = Some(format!("Stream discovery failed: {}", e));
+                report.hint = Some("liblsl loaded but could not resolve - check network permissions for multicast".to_string());
+            }
+            Err(e) => report.error = Some(format!("Stream discovery did not complete: {}", e)),
+        }
+    }
+    
+    println!(
+        "🩺 [DEBUG] LSL environment: loaded {}, version {:?}, {} streams in {:.2} s{}",
+        report.library_loaded,
+        report.library_version,
+        report.streams_found,
+        report.resolve_s,
+        report.error.as_ref().map_or(String::new(), |e| format!(" - {}", e))
+    );
+    report
+}
+
+// Feedback thresholds for one band (what counts as "low"/"high" alpha), in the band's power units
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+struct BandThreshold {
+    low: f32,
+    high: f32,
+}
+
+fn load_band_thresholds(app_handle: &tauri::AppHandle) -> Result<HashMap<String, BandThreshold>, String> {
+    let path = app_data_path(app_handle, "band_thresholds.json")?;
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("❌ Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("❌ Failed to parse {}: {}", path.display(), e))
+}
+
+fn save_band_thresholds(app_handle: &tauri::AppHandle, thresholds: &HashMap<String, BandThreshold>) -> Result<(), String> {
+    let path = app_data_path(app_handle, "band_thresholds.json")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("❌ Failed to create {}: {}", dir.display(), e))?;
+    }
+    
+    let contents = serde_json::to_string_pretty(thresholds).map_err(|e| e.to_string())?;
+    std::fs::write(&path, contents).map_err(|e| format!("❌ Failed to write {}: {}", path.display(), e))
+}
+
+// Factor that brings a declared LSL unit to µV, the unit the filters, artifact clipping and band
+// thresholds assume. Undeclared or unknown units (e.g. raw counts) are left unscaled.
+fn unit_scale(unit: &str) -> f32 {
+    match unit.trim().to_lowercase().as_str() {
+        "microvolts" | "microvolt" | "uv" | "µv" | "μv" => 1.0,
+        "millivolts" | "millivolt" | "mv" => 1e3,
+        "volts" | "volt" | "v" => 1e6,
+        "nanovolts" | "nanovolt" | "nv" => 1e-3,
+        _ => 1.0,
+    }
+}
+
+// Process-wide RNG for anything random (quote selection, simulated data). Unseeded it draws from
+// OS entropy on every use; set_rng_seed makes every later draw reproducible for tests and demos.
+static SEEDED_RNG: std::sync::Mutex<Option<rand::rngs::StdRng>> = std::sync::Mutex::new(None);
+
+fn with_rng<T>(f: impl FnOnce(&mut rand::rngs::StdRng) -> T) -> T {
+    let mut seeded = SEEDED_RNG.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+    match seeded.as_mut() {
+        Some(rng) => f(rng),
+        None => f(&mut rand::rngs::StdRng::from_entropy()),
+    }
+}
+
+// Counters for the spawn_blocking tasks that talk to LSL
+static LSL_TASKS_ACTIVE: AtomicU64 = AtomicU64::new(0);
+static LSL_TASKS_SPAWNED: AtomicU64 = AtomicU64::new(0);
+static LSL_TASKS_DROPPED: AtomicU64 = AtomicU64::new(0);
+
+static LSL_TASKS_ABANDONED: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Serialize, Clone)]
+struct TaskStats {
+    active_blocking: u64, // LSL closures currently running on the blocking pool
+    total_spawned: u64,
+    total_dropped: u64,   // result never delivered: the task panicked or its caller stopped waiting
+    total_abandoned: u64, // of those, given up on after the deadline or at shutdown
+}
+
+// Set once the app starts exiting: no new LSL tasks are started and pending ones stop being
+// awaited, so a pull stuck on a dead socket cannot hold up the exit
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+static SHUTDOWN: tokio::sync::Notify = tokio::sync::Notify::const_new();
+
+fn begin_shutdown() {
+    if !SHUTTING_DOWN.swap(true, Ordering::Relaxed) {
+        println!("👋 [DEBUG] Shutting down - pending LSL tasks are no longer awaited");
+        SHUTDOWN.notify_waiters();
+    }
+}
+
+// Slack on top of a task's own LSL timeouts before the caller abandons it (set_lsl_task_grace)
+const DEFAULT_LSL_TASK_GRACE_MS: u64 = 2000;
+static LSL_TASK_GRACE_MS: AtomicU64 = AtomicU64::new(DEFAULT_LSL_TASK_GRACE_MS);
+
+#[derive(Debug)]
+enum LslTaskError {
+    Join(tokio::task::JoinError),
+    TimedOut(Duration),
+    ShuttingDown,
+}
+
+impl std::fmt::Display for LslTaskError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Join(e) => write!(f, "{}", e),
+            Self::TimedOut(deadline) => write!(f, "LSL call did not return within {:.1} s", deadline.as_secs_f32()),
+            Self::ShuttingDown => write!(f, "application is shutting down"),
+        }
+    }
+}
+
+// spawn_blocking for LSL work, instrumented for get_task_stats. `expected` is the longest the
+// closure's own LSL timeouts allow; past that plus the grace, or at shutdown, the caller stops
+// waiting. Blocking closures cannot be aborted, so an abandoned one still runs to completion.
+async fn spawn_lsl_blocking<F, R>(expected: Duration, task: F) -> Result<R, LslTaskError>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    struct ActiveGuard;
+    impl Drop for ActiveGuard {
+        fn drop(&mut self) {
+            LSL_TASKS_ACTIVE.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+    
+    struct AwaitGuard {
+        delivered: bool,
+    }
+    impl Drop for AwaitGuard {
+        fn drop(&mut self) {
+            if !self.delivered {
+                LSL_TASKS_DROPPED.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+    
+    if SHUTTING_DOWN.load(Ordering::Relaxed) {
+        return Err(LslTaskError::ShuttingDown);
+    }
+    
+    LSL_TASKS_SPAWNED.fetch_add(1, Ordering::Relaxed);
+    LSL_TASKS_ACTIVE.fetch_add(1, Ordering::Relaxed);
+    let mut await_guard = AwaitGuard { delivered: false };
+    let deadline = expected + Duration::from_millis(LSL_TASK_GRACE_MS.load(Ordering::Relaxed));
+    let handle = tokio::task::spawn_blocking(move || {
+        let _active = ActiveGuard;
+        task()
+    });
+    
+    let result = tokio::select! {
+        joined = tokio::time::timeout(deadline, handle) => match joined {
+            Ok(joined) => joined.map_err(LslTaskError::Join),
+            Err(_) => Err(LslTaskError::TimedOut(deadline)),
+        },
+        _ = SHUTDOWN.notified() => Err(LslTaskError::ShuttingDown),
+    };
+    if matches!(result, Err(LslTaskError::TimedOut(_) | LslTaskError::ShuttingDown)) {
+        LSL_TASKS_ABANDONED.fetch_add(1, Ordering::Relaxed);
+        eprintln!("⚠️ [DEBUG] Abandoned an LSL task: {}", result.as_ref().err().map_or(String::new(), |e| e.to_string()));
+    }
+    await_guard.delivered = result.is_ok();
+    result
+}
+
+fn task_stats() -> TaskStats {
+    TaskStats {
+        active_blocking: LSL_TASKS_ACTIVE.load(Ordering::Relaxed),
+        total_spawned: LSL_TASKS_SPAWNED.load(Ordering::Relaxed),
+        total_dropped: LSL_TASKS_DROPPED.load(Ordering::Relaxed),
+        total_abandoned: LSL_TASKS_ABANDONED.load(Ordering::Relaxed),
+    }
+}
+
+// Accepted analysis window lengths (powers of two)
+const MIN_FFT_SIZE: usize = 64;
+const MAX_FFT_SIZE: usize = 8192;
+// set_zero_pad_factor: the band FFT runs on the window padded to this many times its length
+const MAX_ZERO_PAD_FACTOR: usize = 8;
+
+struct EEGProcessor {
+    sample_rate: f32,
+    buffer_size: usize,
+    fft_plan: Arc<dyn Fft<f32>>,
+    band_fft_plan: Arc<dyn RealToComplex<f32>>, // same size; band powers only need bins up to Nyquist
+    zero_pad_factor: Arc<Mutex<usize>>, // 1 = unpadded
+    padded_band_fft: Arc<Mutex<Option<Arc<dyn RealToComplex<f32>>>>>, // replanned when the padded length changes
+    multiresolution: Arc<Mutex<Option<MultiResolution>>>,
+    channel_buffers: Arc<Mutex<Vec<Vec<f32>>>>,
+    filtered_buffers: Arc<Mutex<Vec<Vec<f32>>>>,
+    lsl_connection: Arc<Mutex<LSLConnection>>,
+    filter_config: Arc<Mutex<ChannelFilterConfig>>,
+    channel_filters: Arc<Mutex<Option<Vec<ChannelFilter>>>>,
+    replay_buffer: Arc<Mutex<ReplayBuffer>>,
+    frame_history: Arc<Mutex<FrameHistory>>, // one CombinedFrame per analysis cycle
+    band_log: Arc<Mutex<Option<BandLog>>>,
+    sample_recording: Arc<Mutex<Option<SampleRecording>>>,
+    session_metadata: Arc<Mutex<HashMap<String, String>>>, // written into recording headers
+    // Some = set_event_logging is on. A std mutex, so EventSink::emit_all can log without awaiting;
+    // it is never held across an await.
+    event_log: Arc<std::sync::Mutex<Option<EventLog>>>,
+    event_rate: Arc<std::sync::Mutex<EventRateLimiter>>, // std mutex for the same reason
+    timestamp_source: Arc<Mutex<TimestampSource>>,
+    emit_lsl_timestamp: Arc<Mutex<bool>>, // samples also carry the uncorrected inlet timestamp
+    auto_expand_channels: Arc<Mutex<bool>>, // a sample wider than the stream grows the layout instead of being cut
+    layout_change: Arc<Mutex<Option<ChannelLayoutChange>>>, // newest expansion, emitted by the loop
+    band_format: Arc<Mutex<BandFormat>>,
+    artifact_domain: Arc<Mutex<ArtifactDomain>>,
+    gamma_preemphasis: Arc<Mutex<bool>>,
+    whitening: Arc<Mutex<Option<usize>>>, // half-width in bins of the smoothing used for whitening
+    dc_exclusion_bins: Arc<Mutex<usize>>, // lowest FFT bins left out of every band
+    analysis_method: Arc<Mutex<AnalysisMethod>>,
+    goertzel_points: Arc<Mutex<usize>>, // frequencies evaluated per band by the Goertzel method
+    analysis_source: Arc<Mutex<AnalysisSource>>,
+    fixed_emit_hz: Arc<Mutex<f32>>, // 0 = emit filtered_eeg_sample per sample
+    processing_chain: Arc<Mutex<Option<ProcessingChainWatch>>>, // Some = processing_chain is emitted
+    sample_batch_size: Arc<Mutex<usize>>, // 1 = one eeg_sample event per displayed sample
+    quiet_mode: Arc<Mutex<Option<QuietMode>>>, // Some = only `heartbeat` is emitted
+    processing_run: Arc<Mutex<u64>>, // current start_eeg_processing run
+    timed_session: Arc<Mutex<Option<TimedSession>>>,
+    band_crossings: Arc<Mutex<HashMap<String, BandCrossing>>>,
+    latest_display_sample: Arc<Mutex<Option<FilteredEEGSample>>>,
+    delta_encoder: Arc<Mutex<Option<DeltaEncoder>>>, // Some = eeg_sample_delta is emitted
+    auto_gain: Arc<Mutex<Option<AutoGain>>>, // Some = band powers are normalized to a target RMS
+    band_smoothing: Arc<Mutex<BandSmoothing>>, // per-band EMA of the emitted band powers
+    analysis_enabled: Arc<Mutex<bool>>, // false = samples still stream, the analysis cycle is skipped
+    display_smoother: Arc<Mutex<Option<SavitzkyGolayFilter>>>,
+    dc_offset: Arc<Mutex<Option<Vec<f32>>>>,
+    custom_metrics: Arc<Mutex<Vec<Box<dyn EegMetric>>>>,
+    band_profile: Arc<Mutex<BandProfile>>,
+    named_band_profiles: Arc<Mutex<HashMap<String, BandProfile>>>,
+    active_band_profile: Arc<Mutex<String>>, // name of band_profile in named_band_profiles
+    channel_band_profiles: Arc<Mutex<Vec<Option<String>>>>, // per channel; None uses band_profile
+    channel_signal_types: Arc<Mutex<Vec<SignalType>>>, // per channel; non-EEG AUX channels are filtered too
+    min_band_bins: Arc<Mutex<usize>>,
+    band_resolution_checked: Arc<Mutex<Option<ResolutionCheck>>>, // last situation checked
+    passband_only: Arc<Mutex<bool>>, // bands of filtered data are clipped to each channel's bandpass
+    band_passband_checked: Arc<Mutex<Option<Vec<BandPassbandWarning>>>>, // last warnings emitted
+    erd_windows: Arc<Mutex<ErdWindows>>,
+    pending_markers: Arc<Mutex<Vec<EventMarker>>>,
+    test_markers: Arc<Mutex<TestMarkers>>, // synthetic markers and their deflections (CSV sources only)
+    epoch_markers: Arc<Mutex<VecDeque<EventMarker>>>, // every marker placed, for compute_itpc
+    band_history: Arc<Mutex<VecDeque<Vec<FrequencyBands>>>>, // recent analysis cycles for ERD/ERS
+    overload_policy: Arc<Mutex<OverloadPolicy>>,
+    timestamp_guard: Arc<Mutex<TimestampGuard>>,
+    triggers: Arc<Mutex<Vec<Trigger>>>,
+    next_trigger_id: Arc<Mutex<u64>>,
+    last_bands: Arc<Mutex<Vec<FrequencyBands>>>,
+    last_snr: Arc<Mutex<Vec<ChannelSnr>>>, // of the last analysis cycle's spectra
+    display_gains: Arc<Mutex<Vec<f32>>>, // per channel, applied to emitted samples only
+    calm_score: Arc<Mutex<CalmScoreState>>,
+    meditation_index: Arc<Mutex<MeditationIndexState>>,
+    band_normalization: Arc<Mutex<BandNormalizationState>>, // units of the published band values
+    alpha_blocking: Arc<Mutex<Option<AlphaBlockingTest>>>, // a running run_alpha_blocking_test
+    blinks: Arc<Mutex<BlinkState>>,
+    engagement_mode: Arc<Mutex<EngagementMode>>,
+    pull_timeout: Arc<Mutex<f64>>,
+    filter_rate: Arc<Mutex<f64>>, // rate the active filter coefficients were designed for
+    adaptive_filter_redesign: Arc<Mutex<bool>>,
+    adaptive_notch: Arc<Mutex<Option<AdaptiveNotch>>>, // Some = notches follow the mains frequency
+    frames: broadcast::Sender<ProcessedFrame>,
+    #[cfg(feature = "websocket")]
+    websocket_server: Arc<Mutex<Option<WebSocketServer>>>,
+    match_field: Arc<Mutex<MatchField>>,
+    max_streams: Arc<Mutex<usize>>, // resolved streams considered when matching
+    reconnect_any_match: Arc<Mutex<bool>>, // accept another source matching the name after a loss
+    max_reconnect_interval: Arc<Mutex<f32>>, // seconds, ceiling of the reconnect backoff
+    reconnect_status: Arc<Mutex<Option<ConnectionStatus>>>, // newest attempt, emitted by the loop
+    rate_smoothing: Arc<Mutex<f64>>, // EMA weight of the newest window in the reported rate
+    gap_interpolation: Arc<Mutex<usize>>, // longest gap (samples) bridged by linear interpolation
+    csv_input: Arc<Mutex<Option<mpsc::Receiver<EEGSample>>>>,
+    pull_mode: Arc<Mutex<PullMode>>,
+    inlet_worker: Arc<Mutex<Option<mpsc::Receiver<InletMessage>>>>, // started on the first blocking-mode pull
+    band_thresholds: Arc<Mutex<Option<HashMap<String, BandThreshold>>>>, // None until loaded from disk
+    channel_scales: Arc<Mutex<Vec<f32>>>, // per channel, applied to every pulled sample; defaults from units
+    channel_polarity: Arc<Mutex<Vec<bool>>>, // per channel, true = sign inverted
+    polarity_stage: Arc<Mutex<PolarityStage>>,
+    target_phase_hz: Arc<Mutex<Option<f32>>>,
+    envelope_band: Arc<Mutex<Option<String>>>, // band tracked by band_envelope, None = off
+    aperiodic_range: Arc<Mutex<(f32, f32)>>, // Hz, fit range of the 1/f slope
+    entropy_range: Arc<Mutex<(f32, f32)>>, // Hz, bins the spectral entropy is taken over
+    muscle_artifact: Arc<Mutex<Option<MuscleArtifactConfig>>>, // Some = muscle_artifact is emitted
+    gate_bad_channels: Arc<Mutex<bool>>,
+    gated_channels: Arc<Mutex<Vec<GatedChannel>>>, // excluded as of the last analysis cycle
+    variance_trend: Arc<Mutex<Option<VarianceTrend>>>, // Some = channel_variance_trend is emitted
+    channel_groups: Arc<Mutex<Option<Vec<ChannelGroup>>>>, // None = grouped by 10-10 region
+    score_channels: Arc<Mutex<Option<Vec<usize>>>>, // None = posterior channels of the montage, else all
+    xcorr: Arc<Mutex<Option<XcorrConfig>>>, // Some = xcorr_lag is emitted for that pair
+    individualized_bands: Arc<Mutex<Option<Vec<Option<f32>>>>>, // Some = per-channel TAF moves the theta/alpha edge
+    emit_raw_spectrum: Arc<Mutex<bool>>,
+    settle_samples: Arc<Mutex<usize>>,   // samples discarded after each connect
+    settle_remaining: Arc<Mutex<usize>>,
+}
+
+impl EEGProcessor {
+    fn new() -> Self {
+        println!("🔧 [DEBUG] Creating new EEGProcessor instance");
+        Self {
+            sample_rate: 250.0,
+            buffer_size: 512,
+            fft_plan: FftPlanner::new().plan_fft_forward(512),
+            band_fft_plan: RealFftPlanner::new().plan_fft_forward(512),
+            zero_pad_factor: Arc::new(Mutex::new(1)),
+            padded_band_fft: Arc::new(Mutex::new(None)),
+            multiresolution: Arc::new(Mutex::new(None)),
+            channel_buffers: Arc::new(Mutex::new(vec![Vec::new(); 8])),
+            filtered_buffers: Arc::new(Mutex::new(vec![Vec::new(); 8])),
+            lsl_connection: Arc::new(Mutex::new(LSLConnection::new())),
+            filter_config: Arc::new(Mutex::new(ChannelFilterConfig::default())),
+            channel_filters: Arc::new(Mutex::new(None)),
+            replay_buffer: Arc::new(Mutex::new(ReplayBuffer::new(30.0, 250.0))),
+            frame_history: Arc::new(Mutex::new(FrameHistory { capacity: DEFAULT_FRAME_HISTORY, frames: VecDeque::new() })),
+            band_log: Arc::new(Mutex::new(None)),
+            sample_recording: Arc::new(Mutex::new(None)),
+            session_metadata: Arc::new(Mutex::new(HashMap::new())),
+            event_log: Arc::new(std::sync::Mutex::new(None)),
+            event_rate: Arc::new(std::sync::Mutex::new(EventRateLimiter::new(Some(DEFAULT_MAX_EVENT_RATE)))),
+            timestamp_source: Arc::new(Mutex::new(TimestampSource::Lsl)),
+            emit_lsl_timestamp: Arc::new(Mutex::new(false)),
+            auto_expand_channels: Arc::new(Mutex::new(false)),
+            layout_change: Arc::new(Mutex::new(None)),
+            band_format: Arc::new(Mutex::new(BandFormat::PerChannel)),
+            artifact_domain: Arc::new(Mutex::new(ArtifactDomain::Filtered)),
+            gamma_preemphasis: Arc::new(Mutex::new(false)),
+            whitening: Arc::new(Mutex::new(None)),
+            dc_exclusion_bins: Arc::new(Mutex::new(DEFAULT_DC_EXCLUSION_BINS)),
+            analysis_method: Arc::new(Mutex::new(AnalysisMethod::Fft)),
+            goertzel_points: Arc::new(Mutex::new(DEFAULT_GOERTZEL_POINTS)),
+            analysis_source: Arc::new(Mutex::new(AnalysisSource::Filtered)),
+            fixed_emit_hz: Arc::new(Mutex::new(0.0)),
+            processing_chain: Arc::new(Mutex::new(None)),
+            sample_batch_size: Arc::new(Mutex::new(1)),
+            quiet_mode: Arc::new(Mutex::new(None)),
+            processing_run: Arc::new(Mutex::new(0)),
+            timed_session: Arc::new(Mutex::new(None)),
+            band_crossings: Arc::new(Mutex::new(HashMap::new())),
+            latest_display_sample: Arc::new(Mutex::new(None)),
+            delta_encoder: Arc::new(Mutex::new(None)),
+            auto_gain: Arc::new(Mutex::new(None)),
+            band_smoothing: Arc::new(Mutex::new(BandSmoothing::default())),
+            analysis_enabled: Arc::new(Mutex::new(true)),
+            display_smoother: Arc::new(Mutex::new(None)),
+            dc_offset: Arc::new(Mutex::new(None)),
+            custom_metrics: Arc::new(Mutex::new(Vec::new())),
+            band_profile: Arc::new(Mutex::new(BandProfile::default())),
+            named_band_profiles: Arc::new(Mutex::new(HashMap::from([("classic".to_string(), BandProfile::default())]))),
+            channel_band_profiles: Arc::new(Mutex::new(Vec::new())),
+            channel_signal_types: Arc::new(Mutex::new(Vec::new())),
+            active_band_profile: Arc::new(Mutex::new("classic".to_string())),
+            min_band_bins: Arc::new(Mutex::new(DEFAULT_MIN_BAND_BINS)),
+            band_resolution_checked: Arc::new(Mutex::new(None)),
+            passband_only: Arc::new(Mutex::new(false)),
+            band_passband_checked: Arc::new(Mutex::new(None)),
+            erd_windows: Arc::new(Mutex::new(ErdWindows::default())),
+            pending_markers: Arc::new(Mutex::new(Vec::new())),
+            test_markers: Arc::new(Mutex::new(TestMarkers::default())),
+            epoch_markers: Arc::new(Mutex::new(VecDeque::new())),
+            band_history: Arc::new(Mutex::new(VecDeque::new())),
+            overload_policy: Arc::new(Mutex::new(OverloadPolicy::None)),
+            timestamp_guard: Arc::new(Mutex::new(TimestampGuard::Drop)),
+            triggers: Arc::new(Mutex::new(Vec::new())),
+            next_trigger_id: Arc::new(Mutex::new(1)),
+            last_bands: Arc::new(Mutex::new(Vec::new())),
+            last_snr: Arc::new(Mutex::new(Vec::new())),
+            display_gains: Arc::new(Mutex::new(Vec::new())),
+            calm_score: Arc::new(Mutex::new(CalmScoreState::default())),
+            meditation_index: Arc::new(Mutex::new(MeditationIndexState::default())),
+            band_normalization: Arc::new(Mutex::new(BandNormalizationState::default())),
+            alpha_blocking: Arc::new(Mutex::new(None)),
+            blinks: Arc::new(Mutex::new(BlinkState::default())),
+            engagement_mode: Arc::new(Mutex::new(EngagementMode::PerChannel)),
+            pull_timeout: Arc::new(Mutex::new(DEFAULT_PULL_TIMEOUT_S)),
+            filter_rate: Arc::new(Mutex::new(250.0)),
+            adaptive_filter_redesign: Arc::new(Mutex::new(false)),
+            adaptive_notch: Arc::new(Mutex::new(None)),
+            frames: broadcast::channel(FRAME_CHANNEL_CAPACITY).0,
+            #[cfg(feature = "websocket")]
+            websocket_server: Arc::new(Mutex::new(None)),
+            match_field: Arc::new(Mutex::new(MatchField::Auto)),
+            max_streams: Arc::new(Mutex::new(DEFAULT_MAX_STREAMS)),
+            reconnect_any_match: Arc::new(Mutex::new(false)),
+            max_reconnect_interval: Arc::new(Mutex::new(DEFAULT_MAX_RECONNECT_INTERVAL_S)),
+            reconnect_status: Arc::new(Mutex::new(None)),
+            rate_smoothing: Arc::new(Mutex::new(DEFAULT_RATE_SMOOTHING)),
+            gap_interpolation: Arc::new(Mutex::new(0)),
+            csv_input: Arc::new(Mutex::new(None)),
+            pull_mode: Arc::new(Mutex::new(PullMode::Poll)),
+            inlet_worker: Arc::new(Mutex::new(None)),
+            band_thresholds: Arc::new(Mutex::new(None)),
+            channel_scales: Arc::new(Mutex::new(Vec::new())),
+            channel_polarity: Arc::new(Mutex::new(Vec::new())),
+            polarity_stage: Arc::new(Mutex::new(PolarityStage::BeforeFilter)),
+            target_phase_hz: Arc::new(Mutex::new(None)),
+            envelope_band: Arc::new(Mutex::new(None)),
+            aperiodic_range: Arc::new(Mutex::new(DEFAULT_APERIODIC_RANGE_HZ)),
+            entropy_range: Arc::new(Mutex::new(DEFAULT_ENTROPY_RANGE_HZ)),
+            muscle_artifact: Arc::new(Mutex::new(None)),
+            gate_bad_channels: Arc::new(Mutex::new(false)),
+            gated_channels: Arc::new(Mutex::new(Vec::new())),
+            variance_trend: Arc::new(Mutex::new(None)),
+            channel_groups: Arc::new(Mutex::new(None)),
+            score_channels: Arc::new(Mutex::new(None)),
+            xcorr: Arc::new(Mutex::new(None)),
+            individualized_bands: Arc::new(Mutex::new(None)),
+            emit_raw_spectrum: Arc::new(Mutex::new(false)),
+            settle_samples: Arc::new(Mutex::new(0)),
+            settle_remaining: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    async fn connect_to_lsl(&self, stream_name: &str) -> Result<LSLStreamInfo, String> {
+        self.connect_to_lsl_on(stream_name, None).await
+    }
+
+    // host: Some restricts resolution (here and in the sample pulls) to that machine's streams
+    async fn connect_to_lsl_on(&self, stream_name: &str, host: Option<&str>) -> Result<LSLStreamInfo, String> {
+        println!("🚀 [DEBUG] ===== STARTING LSL CONNECTION PROCESS =====");
+        println!("🔍 [DEBUG] Target stream name: '{}'{}", stream_name, host.map(|h| format!(" on host '{}'", h)).unwrap_or_default());
+        println!("🔍 [DEBUG] Current thread: {:?}", std::thread::current().id());
+        
+        // Use blocking task to handle LSL operations
+        let stream_name_clone = stream_name.to_string();
+        let resolve_host = host.map(str::to_string);
+        let match_field = *self.match_field.lock().await;
+        let max_streams = *self.max_streams.lock().await;
+        // 15 s resolve + 2 s test pull + 2 s header read
+        let result = spawn_lsl_blocking(Duration::from_secs(19), move || {
+            println!("🔍 [DEBUG] Entered blocking task for LSL operations");
+            println!("🔍 [DEBUG] Resolving LSL streams with 15 second timeout...");
+            
+            // MAXIMUM TIMEOUT: Give plenty of time for stream discovery
+            match Self::resolve_candidates(resolve_host.as_deref(), 15.0) {
+                Ok(streams) => {
+                    println!("📡 [DEBUG] ===== LSL STREAM DISCOVERY RESULTS =====");
+                    println!("📡 [DEBUG] Total streams found: {}", streams.len());
+                    
+                    if streams.is_empty() {
+                        println!("❌ [DEBUG] NO LSL STREAMS FOUND AT ALL!");
+                        println!("❌ [DEBUG] This means:");
                         println!("❌ [DEBUG] 1. No LSL applications are running");
                         println!("❌ [DEBUG] 2. UnicornLSL.exe is not started");
                         println!("❌ [DEBUG] 3. Network/firewall issues");
                         return Err("❌ No LSL streams found. Please start UnicornLSL.exe and ensure it's broadcasting stream '123'".to_string());
                     }
                     
-                    // Log ALL available streams with FULL details
-                    for (i, stream) in streams.iter().enumerate() {
-                        println!("📊 [DEBUG] ===== STREAM {} DETAILS =====", i + 1);
-                        println!("📊 [DEBUG]   Name/Hostname: '{}'", stream.hostname());
-                        println!("📊 [DEBUG]   Type: '{}'", stream.stream_type());
-                        println!("📊 [DEBUG]   Source ID: '{}'", stream.source_id());
-                        println!("📊 [DEBUG]   Channels: {}", stream.channel_count());
-                        println!("📊 [DEBUG]   Sample Rate: {:.1} Hz", stream.nominal_srate());
-                        println!("📊 [DEBUG]   UID: '{}'", stream.uid());
-                        println!("📊 [DEBUG] ================================");
+                    // Busy networks can list dozens of streams; only the first max_streams are considered
+                    if streams.len() > max_streams {
+                        println!("⚠️ [DEBUG] {} streams found - only the first {} are considered (set_max_streams)", streams.len(), max_streams);
+                    }
+                    let streams = &streams[..streams.len().min(max_streams)];
+                    
+                    // Log available streams with FULL details
+                    for (i, stream) in streams.iter().enumerate() {
+                        println!("📊 [DEBUG] ===== STREAM {} DETAILS =====", i + 1);
+                        println!("📊 [DEBUG]   Name/Hostname: '{}'", stream.hostname());
+                        println!("📊 [DEBUG]   Type: '{}'", stream.stream_type());
+                        println!("📊 [DEBUG]   Source ID: '{}'", stream.source_id());
+                        println!("📊 [DEBUG]   Channels: {}", stream.channel_count());
+                        println!("📊 [DEBUG]   Sample Rate: {:.1} Hz", stream.nominal_srate());
+                        println!("📊 [DEBUG]   UID: '{}'", stream.uid());
+                        println!("📊 [DEBUG] ================================");
+                    }
+                    
+                    println!("🔍 [DEBUG] ===== STREAM MATCHING PROCESS =====");
+                    println!("🔍 [DEBUG] Looking for stream: '{}' (match field: {:?})", stream_name_clone, match_field);
+                    
+                    let matching_stream = Self::select_stream(streams, &stream_name_clone, match_field, max_streams);
+                    
+                    if let Some(stream_info) = matching_stream {
+                        println!("✅ [DEBUG] ===== FOUND MATCHING STREAM =====");
+                        println!("✅ [DEBUG] Matched stream: '{}'", stream_info.hostname());
+                        println!("✅ [DEBUG] Stream type: '{}'", stream_info.stream_type());
+                        println!("✅ [DEBUG] Source ID: '{}'", stream_info.source_id());
+                        println!("✅ [DEBUG] Channels: {}", stream_info.channel_count());
+                        println!("✅ [DEBUG] Sample rate: {:.1} Hz", stream_info.nominal_srate());
+                        
+                        let channel_count = stream_info.channel_count() as usize;
+                        
+                        // Extract metadata
+                        let stream_type = stream_info.stream_type().to_string();
+                        let source_id = stream_info.source_id().to_string();
+                        
+                        // Extract channel names
+                        let channel_names = Self::extract_real_channel_names_sync(stream_info, channel_count);
+                        
+                        // Check against 10-20 only when the stream uses electrode labels at all (not Ch1..)
+                        if channel_names.iter().any(|name| ELECTRODE_LABELS.iter().any(|l| l.eq_ignore_ascii_case(name.trim()))) {
+                            for issue in check_montage(&channel_names) {
+                                println!("⚠️ [DEBUG] Montage issue: {:?}", issue);
+                            }
+                        }
+                        
+                        // Extract device info
+                        let (manufacturer, device_model) = Self::extract_device_info_sync(stream_info);
+                        
+                        // Create metadata
+                        let metadata = format!(
+                            "🔴 REAL LSL DATA - Type: {} | Source: {} | Channels: {} | Rate: {:.1} Hz | Manufacturer: {} | Model: {}{}",
+                            stream_type,
+                            source_id,
+                            channel_count,
+                            stream_info.nominal_srate(),
+                            manufacturer,
+                            device_model,
+                            if stream_info.nominal_srate() == IRREGULAR_RATE { " | ⚠️ Irregular rate" } else { "" }
+                        );
+
+                        // CRITICAL: Test connection by creating inlet with extended timeout
+                        println!("🔗 [DEBUG] ===== TESTING LSL CONNECTION =====");
+                        println!("🔗 [DEBUG] Creating StreamInlet with 360 buffer, 1 chunk, true recover...");
+                        
+                        match StreamInlet::new(stream_info, 360, 1, true) {
+                            Ok(inlet) => {
+                                println!("✅ [DEBUG] StreamInlet created successfully!");
+                                
+                                // Test data pull with longer timeout
+                                println!("📊 [DEBUG] Testing data pull with 2 second timeout...");
+                                match <StreamInlet as Pullable<f32>>::pull_sample(&inlet, 2.0) {
+                                    Ok((sample, timestamp)) => {
+                                        println!("✅ [DEBUG] SUCCESS! Pulled test sample:");
+                                        println!("✅ [DEBUG]   Sample length: {} channels", sample.len());
+                                        println!("✅ [DEBUG]   Timestamp: {}", timestamp);
+                                        println!("✅ [DEBUG]   First few values: {:?}", &sample[..sample.len().min(5)]);
+                                    }
+                                    Err(e) => {
+                                        println!("⚠️ [DEBUG] No immediate data available (this can be normal): {}", e);
+                                        println!("⚠️ [DEBUG] Will proceed anyway as inlet was created successfully");
+                                    }
+                                }
+                                
+                                let units = Self::extract_channel_units_sync(&inlet, channel_count);
+                                
+                                let info = LSLStreamInfo {
+                                    units,
+                                    channel_format: format!("{:?}", stream_info.channel_format()).to_lowercase(),
+                                    name: stream_info.hostname().to_string(),
+                                    channel_count: stream_info.channel_count(),
+                                    sample_rate: stream_info.nominal_srate(),
+                                    is_connected: true, // REAL connection
+                                    metadata,
+                                    stream_type,
+                                    source_id,
+                                    channel_names,
+                                    manufacturer,
+                                    device_model,
+                                };
+                                
+                                println!("✅ [DEBUG] ===== LSL CONNECTION SUCCESSFUL =====");
+                                println!("✅ [DEBUG] Stream info created: {:?}", info);
+                                Ok((info, channel_count, true))
+                            }
+                            Err(e) => {
+                                println!("❌ [DEBUG] ===== FAILED TO CREATE INLET =====");
+                                println!("❌ [DEBUG] Error: {}", e);
+                                println!("❌ [DEBUG] This could mean:");
+                                println!("❌ [DEBUG] 1. Stream exists but is not accessible");
+                                println!("❌ [DEBUG] 2. Another application is using the stream");
+                                println!("❌ [DEBUG] 3. Network connectivity issues");
+                                Err(format!("❌ Failed to create inlet for LSL stream '{}': {}", stream_name_clone, e))
+                            }
+                        }
+                    } else {
+                        let available_names: Vec<String> = streams.iter()
+                            .map(|s| format!("'{}' (type: {}, source: {})", 
+                                           s.hostname(), s.stream_type(), s.source_id()))
+                            .collect();
+                        
+                        println!("❌ [DEBUG] ===== NO MATCHING STREAM FOUND =====");
+                        println!("❌ [DEBUG] Target: '{}'", stream_name_clone);
+                        println!("❌ [DEBUG] Available streams:");
+                        for (i, name) in available_names.iter().enumerate() {
+                            println!("❌ [DEBUG]   {}: {}", i + 1, name);
+                        }
+                        
+                        Err(format!("❌ No LSL stream found with name: '{}'. Available streams: {}", 
+                                   stream_name_clone, 
+                                   available_names.join(", ")))
+                    }
+                }
+                Err(e) => {
+                    println!("❌ [DEBUG] ===== LSL STREAM RESOLUTION FAILED =====");
+                    println!("❌ [DEBUG] Error: {}", e);
+                    println!("❌ [DEBUG] This usually means:");
+                    println!("❌ [DEBUG] 1. No LSL applications are running");
+                    println!("❌ [DEBUG] 2. LSL library is not properly installed");
+                    println!("❌ [DEBUG] 3. Network/firewall blocking LSL multicast");
+                    println!("❌ [DEBUG] 4. UnicornLSL.exe is not started");
+                    Err(format!("❌ Failed to resolve LSL streams: {}. Make sure UnicornLSL.exe is running and broadcasting stream '{}'.", e, stream_name_clone))
+                }
+            }
+        }).await;
+
+        println!("🔄 [DEBUG] ===== PROCESSING BLOCKING TASK RESULT =====");
+        match result {
+            Ok(Ok((info, channel_count, is_real))) => {
+                println!("✅ [DEBUG] Blocking task succeeded, updating processor state...");
+                
+                self.apply_connection(&info, channel_count, is_real, stream_name, None).await;
+                self.lsl_connection.lock().await.host = host.map(str::to_string);
+                
+                println!("✅ [DEBUG] EEG processor state updated successfully");
+                println!("✅ [DEBUG] ===== LSL CONNECTION COMPLETE =====");
+                Ok(info)
+            }
+            Ok(Err(e)) => {
+                println!("❌ [DEBUG] Blocking task returned error: {}", e);
+                Err(e)
+            }
+            Err(e) => {
+                println!("❌ [DEBUG] Blocking task execution failed: {}", e);
+                Err(format!("❌ Task execution failed: {}", e))
+            }
+        }
+    }
+
+    // Reset the connection and every per-stream state for a newly connected source. csv_input is
+    // the sample feed of a CSV source; None means samples are pulled from LSL.
+    async fn apply_connection(
+        &self,
+        info: &LSLStreamInfo,
+        channel_count: usize,
+        is_real: bool,
+        stream_name: &str,
+        csv_input: Option<mpsc::Receiver<EEGSample>>,
+    ) {
+        // Update connection state
+        let mut connection = self.lsl_connection.lock().await;
+        connection.stream_info = Some(info.clone());
+        connection.channel_count = channel_count;
+        connection.is_real_connection = is_real;
+        connection.stream_name = Some(stream_name.to_string());
+        connection.channel_order = None;
+        connection.next_sample_index = 0;
+        connection.eeg_channel_count = first_aux_channel(&info.channel_names).filter(|&count| count > 0);
+        if let Some(count) = connection.eeg_channel_count {
+            println!("🔧 [DEBUG] Treating the first {} of {} channels as EEG (auxiliary channels follow)", count, channel_count);
+        }
+        connection.irregular_rate = info.sample_rate == IRREGULAR_RATE;
+        connection.recent_timestamps.clear();
+        connection.last_sample = None;
+        connection.pending_samples.clear();
+        connection.string_channels = info.channel_format == "string";
+        connection.csv_source = csv_input.is_some();
+        connection.reconnect = ReconnectBackoff::default();
+        connection.host = None;
+        connection.timestamp_stats = TimestampStats::default();
+        connection.reset_rate_report();
+        connection.overflow_warned = false;
+        if connection.string_channels {
+            println!("🔤 [DEBUG] String-format stream - samples go out as string_sample, filters and FFT are skipped");
+        }
+        if connection.irregular_rate {
+            println!("⚠️ [DEBUG] Stream has an irregular sampling rate - spectral results will use an estimated rate and are approximate");
+        }
+        
+        // Resized before the connection lock is released, so no sample of the new stream can
+        // meet buffers sized for the old one
+        self.resize_channel_buffers(channel_count).await;
+        drop(connection);
+        *self.csv_input.lock().await = csv_input;
+        *self.inlet_worker.lock().await = None;
+        
+        self.replay_buffer.lock().await.frames.clear();
+        self.frame_history.lock().await.frames.clear();
+        *self.dc_offset.lock().await = None;
+        self.pending_markers.lock().await.clear();
+        self.epoch_markers.lock().await.clear();
+        self.gated_channels.lock().await.clear();
+        *self.layout_change.lock().await = None;
+        *self.blinks.lock().await = BlinkState::default();
+        {
+            // A baseline of the previous device's channels doesn't apply
+            let mut normalization = self.band_normalization.lock().await;
+            normalization.baseline.clear();
+            normalization.calibration = None;
+        }
+        {
+            let mut test_markers = self.test_markers.lock().await;
+            test_markers.onsets.clear();
+            test_markers.next_auto = None;
+        }
+        if let Some(trend) = self.variance_trend.lock().await.as_mut() {
+            *trend = VarianceTrend::new(trend.window_s);
+        }
+        self.band_history.lock().await.clear();
+        *self.settle_remaining.lock().await = *self.settle_samples.lock().await;
+        *self.display_gains.lock().await = vec![1.0; channel_count];
+        *self.channel_scales.lock().await = (0..channel_count)
+            .map(|ch| info.units.get(ch).map_or(1.0, |unit| unit_scale(unit)))
+            .collect();
+        *self.channel_polarity.lock().await = vec![false; channel_count];
+        *self.channel_band_profiles.lock().await = vec![None; channel_count];
+        *self.channel_signal_types.lock().await = vec![SignalType::Eeg; channel_count];
+        *self.latest_display_sample.lock().await = None;
+        if let Some(encoder) = self.delta_encoder.lock().await.as_mut() {
+            encoder.reset();
+        }
+        if let Some(auto_gain) = self.auto_gain.lock().await.as_mut() {
+            auto_gain.reset();
+        }
+        self.band_smoothing.lock().await.reset();
+        
+        // Initialize per-channel filters from the global config
+        *self.filter_rate.lock().await = self.sample_rate as f64;
+        let global_config = self.filter_config.lock().await.clone();
+        *self.channel_filters.lock().await = Some(
+            (0..channel_count)
+                .map(|_| ChannelFilter::new(global_config.clone(), self.sample_rate as f64))
+                .collect()
+        );
+    }
+
+    // Reads a CSV source (regular file, growing file or fifo) incrementally. The header names the
+    // channels; a first column called timestamp/time carries sample times, otherwise rows are
+    // stamped on arrival. The bounded channel is the backpressure: when the pipeline falls behind,
+    // the reader blocks, and so does whatever writes to the pipe.
+    async fn connect_to_csv_stream(&self, source: &str) -> Result<LSLStreamInfo, String> {
+        println!("📄 [DEBUG] Connecting to CSV stream '{}'", source);
+        
+        let path = source.to_string();
+        let opened = tokio::task::spawn_blocking(move || {
+            let start = std::time::Instant::now();
+            // Opening a fifo blocks until the writer connects, hence the blocking task
+            let mut reader = open_recording(&path)
+                .map_err(|e| format!("❌ Failed to open CSV source '{}': {}", path, e))?;
+            let mut metadata = Vec::new();
+            let mut line = String::new();
+            // Comment lines (e.g. a recording's metadata block) come before the header
+            loop {
+                match reader.read_line(&mut line) {
+                    // The caller stops waiting at the same limit; this lets the thread end too
+                    Ok(0) if start.elapsed().as_secs_f64() >= CSV_HEADER_TIMEOUT_S => {
+                        return Err(format!("❌ No CSV header from '{}' within {} s", path, CSV_HEADER_TIMEOUT_S));
+                    }
+                    Ok(0) => {
+                        std::thread::sleep(Duration::from_millis(10));
+                        continue;
+                    }
+                    Ok(_) if !line.ends_with('\n') => continue,
+                    Ok(_) => {}
+                    Err(e) => return Err(format!("❌ Failed to read CSV header from '{}': {}", path, e)),
+                }
+                let row = strip_bom(&line).trim();
+                if row.starts_with('#') {
+                    metadata.extend(parse_metadata_line(row));
+                } else if !row.is_empty() {
+                    let header = row.to_string();
+                    return Ok((reader, header, metadata));
+                }
+                line.clear();
+            }
+        });
+        let (reader, header, metadata) = tokio::time::timeout(Duration::from_secs_f64(CSV_HEADER_TIMEOUT_S), opened)
+            .await
+            .map_err(|_| format!("❌ No CSV header from '{}' within {} s", source, CSV_HEADER_TIMEOUT_S))?
+            .map_err(|e| format!("❌ Task execution failed: {}", e))??;
+        
+        // A dump_replay_buffer file plays back its raw half; the filters run again live
+        let layout = CsvLayout::parse(&header)?;
+        let (columns, names) = layout.channels("raw_");
+        let channel_count = columns.len();
+        let timestamp_column = layout.timestamp_column;
+        let metadata_keys: Vec<&str> = metadata.iter().map(|(key, _)| key.as_str()).collect();
+        
+        let info = LSLStreamInfo {
+            channel_format: "float32".to_string(),
+            name: source.to_string(),
+            channel_count: channel_count as i32,
+            sample_rate: IRREGULAR_RATE, // unknown up front; estimated from the sample times
+            is_connected: true,
+            metadata: format!(
+                "📄 CSV STREAM - Source: {} | Channels: {} | Timestamps: {} | Metadata: {}",
+                source,
+                channel_count,
+                if timestamp_column { "from file" } else { "arrival time" },
+                if metadata_keys.is_empty() { "none".to_string() } else { metadata_keys.join(", ") }
+            ),
+            stream_type: "CSV".to_string(),
+            source_id: source.to_string(),
+            units: vec![String::new(); channel_count],
+            channel_names: names,
+            manufacturer: "Unknown".to_string(),
+            device_model: "CSV".to_string(),
+        };
+        
+        let (tx, rx) = mpsc::channel(CSV_CHANNEL_CAPACITY);
+        Self::spawn_csv_reader(reader, layout, columns, tx);
+        self.apply_connection(&info, channel_count, true, source, Some(rx)).await;
+        
+        println!("✅ [DEBUG] CSV stream connected: {} channels, header {:?}", channel_count, info.channel_names);
+        Ok(info)
+    }
+
+    fn spawn_csv_reader(
+        mut reader: Box<dyn std::io::BufRead + Send>,
+        layout: CsvLayout,
+        columns: Vec<usize>,
+        tx: mpsc::Sender<EEGSample>,
+    ) {
+        std::thread::spawn(move || {
+            let start = std::time::Instant::now();
+            let mut line = String::new();
+            let mut line_number = 1usize;
+            let mut skipped_rows = 0usize;
+            
+            // Ends when the connection drops its receiver (disconnect or a new connect)
+            while !tx.is_closed() {
+                match reader.read_line(&mut line) {
+                    // Nothing new yet; a partial line stays in `line` and is completed by later reads
+                    Ok(0) => {
+                        std::thread::sleep(Duration::from_millis(5));
+                        continue;
+                    }
+                    Ok(_) if !line.ends_with('\n') => continue,
+                    Ok(_) => {}
+                    Err(e) => {
+                        eprintln!("❌ [DEBUG] CSV source read failed: {}", e);
+                        break;
+                    }
+                }
+                line_number += 1;
+                
+                let row = line.trim();
+                if !row.is_empty() && !row.starts_with('#') {
+                    match layout.parse_row(row, &columns) {
+                        Ok((timestamp, channels)) => {
+                            let sample = EEGSample {
+                                timestamp: timestamp.unwrap_or_else(|| start.elapsed().as_secs_f64()),
+                                sample_index: 0, // assigned once accepted
+                                channels,
+                                interpolated: false,
+                                lsl_timestamp: None,
+                            };
+                            if tx.blocking_send(sample).is_err() {
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            skipped_rows += 1;
+                            if skipped_rows <= CSV_SKIP_LOG_LIMIT {
+                                println!("⚠️ [DEBUG] Skipping CSV line {}: {}", line_number, e);
+                            }
+                        }
+                    }
+                }
+                line.clear();
+            }
+            println!("📄 [DEBUG] CSV reader stopped after {} lines, {} malformed rows skipped", line_number, skipped_rows);
+        });
+    }
+
+    fn extract_real_channel_names_sync(stream_info: &StreamInfo, channel_count: usize) -> Vec<String> {
+        println!("🔍 [DEBUG] Extracting channel names from LSL stream...");
+        
+        let mut channel_names = Vec::new();
+        let mut known_device = true;
+        
+        // Try to detect device type and use known layouts
+        let source_id = stream_info.source_id().to_lowercase();
+        let stream_name = stream_info.hostname().to_lowercase();
+        
+        println!("🔍 [DEBUG] Device detection - Source ID: '{}', Stream Name: '{}'", source_id, stream_name);
+        
+        // Unicorn Hybrid Black specific channel layout
+        if source_id.contains("unicorn") || stream_name.contains("unicorn") || stream_name == "123" {
+            println!("🦄 [DEBUG] Detected Unicorn Hybrid Black device");
+            let unicorn_channels = vec![
+                "Fz", "C3", "Cz", "C4", "Pz", "PO7", "Oz", "PO8",
+                "ACC_X", "ACC_Y", "ACC_Z", "GYR_X", "GYR_Y", "GYR_Z", 
+                "Battery", "Counter", "Validation"
+            ];
+            
+            for i in 0..channel_count.min(unicorn_channels.len()) {
+                channel_names.push(unicorn_channels[i].to_string());
+            }
+        }
+        // OpenBCI detection
+        else if source_id.contains("openbci") || stream_name.contains("openbci") {
+            println!("🧠 [DEBUG] Detected OpenBCI device");
+            let openbci_8ch = vec!["Fp1", "Fp2", "C3", "C4", "P7", "P8", "O1", "O2"];
+            let openbci_16ch = vec![
+                "Fp1", "Fp2", "F7", "F3", "F4", "F8", "C3", "Cz", 
+                "C4", "T7", "T8", "P7", "P3", "Pz", "P4", "P8"
+            ];
+            
+            let channels = if channel_count <= 8 { &openbci_8ch } else { &openbci_16ch };
+            for i in 0..channel_count.min(channels.len()) {
+                channel_names.push(channels[i].to_string());
+            }
+        }
+        // Emotiv detection
+        else if source_id.contains("emotiv") || stream_name.contains("emotiv") {
+            println!("🎭 [DEBUG] Detected Emotiv device");
+            let emotiv_channels = vec![
+                "AF3", "F7", "F3", "FC5", "T7", "P7", "O1", "O2", 
+                "P8", "T8", "FC6", "F4", "F8", "AF4"
+            ];
+            
+            for i in 0..channel_count.min(emotiv_channels.len()) {
+                channel_names.push(emotiv_channels[i].to_string());
+            }
+        }
+        // Generic fallback
+        else {
+            println!("❓ [DEBUG] Unknown device, using generic channel names");
+            known_device = false;
+            for i in 0..channel_count {
+                channel_names.push(format!("Ch{}", i + 1));
+            }
+        }
+        
+        // More channels than the device's montage: label the extras as auxiliary, not ChN
+        if known_device && channel_names.len() < channel_count {
+            let extras: Vec<String> = (1..=channel_count - channel_names.len()).map(aux_label).collect();
+            println!(
+                "⚠️ [DEBUG] Stream has {} channels but the device montage defines {}; extra channels {:?} are treated as non-EEG",
+                channel_count, channel_names.len(), extras
+            );
+            channel_names.extend(extras);
+        }
+        
+        // Ensure we have the right number of channels
+        while channel_names.len() < channel_count {
+            channel_names.push(format!("Ch{}", channel_names.len() + 1));
+        }
+        
+        // Truncate if we have too many
+        channel_names.truncate(channel_count);
+        
+        println!("✅ [DEBUG] Final channel names: {:?}", channel_names);
+        channel_names
+    }
+
+    // The <desc> block is only present in the full header fetched through an open inlet; resolved
+    // infos don't carry it. Missing, extra or malformed <channel> entries just leave units empty.
+    fn extract_channel_units_sync(inlet: &StreamInlet, channel_count: usize) -> Vec<String> {
+        let mut units = Vec::with_capacity(channel_count);
+        match inlet.info(2.0) {
+            Ok(mut full_info) => {
+                let mut channel = full_info.desc().child("channels").child("channel");
+                while !channel.empty() && units.len() < channel_count {
+                    units.push(channel.child_value_named("unit").trim().to_string());
+                    channel = channel.next_sibling_named("channel");
+                }
+            }
+            Err(e) => println!("⚠️ [DEBUG] Could not fetch the full stream header for channel units: {}", e),
+        }
+        units.resize(channel_count, String::new());
+        
+        println!("📏 [DEBUG] Channel units: {:?}", units);
+        units
+    }
+
+    fn extract_device_info_sync(stream_info: &StreamInfo) -> (String, String) {
+        let (manufacturer, model) = match detect_device(&stream_info.source_id(), &stream_info.hostname()) {
+            Some((_, manufacturer, model)) => (manufacturer, model),
+            None => ("Unknown Manufacturer", "EEG Device"),
+        };
+        (manufacturer.to_string(), model.to_string())
+    }
+
+    async fn disconnect_lsl(&self) {
+        println!("🔌 [DEBUG] Disconnecting from LSL stream");
+        let mut connection = self.lsl_connection.lock().await;
+        connection.stream_info = None;
+        connection.channel_count = 8;
+        connection.is_real_connection = false;
+        connection.stream_name = None;
+        connection.channel_order = None;
+        connection.eeg_channel_count = None;
+        connection.irregular_rate = false;
+        connection.recent_timestamps.clear();
+        connection.last_sample = None;
+        connection.pending_samples.clear();
+        connection.string_channels = false;
+        connection.csv_source = false;
+        connection.reconnect = ReconnectBackoff::default();
+        connection.host = None;
+        connection.reset_rate_report();
+        self.resize_channel_buffers(connection.channel_count).await;
+        drop(connection);
+        *self.reconnect_status.lock().await = None;
+        
+        // Dropping the receiver stops a CSV reader or inlet worker thread
+        *self.csv_input.lock().await = None;
+        *self.inlet_worker.lock().await = None;
+        *self.channel_filters.lock().await = None;
+        *self.latest_display_sample.lock().await = None;
+        println!("✅ [DEBUG] LSL disconnection complete");
+    }
+
+    // True while a gap fill still has samples queued for get_lsl_sample
+    async fn has_pending_samples(&self) -> bool {
+        !self.lsl_connection.lock().await.pending_samples.is_empty()
+    }
+
+    // Create new inlet each time to avoid threading issues
+    async fn get_lsl_sample(&self) -> Option<EEGSample> {
+        let mut connection = self.lsl_connection.lock().await;
+        
+        // Only try to get real data if we have a real connection
+        if !connection.is_real_connection || connection.string_channels {
+            return None;
+        }
+        
+        // Samples queued by a gap fill come out before anything new is pulled
+        if let Some(sample) = connection.pending_samples.pop_front() {
+            return Some(sample);
+        }
+        let irregular_rate = connection.irregular_rate;
+        
+        if connection.csv_source {
+            let channel_order = connection.channel_order.clone();
+            let eeg_channels = connection.eeg_channel_count.unwrap_or(usize::MAX);
+            drop(connection);
+            let mut sample = self.csv_input.lock().await.as_mut()?.try_recv().ok()?;
+            self.apply_test_markers(&mut sample, eeg_channels).await;
+            return self.accept_sample(sample, channel_order, irregular_rate).await;
+        }
+        
+        // The worker thread does its own re-resolving, so the backoff doesn't gate it
+        if *self.pull_mode.lock().await == PullMode::Blocking {
+            let stream_name = connection.stream_name.clone()?;
+            let (channel_count, host) = (connection.channel_count, connection.host.clone());
+            let channel_order = connection.channel_order.clone();
+            let source_id = connection.pinned_source();
+            drop(connection);
+            let target = self.stream_target(stream_name, source_id).await;
+            return self.take_worker_sample(target, channel_count, host, channel_order, irregular_rate).await;
+        }
+        
+        if !connection.reconnect.due() {
+            return None;
+        }
+        let stream_name = connection.stream_name.clone()?;
+        let channel_count = connection.channel_count;
+        let channel_order = connection.channel_order.clone();
+        let host = connection.host.clone();
+        let source_id = connection.pinned_source();
+        drop(connection); // Release lock before blocking operation
+        let pull_timeout = *self.pull_timeout.lock().await;
+        let target = self.stream_target(stream_name.clone(), source_id).await;
+        
+        // Use blocking task for LSL operations - create fresh inlet each time.
+        // Yields (stream found, source switched to, sample) so a missing stream feeds the
+        // reconnect backoff.
+        let expected = Duration::from_secs_f64(0.1 + pull_timeout);
+        let result = spawn_lsl_blocking(expected, move || {
+            match Self::resolve_candidates(host.as_deref(), 0.1) {
+                Ok(streams) => {
+                    let matching_stream = target.select(&streams);
+                    
+                    if let Some(stream_info) = matching_stream.as_ref() {
+                        let sample = match StreamInlet::new(stream_info, 360, 1, true) {
+                            Ok(inlet) => {
+                                // Pull sample with very short timeout
+                                match <StreamInlet as Pullable<f32>>::pull_sample(&inlet, pull_timeout) {
+                                    Ok((sample, timestamp)) => {
+                                        // Extra values are kept for accept_sample to grow or cut
+                                        let mut channels = sample;
+                                        if channels.len() < channel_count {
+                                            channels.resize(channel_count, 0.0);
+                                        }
+                                        
+                                        Some(EEGSample {
+                                            timestamp,
+                                            sample_index: 0, // assigned once accepted
+                                            channels,
+                                            interpolated: false,
+                                            lsl_timestamp: Some(timestamp),
+                                        })
+                                    }
+                                    Err(_) => None, // No data available right now
+                                }
+                            }
+                            Err(_) => None,
+                        };
+                        (true, target.switched(stream_info), sample)
+                    } else {
+                        (false, None, None)
+                    }
+                }
+                Err(_) => (false, None, None),
+            }
+        }).await;
+        
+        // A failed task says nothing about the stream, so it doesn't count as a miss
+        let (found, switched, sample) = result.unwrap_or((true, None, None));
+        self.record_resolve(&stream_name, found).await;
+        if let Some((source_id, name)) = switched {
+            self.record_source_switch(&stream_name, source_id, name).await;
+        }
+        self.accept_sample(sample?, channel_order, irregular_rate).await
+    }
+
+    async fn stream_target(&self, name: String, source_id: Option<String>) -> StreamTarget {
+        StreamTarget {
+            name,
+            match_field: *self.match_field.lock().await,
+            max_streams: *self.max_streams.lock().await,
+            source_id,
+            any_match: *self.reconnect_any_match.lock().await,
+        }
+    }
+
+    // Later pulls pin the new source; connection_status reports the stream taken
+    async fn record_source_switch(&self, stream_name: &str, source_id: String, name: String) {
+        let mut connection = self.lsl_connection.lock().await;
+        let Some(info) = connection.stream_info.as_mut() else { return };
+        let previous = std::mem::replace(&mut info.source_id, source_id.clone());
+        drop(connection);
+        
+        let message = format!(
+            "Stream '{}' reconnected to '{}' with source_id '{}' (was '{}')",
+            stream_name, name, source_id, previous
+        );
+        println!("🔁 [DEBUG] {}", message);
+        *self.reconnect_status.lock().await = Some(ConnectionStatus {
+            status: "switched_source".to_string(),
+            stream_name: Some(name),
+            message,
+            effective_rate: None,
+        });
+    }
+
+    // Accept a device that re-appears under another source_id; off waits for the original source
+    async fn set_reconnect_to_any_match(&self, enabled: bool) {
+        *self.reconnect_any_match.lock().await = enabled;
+        // A running inlet worker keeps the rule it started with, so start a new one
+        *self.inlet_worker.lock().await = None;
+        println!("🔁 [DEBUG] Reconnect to any matching stream: {}", if enabled { "on" } else { "off" });
+    }
+
+    // Grow buffers and filters to fit samples wider than the stream announced; off cuts them back
+    async fn set_auto_expand_channels(&self, enabled: bool) {
+        *self.auto_expand_channels.lock().await = enabled;
+        println!("📐 [DEBUG] Auto-expand channels {}", if enabled { "enabled" } else { "disabled" });
+    }
+
+    // Next queued sample of the blocking-mode worker, starting the worker if it isn't running yet
+    async fn take_worker_sample(
+        &self,
+        target: StreamTarget,
+        channel_count: usize,
+        host: Option<String>,
+        channel_order: Option<Vec<usize>>,
+        irregular_rate: bool,
+    ) -> Option<EEGSample> {
+        let stream_name = target.name.clone();
+        let mut worker = self.inlet_worker.lock().await;
+        if worker.is_none() {
+            *worker = Some(Self::spawn_inlet_worker(target, host, channel_count));
+        }
+        let rx = worker.as_mut()?;
+        
+        let mut updates = Vec::new();
+        let sample = loop {
+            match rx.try_recv() {
+                Ok(InletMessage::Sample(sample)) => break Some(sample),
+                Ok(update) => updates.push(update),
+                Err(_) => break None,
+            }
+        };
+        drop(worker);
+        
+        for update in updates {
+            match update {
+                InletMessage::Resolved(found) => self.record_resolve(&stream_name, found).await,
+                InletMessage::Switched(source_id, name) => self.record_source_switch(&stream_name, source_id, name).await,
+                InletMessage::Sample(_) => {}
+            }
+        }
+        self.accept_sample(sample?, channel_order, irregular_rate).await
+    }
+
+    // Holds one inlet for as long as the stream lasts, re-resolving after a loss; ends when the
+    // processor drops the receiver (disconnect, a new connect or a pull mode change)
+    fn spawn_inlet_worker(
+        mut target: StreamTarget,
+        host: Option<String>,
+        channel_count: usize,
+    ) -> mpsc::Receiver<InletMessage> {
+        let (tx, rx) = mpsc::channel(CSV_CHANNEL_CAPACITY);
+        std::thread::spawn(move || {
+            let stream_name = target.name.clone();
+            println!("🧵 [DEBUG] Inlet worker for '{}' started", stream_name);
+            while !tx.is_closed() {
+                let streams = Self::resolve_candidates(host.as_deref(), 0.5).unwrap_or_default();
+                let selected = target.select(&streams);
+                let switched = selected.as_ref().and_then(|info| target.switched(info));
+                let inlet = selected.and_then(|info| StreamInlet::new(&info, 360, 1, true).ok());
+                if tx.blocking_send(InletMessage::Resolved(inlet.is_some())).is_err() {
+                    break;
+                }
+                let Some(inlet) = inlet else {
+                    std::thread::sleep(Duration::from_secs_f64(INLET_WORKER_RETRY_S));
+                    continue;
+                };
+                if let Some((source_id, name)) = switched {
+                    target.source_id = Some(source_id.clone());
+                    if tx.blocking_send(InletMessage::Switched(source_id, name)).is_err() {
+                        break;
+                    }
+                }
+                
+                while !tx.is_closed() {
+                    match <StreamInlet as Pullable<f32>>::pull_sample(&inlet, BLOCKING_PULL_TIMEOUT_S) {
+                        // A timeout returns timestamp 0.0 with no data
+                        Ok((_, 0.0)) => {}
+                        Ok((values, timestamp)) => {
+                            let mut channels = values;
+                            if channels.len() < channel_count {
+                                channels.resize(channel_count, 0.0);
+                            }
+                            let sample = EEGSample {
+                                timestamp,
+                                sample_index: 0, // assigned once accepted
+                                channels,
+                                interpolated: false,
+                                lsl_timestamp: Some(timestamp),
+                            };
+                            if tx.blocking_send(InletMessage::Sample(sample)).is_err() {
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            println!("⚠️ [DEBUG] Inlet worker lost '{}': {}", stream_name, e);
+                            std::thread::sleep(Duration::from_secs_f64(INLET_WORKER_RETRY_S));
+                            break;
+                        }
+                    }
+                }
+            }
+            println!("🧵 [DEBUG] Inlet worker for '{}' stopped", stream_name);
+        });
+        rx
+    }
+
+    // A sample wider than the connected stream (firmware sending more channels than its metadata
+    // announces) grows the layout with set_auto_expand_channels, otherwise it's cut back with a
+    // warning once per connection. True when the layout grew.
+    async fn fit_sample_width(&self, sample: &mut EEGSample) -> bool {
+        let mut connection = self.lsl_connection.lock().await;
+        let expected = connection.channel_count;
+        let width = sample.channels.len();
+        if width <= expected {
+            return false;
+        }
+        if *self.auto_expand_channels.lock().await {
+            drop(connection);
+            self.expand_channel_layout(width).await;
+            return true;
+        }
+        if !connection.overflow_warned {
+            connection.overflow_warned = true;
+            println!(
+                "⚠️ [DEBUG] Stream sent {} channels but announced {} - extra channels are dropped (set_auto_expand_channels keeps them)",
+                width, expected
+            );
+        }
+        sample.channels.truncate(expected);
+        false
+    }
+
+    // Appends channels up to channel_count, keeping the existing channels' buffers, filter state
+    // and settings; new ones get what a connect would give them
+    async fn expand_channel_layout(&self, channel_count: usize) {
+        let mut connection = self.lsl_connection.lock().await;
+        let previous_count = connection.channel_count;
+        if channel_count <= previous_count {
+            return;
+        }
+        connection.channel_count = channel_count;
+        if let Some(order) = connection.channel_order.as_mut() {
+            order.extend(previous_count..channel_count);
+        }
+        let mut channel_names = Vec::new();
+        if let Some(info) = connection.stream_info.as_mut() {
+            info.channel_count = channel_count as i32;
+            while info.channel_names.len() < channel_count {
+                info.channel_names.push(format!("Ch{}", info.channel_names.len() + 1));
+            }
+            info.units.resize(channel_count, String::new());
+            channel_names = info.channel_names.clone();
+        }
+        {
+            // Grown before the connection lock is released, as on connect
+            let mut raw_buffers = self.channel_buffers.lock().await;
+            let mut filtered_buffers = self.filtered_buffers.lock().await;
+            raw_buffers.resize(channel_count, Vec::new());
+            filtered_buffers.resize(channel_count, Vec::new());
+        }
+        drop(connection);
+        
+        self.display_gains.lock().await.resize(channel_count, 1.0);
+        self.channel_scales.lock().await.resize(channel_count, 1.0);
+        self.channel_polarity.lock().await.resize(channel_count, false);
+        self.channel_band_profiles.lock().await.resize(channel_count, None);
+        self.channel_signal_types.lock().await.resize(channel_count, SignalType::Eeg);
+        *self.latest_display_sample.lock().await = None;
+        if let Some(encoder) = self.delta_encoder.lock().await.as_mut() {
+            encoder.reset();
+        }
+        let rate = *self.filter_rate.lock().await;
+        let global_config = self.filter_config.lock().await.clone();
+        if let Some(filters) = self.channel_filters.lock().await.as_mut() {
+            while filters.len() < channel_count {
+                filters.push(ChannelFilter::new(global_config.clone(), rate));
+            }
+        }
+        
+        println!("📐 [DEBUG] Stream sends {} channels, expected {} - layout expanded", channel_count, previous_count);
+        *self.layout_change.lock().await = Some(ChannelLayoutChange { previous_count, channel_count, channel_names });
+    }
+
+    async fn take_layout_change(&self) -> Option<ChannelLayoutChange> {
+        self.layout_change.lock().await.take()
+    }
+
+    // Common tail of every pulled sample: channel order, rate tracking, gap fill and numbering
+    async fn accept_sample(&self, mut sample: EEGSample, channel_order: Option<Vec<usize>>, irregular_rate: bool) -> Option<EEGSample> {
+        // An expansion also extends the channel order, so read it again afterwards
+        let channel_order = if self.fit_sample_width(&mut sample).await {
+            self.lsl_connection.lock().await.channel_order.clone()
+        } else {
+            channel_order
+        };
+        // Remap right after sampling so buffers, filters and events all see the display order
+        if let Some(order) = channel_order {
+            sample.channels = reorder(&sample.channels, &order);
+        }
+        for (value, scale) in sample.channels.iter_mut().zip(self.channel_scales.lock().await.iter()) {
+            *value *= scale;
+        }
+        self.apply_polarity(&mut sample.channels, PolarityStage::BeforeFilter).await;
+        if !*self.emit_lsl_timestamp.lock().await {
+            sample.lsl_timestamp = None;
+        }
+        
+        let max_gap = *self.gap_interpolation.lock().await;
+        let guard = *self.timestamp_guard.lock().await;
+        let rate_smoothing = *self.rate_smoothing.lock().await;
+        let mut connection = self.lsl_connection.lock().await;
+        
+        // Gap detection, interpolation and the rate estimate all assume time moves forward
+        if let Some(previous) = connection.last_sample.as_ref().map(|s| s.timestamp) {
+            if sample.timestamp <= previous || !sample.timestamp.is_finite() {
+                let stats = &mut connection.timestamp_stats;
+                if sample.timestamp == previous {
+                    stats.duplicates += 1;
+                } else {
+                    stats.out_of_order += 1;
+                }
+                match guard {
+                    TimestampGuard::Drop => {
+                        stats.dropped += 1;
+                        return None;
+                    }
+                    TimestampGuard::Restamp => {
+                        stats.restamped += 1;
+                        sample.timestamp = previous + 1.0 / self.sample_rate as f64;
+                    }
+                }
+            }
+        }
+        
+        connection.recent_timestamps.push_back(sample.timestamp);
+        if connection.recent_timestamps.len() > RATE_ESTIMATE_WINDOW {
+            connection.recent_timestamps.pop_front();
+        }
+        connection.update_rate_report(sample.timestamp, rate_smoothing);
+        
+        let mut samples = match connection.last_sample.as_ref() {
+            Some(previous) if max_gap > 0 && !irregular_rate => {
+                Self::fill_gap(previous, &sample, self.sample_rate as f64, max_gap)
+            }
+            _ => Vec::new(),
+        };
+        if !samples.is_empty() {
+            println!("🩹 [DEBUG] Interpolated a gap of {} samples before t={:.4}", samples.len(), sample.timestamp);
+        }
+        // Only pulled samples are counted; fill_gap gives the filled ones the index before the gap
+        sample.sample_index = connection.next_sample_index;
+        connection.next_sample_index += 1;
+        connection.last_sample = Some(sample.clone());
+        samples.push(sample);
+        connection.pending_samples.extend(samples);
+        connection.pending_samples.pop_front()
+    }
+
+    async fn get_timestamp_stats(&self) -> TimestampStats {
+        self.lsl_connection.lock().await.timestamp_stats.clone()
+    }
+
+    // Samples missing between two pulls, judged from the timestamp step against the nominal rate.
+    // Gaps of 1..=max_gap samples are bridged linearly (marked interpolated); longer ones are left.
+    fn fill_gap(previous: &EEGSample, next: &EEGSample, rate: f64, max_gap: usize) -> Vec<EEGSample> {
+        let step = next.timestamp - previous.timestamp;
+        let missing = (step * rate).round() as i64 - 1;
+        if missing < 1 || missing as usize > max_gap || previous.channels.len() != next.channels.len() {
+            return Vec::new();
+        }
+        
+        let parts = (missing + 1) as f64;
+        (1..=missing)
+            .map(|k| {
+                let t = k as f64 / parts;
+                EEGSample {
+                    timestamp: previous.timestamp + t * step,
+                    sample_index: previous.sample_index,
+                    channels: previous.channels.iter()
+                        .zip(next.channels.iter())
+                        .map(|(&a, &b)| a + (b - a) * t as f32)
+                        .collect(),
+                    interpolated: true,
+                    lsl_timestamp: None,
+                }
+            })
+            .collect()
+    }
+
+    // 0 disables interpolation
+    async fn set_gap_interpolation(&self, max_samples: usize) -> Result<(), String> {
+        if max_samples > MAX_GAP_INTERPOLATION {
+            return Err(format!("❌ Invalid gap interpolation length {}: must be 0-{} samples", max_samples, MAX_GAP_INTERPOLATION));
+        }
+        
+        *self.gap_interpolation.lock().await = max_samples;
+        println!("🩹 [DEBUG] Gap interpolation {}", if max_samples > 0 { format!("up to {} samples", max_samples) } else { "disabled".to_string() });
+        Ok(())
+    }
+
+    // Text counterpart of get_lsl_sample for cf_string streams; None for numeric streams
+    async fn get_string_sample(&self) -> Option<StringSample> {
+        let connection = self.lsl_connection.lock().await;
+        if !connection.is_real_connection || !connection.string_channels || !connection.reconnect.due() {
+            return None;
+        }
+        
+        let stream_name = connection.stream_name.clone()?;
+        let channel_order = connection.channel_order.clone();
+        let host = connection.host.clone();
+        let source_id = connection.pinned_source();
+        drop(connection);
+        let pull_timeout = *self.pull_timeout.lock().await;
+        let target = self.stream_target(stream_name.clone(), source_id).await;
+        
+        let expected = Duration::from_secs_f64(0.1 + pull_timeout);
+        let result = spawn_lsl_blocking(expected, move || {
+            let Ok(streams) = Self::resolve_candidates(host.as_deref(), 0.1) else {
+                return (false, None, None);
+            };
+            let Some(stream_info) = target.select(&streams) else {
+                return (false, None, None);
+            };
+            let switched = target.switched(&stream_info);
+            if stream_info.channel_format() != ChannelFormat::String {
+                return (true, switched, None);
+            }
+            
+            let Ok(inlet) = StreamInlet::new(&stream_info, 360, 1, true) else {
+                return (true, switched, None);
+            };
+            let sample = match <StreamInlet as Pullable<String>>::pull_sample(&inlet, pull_timeout) {
+                Ok((channels, timestamp)) if timestamp != 0.0 => Some(StringSample {
+                    timestamp,
+                    sample_index: 0, // assigned once accepted
+                    channels,
+                }),
+                _ => None,
+            };
+            (true, switched, sample)
+        }).await;
+        
+        let (found, switched, sample) = result.unwrap_or((true, None, None));
+        self.record_resolve(&stream_name, found).await;
+        if let Some((source_id, name)) = switched {
+            self.record_source_switch(&stream_name, source_id, name).await;
+        }
+        let mut sample = sample?;
+        
+        let mut connection = self.lsl_connection.lock().await;
+        sample.sample_index = connection.next_sample_index;
+        connection.next_sample_index += 1;
+        drop(connection);
+        
+        if let Some(order) = channel_order {
+            if order.len() == sample.channels.len() {
+                sample.channels = reorder(&sample.channels, &order);
+            }
+        }
+        
+        Some(sample)
+    }
+
+    // Feeds one resolve outcome of the sample pulls into the reconnect backoff and queues the
+    // matching connection_status for the processing loop
+    async fn record_resolve(&self, stream_name: &str, found: bool) {
+        let mut connection = self.lsl_connection.lock().await;
+        let status = if found {
+            let failures = connection.reconnect.succeeded();
+            if failures == 0 {
+                return;
+            }
+            println!("🔁 [DEBUG] Stream '{}' is back after {} failed attempts", stream_name, failures);
+            ConnectionStatus {
+                status: "reconnected".to_string(),
+                stream_name: Some(stream_name.to_string()),
+                message: format!("Stream '{}' is back after {} failed attempts", stream_name, failures),
+                effective_rate: None,
+            }
+        } else {
+            let max_interval = *self.max_reconnect_interval.lock().await;
+            let delay = connection.reconnect.failed(max_interval);
+            let attempt = connection.reconnect.failures;
+            println!("🔁 [DEBUG] Stream '{}' not found (attempt {}), next try in {:.2} s", stream_name, attempt, delay);
+            ConnectionStatus {
+                status: "reconnecting".to_string(),
+                stream_name: Some(stream_name.to_string()),
+                message: format!("Stream '{}' not found (attempt {}), retrying in {:.1} s", stream_name, attempt, delay),
+                effective_rate: None,
+            }
+        };
+        drop(connection);
+        *self.reconnect_status.lock().await = Some(status);
+    }
+
+    async fn take_reconnect_status(&self) -> Option<ConnectionStatus> {
+        self.reconnect_status.lock().await.take()
+    }
+
+    // Once per report window while samples arrive
+    async fn take_rate_status(&self) -> Option<ConnectionStatus> {
+        let mut connection = self.lsl_connection.lock().await;
+        if !std::mem::take(&mut connection.rate_report_due) {
+            return None;
+        }
+        let rate = connection.smoothed_rate?;
+        Some(ConnectionStatus {
+            status: "streaming".to_string(),
+            stream_name: connection.stream_name.clone(),
+            message: format!("Receiving {:.1} Hz", rate),
+            effective_rate: Some(rate),
+        })
+    }
+    
+    async fn set_rate_smoothing(&self, alpha: f64) -> Result<(), String> {
+        if !(alpha > 0.0 && alpha <= 1.0) {
+            return Err(format!("❌ Invalid rate smoothing {}: must be in (0, 1], 1 disables smoothing", alpha));
+        }
+        
+        *self.rate_smoothing.lock().await = alpha;
+        println!("📶 [DEBUG] Reported sample rate smoothing set to {}", alpha);
+        Ok(())
+    }
+
+    async fn set_max_reconnect_interval(&self, seconds: f32) -> Result<(), String> {
+        if !(RECONNECT_BASE_DELAY_S..=MAX_RECONNECT_INTERVAL_S).contains(&seconds) {
+            return Err(format!(
+                "❌ Invalid reconnect interval {} s: must be between {} and {} seconds",
+                seconds, RECONNECT_BASE_DELAY_S, MAX_RECONNECT_INTERVAL_S
+            ));
+        }
+        
+        *self.max_reconnect_interval.lock().await = seconds;
+        println!("🔁 [DEBUG] Reconnect backoff capped at {} s", seconds);
+        Ok(())
+    }
+
+    // Streams to select from: everything discovery finds, or only those whose LSL hostname is
+    // `host`. Either way liblsl resolves by multicast plus the KnownPeers of its lsl_api.cfg;
+    // resolve_byprop just narrows the query and returns on the host's first response.
+    fn resolve_candidates(host: Option<&str>, wait_time: f64) -> Result<Vec<StreamInfo>, lsl::Error> {
+        match host {
+            Some(host) => resolve_byprop("hostname", host, 1, wait_time),
+            None => resolve_streams(wait_time),
+        }
+    }
+
+    // The one stream-selection rule shared by connect, the sample pulls and health polls, so a
+    // stream that connected is also the one the hot path keeps reading from. Only the first
+    // max_streams resolved streams are looked at; each pass stops at its first match.
+    fn select_stream<'a>(
+        streams: &'a [StreamInfo],
+        stream_name: &str,
+        match_field: MatchField,
+        max_streams: usize,
+    ) -> Option<&'a StreamInfo> {
+        let streams = &streams[..streams.len().min(max_streams)];
+        let exact = |field: MatchField| {
+            streams.iter().find(|stream| field.property(stream).is_some_and(|value| value.eq_ignore_ascii_case(stream_name)))
+        };
+        
+        match match_field {
+            MatchField::Auto => MatchField::AUTO_ORDER.into_iter()
+                .find_map(exact)
+                .or_else(|| streams.iter().find(|stream| Self::legacy_stream_match(stream, stream_name))),
+            field => exact(field),
+        }
+    }
+
+    // Exact uid, partial hostname/source_id match, plus the UnicornLSL "123" and "unicorn"
+    // shorthands; Auto's last resort
+    fn legacy_stream_match(stream: &StreamInfo, stream_name: &str) -> bool {
+        let hostname = stream.hostname().to_lowercase();
+        let source_id = stream.source_id().to_lowercase();
+        let uid = stream.uid().to_lowercase();
+        let target = stream_name.to_lowercase();
+        
+        let exact_uid = uid == target;
+        let unicorn_123 = target == "123" && (hostname == "123" || source_id.contains("unicorn") || uid.contains("unicorn"));
+        let unicorn_match = target.contains("unicorn") && (hostname.contains("unicorn") || source_id.contains("unicorn"));
+        
+        exact_uid ||
+        source_id.contains(&target) || 
+        hostname.contains(&target) ||
+        unicorn_123 ||
+        unicorn_match
+    }
+
+    async fn set_max_streams(&self, n: usize) -> Result<(), String> {
+        if n == 0 {
+            return Err("❌ Invalid stream limit 0: at least one stream must be considered".to_string());
+        }
+        
+        *self.max_streams.lock().await = n;
+        println!("🔍 [DEBUG] Stream discovery limited to {} streams", n);
+        Ok(())
+    }
+
+    async fn set_match_field(&self, field: &str) -> Result<(), String> {
+        let field: MatchField = field.parse()?;
+        *self.match_field.lock().await = field;
+        println!("🔍 [DEBUG] Stream match field set to {:?}", field);
+        Ok(())
+    }
+
+    // Only resolves - never opens an inlet, so the active connection is untouched
+    async fn ping_stream(stream_name: &str, match_field: MatchField, max_streams: usize) -> bool {
+        let stream_name = stream_name.to_string();
+        spawn_lsl_blocking(Duration::from_millis(500), move || {
+            match resolve_streams(0.5) {
+                Ok(streams) => Self::select_stream(&streams, &stream_name, match_field, max_streams).is_some(),
+                Err(_) => false,
+            }
+        })
+        .await
+        .unwrap_or(false)
+    }
+
+    // Opens its own inlet for a short burst and closes it; the active connection is untouched
+    async fn probe_stream(stream_name: &str, match_field: MatchField, max_streams: usize) -> Result<StreamProbe, String> {
+        let stream_name = stream_name.to_string();
+        spawn_lsl_blocking(Duration::from_secs_f64(3.0 + PROBE_BURST_S), move || -> Result<StreamProbe, String> {
+            let streams = resolve_streams(1.0).map_err(|e| format!("❌ Failed to resolve streams: {}", e))?;
+            let stream_info = Self::select_stream(&streams, &stream_name, match_field, max_streams)
+                .ok_or_else(|| format!("❌ Stream '{}' not found", stream_name))?;
+            let channel_count = stream_info.channel_count().max(0) as usize;
+            let names = Self::extract_real_channel_names_sync(stream_info, channel_count);
+            let inlet = StreamInlet::new(stream_info, 360, 1, true)
+                .map_err(|e| format!("❌ Failed to open inlet for '{}': {}", stream_name, e))?;
+            let units = Self::extract_channel_units_sync(&inlet, channel_count);
+            
+            let mut rows: Vec<Vec<f32>> = Vec::new();
+            if stream_info.channel_format() != ChannelFormat::String {
+                let start = std::time::Instant::now();
+                while rows.len() < PROBE_MAX_SAMPLES && start.elapsed().as_secs_f64() < PROBE_BURST_S {
+                    match <StreamInlet as Pullable<f32>>::pull_sample(&inlet, 0.1) {
+                        Ok((sample, timestamp)) if timestamp != 0.0 => rows.push(sample),
+                        _ => {}
+                    }
+                }
+            }
+            inlet.close_stream();
+            
+            let channels: Vec<ChannelRange> = (0..channel_count)
+                .filter(|_| !rows.is_empty())
+                .map(|channel| {
+                    let values: Vec<f32> = rows.iter().filter_map(|row| row.get(channel).copied()).collect();
+                    let n = values.len().max(1) as f32;
+                    let mean = values.iter().sum::<f32>() / n;
+                    ChannelRange {
+                        channel,
+                        name: names.get(channel).cloned().unwrap_or_else(|| format!("Ch{}", channel + 1)),
+                        unit: units.get(channel).cloned().unwrap_or_default(),
+                        min: values.iter().copied().fold(f32::INFINITY, f32::min),
+                        max: values.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+                        mean,
+                        std: (values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / n).sqrt(),
+                    }
+                })
+                .collect();
+            let integral = rows.iter().flatten().all(|v| v.fract() == 0.0);
+            let (unit_guess, suggested_scale) = guess_amplitude_unit(&channels, integral);
+            
+            println!(
+                "🔎 [DEBUG] Probed '{}': {} samples, {:?}, values look like {}",
+                stream_name, rows.len(), stream_info.channel_format(), unit_guess
+            );
+            Ok(StreamProbe {
+                name: stream_info.stream_name(),
+                channel_format: format!("{:?}", stream_info.channel_format()).to_lowercase(),
+                sample_rate: stream_info.nominal_srate(),
+                channel_count: stream_info.channel_count(),
+                samples: rows.len(),
+                channels,
+                unit_guess: unit_guess.to_string(),
+                suggested_scale,
+            })
+        })
+        .await
+        .map_err(|e| format!("❌ Stream probe failed: {}", e))?
+    }
+
+    // Takes effect at the next connect
+    async fn set_settle_samples(&self, n: usize) {
+        *self.settle_samples.lock().await = n;
+        println!("⏳ [DEBUG] Settle samples set to {}", n);
+    }
+
+    // Counts down one settling sample; Some(remaining) means this sample is to be discarded
+    async fn settle_sample(&self) -> Option<usize> {
+        let mut remaining = self.settle_remaining.lock().await;
+        if *remaining == 0 {
+            return None;
+        }
+        *remaining -= 1;
+        Some(*remaining)
+    }
+
+    async fn apply_real_time_filters(&self, sample: &EEGSample) -> FilteredEEGSample {
+        let eeg_channels = self.eeg_channel_limit().await;
+        let artifact_domain = *self.artifact_domain.lock().await;
+        let signal_types = self.channel_signal_types.lock().await.clone();
+        let mut filters_guard = self.channel_filters.lock().await;
+        
+        if let Some(filters) = filters_guard.as_mut() {
+            // Apply each channel's own bandpass + notch chain; AUX channels pass through unless
+            // set_channel_signal_type gave them a preset
+            let filtered = |ch: usize| ch < eeg_channels || signal_types.get(ch).is_some_and(|&t| t != SignalType::Eeg);
+            let mut filtered_channels: Vec<f32> = sample.channels.iter()
+                .enumerate()
+                .map(|(ch, &value)| match filters.get_mut(ch) {
+                    Some(filter) if filtered(ch) => {
+                        if artifact_domain == ArtifactDomain::Raw && value.abs() > ARTIFACT_CLIP_UV {
+                            filter.hold()
+                        } else {
+                            filter.process(value)
+                        }
+                    }
+                    _ => value,
+                })
+                .collect();
+            
+            // Artifact removal - clip extreme values (>300 µV)
+            if artifact_domain == ArtifactDomain::Filtered {
+                for channel_data in filtered_channels.iter_mut().take(eeg_channels) {
+                    *channel_data = clip_artifact(*channel_data);
+                }
+            }
+            
+            FilteredEEGSample {
+                timestamp: sample.timestamp,
+                sample_index: sample.sample_index,
+                channels: filtered_channels,
+                interpolated: sample.interpolated,
+                lsl_timestamp: sample.lsl_timestamp,
+            }
+        } else {
+            // Fallback: simple filtering if filters not initialized
+            let mut filtered_channels = sample.channels.clone();
+            for channel_data in filtered_channels.iter_mut().take(eeg_channels) {
+                *channel_data = clip_artifact(*channel_data);
+                *channel_data *= 0.95; // Simple high-pass
+            }
+            
+            FilteredEEGSample {
+                timestamp: sample.timestamp,
+                sample_index: sample.sample_index,
+                channels: filtered_channels,
+                interpolated: sample.interpolated,
+                lsl_timestamp: sample.lsl_timestamp,
+            }
+        }
+    }
+
+    // Raw and filtered buffers, emptied, one per channel
+    async fn resize_channel_buffers(&self, channel_count: usize) {
+        let mut raw_buffers = self.channel_buffers.lock().await;
+        let mut filtered_buffers = self.filtered_buffers.lock().await;
+        *raw_buffers = vec![Vec::new(); channel_count];
+        *filtered_buffers = vec![Vec::new(); channel_count];
+    }
+
+    async fn update_buffers(&self, sample: &EEGSample, filtered_sample: &FilteredEEGSample) {
+        // The multi-resolution long window may need more filtered history than buffer_size
+        let filtered_capacity = self.multiresolution.lock().await.as_ref()
+            .map_or(self.buffer_size, |m| m.long_len.max(self.buffer_size));
+        let mut raw_buffers = self.channel_buffers.lock().await;
+        let mut filtered_buffers = self.filtered_buffers.lock().await;
+        
+        // Connects size the buffers; a wider sample still gets buffers instead of being cut off
+        let width = sample.channels.len().min(filtered_sample.channels.len());
+        if width > raw_buffers.len().min(filtered_buffers.len()) {
+            println!("⚠️ [DEBUG] Sample has {} channels but buffers hold {} - growing buffers", width, raw_buffers.len());
+            let (raw_len, filtered_len) = (raw_buffers.len(), filtered_buffers.len());
+            raw_buffers.resize(width.max(raw_len), Vec::new());
+            filtered_buffers.resize(width.max(filtered_len), Vec::new());
+        }
+        
+        for (i, (&raw_value, &filtered_value)) in sample.channels.iter()
+            .zip(filtered_sample.channels.iter()).enumerate() {
+            
+            raw_buffers[i].push(raw_value);
+            if raw_buffers[i].len() > self.buffer_size {
+                raw_buffers[i].remove(0);
+            }
+            
+            filtered_buffers[i].push(filtered_value);
+            let excess = filtered_buffers[i].len().saturating_sub(filtered_capacity);
+            filtered_buffers[i].drain(..excess);
+        }
+        
+        self.replay_buffer.lock().await.push(ReplayFrame {
+            timestamp: sample.timestamp,
+            raw: sample.channels.clone(),
+            filtered: filtered_sample.channels.clone(),
+        });
+        
+        if let Some(auto_gain) = self.auto_gain.lock().await.as_mut() {
+            auto_gain.update(&filtered_sample.channels, self.sample_rate);
+        }
+    }
+
+    async fn set_replay_duration(&self, seconds: f64) -> Result<(), String> {
+        if !(seconds > 0.0 && seconds <= 3600.0) {
+            return Err(format!("❌ Invalid replay duration {} s: must be between 0 and 3600 seconds", seconds));
+        }
+        
+        self.replay_buffer.lock().await.resize(seconds, self.sample_rate);
+        println!("⏪ [DEBUG] Replay buffer now keeps the last {:.1} s", seconds);
+        Ok(())
+    }
+
+    // Replaces the whole set; keys and values are single-line so the header block stays parseable
+    async fn set_session_metadata(&self, metadata: HashMap<String, String>) -> Result<(), String> {
+        for (key, value) in &metadata {
+            if key.trim().is_empty() || key.contains(':') || key.contains(['\n', '\r']) || value.contains(['\n', '\r']) {
+                return Err(format!(
+                    "❌ Invalid session metadata '{}': keys must be non-empty without ':' and neither may span lines",
+                    key.escape_debug()
+                ));
+            }
+        }
+        
+        println!("🏷️ [DEBUG] Session metadata set: {:?}", metadata);
+        *self.session_metadata.lock().await = metadata;
+        Ok(())
+    }
+
+    // `# key: value` lines (sorted by key) and a `# config: {...}` line with config_snapshot,
+    // written ahead of a new recording's column header. read_recording and CSV sources skip them.
+    async fn recording_header(&self) -> String {
+        let mut metadata: Vec<(String, String)> = self.session_metadata.lock().await.clone().into_iter().collect();
+        metadata.sort();
+        let mut header: String = metadata.iter().map(|(key, value)| format!("# {}: {}\n", key, value)).collect();
+        header.push_str(&format!("# config: {}\n", self.config_snapshot().await));
+        header
+    }
+
+    // The last buffer_size samples (the analysis window span), from the replay buffer since it
+    // keeps raw, filtered and the timestamp of each sample together. A change of channel count
+    // ends the span, so every row has the same channels.
+    async fn get_dual_snapshot(&self) -> DualSnapshot {
+        let replay = self.replay_buffer.lock().await;
+        let width = replay.frames.back().map_or(0, |frame| frame.raw.len().min(frame.filtered.len()));
+        let mut frames: Vec<&ReplayFrame> = replay.frames.iter()
+            .rev()
+            .take(self.buffer_size)
+            .take_while(|frame| frame.raw.len().min(frame.filtered.len()) == width)
+            .collect();
+        frames.reverse();
+        
+        let column = |values: fn(&ReplayFrame) -> &Vec<f32>, channel: usize| -> Vec<f32> {
+            frames.iter().map(|frame| values(frame)[channel]).collect()
+        };
+        DualSnapshot {
+            raw: (0..width).map(|channel| column(|frame| &frame.raw, channel)).collect(),
+            filtered: (0..width).map(|channel| column(|frame| &frame.filtered, channel)).collect(),
+            timestamps: frames.iter().map(|frame| frame.timestamp).collect(),
+        }
+    }
+
+    // Spectrogram of one channel's filtered samples in the replay buffer: Hann windows of
+    // buffer_size at 50% overlap, from 0 Hz up to the band profile's top edge. Windows or bins
+    // beyond the size limits are averaged together; a smaller grid is enlarged by a whole factor
+    // along each axis.
+    // Without a range the colors span 0..peak (linear) or the top SPECTROGRAM_DB_SPAN dB.
+    async fn render_spectrogram_png(
+        &self,
+        channel: usize,
+        path: &str,
+        scale: SpectrogramScale,
+        range: Option<(f32, f32)>,
+        (max_width, max_height): (u32, u32),
+    ) -> Result<SpectrogramImage, String> {
+        for side in [max_width, max_height] {
+            if !(1..=MAX_SPECTROGRAM_SIDE).contains(&side) {
+                return Err(format!("❌ Invalid image size limit {}: must be between 1 and {}", side, MAX_SPECTROGRAM_SIDE));
+            }
+        }
+        if let Some((low, high)) = range {
+            if !(low.is_finite() && high.is_finite() && low < high) {
+                return Err(format!("❌ Invalid color range {}..{}: needs two finite values, min below max", low, high));
+            }
+        }
+        let rate = self.analysis_rate().await;
+        if !valid_rate(rate) {
+            return Err(format!("❌ Sample rate {} Hz is not usable - cannot compute a spectrogram", rate));
+        }
+        
+        let samples: Vec<f32> = {
+            let replay = self.replay_buffer.lock().await;
+            let width = replay.frames.back().map_or(0, |frame| frame.filtered.len());
+            if channel >= width {
+                return Err(format!("❌ Invalid channel index {}: the replay buffer holds {} channels", channel, width));
+            }
+            replay.frames.iter().filter_map(|frame| frame.filtered.get(channel).copied()).collect()
+        };
+        let n = self.buffer_size;
+        if samples.len() < n {
+            return Err(format!(
+                "❌ Only {} samples buffered, a spectrogram needs at least one {} sample window",
+                samples.len(), n
+            ));
+        }
+        
+        let freq_resolution = rate / n as f32;
+        let top_hz = self.band_profile.lock().await.bands.iter().map(|band| band.high).fold(0.0f32, f32::max).min(rate / 2.0);
+        let bins = ((top_hz / freq_resolution) as usize + 1).min(n / 2 + 1);
+        let mut scratch = vec![Complex::new(0.0, 0.0); self.fft_plan.get_inplace_scratch_len()];
+        let spectra: Vec<Vec<f32>> = (0..=samples.len() - n)
+            .step_by(n / 2)
+            .map(|start| {
+                let spectrum = Self::hann_spectrum(self.fft_plan.as_ref(), &samples[start..start + n], &mut scratch);
+                spectrum.iter().take(bins).map(|bin| bin.norm_sqr() / n as f32).collect()
+            })
+            .collect();
+        let windows = spectra.len();
+        
+        // Columns, then rows within each column, averaged down to the limits
+        let columns = max_width as usize;
+        let rows = (max_height as usize).min(bins);
+        let grid: Vec<Vec<f32>> = (0..windows.min(columns))
+            .map(|column| {
+                let (first, last) = (column * windows / windows.min(columns), (column + 1) * windows / windows.min(columns));
+                let mean: Vec<f32> = (0..bins)
+                    .map(|bin| spectra[first..last].iter().map(|spectrum| spectrum[bin]).sum::<f32>() / (last - first) as f32)
+                    .collect();
+                let mean = average_down(&mean, rows);
+                match scale {
+                    SpectrogramScale::Linear => mean,
+                    SpectrogramScale::Db => mean.iter().map(|p| 10.0 * p.max(1e-12).log10()).collect(),
+                }
+            })
+            .collect();
+        
+        let peak = grid.iter().flatten().copied().fold(f32::MIN, f32::max);
+        let (low, high) = range.unwrap_or(match scale {
+            SpectrogramScale::Linear => (0.0, peak.max(f32::MIN_POSITIVE)),
+            SpectrogramScale::Db => (peak - SPECTROGRAM_DB_SPAN, peak),
+        });
+        
+        let (grid_width, grid_height) = (grid.len() as u32, grid[0].len() as u32);
+        let zoom = ((max_width / grid_width).max(1), (max_height / grid_height).max(1));
+        let (width, height) = (grid_width * zoom.0, grid_height * zoom.1);
+        // Lowest frequency at the bottom
+        let image = image::RgbImage::from_fn(width, height, |x, y| {
+            let value = grid[(x / zoom.0) as usize][(grid_height - 1 - y / zoom.1) as usize];
+            spectrogram_color((value - low) / (high - low))
+        });
+        
+        let file = path.to_string();
+        tokio::task::spawn_blocking(move || image.save_with_format(&file, image::ImageFormat::Png))
+            .await
+            .map_err(|e| format!("❌ Task execution failed: {}", e))?
+            .map_err(|e| format!("❌ Failed to write spectrogram '{}': {}", path, e))?;
+        
+        println!("🖼️ [DEBUG] Spectrogram of channel {} written to '{}': {}x{} from {} windows", channel, path, width, height, windows);
+        Ok(SpectrogramImage {
+            path: path.to_string(),
+            width,
+            height,
+            windows,
+            max_hz: (bins - 1) as f32 * freq_resolution,
+            scale,
+            range: (low, high),
+        })
+    }
+
+    async fn dump_replay_buffer(&self, path: &str) -> Result<usize, String> {
+        // Snapshot the ring so the processing loop isn't blocked while writing
+        let frames: Vec<ReplayFrame> = self.replay_buffer.lock().await.frames.iter().cloned().collect();
+        if frames.is_empty() {
+            return Err("❌ Replay buffer is empty - no samples received yet".to_string());
+        }
+        
+        let channel_count = frames[0].raw.len();
+        let channel_names = match self.get_stream_info().await {
+            Some(info) if info.channel_names.len() == channel_count => info.channel_names,
+            _ => (0..channel_count).map(|i| format!("Ch{}", i + 1)).collect(),
+        };
+        
+        let path = path.to_string();
+        let frame_count = frames.len();
+        let preamble = self.recording_header().await;
+        tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+            let mut writer = std::io::BufWriter::new(std::fs::File::create(&path)?);
+            
+            writer.write_all(preamble.as_bytes())?;
+            let raw_headers: Vec<String> = channel_names.iter().map(|n| format!("raw_{}", n)).collect();
+            let filtered_headers: Vec<String> = channel_names.iter().map(|n| format!("filtered_{}", n)).collect();
+            writeln!(writer, "timestamp,{},{}", raw_headers.join(","), filtered_headers.join(","))?;
+            
+            for frame in &frames {
+                let values: Vec<String> = frame.raw.iter()
+                    .chain(frame.filtered.iter())
+                    .map(|v| v.to_string())
+                    .collect();
+                writeln!(writer, "{:.6},{}", frame.timestamp, values.join(","))?;
+            }
+            writer.flush()
+        })
+        .await
+        .map_err(|e| format!("❌ Task execution failed: {}", e))?
+        .map_err(|e| format!("❌ Failed to write replay buffer: {}", e))?;
+        
+        println!("💾 [DEBUG] Dumped {} replay frames", frame_count);
+        Ok(frame_count)
+    }
+
+    // Disabling drops the logged entries; changing the capacity keeps the newest ones
+    async fn set_event_logging(&self, enabled: bool, capacity: Option<usize>) -> Result<(), String> {
+        let mut event_log = self.event_log.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        if !enabled {
+            *event_log = None;
+            println!("🧾 [DEBUG] Event logging disabled");
+            return Ok(());
+        }
+        
+        let capacity = capacity.unwrap_or(DEFAULT_EVENT_LOG_CAPACITY);
+        if !(1..=MAX_EVENT_LOG_CAPACITY).contains(&capacity) {
+            return Err(format!(
+                "❌ Invalid event log capacity {}: must be between 1 and {}",
+                capacity, MAX_EVENT_LOG_CAPACITY
+            ));
+        }
+        let log = event_log.get_or_insert_with(|| EventLog { capacity, entries: VecDeque::new() });
+        log.capacity = capacity;
+        let excess = log.entries.len().saturating_sub(capacity);
+        log.entries.drain(..excess);
+        println!("🧾 [DEBUG] Event logging enabled, keeping the last {} events", capacity);
+        Ok(())
+    }
+    
+    // 0 turns the limit off. The bucket starts full at the new rate; earlier drop counts are kept.
+    async fn set_max_event_rate(&self, per_second: f64) -> Result<(), String> {
+        if per_second != 0.0 && !(MIN_MAX_EVENT_RATE..=MAX_MAX_EVENT_RATE).contains(&per_second) {
+            return Err(format!(
+                "❌ Invalid maximum event rate {}: must be 0 (unlimited) or between {} and {} events per second",
+                per_second, MIN_MAX_EVENT_RATE, MAX_MAX_EVENT_RATE
+            ));
+        }
+        
+        let mut limiter = self.event_rate.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let (dropped, total_dropped) = (std::mem::take(&mut limiter.dropped), limiter.total_dropped);
+        *limiter = EventRateLimiter::new((per_second != 0.0).then_some(per_second));
+        (limiter.dropped, limiter.total_dropped) = (dropped, total_dropped);
+        drop(limiter);
+        
+        if per_second == 0.0 {
+            println!("🚦 [DEBUG] Event rate limit off");
+        } else {
+            println!("🚦 [DEBUG] Events limited to {} per second", per_second);
+        }
+        Ok(())
+    }
+
+    // Oldest first; empty while event logging is off
+    async fn get_event_log(&self) -> Vec<EventLogEntry> {
+        self.event_log.lock().unwrap_or_else(std::sync::PoisonError::into_inner).as_ref().map_or_else(Vec::new, |log| log.entries.iter().cloned().collect())
+    }
+
+    // Compressed logs are written as gzip, with .gz appended to the path unless it already ends so
+    async fn start_band_logging(&self, path: &str, compressed: bool) -> Result<(), String> {
+        let mut band_log = self.band_log.lock().await;
+        if let Some(log) = band_log.as_ref() {
+            return Err(format!("❌ Band logging already active to '{}' - stop it first", log.path));
+        }
+        let path = if compressed && !path.ends_with(".gz") { format!("{}.gz", path) } else { path.to_string() };
+        let path = path.as_str();
+        
+        let bands: Vec<String> = self.band_profile.lock().await.bands.iter().map(|b| b.name.clone()).collect();
+        let theta_beta = bands.iter().any(|b| b == "theta") && bands.iter().any(|b| b == "beta");
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("❌ Failed to open band log '{}': {}", path, e))?;
+        // Appending to an existing log must not repeat the header
+        let is_empty = file.metadata().map(|m| m.len() == 0).unwrap_or(true);
+        let file = std::io::BufWriter::new(file);
+        let mut writer = if compressed {
+            BandLogWriter::Gzip(flate2::write::GzEncoder::new(file, flate2::Compression::default()))
+        } else {
+            BandLogWriter::Plain(file)
+        };
+        if is_empty {
+            let header = format!("timestamp,channel,{}{}", bands.join(","), if theta_beta { ",theta_beta_ratio" } else { "" });
+            let preamble = self.recording_header().await;
+            writer.write_all(preamble.as_bytes())
+                .and_then(|_| writeln!(writer, "{}", header))
+                .and_then(|_| writer.flush())
+                .map_err(|e| format!("❌ Failed to write band log header: {}", e))?;
+        }
+        
+        println!("📝 [DEBUG] Band logging started to '{}' ({} bands{})", path, bands.len(), if compressed { ", gzip" } else { "" });
+        *band_log = Some(BandLog {
+            path: path.to_string(),
+            writer,
+            bands,
+            theta_beta,
+            rows: 0,
+            last_flush: std::time::Instant::now(),
+        });
+        Ok(())
+    }
+    
+    // Returns the number of rows written since start_band_logging
+    async fn stop_band_logging(&self) -> Result<usize, String> {
+        let log = self.band_log.lock().await.take()
+            .ok_or_else(|| "❌ Band logging is not active".to_string())?;
+        log.writer.finish().map_err(|e| format!("❌ Failed to flush band log '{}': {}", log.path, e))?;
+        println!("📝 [DEBUG] Band logging stopped: {} rows written to '{}'", log.rows, log.path);
+        Ok(log.rows)
+    }
+    
+    async fn log_bands(&self, bands: &[FrequencyBands]) {
+        let mut band_log = self.band_log.lock().await;
+        let Some(log) = band_log.as_mut() else { return };
+        // A full disk or removed drive shouldn't fail every later cycle, so logging stops
+        if let Err(e) = log.write_cycle(bands) {
+            eprintln!("❌ Band log write to '{}' failed, logging stopped: {}", log.path, e);
+            *band_log = None;
+        }
+    }
+
+    // Compressed recordings are written as gzip, with .gz appended to the path unless it already
+    // ends so. An existing file is replaced.
+    async fn start_recording(&self, path: &str, decimation: usize, anti_alias: bool, compressed: bool) -> Result<(), String> {
+        if !(1..=MAX_RECORDING_DECIMATION).contains(&decimation) {
+            return Err(format!("❌ Invalid decimation {}: must be between 1 and {}", decimation, MAX_RECORDING_DECIMATION));
+        }
+        let mut recording = self.sample_recording.lock().await;
+        if let Some(active) = recording.as_ref() {
+            return Err(format!("❌ Recording already active to '{}' - stop it first", active.path));
+        }
+        let path = if compressed && !path.ends_with(".gz") { format!("{}.gz", path) } else { path.to_string() };
+        
+        let channel_count = self.lsl_connection.lock().await.channel_count;
+        let channel_names = match self.get_stream_info().await {
+            Some(info) if info.channel_names.len() == channel_count => info.channel_names,
+            _ => (0..channel_count).map(|i| format!("Ch{}", i + 1)).collect(),
+        };
+        let file = std::fs::File::create(&path)
+            .map_err(|e| format!("❌ Failed to create recording '{}': {}", path, e))?;
+        let file = std::io::BufWriter::new(file);
+        let mut writer = if compressed {
+            BandLogWriter::Gzip(flate2::write::GzEncoder::new(file, flate2::Compression::default()))
+        } else {
+            BandLogWriter::Plain(file)
+        };
+        
+        let mut preamble = self.recording_header().await;
+        if decimation > 1 {
+            preamble.push_str(&format!("# decimation: {}\n# anti_alias: {}\n", decimation, anti_alias));
+        }
+        let raw_headers: Vec<String> = channel_names.iter().map(|n| format!("raw_{}", n)).collect();
+        let filtered_headers: Vec<String> = channel_names.iter().map(|n| format!("filtered_{}", n)).collect();
+        writer.write_all(preamble.as_bytes())
+            .and_then(|_| writeln!(writer, "timestamp,{},{}", raw_headers.join(","), filtered_headers.join(",")))
+            .and_then(|_| writer.flush())
+            .map_err(|e| format!("❌ Failed to write recording header: {}", e))?;
+        
+        if decimation > 1 {
+            println!(
+                "⚠️ [DEBUG] Recording keeps every {}th sample ({}) - content above {:.1} Hz is lost",
+                decimation, if anti_alias { "block mean" } else { "no anti-alias filter" }, self.sample_rate / decimation as f32 / 2.0
+            );
+        }
+        println!("🎙️ [DEBUG] Recording started to '{}' ({} channels{})", path, channel_count, if compressed { ", gzip" } else { "" });
+        *recording = Some(SampleRecording {
+            path,
+            writer,
+            decimator: Decimator::new(decimation, anti_alias),
+            channel_count,
+            rows: 0,
+            last_flush: std::time::Instant::now(),
+        });
+        Ok(())
+    }
+    
+    // Returns the number of rows written since start_recording
+    async fn stop_recording(&self) -> Result<usize, String> {
+        let recording = self.sample_recording.lock().await.take()
+            .ok_or_else(|| "❌ Recording is not active".to_string())?;
+        recording.writer.finish().map_err(|e| format!("❌ Failed to flush recording '{}': {}", recording.path, e))?;
+        println!("🎙️ [DEBUG] Recording stopped: {} rows written to '{}'", recording.rows, recording.path);
+        Ok(recording.rows)
+    }
+    
+    async fn record_sample(&self, sample: &EEGSample, filtered: &FilteredEEGSample) {
+        let mut recording = self.sample_recording.lock().await;
+        let Some(active) = recording.as_mut() else { return };
+        // Like the band log, a failed write stops the recording instead of failing every sample
+        if let Err(e) = active.write_sample(sample, filtered) {
+            eprintln!("❌ Recording write to '{}' failed, recording stopped: {}", active.path, e);
+            if let Some(stopped) = recording.take() {
+                let _ = stopped.writer.finish();
+            }
+        }
+    }
+
+    // Mean band powers per channel over consecutive buffer_size windows; a partial tail is ignored
+    fn session_band_means(
+        &self,
+        channels: &[Vec<f32>],
+        rate: f32,
+        profile: &BandProfile,
+        options: BandOptions,
+    ) -> (Vec<HashMap<String, f32>>, usize) {
+        let mut scratch = vec![Complex::new(0.0, 0.0); self.band_fft_plan.get_scratch_len()];
+        let windows = channels.first().map_or(0, |samples| samples.len() / self.buffer_size);
+        
+        let means = channels.iter()
+            .map(|samples| {
+                let mut sums: HashMap<String, f32> = HashMap::new();
+                for window in samples.chunks_exact(self.buffer_size) {
+                    let (bands, _) = Self::compute_channel_bands(
+                        self.band_fft_plan.as_ref(), window, &mut scratch, rate / self.buffer_size as f32, profile, options,
+                    );
+                    for (band, power) in bands {
+                        *sums.entry(band).or_default() += power;
+                    }
+                }
+                sums.values_mut().for_each(|sum| *sum /= windows.max(1) as f32);
+                sums
+            })
+            .collect();
+        (means, windows)
+    }
+
+    // Before/after comparison of two recordings (dump_replay_buffer files or CSV), analyzed with
+    // the current band profile and buffer size. Each file's rate comes from its timestamps,
+    // falling back to the nominal rate when it has none.
+    async fn compare_sessions(&self, path_a: &str, path_b: &str) -> Result<SessionComparison, String> {
+        let paths = (path_a.to_string(), path_b.to_string());
+        let (recording_a, recording_b) = tokio::task::spawn_blocking(move || {
+            Ok::<_, String>((read_recording(&paths.0)?, read_recording(&paths.1)?))
+        })
+        .await
+        .map_err(|e| format!("❌ Task execution failed: {}", e))??;
+        
+        let (skipped_rows_a, skipped_rows_b) = (recording_a.skipped_rows, recording_b.skipped_rows);
+        let profile = self.band_profile.lock().await.clone();
+        // Same DC exclusion as the live bands; whitening and pre-emphasis are display settings
+        let options = BandOptions { excluded_bins: *self.dc_exclusion_bins.lock().await, ..BandOptions::default() };
+        let analyze = |recording: Recording, path: &str| {
+            let rate = recording.rate.filter(|r| valid_rate(*r))
+                .or_else(|| recording.header_rate().filter(|r| valid_rate(*r)))
+                .unwrap_or(self.sample_rate);
+            let (means, windows) = self.session_band_means(&recording.channels, rate, &profile, options);
+            if windows == 0 {
+                return Err(format!("❌ Recording '{}' is shorter than one {}-sample analysis window", path, self.buffer_size));
+            }
+            Ok((recording.names, means, windows))
+        };
+        let (names_a, means_a, windows_a) = analyze(recording_a, path_a)?;
+        let (names_b, means_b, windows_b) = analyze(recording_b, path_b)?;
+        
+        let mut channels = Vec::new();
+        let mut unmatched: Vec<String> = names_b.iter().filter(|name| !names_a.contains(name)).cloned().collect();
+        for (name, a) in names_a.iter().zip(means_a) {
+            let Some(b) = names_b.iter().position(|other| other == name).map(|i| means_b[i].clone()) else {
+                unmatched.push(name.clone());
+                continue;
+            };
+            let difference = a.iter()
+                .filter_map(|(band, &power_a)| Some((band.clone(), b.get(band)? - power_a)))
+                .collect();
+            let percent_change = a.iter()
+                .filter(|(_, &power_a)| power_a > 0.0)
+                .filter_map(|(band, &power_a)| Some((band.clone(), (b.get(band)? - power_a) / power_a * 100.0)))
+                .collect();
+            channels.push(ChannelComparison { channel: name.clone(), a, b, difference, percent_change });
+        }
+        
+        println!(
+            "📊 [DEBUG] Compared sessions: {} channels ({} vs {} windows), {} unmatched",
+            channels.len(), windows_a, windows_b, unmatched.len()
+        );
+        Ok(SessionComparison { windows_a, windows_b, skipped_rows_a, skipped_rows_b, channels, unmatched })
+    }
+
+    // Custom labels for the connected stream's channels, in display order. The whole montage is
+    // rejected when its length differs from the channel count or any label has a montage issue.
+    async fn load_montage(&self, names: Vec<String>) -> Result<Vec<String>, String> {
+        let mut connection = self.lsl_connection.lock().await;
+        let Some(info) = connection.stream_info.as_mut() else {
+            return Err("❌ No stream connected - connect before loading a montage".to_string());
+        };
+        if names.len() != info.channel_names.len() {
+            return Err(format!("❌ Montage has {} labels but the stream has {} channels", names.len(), info.channel_names.len()));
+        }
+        
+        let issues = check_montage(&names);
+        if !issues.is_empty() {
+            let listed: Vec<String> = issues.iter()
+                .map(|issue| match &issue.suggestion {
+                    Some(suggestion) => format!("{} '{}' (did you mean '{}'?)", issue.kind, issue.name, suggestion),
+                    None => format!("{} '{}'", issue.kind, issue.name),
+                })
+                .collect();
+            return Err(format!("❌ Montage does not match the 10-20 system: {}", listed.join(", ")));
+        }
+        
+        info.channel_names = names.iter().map(|name| name.trim().to_string()).collect();
+        println!("🧠 [DEBUG] Montage loaded: {:?}", info.channel_names);
+        Ok(info.channel_names.clone())
+    }
+    
+    // Channels past this index are non-EEG (status, triggers) and skip filtering and analysis
+    async fn eeg_channel_limit(&self) -> usize {
+        self.lsl_connection.lock().await.eeg_channel_count.unwrap_or(usize::MAX)
+    }
+
+    // Channels in display order with their scalp positions, ready for a topographic plot
+    async fn get_topomap_layout(&self) -> Result<Vec<TopoPoint>, String> {
+        let info = self.get_stream_info().await
+            .ok_or_else(|| "❌ No stream connected - channel names are unknown".to_string())?;
+        let eeg_channels = self.eeg_channel_limit().await;
+        
+        Ok(info.channel_names.iter()
+            .enumerate()
+            .map(|(index, name)| {
+                let position = electrode_position(name);
+                TopoPoint {
+                    name: name.clone(),
+                    x: position.map(|(x, _)| x),
+                    y: position.map(|(_, y)| y),
+                    is_eeg: index < eeg_channels,
+                }
+            })
+            .collect())
+    }
+
+    async fn set_eeg_channel_count(&self, count: usize) -> Result<(), String> {
+        let mut connection = self.lsl_connection.lock().await;
+        if !connection.is_real_connection {
+            return Err("❌ No active LSL connection - connect to a stream first".to_string());
+        }
+        
+        if count == 0 || count > connection.channel_count {
+            return Err(format!(
+                "❌ Invalid EEG channel count {}: must be between 1 and {} (stream channel count)",
+                count, connection.channel_count
+            ));
+        }
+        
+        println!("🔧 [DEBUG] Treating the first {} of {} channels as EEG", count, connection.channel_count);
+        connection.eeg_channel_count = Some(count);
+        Ok(())
+    }
+
+    // Mean rate of recent arrivals, with how many timestamps it is based on
+    fn measured_rate(connection: &LSLConnection) -> Option<(f64, usize)> {
+        let timestamps = &connection.recent_timestamps;
+        match (timestamps.front(), timestamps.back()) {
+            (Some(&first), Some(&last)) if last > first => {
+                Some(((timestamps.len() - 1) as f64 / (last - first), timestamps.len()))
+            }
+            _ => None,
+        }
+    }
+
+    // Rate the analysis should assume: nominal, or for irregular streams the mean rate of recent
+    // arrivals. 0 means no valid rate is known yet (irregular stream without enough samples).
+    async fn analysis_rate(&self) -> f32 {
+        let connection = self.lsl_connection.lock().await;
+        if !connection.irregular_rate {
+            return self.sample_rate;
+        }
+        
+        Self::measured_rate(&connection).map_or(0.0, |(rate, _)| rate as f32)
+    }
+
+    async fn set_adaptive_filter_redesign(&self, enabled: bool) {
+        *self.adaptive_filter_redesign.lock().await = enabled;
+        println!("🛠️ [DEBUG] Adaptive filter redesign {}", if enabled { "enabled" } else { "disabled" });
+    }
+
+    // Bins i (i * resolution in [low, high)) below Nyquist that a band sums over
+    // Bins below `excluded` are never integrated, so they don't count
+    fn band_bin_count(low: f32, high: f32, rate: f32, size: usize, excluded: usize) -> usize {
+        let resolution = rate / size as f32;
+        (excluded..=size / 2).filter(|&i| (low..high).contains(&(i as f32 * resolution))).count()
+    }
+
+    // Re-checked only when the rate, window, minimum or any analyzed band changes, so each
+    // situation is reported once
+    async fn check_band_resolution(&self) -> Vec<BandResolutionWarning> {
+        let rate = self.analysis_rate().await;
+        if !valid_rate(rate) {
+            return Vec::new();
+        }
+        let min_bins = *self.min_band_bins.lock().await;
+        let excluded_bins = *self.dc_exclusion_bins.lock().await;
+        
+        // The global profile plus any per-channel overrides in use
+        let mut bands = self.band_profile.lock().await.bands.clone();
+        {
+            let named = self.named_band_profiles.lock().await;
+            for name in self.channel_band_profiles.lock().await.iter().flatten() {
+                for band in named.get(name).map(|p| p.bands.as_slice()).unwrap_or_default() {
+                    if !bands.contains(band) {
+                        bands.push(band.clone());
+                    }
+                }
+            }
+        }
+        
+        let check = ResolutionCheck { rate, buffer_size: self.buffer_size, min_bins, excluded_bins, bands };
+        let mut checked = self.band_resolution_checked.lock().await;
+        if checked.as_ref() == Some(&check) {
+            return Vec::new();
+        }
+        let ResolutionCheck { buffer_size: size, bands, .. } = checked.insert(check);
+        
+        bands.iter()
+            .filter_map(|band| {
+                let bins = Self::band_bin_count(band.low, band.high, rate, *size, excluded_bins);
+                if bins >= min_bins {
+                    return None;
+                }
+                let suggested_buffer_size = (MIN_FFT_SIZE.trailing_zeros()..=MAX_FFT_SIZE.trailing_zeros())
+                    .map(|power| 1usize << power)
+                    .find(|&n| Self::band_bin_count(band.low, band.high, rate, n, excluded_bins) >= min_bins);
+                println!(
+                    "⚠️ [DEBUG] Band {} ({}-{} Hz) spans only {} bins at {:.3} Hz resolution (suggested buffer size: {:?})",
+                    band.name, band.low, band.high, bins, rate / *size as f32, suggested_buffer_size
+                );
+                Some(BandResolutionWarning {
+                    band: band.name.clone(),
+                    low: band.low,
+                    high: band.high,
+                    bins,
+                    min_bins,
+                    resolution_hz: rate / *size as f32,
+                    suggested_buffer_size,
+                })
+            })
+            .collect()
+    }
+
+    // Each channel's bandpass; empty until a connection has built the filters
+    async fn channel_passbands(&self) -> Vec<(f64, f64)> {
+        self.channel_filters.lock().await.iter()
+            .flatten()
+            .map(|filter| (filter.config.highpass, filter.config.lowpass))
+            .collect()
+    }
+
+    // Re-emitted only when a passband, a channel's band profile or the mode changes
+    async fn check_band_passband(&self) -> Vec<BandPassbandWarning> {
+        let eeg_channels = self.eeg_channel_limit().await;
+        let restricted = *self.passband_only.lock().await;
+        let passbands = self.channel_passbands().await;
+        let profile = self.band_profile.lock().await.clone();
+        let named = self.named_band_profiles.lock().await;
+        let channel_profiles = self.channel_band_profiles.lock().await;
+        
+        let mut warnings: Vec<BandPassbandWarning> = Vec::new();
+        for (channel, &(low, high)) in passbands.iter().enumerate().take(eeg_channels) {
+            let channel_profile = channel_profiles.get(channel)
+                .and_then(|name| named.get(name.as_ref()?))
+                .unwrap_or(&profile);
+            for band in channel_profile.bands.iter().filter(|b| (b.low as f64) < low || (b.high as f64) > high) {
+                let same = |w: &&mut BandPassbandWarning| {
+                    w.band == band.name && w.low == band.low && w.high == band.high && w.passband == (low, high)
+                };
+                match warnings.iter_mut().find(same) {
+                    Some(warning) => warning.channels.push(channel),
+                    None => warnings.push(BandPassbandWarning {
+                        band: band.name.clone(),
+                        low: band.low,
+                        high: band.high,
+                        passband: (low, high),
+                        channels: vec![channel],
+                        unavailable: band.high as f64 <= low || band.low as f64 >= high,
+                        restricted,
+                    }),
+                }
+            }
+        }
+        drop(channel_profiles);
+        drop(named);
+        
+        let mut checked = self.band_passband_checked.lock().await;
+        if checked.as_ref() == Some(&warnings) {
+            return Vec::new();
+        }
+        for warning in &warnings {
+            println!(
+                "⚠️ [DEBUG] Band {} ({}-{} Hz) {} the {}-{} Hz bandpass of channels {:?}",
+                warning.band, warning.low, warning.high,
+                if warning.unavailable { "lies outside" } else { "extends past" },
+                warning.passband.0, warning.passband.1, warning.channels
+            );
+        }
+        checked.insert(warnings).clone()
+    }
+
+    // Off by default: bands are summed over their full range, filter roll-off included.
+    // Only filtered analysis is restricted; raw analysis and raw_frequency_bands never are.
+    async fn set_passband_only(&self, enabled: bool) {
+        *self.passband_only.lock().await = enabled;
+        println!("🎚️ [DEBUG] Band computation restricted to the filter passband: {}", if enabled { "on" } else { "off" });
+    }
+
+    async fn set_min_band_bins(&self, n: usize) -> Result<(), String> {
+        if n == 0 {
+            return Err("❌ Invalid minimum of 0 bins: use at least 1".to_string());
+        }
+        
+        *self.min_band_bins.lock().await = n;
+        println!("📐 [DEBUG] Bands with fewer than {} bins are now reported", n);
+        Ok(())
+    }
+
+    // Rebuild every channel's filters for the measured rate once it drifts past the threshold
+    async fn check_filter_redesign(&self) -> Option<FilterRedesign> {
+        if !*self.adaptive_filter_redesign.lock().await {
+            return None;
+        }
+        
+        let (measured, count) = Self::measured_rate(&*self.lsl_connection.lock().await)?;
+        if count < RATE_ESTIMATE_WINDOW {
+            return None;
+        }
+        
+        let mut filter_rate = self.filter_rate.lock().await;
+        if ((measured - *filter_rate) / *filter_rate).abs() <= FILTER_REDESIGN_THRESHOLD {
+            return None;
+        }
+        
+        let mut filters_guard = self.channel_filters.lock().await;
+        let filters = filters_guard.as_mut()?;
+        if filters.iter().any(|f| f.config.validate(measured).is_err()) {
+            println!("⚠️ [DEBUG] Measured rate {:.1} Hz cannot hold the configured cutoffs - keeping {:.1} Hz filters", measured, *filter_rate);
+            return None;
+        }
+        
+        *filters = filters.iter().map(|f| f.redesigned(measured)).collect();
+        let redesign = FilterRedesign { previous_rate: *filter_rate, new_rate: measured };
+        *filter_rate = measured;
+        println!("🛠️ [DEBUG] Filters redesigned for {:.2} Hz (was {:.2} Hz)", redesign.new_rate, redesign.previous_rate);
+        Some(redesign)
+    }
+
+    async fn set_adaptive_notch(&self, enabled: bool) -> Result<(), String> {
+        let mut adaptive_notch = self.adaptive_notch.lock().await;
+        if !enabled {
+            if adaptive_notch.take().is_some() {
+                // Back to each channel's configured notch
+                let filter_rate = *self.filter_rate.lock().await;
+                for filter in self.channel_filters.lock().await.iter_mut().flatten() {
+                    if let Some(freq) = filter.config.notch {
+                        filter.retune_notch(freq, filter_rate);
+                    }
+                }
+            }
+            println!("🎯 [DEBUG] Adaptive notch disabled");
+            return Ok(());
+        }
+        
+        let nominal = self.filter_config.lock().await.notch
+            .ok_or_else(|| "❌ The notch filter is disabled - enable it before turning on the adaptive notch".to_string())?;
+        *adaptive_notch = Some(AdaptiveNotch::new(nominal as f32));
+        println!("🎯 [DEBUG] Adaptive notch tracking around {} Hz (±{} Hz)", nominal, ADAPTIVE_NOTCH_RANGE_HZ);
+        Ok(())
+    }
+
+    async fn get_adaptive_notch(&self) -> Option<AdaptiveNotch> {
+        self.adaptive_notch.lock().await.clone()
+    }
+
+    // One tracking step at the analysis cadence; Some when the notches were retuned
+    async fn track_mains_frequency(&self) -> Option<AdaptiveNotch> {
+        let nominal = self.filter_config.lock().await.notch? as f32;
+        let eeg_channels = self.eeg_channel_limit().await;
+        let rate = self.analysis_rate().await;
+        if !valid_rate(rate) {
+            return None;
+        }
+        
+        let mut adaptive_notch = self.adaptive_notch.lock().await;
+        let state = adaptive_notch.as_mut()?;
+        // A changed global notch restarts tracking around the new frequency
+        if state.nominal != nominal {
+            *state = AdaptiveNotch::new(nominal);
+        }
+        
+        let buffers = self.channel_buffers.lock().await;
+        let mut scratch = vec![Complex::new(0.0, 0.0); self.fft_plan.get_inplace_scratch_len()];
+        let mut power = vec![0.0f32; self.buffer_size / 2 + 1];
+        for window in buffers.iter().take(eeg_channels).filter_map(|buffer| self.analysis_window(buffer)) {
+            let spectrum = Self::hann_spectrum(self.fft_plan.as_ref(), window, &mut scratch);
+            for (total, bin) in power.iter_mut().zip(spectrum.iter()) {
+                *total += bin.norm_sqr();
+            }
+        }
+        drop(buffers);
+        
+        let retuned = state.update(estimate_mains_peak(&power, rate / self.buffer_size as f32, nominal));
+        // Also catches notches a rate redesign or set_channel_filter rebuilt at the nominal
+        // frequency. Only channels on the global notch follow; other per-channel notches stay.
+        let filter_rate = *self.filter_rate.lock().await;
+        let target = state.frequency as f64;
+        for filter in self.channel_filters.lock().await.iter_mut().flatten() {
+            let follows = filter.config.notch.is_some_and(|freq| freq as f32 == nominal);
+            if follows && filter.mains_frequency().is_some_and(|frequency| (frequency - target).abs() > 1e-6) {
+                filter.retune_notch(target, filter_rate);
+            }
+        }
+        if !retuned {
+            return None;
+        }
+        println!("🎯 [DEBUG] Notch retuned to {:.2} Hz (nominal {} Hz)", state.frequency, nominal);
+        Some(state.clone())
+    }
+
+    async fn buffer_fill_fraction(&self) -> f32 {
+        let eeg_channels = self.eeg_channel_limit().await;
+        let buffers = self.filtered_buffers.lock().await;
+        
+        buffers.iter()
+            .take(eeg_channels)
+            .map(|buffer| (buffer.len() as f32 / self.buffer_size as f32).min(1.0))
+            .fold(None, |min: Option<f32>, fill| Some(min.map_or(fill, |m| m.min(fill))))
+            .unwrap_or(0.0)
+    }
+
+    // Min/max of each channel's newest buffer_size filtered samples (fewer while filling), scaled
+    // by the display gain like filtered_eeg_sample; (0, 0) for a channel with no samples yet
+    async fn get_channel_ranges(&self) -> Vec<(f32, f32)> {
+        let gains = self.display_gains.lock().await.clone();
+        let buffers = self.filtered_buffers.lock().await;
+        
+        buffers.iter()
+            .enumerate()
+            .map(|(channel, buffer)| {
+                let recent = &buffer[buffer.len().saturating_sub(self.buffer_size)..];
+                if recent.is_empty() {
+                    return (0.0, 0.0);
+                }
+                let gain = gains.get(channel).copied().unwrap_or(1.0);
+                let (min, max) = recent.iter()
+                    .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &v| (min.min(v), max.max(v)));
+                (min * gain, max * gain)
+            })
+            .collect()
+    }
+
+    // Newest buffer_size samples of a channel, once that many have arrived. Filtered buffers can
+    // hold more than this while multi-resolution analysis is on.
+    fn analysis_window<'a>(&self, buffer: &'a [f32]) -> Option<&'a [f32]> {
+        buffer.len().checked_sub(self.buffer_size).map(|start| &buffer[start..])
+    }
+
+    // Smallest power-of-two window whose bin spacing rate/N is at most the target resolution
+    fn fft_size_for_resolution(rate: f32, target_resolution_hz: f32) -> Result<usize, String> {
+        if !target_resolution_hz.is_finite() || target_resolution_hz <= 0.0 {
+            return Err(format!("❌ Invalid target resolution {} Hz: must be positive", target_resolution_hz));
+        }
+        if !valid_rate(rate) {
+            return Err(format!("❌ Sample rate {} Hz is not usable - no FFT size can be derived", rate));
+        }
+        
+        let size = ((rate / target_resolution_hz).ceil() as usize).next_power_of_two().max(MIN_FFT_SIZE);
+        if size > MAX_FFT_SIZE {
+            return Err(format!(
+                "❌ A {} Hz resolution at {} Hz needs a {}-sample window, above the {} maximum",
+                target_resolution_hz, rate, size, MAX_FFT_SIZE
+            ));
+        }
+        Ok(size)
+    }
+
+    async fn suggest_fft_size(&mut self, target_resolution_hz: f32, apply: bool) -> Result<usize, String> {
+        let rate = self.analysis_rate().await;
+        let size = Self::fft_size_for_resolution(rate, target_resolution_hz)?;
+        println!(
+            "📐 [DEBUG] {} Hz resolution at {} Hz -> {} samples ({:.3} Hz bins, {:.2} s window)",
+            target_resolution_hz, rate, size, rate / size as f32, size as f32 / rate
+        );
+        
+        if apply {
+            self.set_buffer_size(size).await?;
+        }
+        Ok(size)
+    }
+
+    // Changes the analysis window; the buffers keep their newest samples, so a shrink takes
+    // effect on the next cycle and a grow refills over the extra length
+    async fn set_buffer_size(&mut self, size: usize) -> Result<(), String> {
+        if !size.is_power_of_two() || !(MIN_FFT_SIZE..=MAX_FFT_SIZE).contains(&size) {
+            return Err(format!(
+                "❌ Invalid buffer size {}: must be a power of two between {} and {}",
+                size, MIN_FFT_SIZE, MAX_FFT_SIZE
+            ));
+        }
+        if let Some(bins) = *self.whitening.lock().await {
+            if bins > size / 4 {
+                return Err(format!(
+                    "❌ Buffer size {} is too small for the current ±{} bin whitening (needs at least {})",
+                    size, bins, (bins * 4).next_power_of_two()
+                ));
+            }
+        }
+        
+        self.buffer_size = size;
+        self.fft_plan = FftPlanner::new().plan_fft_forward(size);
+        self.band_fft_plan = RealFftPlanner::new().plan_fft_forward(size);
+        
+        let filtered_capacity = self.multiresolution.lock().await.as_ref()
+            .map_or(size, |m| m.long_len.max(size));
+        for buffer in self.channel_buffers.lock().await.iter_mut() {
+            buffer.drain(..buffer.len().saturating_sub(size));
+        }
+        for buffer in self.filtered_buffers.lock().await.iter_mut() {
+            buffer.drain(..buffer.len().saturating_sub(filtered_capacity));
+        }
+        
+        let rate = self.analysis_rate().await;
+        println!("📐 [DEBUG] Buffer size set to {} samples ({:.3} Hz resolution at {} Hz)", size, rate / size as f32, rate);
+        Ok(())
+    }
+
+    async fn set_multiresolution(&self, enabled: bool, short_len: usize, long_len: usize) -> Result<(), String> {
+        let multiresolution = if enabled {
+            Some(MultiResolution::new(short_len, long_len)?)
+        } else {
+            None
+        };
+        
+        println!(
+            "🔬 [DEBUG] Multi-resolution analysis: {}",
+            if enabled { format!("short {} / long {} samples", short_len, long_len) } else { "off".to_string() }
+        );
+        *self.multiresolution.lock().await = multiresolution;
+        Ok(())
+    }
+
+    // Zero-padding interpolates the spectrum between the window's own bins: peaks can be placed
+    // more finely and the spectrum looks smoother, but the true resolution is still rate / buffer
+    // size, so two rhythms closer than that stay merged. Applies to the band FFT and
+    // get_dominant_frequency; multi-resolution and Goertzel analysis are unpadded.
+    async fn set_zero_pad_factor(&self, factor: usize) -> Result<(), String> {
+        if !(1..=MAX_ZERO_PAD_FACTOR).contains(&factor) {
+            return Err(format!(
+                "❌ Invalid zero-pad factor {}: must be between 1 and {}",
+                factor, MAX_ZERO_PAD_FACTOR
+            ));
+        }
+        
+        *self.zero_pad_factor.lock().await = factor;
+        let rate = self.analysis_rate().await;
+        println!(
+            "📐 [DEBUG] Zero-pad factor set to {} ({:.3} Hz bin spacing, {:.3} Hz true resolution)",
+            factor, rate / (self.buffer_size * factor) as f32, rate / self.buffer_size as f32
+        );
+        Ok(())
+    }
+
+    async fn analyze_frequency_bands(&self, timestamp: f64) -> Vec<FrequencyBands> {
+        let (mut bands, snr) = self.measure_bands(timestamp).await;
+        *self.last_snr.lock().await = snr;
+        self.band_smoothing.lock().await.apply(&mut bands);
+        self.last_bands.lock().await.clone_from(&bands);
+        self.log_bands(&bands).await;
+        bands
+    }
+
+    // The current windows' band powers, gain applied, and the SNR of the same spectra; smoothing
+    // state, last_bands and the band log are left to the caller
+    async fn measure_bands(&self, timestamp: f64) -> (Vec<FrequencyBands>, Vec<ChannelSnr>) {
+        let analysis_source = *self.analysis_source.lock().await;
+        let source = match analysis_source {
+            AnalysisSource::Filtered => &self.filtered_buffers,
+            AnalysisSource::Raw => &self.channel_buffers,
+        };
+        let passbands = if analysis_source == AnalysisSource::Filtered && *self.passband_only.lock().await {
+            Some(self.channel_passbands().await)
+        } else {
+            None
+        };
+        let (mut bands, snr) = self.compute_frequency_bands(source, passbands.as_deref(), timestamp).await;
+        // Scaling the window by g scales every band power by g², so the gain is applied afterwards
+        // (and leaves the SNR as it is)
+        if let Some(auto_gain) = self.auto_gain.lock().await.as_ref() {
+            for channel_bands in bands.iter_mut() {
+                let gain = auto_gain.power_gain(channel_bands.channel);
+                for power in channel_bands.bands.values_mut() {
+                    *power *= gain;
+                }
+            }
+        }
+        (bands, snr)
+    }
+
+    // An immediate analysis of the full windows outside the analysis interval. Unsmoothed, and
+    // neither logged nor kept as the last bands, so the scheduled cycles carry on as if it
+    // hadn't run. Stamped with the newest sample's timestamp, like markers.
+    async fn measure_now(&self) -> Result<Vec<FrequencyBands>, String> {
+        let rate = self.analysis_rate().await;
+        if !valid_rate(rate) {
+            return Err(format!("❌ Sample rate {} Hz is not usable - cannot analyze", rate));
+        }
+        let timestamp = self.replay_buffer.lock().await.frames.back()
+            .map(|frame| frame.timestamp)
+            .ok_or_else(|| "❌ No samples received yet - nothing to measure".to_string())?;
+        
+        let (bands, _) = self.measure_bands(timestamp).await;
+        if bands.is_empty() {
+            return Err("❌ Analysis buffer is not full yet - wait for buffer_fill_progress to reach 1".to_string());
+        }
+        println!("📸 [DEBUG] Measured {} channels on demand at {:.3}", bands.len(), timestamp);
+        Ok(bands)
+    }
+
+    // Seconds 0 turns smoothing of that band off
+    async fn set_band_time_constant(&self, band: String, seconds: f32) -> Result<(), String> {
+        let known_bands = self.band_profile.lock().await.names();
+        if !known_bands.contains(&band) {
+            return Err(format!("❌ Unknown band '{}': expected one of {:?}", band, known_bands));
+        }
+        if !(0.0..=MAX_BAND_TIME_CONSTANT_S).contains(&seconds) {
+            return Err(format!(
+                "❌ Invalid time constant {} s for band '{}': must be between 0 and {} seconds",
+                seconds, band, MAX_BAND_TIME_CONSTANT_S
+            ));
+        }
+        
+        let mut smoothing = self.band_smoothing.lock().await;
+        smoothing.state.retain(|(_, name), _| *name != band);
+        if seconds > 0.0 {
+            println!("⏳ [DEBUG] {} band powers smoothed with a {} s time constant", band, seconds);
+            smoothing.time_constants.insert(band, seconds);
+        } else {
+            println!("⏳ [DEBUG] {} band power smoothing off", band);
+            smoothing.time_constants.remove(&band);
+        }
+        Ok(())
+    }
+
+    // Pairs are found by name each cycle, so renaming or reordering channels applies at once
+    async fn analyze_hemisphere_difference(&self, timestamp: f64, bands: &[FrequencyBands]) -> Option<HemisphereDifference> {
+        let info = self.get_stream_info().await?;
+        let labels: Vec<Option<&str>> = info.channel_names.iter().map(|name| canonical_electrode_label(name)).collect();
+        let band_powers = |channel: usize| bands.iter().find(|b| b.channel == channel);
+        
+        let mut pairs = Vec::new();
+        for (left_channel, left) in labels.iter().enumerate() {
+            let Some(right_label) = left.and_then(mirror_electrode_label) else {
+                continue;
+            };
+            let Some(right_channel) = labels.iter().position(|label| *label == Some(right_label)) else {
+                continue;
+            };
+            let (Some(left_bands), Some(right_bands)) = (band_powers(left_channel), band_powers(right_channel)) else {
+                continue;
+            };
+            
+            let differences = left_bands.bands.iter()
+                .filter_map(|(band, &left_power)| {
+                    let right_power = right_bands.get(band)?;
+                    (left_power > 0.0 && right_power > 0.0).then(|| (band.clone(), right_power.ln() - left_power.ln()))
+                })
+                .collect();
+            pairs.push(HemispherePair {
+                left: info.channel_names[left_channel].clone(),
+                right: info.channel_names[right_channel].clone(),
+                left_channel,
+                right_channel,
+                bands: differences,
+            });
+        }
+        
+        (!pairs.is_empty()).then_some(HemisphereDifference { timestamp, pairs })
+    }
+
+    // An empty map goes back to the default 10-10 regions. Channels past the stream's count are
+    // rejected now; after a reconnect to a smaller stream they are simply not averaged.
+    async fn set_channel_groups(&self, groups: HashMap<String, Vec<usize>>) -> Result<(), String> {
+        if groups.is_empty() {
+            *self.channel_groups.lock().await = None;
+            println!("🗺️ [DEBUG] Channel groups: default scalp regions");
+            return Ok(());
+        }
+        if groups.len() > MAX_CHANNEL_GROUPS {
+            return Err(format!("❌ Too many channel groups ({}): at most {}", groups.len(), MAX_CHANNEL_GROUPS));
+        }
+        
+        let channel_count = {
+            let connection = self.lsl_connection.lock().await;
+            connection.stream_info.is_some().then_some(connection.channel_count)
+        };
+        for (name, channels) in &groups {
+            if name.trim().is_empty() {
+                return Err("❌ Channel group names must not be empty".to_string());
+            }
+            if channels.is_empty() {
+                return Err(format!("❌ Channel group '{}' has no channels", name));
+            }
+            if let Some((_, &channel)) = channels.iter().enumerate().find(|&(i, ch)| channels[..i].contains(ch)) {
+                return Err(format!("❌ Channel index {} listed twice in group '{}'", channel, name));
+            }
+            if let Some(&channel) = channels.iter().find(|&&ch| channel_count.is_some_and(|count| ch >= count)) {
+                return Err(format!(
+                    "❌ Channel index {} in group '{}' out of range (stream has {} channels)",
+                    channel, name, channel_count.unwrap_or_default()
+                ));
+            }
+        }
+        
+        let mut groups: Vec<ChannelGroup> = groups.into_iter()
+            .map(|(name, channels)| ChannelGroup { name, channels })
+            .collect();
+        groups.sort_by(|a, b| a.name.cmp(&b.name));
+        println!("🗺️ [DEBUG] Channel groups: {:?}", groups);
+        *self.channel_groups.lock().await = Some(groups);
+        Ok(())
+    }
+
+    // An empty list goes back to the default. Both score baselines are cleared, as they were
+    // taken over other channels.
+    async fn set_score_channels(&self, mut channels: Vec<usize>) -> Result<(), String> {
+        let channel_count = {
+            let connection = self.lsl_connection.lock().await;
+            connection.stream_info.is_some().then_some(connection.channel_count)
+        };
+        if let Some(&channel) = channels.iter().find(|&&ch| channel_count.is_some_and(|count| ch >= count)) {
+            return Err(format!(
+                "❌ Channel index {} out of range (stream has {} channels)",
+                channel, channel_count.unwrap_or_default()
+            ));
+        }
+        channels.sort_unstable();
+        channels.dedup();
+        
+        if channels.is_empty() {
+            println!("🧘 [DEBUG] Score channels: posterior channels of the montage (baselines cleared)");
+        } else {
+            println!("🧘 [DEBUG] Score channels: {:?} (baselines cleared)", channels);
+        }
+        *self.score_channels.lock().await = (!channels.is_empty()).then_some(channels);
+        self.calm_score.lock().await.baseline = None;
+        self.meditation_index.lock().await.baseline = None;
+        Ok(())
+    }
+
+    // None = every channel; the posterior default applies only to the EEG channels
+    async fn effective_score_channels(&self) -> Option<Vec<usize>> {
+        if let Some(channels) = self.score_channels.lock().await.clone() {
+            return Some(channels);
+        }
+        let eeg_channels = self.eeg_channel_limit().await;
+        let names = self.get_stream_info().await?.channel_names;
+        let posterior = posterior_channels(&names[..eeg_channels.min(names.len())]);
+        (!posterior.is_empty()).then_some(posterior)
+    }
+
+    async fn score_bands(&self, bands: &[FrequencyBands]) -> Vec<FrequencyBands> {
+        match self.effective_score_channels().await {
+            Some(channels) => bands.iter().filter(|b| channels.contains(&b.channel)).cloned().collect(),
+            None => bands.to_vec(),
+        }
+    }
+
+    async fn analyze_regional_bands(&self, timestamp: f64, bands: &[FrequencyBands]) -> Option<RegionalBands> {
+        let groups = match self.channel_groups.lock().await.clone() {
+            Some(groups) => groups,
+            None => default_channel_groups(&self.get_stream_info().await?.channel_names),
+        };
+        let gated = self.gated_bands(bands).await;
+        let regions = regional_band_means(&groups, &gated);
+        (!regions.is_empty()).then_some(RegionalBands { timestamp, regions })
+    }
+
+    // Scale-invariant feedback: band powers are computed as if each channel's filtered signal had
+    // `target_rms` µV RMS over the last `time_constant_s` seconds. Absolute powers (and the
+    // thresholds, triggers and baselines compared against them) then describe the normalized
+    // signal, so this is for relative neurofeedback, not for measurement.
+    async fn set_auto_gain(&self, enabled: bool, target_rms: f32, time_constant_s: f32) -> Result<(), String> {
+        if !enabled {
+            *self.auto_gain.lock().await = None;
+            println!("🎚️ [DEBUG] Automatic gain normalization disabled");
+            return Ok(());
+        }
+        
+        if !target_rms.is_finite() || target_rms <= 0.0 {
+            return Err(format!("❌ Invalid target RMS {}: must be a positive amplitude in µV", target_rms));
+        }
+        if !(MIN_AUTO_GAIN_TIME_CONSTANT_S..=MAX_AUTO_GAIN_TIME_CONSTANT_S).contains(&time_constant_s) {
+            return Err(format!(
+                "❌ Invalid time constant {} s: must be between {} and {} seconds",
+                time_constant_s, MIN_AUTO_GAIN_TIME_CONSTANT_S, MAX_AUTO_GAIN_TIME_CONSTANT_S
+            ));
+        }
+        
+        *self.auto_gain.lock().await = Some(AutoGain::new(target_rms, time_constant_s));
+        println!("🎚️ [DEBUG] Band powers normalized to {} µV RMS (time constant {} s)", target_rms, time_constant_s);
+        Ok(())
+    }
+
+    // Same analysis on the raw (pre-filter, pre-notch) buffers, to show what the filters remove
+    async fn analyze_raw_frequency_bands(&self, timestamp: f64) -> Option<Vec<FrequencyBands>> {
+        if !*self.emit_raw_spectrum.lock().await {
+            return None;
+        }
+        Some(self.compute_frequency_bands(&self.channel_buffers, None, timestamp).await.0)
+    }
+
+    // With `passbands`, each channel's bands are clipped to its (highpass, lowpass)
+    async fn compute_frequency_bands(
+        &self,
+        source: &Mutex<Vec<Vec<f32>>>,
+        passbands: Option<&[(f64, f64)]>,
+        timestamp: f64,
+    ) -> (Vec<FrequencyBands>, Vec<ChannelSnr>) {
+        let eeg_channels = self.eeg_channel_limit().await;
+        let rate = self.analysis_rate().await;
+        if !valid_rate(rate) {
+            // Callers check first; a zero/negative rate would map every bin to NaN/Inf frequencies
+            return (Vec::new(), Vec::new());
+        }
+        let options = BandOptions {
+            gamma_preemphasis: *self.gamma_preemphasis.lock().await,
+            whitening: *self.whitening.lock().await,
+            excluded_bins: *self.dc_exclusion_bins.lock().await,
+        };
+        let multiresolution = self.multiresolution.lock().await.clone();
+        let goertzel_points = match *self.analysis_method.lock().await {
+            AnalysisMethod::Goertzel => Some(*self.goertzel_points.lock().await),
+            AnalysisMethod::Fft => None,
+        };
+        let profile = self.band_profile.lock().await.clone();
+        // Per-channel overrides are resolved by name each cycle, so reloading a named profile applies at once
+        let mut channel_profiles: Vec<Option<BandProfile>> = {
+            let named = self.named_band_profiles.lock().await;
+            self.channel_band_profiles.lock().await.iter()
+                .map(|name| name.as_ref().and_then(|name| named.get(name).cloned()))
+                .collect()
+        };
+        // Individualized bands use the previous cycle's transition frequency of each channel
+        if let Some(tafs) = self.individualized_bands.lock().await.as_ref() {
+            if channel_profiles.len() < tafs.len() {
+                channel_profiles.resize(tafs.len(), None);
+            }
+            for (slot, taf) in channel_profiles.iter_mut().zip(tafs.iter()) {
+                let Some(hz) = *taf else { continue };
+                if let Some(individual) = slot.as_ref().unwrap_or(&profile).with_theta_alpha_boundary(hz) {
+                    *slot = Some(individual);
+                }
+            }
+        }
+        let mut unavailable: Vec<Vec<String>> = Vec::new();
+        if let Some(passbands) = passbands {
+            if channel_profiles.len() < passbands.len() {
+                channel_profiles.resize(passbands.len(), None);
+            }
+            unavailable = passbands.iter()
+                .zip(channel_profiles.iter_mut())
+                .map(|(&(low, high), slot)| {
+                    let (within, outside) = slot.as_ref().unwrap_or(&profile).within(low as f32, high as f32);
+                    *slot = Some(within);
+                    outside
+                })
+                .collect();
+        }
+        let fft = self.band_fft().await;
+        let buffers = source.lock().await;
+        let scratch_len = multiresolution.as_ref().map_or(fft.get_scratch_len(), |m| {
+            fft.get_scratch_len()
+                .max(m.short_plan.get_scratch_len())
+                .max(m.long_plan.get_scratch_len())
+        });
+        
+        // Channels are independent, so spread them over the rayon pool; each worker
+        // reuses its own scratch buffer and the shared plan. collect() keeps channel order.
+        let (bands, snr): (Vec<FrequencyBands>, Vec<Option<ChannelSnr>>) = buffers.par_iter()
+            .enumerate()
+            .take(eeg_channels)
+            .filter_map(|(channel_idx, buffer)| Some((channel_idx, buffer, self.analysis_window(buffer)?)))
+            .map_init(
+                || vec![Complex::new(0.0, 0.0); scratch_len],
+                |scratch, (channel_idx, buffer, window)| {
+                    let profile = channel_profiles.get(channel_idx).and_then(Option::as_ref).unwrap_or(&profile);
+                    let mut bands_for = |plan: &dyn RealToComplex<f32>, window: &[f32]| {
+                        Self::compute_channel_bands(
+                            plan, window, scratch, rate / window.len() as f32, profile, options,
+                        )
+                    };
+                    
+                    // Goertzel computes no spectrum, so it has no SNR; multi-resolution takes the long window's
+                    let (bands, snr_db) = match (goertzel_points, &multiresolution) {
+                        (Some(points), _) => {
+                            (Self::goertzel_channel_bands(window, rate, profile, points, options.excluded_bins), None)
+                        }
+                        // Until the long window has filled, fall back to the standard window
+                        (None, Some(m)) if buffer.len() >= m.long_len => {
+                            let (long, snr_db) = bands_for(m.long_plan.as_ref(), &buffer[buffer.len() - m.long_len..]);
+                            let (short, _) = bands_for(m.short_plan.as_ref(), &buffer[buffer.len() - m.short_len..]);
+                            let scale = |len: usize| self.buffer_size as f32 / len as f32;
+                            (MultiResolution::merge(profile, &long, &short, scale(m.long_len), scale(m.short_len)), Some(snr_db))
+                        }
+                        _ => {
+                            let (bands, snr_db) = bands_for(fft.as_ref(), window);
+                            (bands, Some(snr_db))
+                        }
+                    };
+                    let unavailable = unavailable.get(channel_idx).cloned().unwrap_or_default();
+                    (
+                        FrequencyBands { timestamp, channel: channel_idx, bands, unavailable },
+                        snr_db.map(|db| ChannelSnr { channel: channel_idx, db }),
+                    )
+                },
+            )
+            .unzip();
+        drop(buffers);
+        
+        (bands, snr.into_iter().flatten().collect())
+    }
+
+    // Plan for the standard window, padded by the zero-pad factor
+    async fn band_fft(&self) -> Arc<dyn RealToComplex<f32>> {
+        let len = self.buffer_size * *self.zero_pad_factor.lock().await;
+        if len == self.buffer_size {
+            return Arc::clone(&self.band_fft_plan);
+        }
+        let mut padded = self.padded_band_fft.lock().await;
+        match padded.as_ref() {
+            Some(plan) if plan.len() == len => Arc::clone(plan),
+            _ => Arc::clone(padded.insert(RealFftPlanner::new().plan_fft_forward(len))),
+        }
+    }
+
+    // Real-input FFT: only bins 0..=N/2 are computed, the rest mirror them, so a band reaching
+    // past Nyquist sums up to Nyquist. A plan longer than the window zero-pads it: bins are that
+    // many times denser, so the band sums are divided by the factor to stay on the unpadded scale.
+    // `freq_resolution` is that of the unpadded window. Everything below the first kept unpadded
+    // bin is skipped, including the padded bins between DC and it.
+    fn compute_channel_bands(
+        fft: &dyn RealToComplex<f32>,
+        buffer: &[f32],
+        scratch: &mut [Complex<f32>],
+        freq_resolution: f32,
+        profile: &BandProfile,
+        options: BandOptions,
+    ) -> (HashMap<String, f32>, f32) {
+        let BandOptions { gamma_preemphasis, whitening, excluded_bins } = options;
+        let min_freq = excluded_bins as f32 * freq_resolution;
+        // Perform FFT (the input copy is used as workspace)
+        let mut input = buffer.to_vec();
+        input.resize(fft.len().max(buffer.len()), 0.0);
+        let padding = input.len() as f32 / buffer.len() as f32;
+        let freq_resolution = freq_resolution / padding;
+        let mut buffer_complex = fft.make_output_vec();
+        if let Err(e) = fft.process_with_scratch(&mut input, &mut buffer_complex, scratch) {
+            eprintln!("❌ [DEBUG] Band FFT failed: {}", e);
+            return (profile.bands.iter().map(|band| (band.name.clone(), 0.0)).collect(), f32::NAN);
+        }
+        
+        // Calculate power in frequency bands
+        let mut powers = vec![0.0f32; profile.bands.len()];
+        
+        // Display-only gamma boost: the first-difference response 2·sin(πf/fs), normalised to
+        // unity at the gamma band's low edge so it only raises the band. Gamma is no longer a
+        // quantitative power estimate while this is on; the other bands are unaffected.
+        let bins = input.len() as f32;
+        let emphasis = |i: usize, edge: f32| {
+            let edge_gain = (std::f32::consts::PI * edge / (freq_resolution * bins)).sin();
+            (std::f32::consts::PI * i as f32 / bins).sin() / edge_gain
+        };
+        
+        let mut spectrum: Vec<f32> = buffer_complex.iter().map(|c| c.norm_sqr()).collect();
+        // Below the first bin of the unpadded window is DC, padded or not
+        let snr_db = Self::snr_db(&spectrum, freq_resolution, freq_resolution * padding);
+        if let Some(half_width) = whitening {
+            Self::whiten(&mut spectrum, (half_width as f32 * padding).round() as usize);
+        }
+        
+        for (i, &power) in spectrum.iter().enumerate() {
+            let freq = i as f32 * freq_resolution;
+            if freq < min_freq {
+                continue;
+            }
+            
+            for (band, band_power) in profile.bands.iter().zip(powers.iter_mut()) {
+                if freq >= band.low && freq < band.high {
+                    *band_power += if gamma_preemphasis && band.name == "gamma" {
+                        power * emphasis(i, band.low).powi(2)
+                    } else {
+                        power
+                    };
+                }
+            }
+        }
+        
+        let bands = profile.bands.iter()
+            .zip(powers)
+            .map(|(band, power)| (band.name.clone(), (power / padding).sqrt()))
+            .collect();
+        (bands, snr_db)
+    }
+
+    // Same scale as compute_channel_bands (sqrt of summed unwindowed |X[k]|²) from `points`
+    // Goertzel evaluations spread evenly across each band
+    fn goertzel_channel_bands(
+        buffer: &[f32],
+        rate: f32,
+        profile: &BandProfile,
+        points: usize,
+        excluded_bins: usize,
+    ) -> HashMap<String, f32> {
+        let n = buffer.len();
+        let min_freq = excluded_bins as f32 * rate / n as f32;
+        profile.bands.iter()
+            .map(|band| {
+                let bins = Self::band_bin_count(band.low, band.high, rate, n, excluded_bins);
+                let (low, high) = (band.low.max(min_freq), band.high.min(rate / 2.0));
+                let power = if bins == 0 || high <= low {
+                    0.0
+                } else {
+                    let step = (high - low) / points as f32;
+                    let mean = (0..points)
+                        .map(|j| Self::goertzel_power(buffer, (low + (j as f32 + 0.5) * step) / rate))
+                        .sum::<f32>() / points as f32;
+                    mean * bins as f32
+                };
+                (band.name.clone(), power.sqrt())
+            })
+            .collect()
+    }
+
+    // |X(f)|² of the window at `cycles_per_sample` = f / rate; any frequency, not just bin centres
+    fn goertzel_power(buffer: &[f32], cycles_per_sample: f32) -> f32 {
+        let coeff = 2.0 * (2.0 * std::f64::consts::PI * cycles_per_sample as f64).cos();
+        let (mut s1, mut s2) = (0.0f64, 0.0f64);
+        for &x in buffer {
+            let s0 = x as f64 + coeff * s1 - s2;
+            s2 = s1;
+            s1 = s0;
+        }
+        (s1 * s1 + s2 * s2 - coeff * s1 * s2).max(0.0) as f32
+    }
+
+    async fn set_analysis_method(&self, method: AnalysisMethod, points_per_band: Option<usize>) -> Result<(), String> {
+        if let Some(points) = points_per_band {
+            if !(1..=MAX_GOERTZEL_POINTS).contains(&points) {
+                return Err(format!(
+                    "❌ Invalid Goertzel points per band {}: must be between 1 and {}",
+                    points, MAX_GOERTZEL_POINTS
+                ));
+            }
+            *self.goertzel_points.lock().await = points;
+        }
+        
+        *self.analysis_method.lock().await = method;
+        match method {
+            AnalysisMethod::Fft => println!("📊 [DEBUG] Band powers from the full FFT"),
+            AnalysisMethod::Goertzel => println!(
+                "📊 [DEBUG] Band powers from Goertzel, {} frequencies per band (whitening, pre-emphasis and multi-resolution skipped)",
+                *self.goertzel_points.lock().await
+            ),
+        }
+        Ok(())
+    }
+
+    async fn set_analysis_source(&self, source: AnalysisSource) {
+        *self.analysis_source.lock().await = source;
+        println!("📊 [DEBUG] Band analysis reads the {:?} buffers", source);
+    }
+
+    // Divide each bin by the moving average of the 2·half_width+1 bins around it, flattening
+    // the broadband 1/f trend so oscillatory peaks stand out. The result is unitless: whitened
+    // band power is only meaningful relative to other bands or to itself over time.
+    fn whiten(spectrum: &mut [f32], half_width: usize) {
+        let mut prefix = Vec::with_capacity(spectrum.len() + 1);
+        prefix.push(0.0f64);
+        for &power in spectrum.iter() {
+            prefix.push(prefix[prefix.len() - 1] + power as f64);
+        }
+        
+        let local: Vec<f64> = (0..spectrum.len())
+            .map(|i| {
+                let (start, end) = (i.saturating_sub(half_width), (i + half_width + 1).min(spectrum.len()));
+                (prefix[end] - prefix[start]) / (end - start) as f64
+            })
+            .collect();
+        for (power, local) in spectrum.iter_mut().zip(local) {
+            *power = (*power as f64 / local.max(1e-12)) as f32;
+        }
+    }
+
+    async fn set_whitening(&self, enabled: bool, smoothing_bins: usize) -> Result<(), String> {
+        if enabled && !(1..=self.buffer_size / 4).contains(&smoothing_bins) {
+            return Err(format!(
+                "❌ Invalid whitening smoothing {} bins: must be between 1 and {}",
+                smoothing_bins, self.buffer_size / 4
+            ));
+        }
+        
+        *self.whitening.lock().await = enabled.then_some(smoothing_bins);
+        println!("⚪ [DEBUG] Spectral whitening: {}", if enabled { format!("±{} bins", smoothing_bins) } else { "off".to_string() });
+        Ok(())
+    }
+
+    // 0 puts the DC bin back into the lowest band
+    async fn set_dc_exclusion_bins(&self, bins: usize) -> Result<(), String> {
+        if bins > MAX_DC_EXCLUSION_BINS {
+            return Err(format!(
+                "❌ Invalid DC exclusion {} bins: must be between 0 and {}",
+                bins, MAX_DC_EXCLUSION_BINS
+            ));
+        }
+        
+        *self.dc_exclusion_bins.lock().await = bins;
+        // Band bin counts change, so re-check the resolution warnings
+        *self.band_resolution_checked.lock().await = None;
+        println!("📊 [DEBUG] Lowest {} FFT bin(s) excluded from band powers", bins);
+        Ok(())
+    }
+
+    // Phase is read from the FFT bin nearest `freq` over the filtered analysis window and
+    // advanced to the newest sample. Latency and accuracy: the value describes the newest
+    // buffered sample, which is already behind real time by the filter group delay (tens of ms
+    // for the 4th-order bandpass) plus up to one 4 ms tick. The estimate spans the whole window
+    // (buffer_size samples, ~2 s at 250 Hz), so it follows slow rhythms well but quick phase
+    // resets late. Off-bin frequencies (up to half the rate/buffer_size resolution away) add an
+    // error of π·Δf/resolution·(N-1)/N.
+    async fn get_phase(&self, channel: usize, freq: f32) -> Result<f32, String> {
+        let (bin, _) = self.phase_bin(freq).await?;
+        let buffers = self.filtered_buffers.lock().await;
+        let buffer = buffers.get(channel)
+            .ok_or_else(|| format!("❌ Channel index {} out of range (stream has {} channels)", channel, buffers.len()))?;
+        let window = self.analysis_window(buffer)
+            .ok_or_else(|| "❌ Analysis buffer is not full yet - wait for buffer_fill_progress to reach 1".to_string())?;
+        Ok(Self::bin_phase(window, bin))
+    }
+
+    // FFT bin for a target frequency and that bin's centre frequency
+    async fn phase_bin(&self, freq: f32) -> Result<(usize, f32), String> {
+        let rate = self.analysis_rate().await;
+        if !valid_rate(rate) {
+            return Err("❌ No valid sample rate known yet".to_string());
+        }
+        if !(freq > 0.0 && freq < rate / 2.0) {
+            return Err(format!("❌ Invalid phase frequency {} Hz: must be between 0 and {:.1} Hz", freq, rate / 2.0));
+        }
+        
+        let resolution = rate / self.buffer_size as f32;
+        let bin = ((freq / resolution).round() as usize).max(1);
+        Ok((bin, bin as f32 * resolution))
+    }
+
+    // Single-bin DFT (O(N), cheaper than a full FFT for one frequency) on the mean-removed window,
+    // rotated from the window start to its last sample
+    fn bin_phase(window: &[f32], bin: usize) -> f32 {
+        let n = window.len();
+        let mean = window.iter().sum::<f32>() / n as f32;
+        let step = 2.0 * std::f64::consts::PI * bin as f64 / n as f64;
+        let (re, im) = window.iter().enumerate().fold((0.0f64, 0.0f64), |(re, im), (i, &x)| {
+            let angle = step * i as f64;
+            let x = (x - mean) as f64;
+            (re + x * angle.cos(), im - x * angle.sin())
+        });
+        
+        let phase = im.atan2(re) + step * (n - 1) as f64;
+        (phase + std::f64::consts::PI).rem_euclid(2.0 * std::f64::consts::PI) as f32 - std::f32::consts::PI
+    }
+
+    async fn set_target_phase(&self, freq: Option<f32>) -> Result<(), String> {
+        if let Some(freq) = freq {
+            let (_, bin_freq) = self.phase_bin(freq).await?;
+            println!("🌀 [DEBUG] Target phase tracking at {} Hz (bin centre {:.2} Hz)", freq, bin_freq);
+        } else {
+            println!("🌀 [DEBUG] Target phase tracking off");
+        }
+        *self.target_phase_hz.lock().await = freq;
+        Ok(())
+    }
+
+    async fn compute_target_phase(&self, timestamp: f64) -> Option<TargetPhase> {
+        let freq = (*self.target_phase_hz.lock().await)?;
+        let (bin, frequency) = self.phase_bin(freq).await.ok()?;
+        let eeg_channels = self.eeg_channel_limit().await;
+        let buffers = self.filtered_buffers.lock().await;
+        
+        let phases = buffers.iter()
+            .take(eeg_channels)
+            .map(|buffer| self.analysis_window(buffer).map(|window| Self::bin_phase(window, bin)))
+            .collect::<Option<Vec<f32>>>()?;
+        Some(TargetPhase { timestamp, frequency, phases })
+    }
+
+    // Hilbert envelope via the FFT: keep only the in-band positive-frequency bins (the band-pass),
+    // double them (the analytic signal) and synthesize the single output sample that is needed
+    fn band_envelope(fft: &dyn Fft<f32>, window: &[f32], scratch: &mut [Complex<f32>], rate: f32, low: f32, high: f32) -> f32 {
+        let n = window.len();
+        let mean = window.iter().sum::<f32>() / n as f32;
+        let mut spectrum: Vec<Complex<f32>> = window.iter().map(|&x| Complex::new(x - mean, 0.0)).collect();
+        fft.process_with_scratch(&mut spectrum, scratch);
+        
+        let at = (n - 1 - n / ENVELOPE_GUARD_FRACTION) as f32;
+        let resolution = rate / n as f32;
+        let analytic: Complex<f32> = spectrum.iter()
+            .enumerate()
+            .take(n.div_ceil(2))
+            .skip(1)
+            .filter(|(k, _)| (low..high).contains(&(*k as f32 * resolution)))
+            .map(|(k, bin)| bin * Complex::from_polar(1.0, 2.0 * std::f32::consts::PI * k as f32 * at / n as f32))
+            .sum();
+        2.0 * analytic.norm() / n as f32
+    }
+
+    async fn set_envelope_band(&self, band: Option<String>) -> Result<(), String> {
+        if let Some(band) = &band {
+            let profile = self.band_profile.lock().await;
+            let Some(definition) = profile.bands.iter().find(|b| &b.name == band) else {
+                let names: Vec<&str> = profile.bands.iter().map(|b| b.name.as_str()).collect();
+                return Err(format!("❌ Unknown band '{}': the active profile has {}", band, names.join(", ")));
+            };
+            println!("〰️ [DEBUG] Tracking the {} envelope ({}-{} Hz)", band, definition.low, definition.high);
+        } else {
+            println!("〰️ [DEBUG] Band envelope tracking off");
+        }
+        *self.envelope_band.lock().await = band;
+        Ok(())
+    }
+
+    // The band is looked up by name each time, so it follows edits of the active profile
+    async fn compute_band_envelope(&self, timestamp: f64) -> Option<Vec<BandEnvelope>> {
+        let band = self.envelope_band.lock().await.clone()?;
+        let definition = self.band_profile.lock().await.bands.iter().find(|b| b.name == band)?.clone();
+        let rate = self.analysis_rate().await;
+        if !valid_rate(rate) {
+            return None;
+        }
+        let eeg_channels = self.eeg_channel_limit().await;
+        let buffers = self.filtered_buffers.lock().await;
+        let mut scratch = vec![Complex::new(0.0, 0.0); self.fft_plan.get_inplace_scratch_len()];
+        
+        let envelopes: Vec<BandEnvelope> = buffers.iter()
+            .enumerate()
+            .take(eeg_channels)
+            .filter_map(|(channel, buffer)| Some((channel, self.analysis_window(buffer)?)))
+            .map(|(channel, window)| BandEnvelope {
+                timestamp,
+                channel,
+                band: band.clone(),
+                amplitude: Self::band_envelope(self.fft_plan.as_ref(), window, &mut scratch, rate, definition.low, definition.high),
+            })
+            .collect();
+        (!envelopes.is_empty()).then_some(envelopes)
+    }
+
+    async fn set_aperiodic_range(&self, low_hz: f32, high_hz: f32) -> Result<(), String> {
+        if !(low_hz > 0.0 && low_hz < high_hz && high_hz.is_finite()) {
+            return Err(format!("❌ Invalid aperiodic fit range {}-{} Hz: need 0 < low < high", low_hz, high_hz));
+        }
+        
+        *self.aperiodic_range.lock().await = (low_hz, high_hz);
+        println!("📉 [DEBUG] Aperiodic fit range set to {}-{} Hz", low_hz, high_hz);
+        Ok(())
+    }
+
+    async fn analyze_aperiodic(&self) -> Vec<Aperiodic> {
+        let eeg_channels = self.eeg_channel_limit().await;
+        let rate = self.analysis_rate().await;
+        if !valid_rate(rate) {
+            return Vec::new();
+        }
+        let range = *self.aperiodic_range.lock().await;
+        
+        let buffers = self.channel_buffers.lock().await;
+        let mut scratch = vec![Complex::new(0.0, 0.0); self.fft_plan.get_inplace_scratch_len()];
+        
+        buffers.iter()
+            .enumerate()
+            .take(eeg_channels)
+            .filter_map(|(channel, buffer)| Some((channel, self.analysis_window(buffer)?)))
+            .filter_map(|(channel, window)| {
+                let (slope, offset) = Self::fit_aperiodic(self.fft_plan.as_ref(), window, &mut scratch, rate, range)?;
+                Some(Aperiodic { channel, slope, offset })
+            })
+            .collect()
+    }
+
+    // Least-squares line through (log10 f, log10 power); None when the range holds under two bins
+    fn fit_aperiodic(
+        fft: &dyn Fft<f32>,
+        buffer: &[f32],
+        scratch: &mut [Complex<f32>],
+        rate: f32,
+        (low_hz, high_hz): (f32, f32),
+    ) -> Option<(f32, f32)> {
+        let spectrum = Self::hann_spectrum(fft, buffer, scratch);
+        let freq_resolution = rate / buffer.len() as f32;
+        
+        let points: Vec<(f64, f64)> = spectrum.iter()
+            .enumerate()
+            .take(buffer.len() / 2 + 1)
+            .skip(1)
+            .map(|(i, bin)| (i as f32 * freq_resolution, bin.norm_sqr()))
+            .filter(|&(freq, _)| {
+                freq >= low_hz && freq <= high_hz
+                    && [50.0f32, 60.0].iter().all(|mains| (freq - mains).abs() > MAINS_EXCLUSION_HZ)
+            })
+            .map(|(freq, power)| ((freq as f64).log10(), (power.max(APERIODIC_POWER_FLOOR) as f64).log10()))
+            .collect();
+        if points.len() < 2 {
+            return None;
+        }
+        
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+        let sxy: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+        let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        let slope = sxy / sxx;
+        Some((slope as f32, (mean_y - slope * mean_x) as f32))
+    }
+
+    async fn set_entropy_range(&self, low_hz: f32, high_hz: f32) -> Result<(), String> {
+        if !(low_hz >= 0.0 && low_hz < high_hz && high_hz.is_finite()) {
+            return Err(format!("❌ Invalid spectral entropy range {}-{} Hz: need 0 <= low < high", low_hz, high_hz));
+        }
+        
+        *self.entropy_range.lock().await = (low_hz, high_hz);
+        println!("🌀 [DEBUG] Spectral entropy range set to {}-{} Hz", low_hz, high_hz);
+        Ok(())
+    }
+
+    async fn analyze_spectral_entropy(&self) -> Vec<SpectralEntropy> {
+        let eeg_channels = self.eeg_channel_limit().await;
+        let rate = self.analysis_rate().await;
+        if !valid_rate(rate) {
+            return Vec::new();
+        }
+        let range = *self.entropy_range.lock().await;
+        
+        let buffers = self.channel_buffers.lock().await;
+        let mut scratch = vec![Complex::new(0.0, 0.0); self.fft_plan.get_inplace_scratch_len()];
+        
+        buffers.iter()
+            .enumerate()
+            .take(eeg_channels)
+            .filter_map(|(channel, buffer)| Some((channel, self.analysis_window(buffer)?)))
+            .filter_map(|(channel, window)| {
+                let value = Self::spectral_entropy(self.fft_plan.as_ref(), window, &mut scratch, rate, range)?;
+                Some(SpectralEntropy { channel, value })
+            })
+            .collect()
+    }
+
+    // Entropy of the raw spectrum over the range, see normalized_entropy
+    fn spectral_entropy(
+        fft: &dyn Fft<f32>,
+        buffer: &[f32],
+        scratch: &mut [Complex<f32>],
+        rate: f32,
+        (low_hz, high_hz): (f32, f32),
+    ) -> Option<f32> {
+        let spectrum = Self::hann_spectrum(fft, buffer, scratch);
+        let freq_resolution = rate / buffer.len() as f32;
+        
+        let powers: Vec<f64> = spectrum.iter()
+            .enumerate()
+            .take(buffer.len() / 2 + 1)
+            .skip(1)
+            .filter(|&(i, _)| {
+                let freq = i as f32 * freq_resolution;
+                freq >= low_hz && freq <= high_hz
+            })
+            .map(|(_, bin)| bin.norm_sqr().max(APERIODIC_POWER_FLOOR) as f64)
+            .collect();
+        normalized_entropy(&powers)
+    }
+
+    // Also records each channel's TAF for individualized bands, clearing it when none is found
+    async fn analyze_taf(&self) -> Vec<Taf> {
+        let eeg_channels = self.eeg_channel_limit().await;
+        let rate = self.analysis_rate().await;
+        if !valid_rate(rate) {
+            return Vec::new();
+        }
+        
+        let buffers = self.channel_buffers.lock().await;
+        let mut scratch = vec![Complex::new(0.0, 0.0); self.fft_plan.get_inplace_scratch_len()];
+        let freq_resolution = rate / self.buffer_size as f32;
+        let found: Vec<(usize, Option<f32>)> = buffers.iter()
+            .enumerate()
+            .take(eeg_channels)
+            .map(|(channel, buffer)| {
+                let hz = self.analysis_window(buffer).and_then(|window| {
+                    let spectrum = Self::hann_spectrum(self.fft_plan.as_ref(), window, &mut scratch);
+                    let power: Vec<f32> = spectrum.iter().take(self.buffer_size / 2 + 1).map(|bin| bin.norm_sqr()).collect();
+                    transition_frequency(&power, freq_resolution)
+                });
+                (channel, hz)
+            })
+            .collect();
+        drop(buffers);
+        
+        if let Some(tafs) = self.individualized_bands.lock().await.as_mut() {
+            *tafs = found.iter().map(|&(_, hz)| hz).collect();
+        }
+        found.into_iter().filter_map(|(channel, hz)| Some(Taf { channel, hz: hz? })).collect()
+    }
+
+    async fn set_individualized_bands(&self, enabled: bool) -> Result<(), String> {
+        if enabled {
+            let names = self.band_profile.lock().await.names();
+            if !(names.iter().any(|n| n == "theta") && names.iter().any(|n| n == "alpha")) {
+                return Err("❌ Individualized bands need a band profile with theta and alpha bands".to_string());
+            }
+        }
+        
+        let mut individualized = self.individualized_bands.lock().await;
+        match (enabled, individualized.is_some()) {
+            (true, false) => *individualized = Some(Vec::new()),
+            (false, _) => *individualized = None,
+            (true, true) => {}
+        }
+        println!("🎯 [DEBUG] Individualized theta/alpha boundary {}", if enabled { "enabled" } else { "disabled" });
+        Ok(())
+    }
+
+    // Peak of the power spectrum averaged over every EEG channel with a full window (and not
+    // gated out); NaN until there is data
+    async fn get_dominant_frequency(&self) -> f32 {
+        let eeg_channels = self.eeg_channel_limit().await;
+        let rate = self.analysis_rate().await;
+        if !valid_rate(rate) {
+            return f32::NAN;
+        }
+        
+        let len = self.buffer_size * *self.zero_pad_factor.lock().await;
+        let fft = if len == self.buffer_size { Arc::clone(&self.fft_plan) } else { FftPlanner::new().plan_fft_forward(len) };
+        
+        let gated: Vec<usize> = self.gated_channels.lock().await.iter().map(|g| g.channel).collect();
+        let buffers = self.channel_buffers.lock().await;
+        let mut scratch = vec![Complex::new(0.0, 0.0); fft.get_inplace_scratch_len()];
+        let mut power = vec![0.0f32; len / 2 + 1];
+        let mut channels = 0;
+        let windows = buffers.iter()
+            .enumerate()
+            .take(eeg_channels)
+            .filter(|(channel, _)| !gated.contains(channel))
+            .filter_map(|(_, buffer)| self.analysis_window(buffer));
+        for window in windows {
+            let spectrum = Self::hann_spectrum(fft.as_ref(), window, &mut scratch);
+            for (total, bin) in power.iter_mut().zip(spectrum.iter()) {
+                *total += bin.norm_sqr();
+            }
+            channels += 1;
+        }
+        drop(buffers);
+        if channels == 0 {
+            return f32::NAN;
+        }
+        
+        let freq_resolution = rate / len as f32;
+        let (low_hz, high_hz) = DOMINANT_FREQUENCY_RANGE_HZ;
+        power.iter()
+            .enumerate()
+            .map(|(i, &total)| (i as f32 * freq_resolution, total / channels as f32))
+            .filter(|&(freq, _)| freq >= low_hz && freq <= high_hz)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(f32::NAN, |(freq, _)| freq)
+    }
+
+    // One THD per EEG channel from the raw spectrum (the filters would shape the harmonics);
+    // NaN for a channel whose window hasn't filled or that carries no power at the fundamental
+    async fn measure_thd(&self, fundamental_hz: f32) -> Result<Vec<f32>, String> {
+        let eeg_channels = self.eeg_channel_limit().await;
+        let rate = self.analysis_rate().await;
+        if !valid_rate(rate) {
+            return Err(format!("❌ Sample rate {} Hz is not usable - cannot measure THD", rate));
+        }
+        let freq_resolution = rate / self.buffer_size as f32;
+        let fundamental_bin = fundamental_hz / freq_resolution;
+        // Closer than this, the fundamental's and second harmonic's main lobes overlap
+        if !fundamental_bin.is_finite() || fundamental_bin < (2 * THD_PEAK_BINS + 1) as f32 {
+            return Err(format!(
+                "❌ Invalid fundamental {} Hz: must be at least {:.2} Hz at the current resolution",
+                fundamental_hz, (2 * THD_PEAK_BINS + 1) as f32 * freq_resolution,
+            ));
+        }
+        if 2.0 * fundamental_hz >= rate / 2.0 {
+            return Err(format!(
+                "❌ Fundamental {} Hz has no harmonic below the {} Hz Nyquist frequency",
+                fundamental_hz, rate / 2.0,
+            ));
+        }
+        
+        let buffers = self.channel_buffers.lock().await;
+        let mut scratch = vec![Complex::new(0.0, 0.0); self.fft_plan.get_inplace_scratch_len()];
+        let thd: Vec<f32> = buffers.iter()
+            .take(eeg_channels)
+            .map(|buffer| {
+                self.analysis_window(buffer)
+                    .and_then(|window| {
+                        let spectrum = Self::hann_spectrum(self.fft_plan.as_ref(), window, &mut scratch);
+                        let power: Vec<f32> = spectrum.iter().take(self.buffer_size / 2 + 1).map(|bin| bin.norm_sqr()).collect();
+                        harmonic_distortion(&power, fundamental_bin)
+                    })
+                    .unwrap_or(f32::NAN)
+            })
+            .collect();
+        
+        println!("📐 [DEBUG] THD at {} Hz: {:?}", fundamental_hz, thd);
+        Ok(thd)
+    }
+
+    async fn get_ssvep_response(&self, freqs: &[f32], harmonics: Option<usize>) -> Result<Vec<ChannelFreqResponse>, String> {
+        let eeg_channels = self.eeg_channel_limit().await;
+        let rate = self.analysis_rate().await;
+        if !valid_rate(rate) {
+            return Err(format!("❌ Sample rate {} Hz is not usable - cannot measure SSVEP responses", rate));
+        }
+        let harmonics = harmonics.unwrap_or(DEFAULT_SSVEP_HARMONICS);
+        if !(1..=MAX_SSVEP_HARMONICS).contains(&harmonics) {
+            return Err(format!("❌ Invalid harmonic count {}: must be between 1 and {}", harmonics, MAX_SSVEP_HARMONICS));
+        }
+        if freqs.is_empty() || freqs.len() > MAX_SSVEP_FREQUENCIES {
+            return Err(format!("❌ Invalid frequency list: must hold between 1 and {} frequencies", MAX_SSVEP_FREQUENCIES));
+        }
+        let freq_resolution = rate / self.buffer_size as f32;
+        if let Some(&bad) = freqs.iter().find(|&&f| !(f.is_finite() && f >= freq_resolution && f < rate / 2.0)) {
+            return Err(format!(
+                "❌ Invalid stimulation frequency {} Hz: must be between {:.2} Hz (one bin) and the {} Hz Nyquist frequency",
+                bad, freq_resolution, rate / 2.0
+            ));
+        }
+        
+        let buffers = self.channel_buffers.lock().await;
+        let mut scratch = vec![Complex::new(0.0, 0.0); self.fft_plan.get_inplace_scratch_len()];
+        let responses = buffers.iter()
+            .enumerate()
+            .take(eeg_channels)
+            .filter_map(|(channel, buffer)| Some((channel, self.analysis_window(buffer)?)))
+            .map(|(channel, window)| {
+                let spectrum = Self::hann_spectrum(self.fft_plan.as_ref(), window, &mut scratch);
+                let power: Vec<f32> = spectrum.iter().take(self.buffer_size / 2 + 1).map(|bin| bin.norm_sqr()).collect();
+                let responses = freqs.iter()
+                    .map(|&frequency| {
+                        let harmonics: Vec<HarmonicResponse> = (1..=harmonics)
+                            .map(|harmonic| (harmonic, frequency * harmonic as f32))
+                            .take_while(|&(_, freq)| freq < rate / 2.0)
+                            .map(|(harmonic, freq)| {
+                                let (power, snr) = ssvep_peak(&power, (freq / freq_resolution).round() as usize);
+                                HarmonicResponse { harmonic, frequency: freq, power, snr }
+                            })
+                            .collect();
+                        FreqResponse { frequency, total_power: harmonics.iter().map(|h| h.power).sum(), harmonics }
+                    })
+                    .collect();
+                ChannelFreqResponse { channel, responses }
+            })
+            .collect();
+        Ok(responses)
+    }
+
+    async fn set_muscle_artifact(&self, enabled: bool, threshold: Option<f32>, exclude_from_averages: Option<bool>) -> Result<(), String> {
+        if !enabled {
+            *self.muscle_artifact.lock().await = None;
+            println!("💪 [DEBUG] Muscle artifact detection disabled");
+            return Ok(());
+        }
+        
+        let threshold = threshold.unwrap_or(DEFAULT_MUSCLE_THRESHOLD);
+        if !(threshold > 0.0 && threshold < 1.0) {
+            return Err(format!("❌ Invalid muscle artifact threshold {}: must be between 0 and 1 (exclusive)", threshold));
+        }
+        let config = MuscleArtifactConfig { threshold, exclude_from_averages: exclude_from_averages.unwrap_or(false) };
+        *self.muscle_artifact.lock().await = Some(config);
+        println!(
+            "💪 [DEBUG] Muscle artifact detection: >{} Hz share above {}{}",
+            MUSCLE_CUTOFF_HZ, threshold, if config.exclude_from_averages { ", excluded from averages" } else { "" }
+        );
+        Ok(())
+    }
+
+    async fn set_variance_trend(&self, enabled: bool, window_s: Option<f64>) -> Result<(), String> {
+        if !enabled {
+            *self.variance_trend.lock().await = None;
+            println!("📈 [DEBUG] Channel variance trend disabled");
+            return Ok(());
+        }
+        
+        let window_s = window_s.unwrap_or(DEFAULT_VARIANCE_WINDOW_S);
+        if !(MIN_VARIANCE_WINDOW_S..=MAX_VARIANCE_WINDOW_S).contains(&window_s) {
+            return Err(format!(
+                "❌ Invalid variance window {} s: must be between {} and {} seconds",
+                window_s, MIN_VARIANCE_WINDOW_S, MAX_VARIANCE_WINDOW_S
+            ));
+        }
+        *self.variance_trend.lock().await = Some(VarianceTrend::new(window_s));
+        println!("📈 [DEBUG] Channel variance over {} s, reported every {} s", window_s, VARIANCE_BLOCK_S);
+        Ok(())
+    }
+
+    async fn update_variance_trend(&self, sample: &EEGSample) -> Option<ChannelVarianceTrend> {
+        self.variance_trend.lock().await.as_mut()?.push(sample.timestamp, &sample.channels)
+    }
+
+    // Works on the raw buffers: the bandpass removes exactly the power this looks for. None when
+    // detection is off, nothing is flagged, or Nyquist doesn't reach past the cutoff.
+    async fn analyze_muscle_artifact(&self, timestamp: f64) -> Option<MuscleArtifact> {
+        let config = (*self.muscle_artifact.lock().await)?;
+        let rate = self.analysis_rate().await;
+        if !valid_rate(rate) || rate / 2.0 <= MUSCLE_CUTOFF_HZ + MAINS_EXCLUSION_HZ {
+            return None;
+        }
+        let eeg_channels = self.eeg_channel_limit().await;
+        let freq_resolution = rate / self.buffer_size as f32;
+        let near_mains = |freq: f32| [50.0f32, 60.0].iter().any(|mains| {
+            let harmonic = (freq / mains).round().max(1.0) * mains;
+            (freq - harmonic).abs() <= MAINS_EXCLUSION_HZ
+        });
+        
+        let buffers = self.channel_buffers.lock().await;
+        let mut scratch = vec![Complex::new(0.0, 0.0); self.fft_plan.get_inplace_scratch_len()];
+        let channels: Vec<MuscleArtifactChannel> = buffers.iter()
+            .enumerate()
+            .take(eeg_channels)
+            .filter_map(|(channel, buffer)| Some((channel, self.analysis_window(buffer)?)))
+            .filter_map(|(channel, window)| {
+                let spectrum = Self::hann_spectrum(self.fft_plan.as_ref(), window, &mut scratch);
+                let (mut high, mut total) = (0.0f64, 0.0f64);
+                for (i, bin) in spectrum.iter().enumerate().take(self.buffer_size / 2 + 1) {
+                    let freq = i as f32 * freq_resolution;
+                    if freq < 1.0 || near_mains(freq) {
+                        continue;
+                    }
+                    let power = bin.norm_sqr() as f64;
+                    total += power;
+                    if freq > MUSCLE_CUTOFF_HZ {
+                        high += power;
+                    }
+                }
+                let high_frequency_ratio = if total > 0.0 { (high / total) as f32 } else { 0.0 };
+                (high_frequency_ratio > config.threshold).then_some(MuscleArtifactChannel { channel, high_frequency_ratio })
+            })
+            .collect();
+        
+        (!channels.is_empty()).then_some(MuscleArtifact { timestamp, channels })
+    }
+
+    // Bands for the heartbeat and session averages: flagged channels drop out when so configured
+    async fn bands_for_averages(&self, bands: &[FrequencyBands], artifact: Option<&MuscleArtifact>) -> Vec<FrequencyBands> {
+        let exclude = self.muscle_artifact.lock().await.is_some_and(|config| config.exclude_from_averages);
+        match artifact {
+            Some(artifact) if exclude => bands.iter()
+                .filter(|b| !artifact.channels.iter().any(|flagged| flagged.channel == b.channel))
+                .cloned()
+                .collect(),
+            _ => bands.to_vec(),
+        }
+    }
+
+    async fn set_gate_bad_channels(&self, enabled: bool) {
+        *self.gate_bad_channels.lock().await = enabled;
+        if !enabled {
+            self.gated_channels.lock().await.clear();
+        }
+        println!("🚧 [DEBUG] Bad channel gating of averaged outputs {}", if enabled { "enabled" } else { "disabled" });
+    }
+
+    // Flags the EEG channels whose raw and filtered windows are both full. Some when gating is
+    // on and the excluded set differs from the last cycle's.
+    async fn evaluate_channel_gate(&self, timestamp: f64) -> Option<ChannelGate> {
+        if !*self.gate_bad_channels.lock().await {
+            return None;
+        }
+        let eeg_channels = self.eeg_channel_limit().await;
+        let raw = self.channel_buffers.lock().await;
+        let filtered = self.filtered_buffers.lock().await;
+        let excluded: Vec<GatedChannel> = raw.iter()
+            .zip(filtered.iter())
+            .enumerate()
+            .take(eeg_channels)
+            .filter_map(|(channel, (raw, filtered))| {
+                let reason = bad_channel_reason(self.analysis_window(raw)?, self.analysis_window(filtered)?)?;
+                Some(GatedChannel { channel, reason })
+            })
+            .collect();
+        drop((raw, filtered));
+        
+        let mut gated = self.gated_channels.lock().await;
+        if *gated == excluded {
+            return None;
+        }
+        println!("🚧 [DEBUG] Channels excluded from averages: {:?}", excluded);
+        *gated = excluded.clone();
+        Some(ChannelGate { timestamp, excluded })
+    }
+
+    // 0 stops keeping frames (and drops the ones kept)
+    async fn set_frame_history(&self, length: usize) -> Result<(), String> {
+        if length > MAX_FRAME_HISTORY {
+            return Err(format!("❌ Invalid frame history length {}: must be at most {} frames", length, MAX_FRAME_HISTORY));
+        }
+        
+        let mut history = self.frame_history.lock().await;
+        history.capacity = length;
+        let excess = history.frames.len().saturating_sub(length);
+        history.frames.drain(..excess);
+        println!("🕰️ [DEBUG] Keeping the last {} combined frames", length);
+        Ok(())
+    }
+
+    async fn get_frame_at(&self, timestamp: f64) -> Result<Option<CombinedFrame>, String> {
+        if !timestamp.is_finite() {
+            return Err(format!("❌ Invalid timestamp {}", timestamp));
+        }
+        Ok(self.frame_history.lock().await.nearest(timestamp).cloned())
+    }
+
+    // Bands without the channels the last gate check excluded
+    async fn gated_bands(&self, bands: &[FrequencyBands]) -> Vec<FrequencyBands> {
+        let gated = self.gated_channels.lock().await;
+        bands.iter()
+            .filter(|b| !gated.iter().any(|g| g.channel == b.channel))
+            .cloned()
+            .collect()
+    }
+
+    // From the last analysis cycle
+    async fn last_snr(&self) -> Vec<ChannelSnr> {
+        self.last_snr.lock().await.clone()
+    }
+
+    // Spectrum of a raw window with the electrode DC offset removed and a Hann taper applied:
+    // without the taper a strong rhythm leaks across the whole spectrum
+    // Zero-padded to the plan's length when it is longer than the buffer
+    fn hann_spectrum(fft: &dyn Fft<f32>, buffer: &[f32], scratch: &mut [Complex<f32>]) -> Vec<Complex<f32>> {
+        let n = buffer.len() as f32;
+        let mean = buffer.iter().sum::<f32>() / n;
+        let mut spectrum: Vec<Complex<f32>> = buffer.iter()
+            .enumerate()
+            .map(|(i, &x)| {
+                let hann = 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / n).cos();
+                Complex::new((x - mean) * hann, 0.0)
+            })
+            .collect();
+        spectrum.resize(fft.len().max(buffer.len()), Complex::new(0.0, 0.0));
+        fft.process_with_scratch(&mut spectrum, scratch);
+        spectrum
+    }
+
+    // In-band over out-of-band power of a 0..=Nyquist power spectrum; bins below `min_freq` (DC)
+    // are left out
+    fn snr_db(spectrum: &[f32], freq_resolution: f32, min_freq: f32) -> f32 {
+        let (mut signal, mut noise) = (0.0f64, 0.0f64);
+        for (i, &power) in spectrum.iter().enumerate() {
+            let power = power as f64;
+            let freq = i as f32 * freq_resolution;
+            if freq < min_freq {
+                continue;
+            }
+            if freq >= SNR_BAND_HZ.0 && freq < SNR_BAND_HZ.1 {
+                signal += power;
+            } else {
+                noise += power;
+            }
+        }
+        
+        (10.0 * (signal.max(1e-20) / noise.max(1e-20)).log10()) as f32
+    }
+
+    async fn analyze_hjorth(&self, timestamp: f64) -> Vec<HjorthParameters> {
+        let eeg_channels = self.eeg_channel_limit().await;
+        let buffers = self.filtered_buffers.lock().await;
+        
+        buffers.iter()
+            .enumerate()
+            .take(eeg_channels)
+            .filter_map(|(channel, buffer)| Some((channel, self.analysis_window(buffer)?)))
+            .map(|(channel, buffer)| {
+                let (activity, mobility, complexity) = hjorth_parameters(buffer);
+                HjorthParameters { timestamp, channel, activity, mobility, complexity }
+            })
+            .collect()
+    }
+
+    async fn analyze_correlation(&self, timestamp: f64) -> Option<CorrelationMatrix> {
+        let eeg_channels = self.eeg_channel_limit().await;
+        let buffers = self.filtered_buffers.lock().await;
+        
+        let (channels, centered): (Vec<usize>, Vec<Vec<f64>>) = buffers.iter()
+            .enumerate()
+            .take(eeg_channels)
+            .filter_map(|(channel, buffer)| Some((channel, self.analysis_window(buffer)?)))
+            .map(|(channel, window)| {
+                let mean = window.iter().map(|&v| v as f64).sum::<f64>() / window.len() as f64;
+                (channel, window.iter().map(|&v| v as f64 - mean).collect())
+            })
+            .unzip();
+        drop(buffers);
+        
+        if channels.len() < 2 {
+            return None;
+        }
+        
+        let norms: Vec<f64> = centered.iter().map(|c| c.iter().map(|v| v * v).sum::<f64>().sqrt()).collect();
+        let size = channels.len();
+        let mut values = vec![0.0f32; size * size];
+        for i in 0..size {
+            values[i * size + i] = 1.0;
+            for j in i + 1..size {
+                // A flat channel has no defined correlation; report 0 rather than NaN
+                let denominator = norms[i] * norms[j];
+                let r = if denominator > 0.0 {
+                    centered[i].iter().zip(centered[j].iter()).map(|(a, b)| a * b).sum::<f64>() / denominator
+                } else {
+                    0.0
+                };
+                values[i * size + j] = r as f32;
+                values[j * size + i] = r as f32;
+            }
+        }
+        
+        Some(CorrelationMatrix { timestamp, channels, size, values })
+    }
+
+    // None turns the estimator off
+    async fn set_xcorr_pair(&self, pair: Option<(usize, usize)>, max_lag_ms: f32) -> Result<(), String> {
+        let Some((a, b)) = pair else {
+            *self.xcorr.lock().await = None;
+            println!("🔀 [DEBUG] Cross-correlation lag estimation off");
+            return Ok(());
+        };
+        if a == b {
+            return Err(format!("❌ Invalid cross-correlation pair ({}, {}): pick two different channels", a, b));
+        }
+        if !(max_lag_ms > 0.0 && max_lag_ms <= MAX_XCORR_LAG_MS) {
+            return Err(format!("❌ Invalid maximum lag {} ms: must be above 0 and at most {} ms", max_lag_ms, MAX_XCORR_LAG_MS));
+        }
+        let connection = self.lsl_connection.lock().await;
+        if connection.is_real_connection && a.max(b) >= connection.channel_count {
+            return Err(format!(
+                "❌ Channel index {} out of range (stream has {} channels)",
+                a.max(b), connection.channel_count
+            ));
+        }
+        drop(connection);
+        
+        *self.xcorr.lock().await = Some(XcorrConfig { pair: (a, b), max_lag_ms });
+        println!("🔀 [DEBUG] Cross-correlation lag of channels {} -> {} within ±{} ms", a, b, max_lag_ms);
+        Ok(())
+    }
+
+    async fn analyze_xcorr_lag(&self, timestamp: f64) -> Option<XcorrLag> {
+        let config = (*self.xcorr.lock().await)?;
+        let rate = self.analysis_rate().await;
+        if !valid_rate(rate) {
+            return None;
+        }
+        
+        let buffers = self.filtered_buffers.lock().await;
+        let (a, b) = config.pair;
+        let window = |ch: usize| buffers.get(ch).and_then(|buffer| self.analysis_window(buffer))
+            .map(|window| window.iter().map(|&v| v as f64).collect::<Vec<f64>>());
+        let (first, second) = (window(a)?, window(b)?);
+        drop(buffers);
+        
+        let max_lag = (config.max_lag_ms / 1000.0 * rate).round() as usize;
+        let (lag, peak) = xcorr_peak(&first, &second, max_lag)?;
+        Some(XcorrLag { timestamp, pair: config.pair, lag_ms: lag as f32 * 1000.0 / rate, peak: peak as f32 })
+    }
+
+    async fn add_trigger(&self, metric: TriggerMetric, channel: usize, op: TriggerOp, threshold: f32) -> Result<u64, String> {
+        if !threshold.is_finite() {
+            return Err(format!("❌ Invalid trigger threshold {}", threshold));
+        }
+        
+        let connection = self.lsl_connection.lock().await;
+        if connection.is_real_connection && channel >= connection.channel_count {
+            return Err(format!(
+                "❌ Channel index {} out of range (stream has {} channels)",
+                channel, connection.channel_count
+            ));
+        }
+        drop(connection);
+        
+        let mut next_id = self.next_trigger_id.lock().await;
+        let id = *next_id;
+        *next_id += 1;
+        
+        println!("🎯 [DEBUG] Trigger {} added: {:?} on channel {} {:?} {}", id, metric, channel, op, threshold);
+        self.triggers.lock().await.push(Trigger {
+            id,
+            metric,
+            channel,
+            op,
+            threshold,
+            armed: true,
+            last_fired: None,
+        });
+        Ok(id)
+    }
+
+    async fn remove_trigger(&self, id: u64) -> Result<(), String> {
+        let mut triggers = self.triggers.lock().await;
+        let before = triggers.len();
+        triggers.retain(|t| t.id != id);
+        if triggers.len() == before {
+            return Err(format!("❌ No trigger with id {}", id));
+        }
+        
+        println!("🎯 [DEBUG] Trigger {} removed", id);
+        Ok(())
+    }
+
+    async fn evaluate_triggers(&self, timestamp: f64, bands: &[FrequencyBands]) -> Vec<TriggerFired> {
+        let mut triggers = self.triggers.lock().await;
+        if triggers.is_empty() {
+            return Vec::new();
+        }
+        
+        let buffers = self.filtered_buffers.lock().await;
+        let mut fired = Vec::new();
+        for trigger in triggers.iter_mut() {
+            let channel_bands = bands.iter().find(|b| b.channel == trigger.channel);
+            let value = match (&trigger.metric, channel_bands) {
+                (TriggerMetric::Band(band), Some(b)) => match b.get(band) {
+                    Some(value) => value,
+                    None => continue, // band not in the active profile
+                },
+                (TriggerMetric::ThetaBetaRatio, Some(b)) => match (b.get("theta"), b.get("beta")) {
+                    (Some(theta), Some(beta)) if beta > 0.0 => theta / beta,
+                    _ => continue,
+                },
+                (TriggerMetric::Rms, _) => match buffers.get(trigger.channel).and_then(|b| self.analysis_window(b)) {
+                    Some(window) => (window.iter().map(|v| v * v).sum::<f32>() / window.len() as f32).sqrt(),
+                    None => continue,
+                },
+                _ => continue, // channel has no analysis result this cycle
+            };
+            
+            if trigger.evaluate(value, timestamp) {
+                fired.push(TriggerFired { id: trigger.id, value, timestamp });
+            }
+        }
+        fired
+    }
+
+    async fn set_erd_windows(&self, baseline_s: f64, activity_s: f64) -> Result<(), String> {
+        for (name, seconds) in [("baseline", baseline_s), ("activity", activity_s)] {
+            if !(seconds > 0.0 && seconds <= 30.0) {
+                return Err(format!("❌ Invalid ERD/ERS {} window {} s: must be between 0 and 30 seconds", name, seconds));
+            }
+        }
+        
+        *self.erd_windows.lock().await = ErdWindows { baseline_s, activity_s };
+        println!("📉 [DEBUG] ERD/ERS windows: baseline {} s, activity {} s", baseline_s, activity_s);
+        Ok(())
+    }
+
+    // Markers are stamped with the newest sample's timestamp so they share the analysis clock
+    async fn mark_event(&self, label: Option<String>) -> Result<f64, String> {
+        let timestamp = self.replay_buffer.lock().await.frames.back()
+            .map(|frame| frame.timestamp)
+            .ok_or_else(|| "❌ No samples received yet - cannot place a marker".to_string())?;
+        
+        println!("📍 [DEBUG] Event marker {:?} at {:.3}", label, timestamp);
+        self.place_marker(EventMarker { timestamp, label }).await;
+        Ok(timestamp)
+    }
+
+    // Pending for ERD/ERS and kept for epoching
+    async fn place_marker(&self, marker: EventMarker) {
+        let mut epoch_markers = self.epoch_markers.lock().await;
+        if epoch_markers.len() == MAX_EPOCH_MARKERS {
+            epoch_markers.pop_front();
+        }
+        epoch_markers.push_back(marker.clone());
+        drop(epoch_markers);
+        self.pending_markers.lock().await.push(marker);
+    }
+
+    // Epochs start at the first sample at or after each marker (optionally only markers with
+    // `label`) and use the filtered samples, so only markers within the replay buffer count
+    async fn compute_itpc(&self, label: Option<String>, epoch_ms: Option<u64>) -> Result<Itpc, String> {
+        let epoch_ms = epoch_ms.unwrap_or(DEFAULT_ITPC_EPOCH_MS);
+        if !(MIN_ITPC_EPOCH_MS..=MAX_ITPC_EPOCH_MS).contains(&epoch_ms) {
+            return Err(format!(
+                "❌ Invalid epoch length {} ms: must be between {} and {} ms",
+                epoch_ms, MIN_ITPC_EPOCH_MS, MAX_ITPC_EPOCH_MS
+            ));
+        }
+        let rate = self.analysis_rate().await;
+        if !valid_rate(rate) {
+            return Err(format!("❌ Sample rate {} Hz is not usable - cannot compute ITPC", rate));
+        }
+        let epoch_samples = (epoch_ms as f32 / 1000.0 * rate).round() as usize;
+        let eeg_channels = self.eeg_channel_limit().await;
+        
+        let markers: Vec<f64> = self.epoch_markers.lock().await.iter()
+            .filter(|marker| label.is_none() || marker.label == label)
+            .map(|marker| marker.timestamp)
+            .collect();
+        let replay = self.replay_buffer.lock().await;
+        let mut epochs: Vec<Vec<Vec<f32>>> = Vec::new(); // epoch, channel, sample
+        for &onset in &markers {
+            let start = replay.frames.partition_point(|frame| frame.timestamp < onset);
+            let frames: Vec<&ReplayFrame> = replay.frames.range(start..).take(epoch_samples).collect();
+            if frames.len() < epoch_samples {
+                continue;
+            }
+            let width = frames.iter().map(|frame| frame.filtered.len()).min().unwrap_or(0).min(eeg_channels);
+            epochs.push((0..width).map(|channel| frames.iter().map(|frame| frame.filtered[channel]).collect()).collect());
+        }
+        drop(replay);
+        let skipped_markers = markers.len() - epochs.len();
+        if epochs.len() < MIN_ITPC_EPOCHS {
+            return Err(format!(
+                "❌ Only {} complete epochs of {} ms in the replay buffer ({} matching markers) - need at least {}",
+                epochs.len(), epoch_ms, markers.len(), MIN_ITPC_EPOCHS
+            ));
+        }
+        
+        let fft = FftPlanner::new().plan_fft_forward(epoch_samples);
+        let mut scratch = vec![Complex::new(0.0, 0.0); fft.get_inplace_scratch_len()];
+        let width = epochs.iter().map(Vec::len).min().unwrap_or(0);
+        let channels = (0..width)
+            .map(|channel| {
+                let spectra: Vec<Vec<Complex<f32>>> = epochs.iter()
+                    .map(|epoch| Self::hann_spectrum(fft.as_ref(), &epoch[channel], &mut scratch))
+                    .collect();
+                ChannelItpc { channel, itpc: inter_trial_coherence(&spectra) }
+            })
+            .collect();
+        let frequencies = (0..=epoch_samples / 2).map(|bin| bin as f32 * rate / epoch_samples as f32).collect();
+        
+        println!("🌀 [DEBUG] ITPC over {} epochs of {} ms ({} markers skipped)", epochs.len(), epoch_ms, skipped_markers);
+        Ok(Itpc { epochs: epochs.len(), epoch_ms, skipped_markers, frequencies, channels })
+    }
+
+    // A marker like mark_event's, plus the synthetic deflection on the samples after it.
+    // Only CSV sources are altered; live streams are never touched.
+    async fn inject_test_marker(&self, code: String) -> Result<f64, String> {
+        if !self.lsl_connection.lock().await.csv_source {
+            return Err("❌ Test markers need a CSV source (connect_to_csv_stream)".to_string());
+        }
+        let timestamp = self.mark_event(Some(code)).await?;
+        self.test_markers.lock().await.onsets.push(timestamp);
+        Ok(timestamp)
+    }
+
+    // Places an "auto" test marker every interval on the CSV sample clock; 0 turns it off
+    async fn set_auto_markers(&self, interval_ms: u64) -> Result<(), String> {
+        if interval_ms != 0 && !(MIN_AUTO_MARKER_INTERVAL_MS..=MAX_AUTO_MARKER_INTERVAL_MS).contains(&interval_ms) {
+            return Err(format!(
+                "❌ Invalid auto marker interval {} ms: must be 0 (off) or between {} and {} ms",
+                interval_ms, MIN_AUTO_MARKER_INTERVAL_MS, MAX_AUTO_MARKER_INTERVAL_MS
+            ));
+        }
+        
+        let mut test_markers = self.test_markers.lock().await;
+        test_markers.auto_interval_s = (interval_ms != 0).then(|| interval_ms as f64 / 1000.0);
+        test_markers.next_auto = None;
+        match test_markers.auto_interval_s {
+            Some(_) => println!("📍 [DEBUG] Auto test markers every {} ms (CSV sources only)", interval_ms),
+            None => println!("📍 [DEBUG] Auto test markers off"),
+        }
+        Ok(())
+    }
+
+    // Deflections are in the file's units, ahead of unit scaling
+    async fn apply_test_markers(&self, sample: &mut EEGSample, eeg_channels: usize) {
+        let mut test_markers = self.test_markers.lock().await;
+        let mut placed = None;
+        if let Some(interval) = test_markers.auto_interval_s {
+            let next = *test_markers.next_auto.get_or_insert(sample.timestamp + interval);
+            if sample.timestamp >= next {
+                // A jump in the file's timestamps restarts the cadence rather than bursting
+                let following = next + interval;
+                test_markers.next_auto = Some(if following <= sample.timestamp { sample.timestamp + interval } else { following });
+                test_markers.onsets.push(sample.timestamp);
+                placed = Some(sample.timestamp);
+            }
+        }
+        test_markers.onsets.retain(|onset| sample.timestamp - onset < TEST_ERP_DURATION_S);
+        let deflection = test_markers.deflection(sample.timestamp);
+        drop(test_markers);
+        
+        if deflection != 0.0 {
+            sample.channels.iter_mut().take(eeg_channels).for_each(|value| *value += deflection);
+        }
+        if let Some(timestamp) = placed {
+            println!("📍 [DEBUG] Auto test marker at {:.3}", timestamp);
+            self.place_marker(EventMarker { timestamp, label: Some(AUTO_MARKER_CODE.to_string()) }).await;
+        }
+    }
+
+    // Record this analysis cycle and resolve every marker whose activity window has passed
+    async fn evaluate_erd_ers(&self, timestamp: f64, bands: &[FrequencyBands]) -> Vec<ErdErs> {
+        let windows = *self.erd_windows.lock().await;
+        let mut history = self.band_history.lock().await;
+        history.push_back(bands.to_vec());
+        let oldest_needed = timestamp - windows.baseline_s - windows.activity_s - 1.0;
+        while history.front().and_then(|cycle| cycle.first()).is_some_and(|b| b.timestamp < oldest_needed) {
+            history.pop_front();
+        }
+        
+        let mut markers = self.pending_markers.lock().await;
+        let (ready, waiting): (Vec<EventMarker>, Vec<EventMarker>) = markers.drain(..)
+            .partition(|marker| timestamp >= marker.timestamp + windows.activity_s);
+        *markers = waiting;
+        
+        let mut results = Vec::new();
+        for marker in ready {
+            // Mean power per (channel, band) over the cycles that fall inside [start, end)
+            let mean_power = |start: f64, end: f64| {
+                let mut sums: HashMap<(usize, String), (f32, usize)> = HashMap::new();
+                for b in history.iter().flatten().filter(|b| b.timestamp >= start && b.timestamp < end) {
+                    for (band, amplitude) in &b.bands {
+                        let entry = sums.entry((b.channel, band.clone())).or_insert((0.0, 0));
+                        entry.0 += amplitude * amplitude;
+                        entry.1 += 1;
+                    }
+                }
+                sums.into_iter().map(|(key, (sum, n))| (key, sum / n as f32)).collect::<HashMap<_, _>>()
+            };
+            
+            let baseline = mean_power(marker.timestamp - windows.baseline_s, marker.timestamp);
+            let activity = mean_power(marker.timestamp, marker.timestamp + windows.activity_s);
+            if baseline.is_empty() {
+                println!("⚠️ [DEBUG] ERD/ERS: no baseline data before marker at {:.3}", marker.timestamp);
+                continue;
+            }
+            
+            let mut marker_results: Vec<ErdErs> = activity.iter()
+                .filter_map(|((channel, band), &power)| {
+                    let reference = baseline.get(&(*channel, band.clone()))?.max(ERD_POWER_FLOOR);
+                    Some(ErdErs {
+                        band: band.clone(),
+                        channel: *channel,
+                        percent_change: (power - reference) / reference * 100.0,
+                        marker_timestamp: marker.timestamp,
+                        label: marker.label.clone(),
+                    })
+                })
+                .collect();
+            marker_results.sort_by(|a, b| a.channel.cmp(&b.channel).then_with(|| a.band.cmp(&b.band)));
+            results.extend(marker_results);
+        }
+        results
+    }
+
+    async fn set_score_weights(&self, weights: HashMap<String, f32>) -> Result<(), String> {
+        let known_bands = self.band_profile.lock().await.names();
+        if weights.is_empty() || weights.values().all(|w| *w == 0.0) {
+            return Err("❌ Score weights must contain at least one non-zero weight".to_string());
+        }
+        // Keys match band names case-insensitively and are stored as the profile spells them
+        let mut resolved = HashMap::with_capacity(weights.len());
+        for (band, weight) in weights {
+            let Some(name) = known_bands.iter().find(|b| b.eq_ignore_ascii_case(&band)) else {
+                return Err(format!("❌ Unknown band '{}': expected one of {:?}", band, known_bands));
+            };
+            if !weight.is_finite() {
+                return Err(format!("❌ Invalid weight {} for band '{}'", weight, band));
+            }
+            if resolved.insert(name.clone(), weight).is_some() {
+                return Err(format!("❌ Band '{}' is weighted more than once", name));
+            }
+        }
+        let weights = resolved;
+        
+        let mut state = self.calm_score.lock().await;
+        println!("🧘 [DEBUG] Calm score weights set to {:?} (baseline cleared)", weights);
+        state.weights = weights;
+        state.baseline = None; // a baseline of the old weighting no longer applies
+        Ok(())
+    }
+
+    // Without `band`, sets every band and drops the per-band overrides
+    async fn set_band_normalization(&self, mode: BandNormalization, band: Option<String>) -> Result<(), String> {
+        let mut normalization = self.band_normalization.lock().await;
+        match band {
+            Some(band) => {
+                let known_bands = self.band_profile.lock().await.names();
+                if !known_bands.contains(&band) {
+                    return Err(format!("❌ Unknown band '{}': expected one of {:?}", band, known_bands));
+                }
+                println!("📐 [DEBUG] Band '{}' normalized as {:?}", band, mode);
+                normalization.per_band.insert(band, mode);
+            }
+            None => {
+                println!("📐 [DEBUG] All bands normalized as {:?}", mode);
+                normalization.mode = mode;
+                normalization.per_band.clear();
+            }
+        }
+        if matches!(mode, BandNormalization::Zscore | BandNormalization::DbBaseline) && normalization.baseline.is_empty() {
+            println!("⚠️ [DEBUG] No band baseline yet - {:?} values stay null until calibrate_band_baseline finishes", mode);
+        }
+        Ok(())
+    }
+
+    async fn calibrate_band_baseline(&self, seconds: f64) -> Result<(), String> {
+        if !(5.0..=600.0).contains(&seconds) {
+            return Err(format!("❌ Invalid calibration duration {} s: must be between 5 and 600 seconds", seconds));
+        }
+        
+        self.band_normalization.lock().await.calibration = Some(BandCalibration { duration_s: seconds, start: None, powers: HashMap::new() });
+        println!("📐 [DEBUG] Band baseline calibration started for {} s", seconds);
+        Ok(())
+    }
+
+    async fn calibrate_calm_score(&self, seconds: f64) -> Result<(), String> {
+        if !(5.0..=600.0).contains(&seconds) {
+            return Err(format!("❌ Invalid calibration duration {} s: must be between 5 and 600 seconds", seconds));
+        }
+        
+        self.calm_score.lock().await.calibration = Some((seconds, None, Vec::new()));
+        println!("🧘 [DEBUG] Calm score calibration started for {} s", seconds);
+        Ok(())
+    }
+
+    async fn set_meditation_index_weights(&self, weights: MeditationWeights) -> Result<(), String> {
+        let all = [weights.alpha, weights.theta, weights.beta];
+        if all.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            return Err(format!("❌ Invalid meditation index weights {:?}: must be finite and not negative", weights));
+        }
+        if weights.beta == 0.0 || weights.alpha + weights.theta == 0.0 {
+            return Err("❌ Meditation index weights need a non-zero beta and a non-zero alpha or theta".to_string());
+        }
+        
+        let mut state = self.meditation_index.lock().await;
+        println!("🧘 [DEBUG] Meditation index weights set to {:?} (baseline cleared)", weights);
+        state.weights = weights;
+        state.baseline = None; // a baseline of the old weighting no longer applies
+        Ok(())
+    }
+
+    async fn calibrate_meditation_index(&self, seconds: f64) -> Result<(), String> {
+        if !(5.0..=600.0).contains(&seconds) {
+            return Err(format!("❌ Invalid calibration duration {} s: must be between 5 and 600 seconds", seconds));
+        }
+        
+        self.meditation_index.lock().await.calibration = Some((seconds, None, Vec::new()));
+        println!("🧘 [DEBUG] Meditation index calibration started for {} s", seconds);
+        Ok(())
+    }
+
+    // Restarts a test already running
+    async fn run_alpha_blocking_test(&self, phase_s: f64) -> Result<(), String> {
+        if !(ALPHA_BLOCKING_SETTLE_S * 2.0..=300.0).contains(&phase_s) {
+            return Err(format!(
+                "❌ Invalid phase duration {} s: must be between {} and 300 seconds",
+                phase_s, ALPHA_BLOCKING_SETTLE_S * 2.0
+            ));
+        }
+        if !self.band_profile.lock().await.names().iter().any(|name| name == "alpha") {
+            return Err("❌ The alpha blocking test needs a band profile with an alpha band".to_string());
+        }
+        
+        *self.alpha_blocking.lock().await = Some(AlphaBlockingTest {
+            phase_s,
+            phase: AlphaBlockingPhase::EyesClosed,
+            phase_start: None,
+            eyes_closed: HashMap::new(),
+            eyes_open: HashMap::new(),
+        });
+        println!("👁️ [DEBUG] Alpha blocking test started, {} s per phase", phase_s);
+        Ok(())
+    }
+
+    // Called every analysis cycle; the prompt when a phase begins, the result when the test ends
+    async fn update_alpha_blocking(
+        &self,
+        timestamp: f64,
+        bands: &[FrequencyBands],
+    ) -> (Option<AlphaBlockingPrompt>, Option<AlphaBlockingResult>) {
+        let mut state = self.alpha_blocking.lock().await;
+        let Some(test) = state.as_mut() else { return (None, None) };
+        
+        let prompt = |phase: AlphaBlockingPhase| {
+            let instruction = match phase {
+                AlphaBlockingPhase::EyesClosed => "Close your eyes and relax",
+                AlphaBlockingPhase::EyesOpen => "Open your eyes and look at a fixed point",
+            };
+            println!("👁️ [DEBUG] Alpha blocking test: {}", instruction);
+            AlphaBlockingPrompt { phase, duration_s: test.phase_s, instruction: instruction.to_string() }
+        };
+        let start = match test.phase_start {
+            Some(start) => start,
+            None => {
+                test.phase_start = Some(timestamp);
+                return (Some(prompt(test.phase)), None);
+            }
+        };
+        
+        let elapsed = timestamp - start;
+        if elapsed >= ALPHA_BLOCKING_SETTLE_S && elapsed < test.phase_s {
+            let phase_powers = match test.phase {
+                AlphaBlockingPhase::EyesClosed => &mut test.eyes_closed,
+                AlphaBlockingPhase::EyesOpen => &mut test.eyes_open,
+            };
+            for channel_bands in bands {
+                if let Some(alpha) = channel_bands.get("alpha") {
+                    phase_powers.entry(channel_bands.channel).or_default().push(alpha * alpha);
+                }
+            }
+            return (None, None);
+        }
+        if elapsed < test.phase_s {
+            return (None, None);
+        }
+        
+        if test.phase == AlphaBlockingPhase::EyesClosed {
+            test.phase = AlphaBlockingPhase::EyesOpen;
+            test.phase_start = Some(timestamp);
+            return (Some(prompt(AlphaBlockingPhase::EyesOpen)), None);
+        }
+        
+        let Some(test) = state.take() else { return (None, None) };
+        let mean = |powers: &Vec<f32>| powers.iter().sum::<f32>() / powers.len() as f32;
+        let mut channels: Vec<ChannelAlphaBlocking> = test.eyes_closed.iter()
+            .filter_map(|(&channel, closed)| {
+                let (eyes_closed, eyes_open) = (mean(closed), mean(test.eyes_open.get(&channel)?));
+                (eyes_closed > 0.0).then(|| ChannelAlphaBlocking {
+                    channel,
+                    eyes_closed,
+                    eyes_open,
+                    percent_reduction: 100.0 * (1.0 - eyes_open / eyes_closed),
+                })
+            })
+            .collect();
+        channels.sort_by_key(|c| c.channel);
+        
+        let overall = |value: fn(&ChannelAlphaBlocking) -> f32| {
+            (!channels.is_empty()).then(|| channels.iter().map(value).sum::<f32>() / channels.len() as f32)
+        };
+        let (eyes_closed, eyes_open) = (overall(|c| c.eyes_closed), overall(|c| c.eyes_open));
+        let percent_reduction = eyes_closed.zip(eyes_open).map(|(closed, open)| 100.0 * (1.0 - open / closed));
+        let detected = percent_reduction.is_some_and(|pct| pct >= ALPHA_BLOCKING_MIN_REDUCTION_PCT);
+        println!(
+            "👁️ [DEBUG] Alpha blocking test done: {:?}% reduction over {} channels ({})",
+            percent_reduction, channels.len(), if detected { "detected" } else { "not detected" }
+        );
+        
+        (None, Some(AlphaBlockingResult { timestamp, eyes_closed, eyes_open, percent_reduction, detected, channels }))
+    }
+
+    // Restarts a calibration already running; an existing template is kept until the new one is built
+    async fn calibrate_blinks(&self, count: usize) -> Result<(), String> {
+        if !(MIN_BLINK_CALIBRATION_COUNT..=MAX_BLINK_CALIBRATION_COUNT).contains(&count) {
+            return Err(format!(
+                "❌ Invalid blink count {}: must be between {} and {}",
+                count, MIN_BLINK_CALIBRATION_COUNT, MAX_BLINK_CALIBRATION_COUNT
+            ));
+        }
+        let info = self.get_stream_info().await
+            .ok_or_else(|| "❌ No stream connected - connect before calibrating blinks".to_string())?;
+        let sample_rate = *self.filter_rate.lock().await;
+        let window_len = ((BLINK_PRE_S + BLINK_POST_S) * sample_rate).round() as usize + 1;
+        if window_len > self.buffer_size {
+            return Err(format!(
+                "❌ Blink window of {} samples exceeds the {}-sample buffer at {} Hz",
+                window_len, self.buffer_size, sample_rate
+            ));
+        }
+        
+        let eeg_channels = self.eeg_channel_limit().await.min(info.channel_names.len());
+        let channels = blink_channels(&info.channel_names[..eeg_channels]);
+        let mut state = self.blinks.lock().await;
+        state.channels = channels;
+        state.pre_len = (BLINK_PRE_S * sample_rate).round() as usize;
+        state.sample_rate = sample_rate;
+        state.detector = Some(BlinkDetector::new(
+            (BLINK_POST_S * sample_rate).round() as usize,
+            (BLINK_REFRACTORY_S * sample_rate).round() as usize,
+        ));
+        state.calibration = Some(BlinkCalibration { target: count, started: None, windows: Vec::new() });
+        println!("😉 [DEBUG] Blink calibration started: {} blinks, detected on channels {:?}", count, state.channels);
+        Ok(())
+    }
+
+    async fn set_blink_correction(&self, enabled: bool) -> Result<(), String> {
+        let mut state = self.blinks.lock().await;
+        if enabled {
+            let template = state.template.as_ref()
+                .ok_or_else(|| "❌ No blink template yet - run calibrate_blinks first".to_string())?;
+            let post_len = (template.post_s * template.sample_rate).round() as usize;
+            if state.detector.is_none() {
+                state.detector = Some(BlinkDetector::new(post_len, (BLINK_REFRACTORY_S * template.sample_rate).round() as usize));
+            }
+        } else if state.calibration.is_none() {
+            state.detector = None;
+        }
+        state.correction = enabled;
+        state.corrected = 0;
+        println!("😉 [DEBUG] Blink correction {}", if enabled { "enabled" } else { "disabled" });
+        Ok(())
+    }
+
+    // Called for every sample after the buffers are updated; calibration progress and, when a
+    // calibration ends, the new template
+    async fn update_blinks(&self, timestamp: f64) -> (Option<BlinkCalibrationProgress>, Option<BlinkTemplate>) {
+        let mut state = self.blinks.lock().await;
+        if state.detector.is_none() {
+            return (None, None);
+        }
+        let progress = |status: &str, calibration: &BlinkCalibration, instruction: Option<String>| BlinkCalibrationProgress {
+            timestamp,
+            status: status.to_string(),
+            detected: calibration.windows.len(),
+            target: calibration.target,
+            instruction,
+        };
+        
+        let mut filtered_buffers = self.filtered_buffers.lock().await;
+        let detection: Vec<f32> = state.channels.iter()
+            .filter_map(|&channel| filtered_buffers.get(channel)?.last().copied())
+            .collect();
+        let value = if detection.is_empty() { 0.0 } else { detection.iter().sum::<f32>() / detection.len() as f32 };
+        let peak_age = state.detector.as_mut().and_then(|detector| detector.push(value));
+        let window = peak_age.and_then(|age| blink_window(&filtered_buffers, age, state.pre_len).map(|window| (age, window)));
+        
+        let pre_len = state.pre_len;
+        let BlinkState { channels, sample_rate, calibration: pending, template, correction, corrected, detector, .. } = &mut *state;
+        if let Some(calibration) = pending.as_mut() {
+            let started = match calibration.started {
+                Some(started) => started,
+                None => {
+                    calibration.started = Some(timestamp);
+                    let instruction = format!("Blink {} times, about a second apart, then keep your eyes still", calibration.target);
+                    println!("😉 [DEBUG] Blink calibration: {}", instruction);
+                    return (Some(progress("started", calibration, Some(instruction))), None);
+                }
+            };
+            
+            let mut update = None;
+            if let Some((_, window)) = window {
+                calibration.windows.push(window);
+                println!("😉 [DEBUG] Blink {}/{} detected", calibration.windows.len(), calibration.target);
+                update = Some(progress("blink", calibration, None));
+            }
+            let timed_out = timestamp - started >= BLINK_CALIBRATION_S_PER_BLINK * calibration.target as f64;
+            if calibration.windows.len() < calibration.target && !timed_out {
+                return (update, None);
+            }
+            
+            let Some(finished) = pending.take() else { return (update, None) };
+            if !*correction {
+                *detector = None;
+            }
+            if finished.windows.len() < MIN_BLINK_CALIBRATION_COUNT {
+                println!("⚠️ [DEBUG] Blink calibration timed out with {} of {} blinks", finished.windows.len(), finished.target);
+                return (Some(progress("timed_out", &finished, None)), None);
+            }
+            
+            let count = finished.windows.len() as f32;
+            let mut mean = finished.windows[0].clone();
+            for window in &finished.windows[1..] {
+                for (channel_mean, channel) in mean.iter_mut().zip(window) {
+                    for (m, &v) in channel_mean.iter_mut().zip(channel) {
+                        *m += v;
+                    }
+                }
+            }
+            mean.iter_mut().flatten().for_each(|m| *m /= count);
+            let built = BlinkTemplate {
+                timestamp,
+                blinks: finished.windows.len(),
+                sample_rate: *sample_rate,
+                pre_s: BLINK_PRE_S,
+                post_s: BLINK_POST_S,
+                detection_channels: channels.clone(),
+                channels: mean,
+            };
+            println!("😉 [DEBUG] Blink template built from {} blinks", built.blinks);
+            *template = Some(built.clone());
+            return (Some(progress("done", &finished, None)), Some(built));
+        }
+        
+        // Live correction: the blink's part of the analysis buffers is replaced by what remains
+        // after the fitted template
+        if let (true, Some((age, window)), Some(template)) = (*correction, window, template.as_ref()) {
+            let Some(scale) = blink_scale(&window, &template.channels, channels) else { return (None, None) };
+            let scale = scale.clamp(0.0, MAX_BLINK_SCALE);
+            for (buffer, shape) in filtered_buffers.iter_mut().zip(&template.channels) {
+                let Some(start) = buffer.len().checked_sub(age + 1 + pre_len) else { continue };
+                for (value, t) in buffer[start..].iter_mut().zip(shape) {
+                    *value -= scale * t;
+                }
+            }
+            *corrected += 1;
+            println!("😉 [DEBUG] Blink removed from the analysis buffers (template scale {:.2})", scale);
+        }
+        (None, None)
+    }
+
+    // None when no channel reports all three bands (e.g. a custom band profile)
+    async fn compute_engagement_index(&self, timestamp: f64, bands: &[FrequencyBands]) -> Option<EngagementIndex> {
+        let channels: Vec<ChannelEngagement> = bands.iter()
+            .filter_map(|b| Some(ChannelEngagement { channel: b.channel, value: engagement_index(b)? }))
+            .collect();
+        if channels.is_empty() {
+            return None;
+        }
+        
+        Some(match *self.engagement_mode.lock().await {
+            EngagementMode::PerChannel => EngagementIndex { timestamp, channels: Some(channels), average: None },
+            EngagementMode::Average => {
+                let gated = self.gated_channels.lock().await;
+                let channels: Vec<&ChannelEngagement> = channels.iter()
+                    .filter(|c| !gated.iter().any(|g| g.channel == c.channel))
+                    .collect();
+                if channels.is_empty() {
+                    return None;
+                }
+                let average = channels.iter().map(|c| c.value).sum::<f32>() / channels.len() as f32;
+                EngagementIndex { timestamp, channels: None, average: Some(average) }
+            }
+        })
+    }
+
+    async fn set_engagement_mode(&self, mode: EngagementMode) {
+        *self.engagement_mode.lock().await = mode;
+        println!("🎯 [DEBUG] Engagement index mode set to {:?}", mode);
+    }
+
+    async fn compute_calm_score(&self, timestamp: f64, bands: &[FrequencyBands]) -> Option<CalmScore> {
+        let bands = self.score_bands(bands).await;
+        let mut state = self.calm_score.lock().await;
+        let weight_norm: f32 = state.weights.values().map(|w| w.abs()).sum();
+        
+        let per_channel: Vec<f32> = bands.iter()
+            .filter_map(|b| {
+                let total: f32 = b.bands.values().map(|v| v * v).sum();
+                (total > 0.0).then(|| {
+                    state.weights.iter()
+                        .map(|(band, weight)| weight * b.get(band).map_or(0.0, |v| v * v) / total)
+                        .sum::<f32>() / weight_norm
+                })
+            })
+            .collect();
+        if per_channel.is_empty() {
+            return None;
+        }
+        let raw = per_channel.iter().sum::<f32>() / per_channel.len() as f32;
+        
+        let mut calibrating = false;
+        if let Some((duration, start, scores)) = state.calibration.as_mut() {
+            let start = *start.get_or_insert(timestamp);
+            scores.push(raw);
+            if timestamp - start >= *duration {
+                let mean = scores.iter().sum::<f32>() / scores.len() as f32;
+                let variance = scores.iter().map(|s| (s - mean).powi(2)).sum::<f32>() / scores.len() as f32;
+                println!("🧘 [DEBUG] Calm score baseline: mean {:.4}, sd {:.4} over {} cycles", mean, variance.sqrt(), scores.len());
+                state.baseline = Some((mean, variance.sqrt()));
+                state.calibration = None;
+            } else {
+                calibrating = true;
+            }
+        }
+        
+        let score = match state.baseline {
+            Some((mean, sd)) => 50.0 + 50.0 * ((raw - mean) / sd.max(1e-3) / 2.0).tanh(),
+            None => 50.0 + 50.0 * raw,
+        };
+        
+        Some(CalmScore {
+            timestamp,
+            score: score.clamp(0.0, 100.0),
+            raw,
+            calibrated: state.baseline.is_some(),
+            calibrating,
+        })
+    }
+
+    // Channel mean of the index over the score channels the gate keeps
+    async fn mean_meditation_index(&self, bands: &[FrequencyBands], weights: MeditationWeights) -> Option<f32> {
+        let indices: Vec<f32> = self.gated_bands(&self.score_bands(bands).await).await.iter()
+            .filter_map(|b| meditation_index(b, weights))
+            .collect();
+        (!indices.is_empty()).then(|| indices.iter().sum::<f32>() / indices.len() as f32)
+    }
+
+    async fn compute_meditation_index(&self, timestamp: f64, bands: &[FrequencyBands]) -> Option<MeditationIndex> {
+        let weights = self.meditation_index.lock().await.weights;
+        let index = self.mean_meditation_index(bands, weights).await?;
+        let mut state = self.meditation_index.lock().await;
+        
+        let mut calibrating = false;
+        if let Some((duration, start, indices)) = state.calibration.as_mut() {
+            let start = *start.get_or_insert(timestamp);
+            indices.push(index);
+            if timestamp - start >= *duration {
+                let mean = indices.iter().sum::<f32>() / indices.len() as f32;
+                println!("🧘 [DEBUG] Meditation index baseline: {:.4} over {} cycles", mean, indices.len());
+                state.baseline = Some(mean);
+                state.calibration = None;
+            } else {
+                calibrating = true;
+            }
+        }
+        
+        let normalized = state.baseline.map(|baseline| index / baseline.max(ENGAGEMENT_POWER_FLOOR));
+        Some(MeditationIndex {
+            timestamp,
+            index,
+            normalized,
+            tier: normalized.map(MeditationTier::from_normalized),
+            calibrating,
+        })
+    }
+
+    // Tier of the session's mean bands, None until the index has a baseline
+    async fn session_meditation_tier(&self, mean_bands: &[FrequencyBands]) -> Option<MeditationTier> {
+        let (weights, baseline) = {
+            let state = self.meditation_index.lock().await;
+            (state.weights, state.baseline?)
+        };
+        let index = self.mean_meditation_index(mean_bands, weights).await?;
+        Some(MeditationTier::from_normalized(index / baseline.max(ENGAGEMENT_POWER_FLOOR)))
+    }
+
+    // A second registration under the same name keeps the metric already running
+    async fn register_metric(&self, metric: Box<dyn EegMetric>) {
+        let mut metrics = self.custom_metrics.lock().await;
+        if metrics.iter().any(|registered| registered.name() == metric.name()) {
+            println!("🧩 [DEBUG] Custom metric '{}' is already registered", metric.name());
+            return;
+        }
+        println!("🧩 [DEBUG] Registered custom metric '{}'", metric.name());
+        metrics.push(metric);
+    }
+
+    async fn compute_custom_metrics(&self, timestamp: f64) -> Vec<CustomMetricResult> {
+        let mut metrics = self.custom_metrics.lock().await;
+        if metrics.is_empty() {
+            return Vec::new();
+        }
+        
+        let eeg_channels = self.eeg_channel_limit().await;
+        let rate = self.analysis_rate().await;
+        let buffers = self.filtered_buffers.lock().await;
+        let mut results = Vec::new();
+        for metric in metrics.iter_mut() {
+            for (channel, buffer) in buffers.iter().enumerate().take(eeg_channels) {
+                let Some(buffer) = self.analysis_window(buffer) else {
+                    continue;
+                };
+                
+                results.push(CustomMetricResult {
+                    timestamp,
+                    metric: metric.name().to_string(),
+                    channel,
+                    value: metric.compute(channel, buffer, rate),
+                });
+            }
+        }
+        results
+    }
+
+    async fn set_channel_filter(&self, index: usize, config: ChannelFilterConfig) -> Result<(), String> {
+        let filter_rate = *self.filter_rate.lock().await;
+        config.validate(filter_rate)?;
+        
+        let mut filters_guard = self.channel_filters.lock().await;
+        let filters = filters_guard.as_mut()
+            .ok_or_else(|| "❌ No active LSL connection - connect to a stream first".to_string())?;
+        
+        if index >= filters.len() {
+            return Err(format!("❌ Channel index {} out of range (stream has {} channels)", index, filters.len()));
+        }
+        
+        println!("🔧 [DEBUG] Channel {} filter: {:?} -> {:?}", index, filters[index].config, config);
+        filters[index] = ChannelFilter::new(config, filter_rate);
+        Ok(())
+    }
+
+    // Replaces the channel's filter with the type's preset; returns the config applied
+    async fn set_channel_signal_type(&self, index: usize, signal_type: SignalType) -> Result<ChannelFilterConfig, String> {
+        let filter_rate = *self.filter_rate.lock().await;
+        let config = signal_type.filter_preset(&*self.filter_config.lock().await, filter_rate);
+        config.validate(filter_rate)?;
+        
+        let mut filters_guard = self.channel_filters.lock().await;
+        let filters = filters_guard.as_mut()
+            .ok_or_else(|| "❌ No active LSL connection - connect to a stream first".to_string())?;
+        if index >= filters.len() {
+            return Err(format!("❌ Channel index {} out of range (stream has {} channels)", index, filters.len()));
+        }
+        
+        println!("🫀 [DEBUG] Channel {} is now {:?}: {}-{} Hz, notch {:?}", index, signal_type, config.highpass, config.lowpass, config.notch);
+        filters[index] = ChannelFilter::new(config.clone(), filter_rate);
+        if let Some(slot) = self.channel_signal_types.lock().await.get_mut(index) {
+            *slot = signal_type;
+        }
+        Ok(config)
+    }
+
+    async fn set_notch_q(&self, q: f64) -> Result<(), String> {
+        if !(q > 0.0 && q.is_finite()) {
+            return Err(format!("❌ Invalid notch Q {}: must be a positive number", q));
+        }
+        
+        self.filter_config.lock().await.notch_q = q;
+        
+        let filter_rate = *self.filter_rate.lock().await;
+        if let Some(filters) = self.channel_filters.lock().await.as_mut() {
+            for filter in filters.iter_mut() {
+                filter.set_notch_q(q, filter_rate);
+            }
+        }
+        
+        println!("🔧 [DEBUG] Notch Q set to {}", q);
+        Ok(())
+    }
+
+    async fn set_mains_method(&self, method: MainsMethod) {
+        self.filter_config.lock().await.mains_method = method;
+        
+        let filter_rate = *self.filter_rate.lock().await;
+        if let Some(filters) = self.channel_filters.lock().await.as_mut() {
+            for filter in filters.iter_mut() {
+                filter.set_mains_method(method, filter_rate);
+            }
+        }
+        
+        println!("🔧 [DEBUG] Mains removal set to {:?}", method);
+    }
+
+    async fn get_mains_canceller_status(&self) -> Vec<MainsCancellerStatus> {
+        self.channel_filters.lock().await.iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(channel, filter)| {
+                let canceller = filter.canceller.as_ref()?;
+                Some(MainsCancellerStatus {
+                    channel,
+                    frequency: canceller.frequency,
+                    amplitude: canceller.amplitude(),
+                    converged: canceller.converged(),
+                })
+            })
+            .collect()
+    }
+
+    async fn set_median_despike(&self, len: usize) -> Result<(), String> {
+        MedianFilter::validate_len(len)?;
+        
+        self.filter_config.lock().await.median_despike = len;
+        if let Some(filters) = self.channel_filters.lock().await.as_mut() {
+            for filter in filters.iter_mut() {
+                filter.set_median_despike(len);
+            }
+        }
+        
+        println!("🔧 [DEBUG] Median despike {}", if len > 1 { format!("set to {} points", len) } else { "disabled".to_string() });
+        Ok(())
+    }
+
+    // Feed a synthetic composite signal through a fresh filter chain built from the global config
+    async fn run_filter_self_test(&self) -> SelfTestReport {
+        use std::f64::consts::PI;
+        
+        let config = self.filter_config.lock().await.clone();
+        let sample_rate = self.sample_rate as f64;
+        let mut filter = ChannelFilter::new(config.clone(), sample_rate);
+        
+        // Components (µV): DC + slow drift + alpha + both mains frequencies, plus isolated spikes
+        const DC: f64 = 100.0;
+        const DRIFT: (f64, f64) = (0.2, 50.0);
+        const ALPHA: (f64, f64) = (10.0, 20.0);
+        const MAINS_50: (f64, f64) = (50.0, 30.0);
+        const MAINS_60: (f64, f64) = (60.0, 30.0);
+        
+        // 6 s to settle, then a 10 s window holding an integer number of cycles of every tone
+        let settle = (6.0 * sample_rate) as usize;
+        let window = (10.0 * sample_rate) as usize;
+        let spike_every = (1.7 * sample_rate) as usize;
+        
+        let mut output = Vec::with_capacity(window);
+        for n in 0..settle + window {
+            let t = n as f64 / sample_rate;
+            let tone = |(freq, amp): (f64, f64)| amp * (2.0 * PI * freq * t).sin();
+            let mut x = DC + tone(DRIFT) + tone(ALPHA) + tone(MAINS_50) + tone(MAINS_60);
+            if n % spike_every == spike_every / 2 {
+                x += 250.0;
+            }
+            
+            let y = clip_artifact(filter.process(x as f32));
+            if n >= settle {
+                output.push(y as f64);
+            }
+        }
+        
+        // Amplitude of a single tone in the steady-state output (DFT projection)
+        let amplitude_at = |freq: f64| -> f64 {
+            if freq == 0.0 {
+                return (output.iter().sum::<f64>() / output.len() as f64).abs();
+            }
+            let (mut re, mut im) = (0.0, 0.0);
+            for (n, &y) in output.iter().enumerate() {
+                let phase = 2.0 * PI * freq * (n + settle) as f64 / sample_rate;
+                re += y * phase.cos();
+                im += y * phase.sin();
+            }
+            2.0 * (re * re + im * im).sqrt() / output.len() as f64
+        };
+        let gain_db = |freq: f64, input: f64| (20.0 * (amplitude_at(freq).max(1e-12) / input).log10()) as f32;
+        
+        let mut checks = Vec::new();
+        let mut attenuated = |name: &str, db: f32, limit: f32| checks.push(SelfTestCheck {
+            name: name.to_string(),
+            passed: db <= limit,
+            measured_db: db,
+            requirement: format!("<= {:.0} dB", limit),
+        });
+        
+        attenuated("dc_offset", gain_db(0.0, DC), -20.0);
+        attenuated("drift_0.2hz", gain_db(DRIFT.0, DRIFT.1), -12.0);
+        for mains in [MAINS_50, MAINS_60] {
+            // The notched mains frequency must be deeply suppressed, the other one by the low-pass
+            let limit = if config.notch == Some(mains.0) { -20.0 } else { -6.0 };
+            attenuated(&format!("mains_{}hz", mains.0), gain_db(mains.0, mains.1), limit);
+        }
+        
+        let alpha_db = gain_db(ALPHA.0, ALPHA.1);
+        checks.push(SelfTestCheck {
+            name: "alpha_10hz".to_string(),
+            passed: alpha_db.abs() <= 1.0,
+            measured_db: alpha_db,
+            requirement: "within ±1 dB".to_string(),
+        });
+        
+        let passed = checks.iter().all(|c| c.passed);
+        println!("🧪 [DEBUG] Filter self-test {}: {:?}", if passed { "PASSED" } else { "FAILED" }, checks);
+        
+        SelfTestReport {
+            passed,
+            sample_rate,
+            config,
+            checks,
+        }
+    }
+
+    // Needs no connection and leaves the app's own processor untouched; takes the connect
+    // resolve time (15 s) plus however long the pull path needs to fill E2E_BUFFER_SIZE samples
+    async fn run_end_to_end_test() -> E2EReport {
+        let stream_name = format!("zen_e2e_{}", std::process::id());
+        let mut report = E2EReport {
+            passed: false,
+            stream_name: stream_name.clone(),
+            connected: false,
+            samples_received: 0,
+            effective_rate: None,
+            channels: Vec::new(),
+            error: None,
+        };
+        
+        let stop = Arc::new(AtomicBool::new(false));
+        let (ready_tx, ready_rx) = std::sync::mpsc::channel::<Result<(), String>>();
+        let outlet_thread = {
+            let (stream_name, stop) = (stream_name.clone(), stop.clone());
+            std::thread::spawn(move || {
+                let outlet = StreamInfo::new(&stream_name, "EEG", E2E_TONES.len() as u32, E2E_SAMPLE_RATE, ChannelFormat::Float32, &stream_name)
+                    .and_then(|info| StreamOutlet::new(&info, 0, 360));
+                let outlet = match outlet {
+                    Ok(outlet) => outlet,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(format!("❌ Could not create the test outlet: {}", e)));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(()));
+                
+                // Real-time pacing against the start, so sleep jitter doesn't accumulate
+                let start = std::time::Instant::now();
+                let mut n = 0u64;
+                while !stop.load(Ordering::Relaxed) {
+                    let t = n as f64 / E2E_SAMPLE_RATE;
+                    let sample: Vec<f32> = E2E_TONES.iter()
+                        .map(|(_, freq)| (E2E_AMPLITUDE * (2.0 * std::f64::consts::PI * freq * t).sin()) as f32)
+                        .collect();
+                    if let Err(e) = outlet.push_sample(&sample) {
+                        eprintln!("❌ [DEBUG] Test outlet push failed: {}", e);
+                    }
+                    n += 1;
+                    let due = start + Duration::from_secs_f64(n as f64 / E2E_SAMPLE_RATE);
+                    if let Some(wait) = due.checked_duration_since(std::time::Instant::now()) {
+                        std::thread::sleep(wait);
+                    }
+                }
+            })
+        };
+        
+        let ready = tokio::task::spawn_blocking(move || ready_rx.recv_timeout(Duration::from_secs(5)))
+            .await
+            .map_err(|e| format!("❌ Task execution failed: {}", e))
+            .and_then(|ready| ready.map_err(|_| "❌ Test outlet did not start".to_string())?);
+        let outcome = match ready {
+            Ok(()) => Self::exercise_end_to_end(&stream_name, &mut report).await,
+            Err(e) => Err(e),
+        };
+        
+        stop.store(true, Ordering::Relaxed);
+        if outlet_thread.join().is_err() {
+            eprintln!("❌ [DEBUG] Test outlet thread panicked");
+        }
+        
+        if let Err(e) = outcome {
+            report.error = Some(e);
+        }
+        report.passed = report.error.is_none() && !report.channels.is_empty() && report.channels.iter().all(|c| c.passed);
+        println!("🧪 [DEBUG] End-to-end test {}: {:?}", if report.passed { "PASSED" } else { "FAILED" }, report);
+        report
+    }
+
+    async fn exercise_end_to_end(stream_name: &str, report: &mut E2EReport) -> Result<(), String> {
+        let mut probe = EEGProcessor::new();
+        probe.set_buffer_size(E2E_BUFFER_SIZE).await?;
+        probe.connect_to_lsl(stream_name).await?;
+        report.connected = true;
+        
+        let start = std::time::Instant::now();
+        let (mut first_timestamp, mut last_timestamp) = (None, 0.0);
+        while probe.buffer_fill_fraction().await < 1.0 {
+            if start.elapsed() > Duration::from_secs(E2E_TIMEOUT_S) {
+                return Err(format!(
+                    "❌ Only {} of {} samples arrived within {} s",
+                    report.samples_received, E2E_BUFFER_SIZE, E2E_TIMEOUT_S
+                ));
+            }
+            let Some(sample) = probe.get_lsl_sample().await else {
+                tokio::time::sleep(Duration::from_millis(4)).await;
+                continue;
+            };
+            
+            let filtered = probe.apply_real_time_filters(&sample).await;
+            probe.update_buffers(&sample, &filtered).await;
+            report.samples_received += 1;
+            first_timestamp.get_or_insert(sample.timestamp);
+            last_timestamp = sample.timestamp;
+        }
+        report.effective_rate = first_timestamp
+            .filter(|first| last_timestamp > *first)
+            .map(|first| (report.samples_received - 1) as f64 / (last_timestamp - first));
+        
+        let bands = probe.analyze_frequency_bands(last_timestamp).await;
+        report.channels = E2E_TONES.iter()
+            .enumerate()
+            .map(|(channel, &(band, tone_hz))| {
+                let dominant_band = bands.iter()
+                    .find(|b| b.channel == channel)
+                    .and_then(|b| b.bands.iter().max_by(|x, y| x.1.total_cmp(y.1)))
+                    .map(|(name, _)| name.clone());
+                E2EChannel {
+                    channel,
+                    tone_hz,
+                    expected_band: band.to_string(),
+                    passed: dominant_band.as_deref() == Some(band),
+                    dominant_band,
+                }
+            })
+            .collect();
+        
+        probe.disconnect_lsl().await;
+        Ok(())
+    }
+
+    async fn set_channel_order(&self, permutation: Vec<usize>) -> Result<(), String> {
+        let mut connection = self.lsl_connection.lock().await;
+        if !connection.is_real_connection {
+            return Err("❌ No active LSL connection - connect to a stream first".to_string());
+        }
+        
+        let channel_count = connection.channel_count;
+        let mut seen = vec![false; channel_count];
+        let is_permutation = permutation.len() == channel_count
+            && permutation.iter().all(|&i| i < channel_count && !std::mem::replace(&mut seen[i], true));
+        if !is_permutation {
+            return Err(format!(
+                "❌ Invalid channel order {:?}: must be a permutation of 0..{}",
+                permutation, channel_count
+            ));
+        }
+        
+        // Per-channel state is stored in the current display order, so move it by the
+        // relative permutation (where each device channel sits now -> where it goes)
+        let current: Vec<usize> = connection.channel_order.clone().unwrap_or_else(|| (0..channel_count).collect());
+        let mut current_position = vec![0; channel_count];
+        for (position, &device_channel) in current.iter().enumerate() {
+            current_position[device_channel] = position;
+        }
+        let moves: Vec<usize> = permutation.iter().map(|&device_channel| current_position[device_channel]).collect();
+        
+        if let Some(info) = connection.stream_info.as_mut() {
+            if info.channel_names.len() == channel_count {
+                info.channel_names = reorder(&info.channel_names, &moves);
+            }
+            if info.units.len() == channel_count {
+                info.units = reorder(&info.units, &moves);
+            }
+        }
+        if let Some(filters) = self.channel_filters.lock().await.as_mut() {
+            *filters = reorder(filters, &moves);
+        }
+        for buffers in [&self.channel_buffers, &self.filtered_buffers] {
+            let mut buffers = buffers.lock().await;
+            if buffers.len() == channel_count {
+                *buffers = reorder(&buffers, &moves);
+            }
+        }
+        if let Some(offset) = self.dc_offset.lock().await.as_mut() {
+            if offset.len() == channel_count {
+                *offset = reorder(offset, &moves);
+            }
+        }
+        let mut gains = self.display_gains.lock().await;
+        if gains.len() == channel_count {
+            *gains = reorder(&gains, &moves);
+        }
+        drop(gains);
+        let mut scales = self.channel_scales.lock().await;
+        if scales.len() == channel_count {
+            *scales = reorder(&scales, &moves);
+        }
+        drop(scales);
+        let mut polarity = self.channel_polarity.lock().await;
+        if polarity.len() == channel_count {
+            *polarity = reorder(&polarity, &moves);
+        }
+        drop(polarity);
+        let mut channel_profiles = self.channel_band_profiles.lock().await;
+        if channel_profiles.len() == channel_count {
+            *channel_profiles = reorder(&channel_profiles, &moves);
+        }
+        drop(channel_profiles);
+        let mut signal_types = self.channel_signal_types.lock().await;
+        if signal_types.len() == channel_count {
+            *signal_types = reorder(&signal_types, &moves);
+        }
+        drop(signal_types);
+        self.replay_buffer.lock().await.frames.clear();
+        self.band_smoothing.lock().await.reset();
+        
+        connection.last_sample = None;
+        connection.pending_samples.clear();
+        println!("🔀 [DEBUG] Channel order set to {:?}", permutation);
+        connection.channel_order = Some(permutation);
+        Ok(())
+    }
+
+    async fn get_filter_coefficients(&self) -> Result<Vec<FilterCoeffs>, String> {
+        let filter_rate = *self.filter_rate.lock().await;
+        let filters_guard = self.channel_filters.lock().await;
+        let filters = filters_guard.as_ref()
+            .ok_or_else(|| "❌ No active LSL connection - filters are not initialized".to_string())?;
+        
+        Ok(filters.iter()
+            .enumerate()
+            .map(|(channel, filter)| FilterCoeffs {
+                channel,
+                order: filter.bandpass.order,
+                bandpass_b: filter.bandpass.b.clone(),
+                bandpass_a: filter.bandpass.a.clone(),
+                notch_b: filter.notch.as_ref().map(|n| n.b.clone()),
+                notch_a: filter.notch.as_ref().map(|n| n.a.clone()),
+                sample_rate: filter_rate,
+                cutoffs: filter.config.clone(),
+            })
+            .collect())
+    }
+
+    async fn get_impulse_responses(&self, channel: usize, length: usize) -> Result<ImpulseResponses, String> {
+        if length == 0 || length > MAX_IMPULSE_RESPONSE_LEN {
+            return Err(format!("❌ Invalid impulse response length {}: must be 1-{} samples", length, MAX_IMPULSE_RESPONSE_LEN));
+        }
+        
+        let filter_rate = *self.filter_rate.lock().await;
+        let filters_guard = self.channel_filters.lock().await;
+        let filters = filters_guard.as_ref()
+            .ok_or_else(|| "❌ No active LSL connection - filters are not initialized".to_string())?;
+        let filter = filters.get(channel)
+            .ok_or_else(|| format!("❌ Channel index {} out of range (stream has {} channels)", channel, filters.len()))?;
+        
+        let fresh = ChannelFilter::new(filter.config.clone(), filter_rate);
+        let impulse = (0..length).map(|i| if i == 0 { 1.0 } else { 0.0 });
+        let mut bandpass = fresh.bandpass;
+        let bandpass_ir = impulse.clone().map(|x| bandpass.process_sample(0, x) as f64).collect();
+        let notch_ir = fresh.notch.map(|mut notch| impulse.map(|x| notch.process_sample(0, x) as f64).collect());
+        
+        Ok(ImpulseResponses {
+            channel,
+            sample_rate: filter_rate,
+            bandpass_ir,
+            notch_ir,
+            // compute_channel_bands applies no taper
+            window: vec![1.0; self.buffer_size],
+        })
+    }
+
+    // Everything needed to describe the current processing state in a bug report
+    // Every tunable, as in the debug snapshot and the header of recordings
+    async fn config_snapshot(&self) -> serde_json::Value {
+        let band_profile: Vec<serde_json::Value> = self.band_profile.lock().await.bands.iter()
+            .map(|b| serde_json::json!({ "name": b.name, "low": b.low, "high": b.high }))
+            .collect();
+        let multiresolution = self.multiresolution.lock().await.as_ref()
+            .map(|m| serde_json::json!({ "short_len": m.short_len, "long_len": m.long_len }));
+        let display_smoother = self.display_smoother.lock().await.as_ref()
+            .map(|s| serde_json::json!({ "window": s.window, "order": s.order }));
+        let erd_windows = *self.erd_windows.lock().await;
+        let triggers: Vec<serde_json::Value> = self.triggers.lock().await.iter()
+            .map(|t| serde_json::json!({
+                "id": t.id,
+                "metric": format!("{:?}", t.metric),
+                "channel": t.channel,
+                "op": format!("{:?}", t.op),
+                "threshold": t.threshold,
+                "armed": t.armed,
+            }))
+            .collect();
+        let custom_metrics: Vec<String> = self.custom_metrics.lock().await.iter().map(|m| m.name().to_string()).collect();
+        
+        serde_json::json!({
+            "filter": *self.filter_config.lock().await,
+            "timestamp_source": *self.timestamp_source.lock().await,
+            "emit_lsl_timestamp": *self.emit_lsl_timestamp.lock().await,
+            "band_format": *self.band_format.lock().await,
+            "artifact_domain": *self.artifact_domain.lock().await,
+            "gamma_preemphasis": *self.gamma_preemphasis.lock().await,
+            "whitening_bins": *self.whitening.lock().await,
+            "dc_exclusion_bins": *self.dc_exclusion_bins.lock().await,
+            "passband_only": *self.passband_only.lock().await,
+            "zero_pad_factor": *self.zero_pad_factor.lock().await,
+            "muscle_artifact": *self.muscle_artifact.lock().await,
+            "gate_bad_channels": *self.gate_bad_channels.lock().await,
+            "blink_correction": self.blinks.lock().await.correction,
+            "frame_history": self.frame_history.lock().await.capacity,
+            "variance_trend_window_s": self.variance_trend.lock().await.as_ref().map(|trend| trend.window_s),
+            "channel_groups": *self.channel_groups.lock().await,
+            "score_channels": *self.score_channels.lock().await,
+            "xcorr": *self.xcorr.lock().await,
+            "engagement_mode": *self.engagement_mode.lock().await,
+            "band_normalization": self.band_normalization.lock().await.mode,
+            "band_normalization_per_band": self.band_normalization.lock().await.per_band.clone(),
+            "band_crossings": *self.band_crossings.lock().await,
+            "individualized_bands": *self.individualized_bands.lock().await,
+            "analysis_method": *self.analysis_method.lock().await,
+            "analysis_source": *self.analysis_source.lock().await,
+            "adaptive_notch": *self.adaptive_notch.lock().await,
+            "goertzel_points": *self.goertzel_points.lock().await,
+            "multiresolution": multiresolution,
+            "fixed_emit_hz": *self.fixed_emit_hz.lock().await,
+            "sample_batch_size": *self.sample_batch_size.lock().await,
+            "max_event_rate": self.event_rate.lock().unwrap_or_else(std::sync::PoisonError::into_inner).per_second,
+            "delta_quantization": self.delta_encoder.lock().await.as_ref().map(|e| e.quantization),
+            "analysis_enabled": *self.analysis_enabled.lock().await,
+            "band_time_constants": self.band_smoothing.lock().await.time_constants,
+            "auto_gain": self.auto_gain.lock().await.as_ref()
+                .map(|g| serde_json::json!({ "target_rms": g.target_rms, "time_constant_s": g.time_constant_s })),
+            "overload_policy": *self.overload_policy.lock().await,
+            "timestamp_guard": *self.timestamp_guard.lock().await,
+            "pull_timeout_s": *self.pull_timeout.lock().await,
+            "pull_mode": *self.pull_mode.lock().await,
+            "match_field": *self.match_field.lock().await,
+            "max_streams": *self.max_streams.lock().await,
+            "reconnect_any_match": *self.reconnect_any_match.lock().await,
+            "auto_expand_channels": *self.auto_expand_channels.lock().await,
+            "max_reconnect_interval": *self.max_reconnect_interval.lock().await,
+            "gap_interpolation": *self.gap_interpolation.lock().await,
+            "settle_samples": *self.settle_samples.lock().await,
+            "settle_remaining": *self.settle_remaining.lock().await,
+            "filter_rate": *self.filter_rate.lock().await,
+            "adaptive_filter_redesign": *self.adaptive_filter_redesign.lock().await,
+            "savgol": display_smoother,
+            "dc_offset": *self.dc_offset.lock().await,
+            "display_gains": *self.display_gains.lock().await,
+            "channel_scales": *self.channel_scales.lock().await,
+            "channel_polarity": *self.channel_polarity.lock().await,
+            "polarity_stage": *self.polarity_stage.lock().await,
+            "channel_band_profiles": *self.channel_band_profiles.lock().await,
+            "channel_signal_types": *self.channel_signal_types.lock().await,
+            "band_profile": band_profile,
+            "active_band_profile": *self.active_band_profile.lock().await,
+            "erd_windows": { "baseline_s": erd_windows.baseline_s, "activity_s": erd_windows.activity_s },
+            "auto_marker_interval_s": self.test_markers.lock().await.auto_interval_s,
+            "triggers": triggers,
+            "custom_metrics": custom_metrics,
+        })
+    }
+
+    async fn capture_debug_snapshot(&self) -> Result<String, String> {
+        let connection = self.lsl_connection.lock().await.clone();
+        let buffer_fill: Vec<usize> = self.filtered_buffers.lock().await.iter().map(Vec::len).collect();
+        let raw_buffer_fill: Vec<usize> = self.channel_buffers.lock().await.iter().map(Vec::len).collect();
+        // Read before the json! below, whose temporaries would keep the std guard across its awaits
+        let event_log = self.event_log.lock().unwrap_or_else(std::sync::PoisonError::into_inner).as_ref()
+            .map(|log| serde_json::json!({ "capacity": log.capacity, "entries": log.entries.len() }));
+        let events_dropped = self.event_rate.lock().unwrap_or_else(std::sync::PoisonError::into_inner).total_dropped;
+        
+        let snapshot = serde_json::json!({
+            "captured_at": chrono::Utc::now().to_rfc3339(),
+            "processor": {
+                "sample_rate": self.sample_rate,
+                "analysis_rate": self.analysis_rate().await,
+                "buffer_size": self.buffer_size,
+            },
+            "connection": {
+                "stream_name": connection.stream_name,
+                "is_real_connection": connection.is_real_connection,
+                "channel_count": connection.channel_count,
+                "channel_order": connection.channel_order,
+                "eeg_channel_count": connection.eeg_channel_count,
+                "irregular_rate": connection.irregular_rate,
+                "string_channels": connection.string_channels,
+                "next_sample_index": connection.next_sample_index,
+                "stream_info": connection.stream_info,
+            },
+            "config": self.config_snapshot().await,
+            "session_metadata": *self.session_metadata.lock().await,
+            "buffers": {
+                "filtered_fill": buffer_fill,
+                "raw_fill": raw_buffer_fill,
+                "replay_frames": self.replay_buffer.lock().await.frames.len(),
+                "band_log": self.band_log.lock().await.as_ref().map(|log| serde_json::json!({ "path": log.path, "rows": log.rows })),
+                "recording": self.sample_recording.lock().await.as_ref().map(|recording| serde_json::json!({
+                    "path": recording.path,
+                    "rows": recording.rows,
+                    "decimation": recording.decimator.factor,
+                })),
+                "event_log": event_log,
+                "events_dropped": events_dropped,
+                "pending_markers": self.pending_markers.lock().await.len(),
+            },
+            "filter_coefficients": self.get_filter_coefficients().await.ok(),
+            "last_bands": *self.last_bands.lock().await,
+            "task_stats": task_stats(),
+        });
+        
+        serde_json::to_string_pretty(&snapshot).map_err(|e| format!("❌ Failed to serialize debug snapshot: {}", e))
+    }
+
+    // Band set for one channel (e.g. an EMG channel in an EEG montage); None returns it to the global profile
+    async fn set_channel_band_profile(&self, index: usize, profile_name: Option<String>) -> Result<(), String> {
+        if let Some(name) = &profile_name {
+            let named = self.named_band_profiles.lock().await;
+            if !named.contains_key(name) {
+                let mut known: Vec<String> = named.keys().cloned().collect();
+                known.sort();
+                return Err(format!("❌ Unknown band profile '{}' (loaded: {})", name, known.join(", ")));
+            }
+        }
+        
+        let mut channel_profiles = self.channel_band_profiles.lock().await;
+        if index >= channel_profiles.len() {
+            return Err(format!("❌ Channel index {} out of range (stream has {} channels)", index, channel_profiles.len()));
+        }
+        
+        println!("🎼 [DEBUG] Channel {} band profile set to {}", index, profile_name.as_deref().unwrap_or("global"));
+        channel_profiles[index] = profile_name;
+        Ok(())
+    }
+
+    // Overrides the unit-derived default until the next connect
+    async fn set_channel_scale(&self, index: usize, scale: f32) -> Result<Vec<f32>, String> {
+        if !(scale.is_finite() && scale != 0.0) {
+            return Err(format!("❌ Invalid channel scale {}: must be a non-zero number", scale));
+        }
+        
+        let mut scales = self.channel_scales.lock().await;
+        if index >= scales.len() {
+            return Err(format!("❌ Channel index {} out of range (stream has {} channels)", index, scales.len()));
+        }
+        
+        scales[index] = scale;
+        println!("📏 [DEBUG] Scale for channel {} set to {}", index, scale);
+        Ok(scales.clone())
+    }
+
+    async fn set_channel_polarity(&self, index: usize, inverted: bool) -> Result<Vec<bool>, String> {
+        let mut polarity = self.channel_polarity.lock().await;
+        if index >= polarity.len() {
+            return Err(format!("❌ Channel index {} out of range (stream has {} channels)", index, polarity.len()));
+        }
+        
+        polarity[index] = inverted;
+        println!("🔃 [DEBUG] Channel {} polarity {}", index, if inverted { "inverted" } else { "normal" });
+        Ok(polarity.clone())
+    }
+
+    async fn set_polarity_stage(&self, stage: PolarityStage) {
+        *self.polarity_stage.lock().await = stage;
+        println!("🔃 [DEBUG] Channel polarity applied {:?}", stage);
+    }
+
+    // Flips the inverted channels when `stage` is the configured one
+    async fn apply_polarity(&self, channels: &mut [f32], stage: PolarityStage) {
+        if *self.polarity_stage.lock().await != stage {
+            return;
+        }
+        for (value, &inverted) in channels.iter_mut().zip(self.channel_polarity.lock().await.iter()) {
+            if inverted {
+                *value = -*value;
+            }
+        }
+    }
+
+    async fn set_display_gain(&self, index: usize, gain: f32) -> Result<Vec<f32>, String> {
+        if !(gain.is_finite() && gain > 0.0) {
+            return Err(format!("❌ Invalid display gain {}: must be a positive number", gain));
+        }
+        
+        let mut gains = self.display_gains.lock().await;
+        if index >= gains.len() {
+            return Err(format!("❌ Channel index {} out of range (stream has {} channels)", index, gains.len()));
+        }
+        
+        gains[index] = gain;
+        println!("🔍 [DEBUG] Display gain for channel {} set to {}", index, gain);
+        Ok(gains.clone())
+    }
+
+    // One-shot: sets every display gain so each channel's filtered RMS over the last `seconds`
+    // (from the replay buffer) shows at the median channel's RMS. Flat channels get gain 1;
+    // the analysis never sees these gains.
+    async fn auto_normalize_display(&self, seconds: f64) -> Result<Vec<f32>, String> {
+        if !(seconds > 0.0 && seconds.is_finite()) {
+            return Err(format!("❌ Invalid normalization window {} s: must be a positive number of seconds", seconds));
+        }
+        
+        let needed = ((seconds * self.sample_rate as f64).round() as usize).max(1);
+        let windows: Vec<Vec<f32>> = {
+            let replay = self.replay_buffer.lock().await;
+            if replay.frames.len() < needed {
+                return Err(format!(
+                    "❌ Only {:.1} s of samples buffered, {} s needed (the replay buffer keeps {:.1} s)",
+                    replay.frames.len() as f64 / self.sample_rate as f64, seconds, replay.seconds
+                ));
+            }
+            replay.frames.iter().skip(replay.frames.len() - needed).map(|frame| frame.filtered.clone()).collect()
+        };
+        
+        let mut gains = self.display_gains.lock().await;
+        let rms: Vec<f32> = (0..gains.len())
+            .map(|channel| {
+                let values: Vec<f32> = windows.iter().filter_map(|frame| frame.get(channel).copied()).collect();
+                let mean = values.iter().sum::<f32>() / values.len().max(1) as f32;
+                (values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len().max(1) as f32).sqrt()
+            })
+            .collect();
+        let mut active: Vec<f32> = rms.iter().copied().filter(|r| *r > AUTO_NORMALIZE_MIN_RMS).collect();
+        if active.is_empty() {
+            return Err("❌ Every channel is flat over the window - nothing to normalize".to_string());
+        }
+        active.sort_by(|a, b| a.total_cmp(b));
+        let reference = active[active.len() / 2];
+        
+        for (gain, rms) in gains.iter_mut().zip(rms.iter()) {
+            *gain = if *rms > AUTO_NORMALIZE_MIN_RMS { reference / rms } else { 1.0 };
+        }
+        println!("🔍 [DEBUG] Display gains normalized to {:.2} µV RMS over {} s: {:?}", reference, seconds, *gains);
+        Ok(gains.clone())
+    }
+
+    // Persisted thresholds are read on first use, so a session starts with the last saved set
+    async fn band_thresholds(&self, app_handle: &tauri::AppHandle) -> HashMap<String, BandThreshold> {
+        let mut thresholds = self.band_thresholds.lock().await;
+        if thresholds.is_none() {
+            *thresholds = Some(load_band_thresholds(app_handle).unwrap_or_else(|e| {
+                eprintln!("⚠️ [DEBUG] Ignoring saved band thresholds: {}", e);
+                HashMap::new()
+            }));
+        }
+        thresholds.clone().unwrap_or_default()
+    }
+
+    async fn set_band_thresholds(
+        &self,
+        app_handle: &tauri::AppHandle,
+        thresholds: HashMap<String, BandThreshold>,
+    ) -> Result<(), String> {
+        for (band, threshold) in &thresholds {
+            if !(threshold.low.is_finite() && threshold.high.is_finite() && threshold.low < threshold.high) {
+                return Err(format!(
+                    "❌ Invalid thresholds for band '{}': low {} must be below high {}",
+                    band, threshold.low, threshold.high
+                ));
+            }
+        }
+        
+        save_band_thresholds(app_handle, &thresholds)?;
+        println!("🎯 [DEBUG] Band thresholds set: {:?}", thresholds);
+        *self.band_thresholds.lock().await = Some(thresholds);
+        Ok(())
+    }
+
+    // Receiver for every processed frame; the Tauri event forwarder is one such subscriber, so
+    // embedding code can consume the engine's output without going through the UI transport.
+    // A receiver that falls more than FRAME_CHANNEL_CAPACITY frames behind gets a Lagged error.
+    fn subscribe(&self) -> broadcast::Receiver<ProcessedFrame> {
+        self.frames.subscribe()
+    }
+
+    fn publish(&self, frame: ProcessedFrame) {
+        // Only fails when nobody is subscribed, which is fine
+        let _ = self.frames.send(frame);
+    }
+
+    // Port 0 lets the OS pick one; returns the port listened on
+    #[cfg(feature = "websocket")]
+    async fn start_websocket_server(&self, app_handle: &tauri::AppHandle, port: u16) -> Result<u16, String> {
+        let mut server = self.websocket_server.lock().await;
+        if let Some(running) = server.as_ref() {
+            return Err(format!("❌ WebSocket server already running on port {} - stop it first", running.port));
+        }
+        
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", port)).await
+            .map_err(|e| format!("❌ Failed to listen on port {}: {}", port, e))?;
+        let port = listener.local_addr().map_err(|e| format!("❌ Failed to read the WebSocket port: {}", e))?.port();
+        self.band_thresholds(app_handle).await;
+        let outputs = WebSocketOutputs {
+            band_format: self.band_format.clone(),
+            band_thresholds: self.band_thresholds.clone(),
+            quiet_mode: self.quiet_mode.clone(),
+        };
+        let frames = self.frames.clone();
+        
+        let task = tokio::spawn(async move {
+            let mut clients = tokio::task::JoinSet::new();
+            loop {
+                tokio::select! {
+                    accepted = listener.accept() => match accepted {
+                        Ok((stream, _)) => {
+                            clients.spawn(serve_websocket_client(stream, frames.subscribe(), outputs.clone()));
+                        }
+                        Err(e) => eprintln!("❌ [DEBUG] WebSocket accept failed: {}", e),
+                    },
+                    Some(_) = clients.join_next() => {}
+                }
+            }
+        });
+        
+        println!("🌐 [DEBUG] WebSocket server listening on 127.0.0.1:{}", port);
+        *server = Some(WebSocketServer { port, task });
+        Ok(port)
+    }
+    
+    #[cfg(feature = "websocket")]
+    async fn stop_websocket_server(&self) -> Result<(), String> {
+        let server = self.websocket_server.lock().await.take()
+            .ok_or_else(|| "❌ WebSocket server is not running".to_string())?;
+        server.task.abort();
+        println!("🌐 [DEBUG] WebSocket server on port {} stopped", server.port);
+        Ok(())
+    }
+
+    // Visualization only: scales a copy headed for the UI, never the buffers or analysis input
+    async fn apply_display_gain(&self, channels: &mut [f32]) {
+        for (value, gain) in channels.iter_mut().zip(self.display_gains.lock().await.iter()) {
+            *value *= gain;
+        }
+    }
+
+    async fn get_stream_info(&self) -> Option<LSLStreamInfo> {
+        let connection = self.lsl_connection.lock().await;
+        connection.stream_info.clone()
+    }
+
+    // Rebuilt from the stored stream info with the same matching connect used. CSV sources are
+    // never a known device, whatever their file name contains.
+    async fn get_detected_device(&self) -> Option<DeviceProfile> {
+        let connection = self.lsl_connection.lock().await;
+        let info = connection.stream_info.as_ref()?;
+        let matched = if connection.csv_source { None } else { detect_device(&info.source_id, &info.name) };
+        
+        Some(DeviceProfile {
+            manufacturer: info.manufacturer.clone(),
+            model: info.device_model.clone(),
+            montage: info.channel_names.clone(),
+            matched_keyword: matched.map(|(keyword, _, _)| keyword.to_string()),
+            is_known: matched.is_some(),
+        })
+    }
+
+    // Window 0 or 1 turns smoothing off
+    async fn set_savgol(&self, window: usize, order: usize) -> Result<(), String> {
+        let smoother = if window <= 1 {
+            None
+        } else {
+            Some(SavitzkyGolayFilter::new(window, order)?)
+        };
+        
+        println!("〰️ [DEBUG] Savitzky-Golay display smoothing: {:?}", smoother.as_ref().map(|s| (s.window, s.order)));
+        *self.display_smoother.lock().await = smoother;
+        Ok(())
+    }
+
+    // Visualization-only copy of the filtered sample; buffers and analysis keep the unsmoothed data.
+    // Returns None while the smoothing window is still filling.
+    async fn smooth_for_display(&self, filtered_sample: &FilteredEEGSample) -> Option<FilteredEEGSample> {
+        match self.display_smoother.lock().await.as_mut() {
+            Some(smoother) => smoother.process(filtered_sample),
+            None => Some(filtered_sample.clone()),
+        }
+    }
+
+    // Average each raw channel over the last `seconds` (taken from the replay ring) and subtract
+    // that fixed offset from every following sample until clear_dc_offset
+    async fn measure_dc_offset(&self, seconds: f64) -> Result<Vec<f32>, String> {
+        const MIN_SAMPLES: usize = 10;
+        
+        // Negative or NaN windows saturate to 0 samples
+        let required = (seconds * self.sample_rate as f64).round() as usize;
+        if required < MIN_SAMPLES {
+            return Err(format!(
+                "❌ Measurement window {} s is too short: need at least {} samples",
+                seconds, MIN_SAMPLES
+            ));
+        }
+        
+        let replay = self.replay_buffer.lock().await;
+        if replay.frames.len() < required {
+            return Err(format!(
+                "❌ Not enough data for a {} s baseline: {} of {} samples available (replay window is {:.1} s)",
+                seconds, replay.frames.len(), required, replay.seconds
+            ));
+        }
+        
+        let window: Vec<&ReplayFrame> = replay.frames.iter().rev().take(required).collect();
+        let channel_count = window[0].raw.len();
+        let mut means = vec![0.0f64; channel_count];
+        for frame in &window {
+            for (mean, &value) in means.iter_mut().zip(frame.raw.iter()) {
+                *mean += value as f64;
+            }
+        }
+        drop(replay);
+        
+        // Samples in the ring are already corrected by any active offset, so accumulate onto it
+        let mut dc_offset = self.dc_offset.lock().await;
+        let previous = dc_offset.clone().unwrap_or_else(|| vec![0.0; channel_count]);
+        let offset: Vec<f32> = means.iter()
+            .zip(previous.iter().chain(std::iter::repeat(&0.0)))
+            .map(|(sum, prev)| (sum / required as f64) as f32 + prev)
+            .collect();
+        
+        println!("📏 [DEBUG] DC offset measured over {} samples: {:?}", required, offset);
+        *dc_offset = Some(offset.clone());
+        Ok(offset)
+    }
+
+    async fn clear_dc_offset(&self) {
+        *self.dc_offset.lock().await = None;
+        println!("📏 [DEBUG] DC offset cleared");
+    }
+
+    async fn apply_dc_offset(&self, sample: &mut EEGSample) {
+        if let Some(offset) = self.dc_offset.lock().await.as_ref() {
+            for (value, dc) in sample.channels.iter_mut().zip(offset.iter()) {
+                *value -= dc;
+            }
+        }
+    }
+
+    async fn set_fixed_emit_hz(&self, hz: f32) -> Result<(), String> {
+        if !(0.0..=250.0).contains(&hz) {
+            return Err(format!("❌ Invalid emit rate {} Hz: must be between 0 (off) and 250 Hz", hz));
+        }
+        
+        *self.fixed_emit_hz.lock().await = hz;
+        if hz > 0.0 {
+            println!("⏱️ [DEBUG] filtered_eeg_sample now emitted at a fixed {} Hz", hz);
+        } else {
+            println!("⏱️ [DEBUG] filtered_eeg_sample emitted per sample again");
+        }
+        Ok(())
+    }
+
+    // The stages a pulled sample currently goes through, from accept_sample to the display event.
+    // Filter stages list every EEG channel, since set_channel_filter can make them differ.
+    async fn describe_processing_chain(&self) -> ProcessingChain {
+        let eeg_channels = self.eeg_channel_limit().await;
+        let mut stages = Vec::new();
+        let mut stage = |stage: &str, params: serde_json::Value| stages.push(ProcessingStage { stage: stage.to_string(), params });
+        
+        let scales = self.channel_scales.lock().await.clone();
+        if scales.iter().any(|&scale| scale != 1.0) {
+            stage("channel_scale", serde_json::json!({ "scales": scales }));
+        }
+        let polarity = self.channel_polarity.lock().await.clone();
+        let polarity_stage = *self.polarity_stage.lock().await;
+        let inverted: Vec<usize> = polarity.iter().enumerate().filter(|(_, &inv)| inv).map(|(ch, _)| ch).collect();
+        if !inverted.is_empty() && polarity_stage == PolarityStage::BeforeFilter {
+            stage("polarity", serde_json::json!({ "inverted": inverted }));
+        }
+        stage("timestamp_guard", serde_json::json!({ "mode": *self.timestamp_guard.lock().await }));
+        let max_gap = *self.gap_interpolation.lock().await;
+        if max_gap > 0 {
+            stage("gap_interpolation", serde_json::json!({ "max_gap_samples": max_gap }));
+        }
+        if let Some(offset) = self.dc_offset.lock().await.as_ref() {
+            stage("dc_offset", serde_json::json!({ "offsets": offset }));
+        }
+        
+        let artifact_domain = *self.artifact_domain.lock().await;
+        if artifact_domain == ArtifactDomain::Raw {
+            stage("artifact_hold", serde_json::json!({ "threshold_uv": ARTIFACT_CLIP_UV }));
+        }
+        match self.channel_filters.lock().await.as_ref() {
+            Some(filters) => {
+                let configs: Vec<&ChannelFilterConfig> = filters.iter().take(eeg_channels).map(|f| &f.config).collect();
+                if configs.iter().any(|c| c.median_despike > 1) {
+                    let windows: Vec<usize> = configs.iter().map(|c| c.median_despike).collect();
+                    stage("median_despike", serde_json::json!({ "windows": windows }));
+                }
+                let bands: Vec<serde_json::Value> = configs.iter()
+                    .map(|c| serde_json::json!({ "highpass": c.highpass, "lowpass": c.lowpass }))
+                    .collect();
+                stage("bandpass", serde_json::json!({ "type": "butterworth", "order": 4, "channels": bands }));
+                if configs.iter().any(|c| c.notch.is_some()) {
+                    let notches: Vec<serde_json::Value> = configs.iter()
+                        .map(|c| c.notch.map(|freq| serde_json::json!({ "freq": freq, "q": c.notch_q })).unwrap_or_default())
+                        .collect();
+                    stage("notch", serde_json::json!({ "channels": notches }));
+                }
+            }
+            None => stage("fallback_filter", serde_json::json!({ "gain": 0.95 })),
+        }
+        if artifact_domain == ArtifactDomain::Filtered {
+            stage("artifact_clip", serde_json::json!({ "threshold_uv": ARTIFACT_CLIP_UV }));
+        }
+        if !inverted.is_empty() && polarity_stage == PolarityStage::AfterFilter {
+            stage("polarity", serde_json::json!({ "inverted": inverted }));
+        }
+        
+        if let Some(smoother) = self.display_smoother.lock().await.as_ref() {
+            stage("display_smoothing", serde_json::json!({ "window": smoother.window, "order": smoother.order }));
+        }
+        let gains = self.display_gains.lock().await.clone();
+        if gains.iter().any(|&gain| gain != 1.0) {
+            stage("display_gain", serde_json::json!({ "gains": gains }));
+        }
+        let fixed_emit_hz = *self.fixed_emit_hz.lock().await;
+        if fixed_emit_hz > 0.0 {
+            stage("fixed_emit_rate", serde_json::json!({ "hz": fixed_emit_hz }));
+        }
+        
+        ProcessingChain { stages }
+    }
+
+    async fn set_emit_processing_chain(&self, enabled: bool) {
+        // A fresh watch emits the current chain at the next check
+        *self.processing_chain.lock().await = enabled.then_some(ProcessingChainWatch { last: None, checked: None });
+        println!("🧾 [DEBUG] processing_chain events {}", if enabled { "enabled" } else { "disabled" });
+    }
+
+    async fn take_processing_chain_change(&self) -> Option<ProcessingChain> {
+        let watch = self.processing_chain.lock().await;
+        if matches!(watch.as_ref()?.checked, Some(t) if t.elapsed() < PROCESSING_CHAIN_CHECK_INTERVAL) {
+            return None;
+        }
+        drop(watch);
+        let chain = self.describe_processing_chain().await;
+        
+        // Re-lock: the setting may have been turned off meanwhile
+        let mut watch = self.processing_chain.lock().await;
+        let watch = watch.as_mut()?;
+        watch.checked = Some(std::time::Instant::now());
+        if watch.last.as_ref() == Some(&chain) {
+            return None;
+        }
+        watch.last = Some(chain.clone());
+        Some(chain)
+    }
+
+    async fn set_sample_batch_size(&self, n: usize) -> Result<(), String> {
+        if !(1..=MAX_SAMPLE_BATCH_SIZE).contains(&n) {
+            return Err(format!("❌ Invalid sample batch size {}: must be between 1 and {}", n, MAX_SAMPLE_BATCH_SIZE));
+        }
+        
+        *self.sample_batch_size.lock().await = n;
+        if n > 1 {
+            println!("📦 [DEBUG] Raw samples now emitted as eeg_sample_batch of {}", n);
+        } else {
+            println!("📦 [DEBUG] Raw samples emitted as single eeg_sample events again");
+        }
+        Ok(())
+    }
+
+    async fn set_quiet_mode(&self, enabled: bool, heartbeat_interval: Option<f64>) -> Result<(), String> {
+        let interval_s = heartbeat_interval.unwrap_or(DEFAULT_HEARTBEAT_INTERVAL_S);
+        if !(0.1..=60.0).contains(&interval_s) {
+            return Err(format!("❌ Invalid heartbeat interval {} s: must be between 0.1 and 60 seconds", interval_s));
+        }
+        
+        *self.quiet_mode.lock().await = enabled.then(|| QuietMode::new(interval_s));
+        if enabled {
+            println!("🤫 [DEBUG] Quiet mode on: events suppressed, heartbeat every {} s", interval_s);
+        } else {
+            println!("🤫 [DEBUG] Quiet mode off: all events emitted again");
+        }
+        Ok(())
+    }
+
+    async fn is_quiet(&self) -> bool {
+        self.quiet_mode.lock().await.is_some()
+    }
+
+    async fn record_heartbeat_sample(&self) {
+        if let Some(quiet) = self.quiet_mode.lock().await.as_mut() {
+            quiet.samples += 1;
+        }
+    }
+
+    async fn record_heartbeat_analysis(&self, bands: &[FrequencyBands], snr: &[ChannelSnr]) {
+        let mut quiet_mode = self.quiet_mode.lock().await;
+        let Some(quiet) = quiet_mode.as_mut() else { return };
+        quiet.bands.add(bands);
+        quiet.snr = snr.to_vec();
+    }
+
+    // Some once the interval has passed; the aggregates then start over
+    async fn take_heartbeat(&self, timestamp: f64) -> Option<Heartbeat> {
+        let effective_rate = self.lsl_connection.lock().await.smoothed_rate;
+        let mut quiet_mode = self.quiet_mode.lock().await;
+        let quiet = quiet_mode.as_mut()?;
+        if quiet.started.elapsed().as_secs_f64() < quiet.interval_s {
+            return None;
+        }
+        
+        let finished = std::mem::replace(quiet, QuietMode::new(quiet.interval_s));
+        Some(Heartbeat {
+            timestamp,
+            interval_s: finished.interval_s,
+            samples: finished.samples,
+            effective_rate,
+            mean_bands: finished.bands.mean(timestamp),
+            snr: finished.snr,
+        })
+    }
+
+    async fn set_timed_session(&self, seconds: f64, with_quote: bool) -> Result<(), String> {
+        if !seconds.is_finite() || seconds <= 0.0 || seconds > MAX_TIMED_SESSION_S {
+            return Err(format!("❌ Invalid session length {} s: must be above 0 and at most {} s", seconds, MAX_TIMED_SESSION_S));
+        }
+        
+        *self.timed_session.lock().await = Some(TimedSession {
+            duration_s: seconds,
+            started: std::time::Instant::now(),
+            samples: 0,
+            bands: BandAverage::default(),
+            with_quote,
+        });
+        println!("⏱️ [DEBUG] Timed session of {} s started", seconds);
+        Ok(())
+    }
+
+    async fn record_session_sample(&self) {
+        if let Some(session) = self.timed_session.lock().await.as_mut() {
+            session.samples += 1;
+        }
+    }
+
+    async fn record_session_analysis(&self, bands: &[FrequencyBands]) {
+        if let Some(session) = self.timed_session.lock().await.as_mut() {
+            session.bands.add(bands);
+        }
+        
+        for (band, crossing) in self.band_crossings.lock().await.iter_mut() {
+            let powers: Vec<f32> = bands.iter().filter_map(|channel_bands| channel_bands.get(band)).collect();
+            if !powers.is_empty() {
+                crossing.update(powers.iter().sum::<f32>() / powers.len() as f32);
+            }
+        }
+    }
+
+    // None stops counting the band; a new threshold starts its count from zero
+    async fn set_band_crossing_threshold(&self, band: String, threshold: Option<f32>) -> Result<(), String> {
+        let mut crossings = self.band_crossings.lock().await;
+        let Some(threshold) = threshold else {
+            crossings.remove(&band);
+            println!("📈 [DEBUG] Stopped counting '{}' crossings", band);
+            return Ok(());
+        };
+        
+        let known_bands = self.band_profile.lock().await.names();
+        if !known_bands.contains(&band) {
+            return Err(format!("❌ Unknown band '{}': expected one of {:?}", band, known_bands));
+        }
+        if !threshold.is_finite() {
+            return Err(format!("❌ Invalid crossing threshold {} for band '{}': must be finite", threshold, band));
+        }
+        
+        println!("📈 [DEBUG] Counting '{}' crossings above {}", band, threshold);
+        crossings.insert(band, BandCrossing { threshold, count: 0, above: false });
+        Ok(())
+    }
+
+    async fn get_session_stats(&self) -> SessionStats {
+        let crossings = self.band_crossings.lock().await.iter()
+            .map(|(band, crossing)| (band.clone(), crossing.count))
+            .collect();
+        SessionStats { crossings }
+    }
+
+    // Some once, when the timed session has run its length
+    async fn take_session_complete(&self, timestamp: f64) -> Option<SessionSummary> {
+        let mut timed_session = self.timed_session.lock().await;
+        let running = timed_session.as_ref()?;
+        if running.started.elapsed().as_secs_f64() < running.duration_s {
+            return None;
+        }
+        
+        let session = timed_session.take()?;
+        let mean_bands = session.bands.mean(timestamp);
+        let quote = match session.with_quote {
+            true => Some(meditation_quote(self.session_meditation_tier(&mean_bands).await)),
+            false => None,
+        };
+        Some(SessionSummary {
+            timestamp,
+            duration_s: session.started.elapsed().as_secs_f64(),
+            samples: session.samples,
+            mean_bands,
+            crossings: self.get_session_stats().await.crossings,
+            quote,
+        })
+    }
+
+    async fn set_analysis_enabled(&self, enabled: bool) {
+        *self.analysis_enabled.lock().await = enabled;
+        if enabled {
+            println!("📈 [DEBUG] Frequency analysis resumed");
+        } else {
+            println!("📈 [DEBUG] Frequency analysis paused - raw and filtered samples keep streaming");
+        }
+    }
+
+    async fn set_delta_encoding(&self, enabled: bool, quantization: Option<f32>) -> Result<(), String> {
+        if !enabled {
+            *self.delta_encoder.lock().await = None;
+            println!("📦 [DEBUG] Delta encoding disabled");
+            return Ok(());
+        }
+        
+        let quantization = quantization.unwrap_or(DEFAULT_DELTA_QUANTIZATION);
+        if !quantization.is_finite() || quantization <= 0.0 {
+            return Err(format!("❌ Invalid delta quantization {}: must be a positive step in µV", quantization));
+        }
+        
+        // A fresh encoder starts with a keyframe
+        *self.delta_encoder.lock().await = Some(DeltaEncoder::new(quantization));
+        println!("📦 [DEBUG] eeg_sample_delta enabled, {} µV per step", quantization);
+        Ok(())
+    }
+
+    async fn encode_delta(&self, sample: &FilteredEEGSample) -> Option<EEGSampleDelta> {
+        self.delta_encoder.lock().await.as_mut().map(|encoder| encoder.encode(sample))
+    }
+
+    async fn set_pull_timeout(&self, seconds: f64) -> Result<(), String> {
+        if !(0.0..=MAX_PULL_TIMEOUT_S).contains(&seconds) {
+            return Err(format!(
+                "❌ Invalid pull timeout {} s: must be between 0 and {} seconds",
+                seconds, MAX_PULL_TIMEOUT_S
+            ));
+        }
+        
+        *self.pull_timeout.lock().await = seconds;
+        println!("⏳ [DEBUG] LSL pull timeout set to {} s", seconds);
+        Ok(())
+    }
+
+    // pull_timeout only applies to polling; string streams are always polled
+    async fn set_pull_mode(&self, mode: PullMode) {
+        *self.pull_mode.lock().await = mode;
+        // A running worker stops once its receiver is gone; blocking mode starts a fresh one
+        *self.inlet_worker.lock().await = None;
+        println!("⏳ [DEBUG] LSL pull mode set to {:?}", mode);
+    }
+
+    // Bumped by every start and by stop_eeg_processing; a processing run's tasks exit once it moves on
+    async fn begin_processing_run(&self) -> u64 {
+        for crossing in self.band_crossings.lock().await.values_mut() {
+            crossing.count = 0;
+            crossing.above = false;
+        }
+        let mut run = self.processing_run.lock().await;
+        *run += 1;
+        *run
+    }
+
+    async fn stop_processing(&self) {
+        *self.processing_run.lock().await += 1;
+        if self.timed_session.lock().await.take().is_some() {
+            println!("⏱️ [DEBUG] Timed session cancelled");
+        }
+    }
+
+    async fn set_timestamp_source(&self, source: TimestampSource) {
+        *self.timestamp_source.lock().await = source;
+        println!("🕒 [DEBUG] Timestamp source set to {:?}", source);
+    }
+
+    async fn set_emit_lsl_timestamp(&self, enabled: bool) {
+        *self.emit_lsl_timestamp.lock().await = enabled;
+        println!("🕒 [DEBUG] Raw lsl_timestamp in samples {}", if enabled { "enabled" } else { "disabled" });
+    }
+
+    // Every tunable back to its value in new() (and per-channel settings to their connect
+    // defaults), with the filters rebuilt for the stream's nominal rate. The connection, the
+    // buffers, recordings, session metadata, the event log, registered metrics, loaded band profiles
+    // and a running timed session are kept. Saved band thresholds are cleared on disk as well.
+    async fn reset_config(&self, app_handle: &tauri::AppHandle) -> Result<ProcessingChain, String> {
+        // Filters
+        let filter_config = ChannelFilterConfig::default();
+        *self.filter_config.lock().await = filter_config.clone();
+        *self.filter_rate.lock().await = self.sample_rate as f64;
+        if let Some(filters) = self.channel_filters.lock().await.as_mut() {
+            *filters = (0..filters.len())
+                .map(|_| ChannelFilter::new(filter_config.clone(), self.sample_rate as f64))
+                .collect();
+        }
+        *self.adaptive_filter_redesign.lock().await = false;
+        *self.adaptive_notch.lock().await = None;
+        *self.artifact_domain.lock().await = ArtifactDomain::Filtered;
+        *self.dc_offset.lock().await = None;
+        *self.display_smoother.lock().await = None;
+        *self.gap_interpolation.lock().await = 0;
+        *self.settle_samples.lock().await = 0;
+        *self.timestamp_guard.lock().await = TimestampGuard::Drop;
+        *self.polarity_stage.lock().await = PolarityStage::BeforeFilter;
+        
+        // Per-channel settings, keeping the connected channel count
+        let units = self.get_stream_info().await.map(|info| info.units).unwrap_or_default();
+        for (ch, scale) in self.channel_scales.lock().await.iter_mut().enumerate() {
+            *scale = units.get(ch).map_or(1.0, |unit| unit_scale(unit));
+        }
+        self.display_gains.lock().await.fill(1.0);
+        self.channel_polarity.lock().await.fill(false);
+        self.channel_band_profiles.lock().await.fill(None);
+        self.channel_signal_types.lock().await.fill(SignalType::Eeg);
+        
+        // Bands and analysis
+        *self.band_profile.lock().await = BandProfile::default();
+        self.named_band_profiles.lock().await.insert("classic".to_string(), BandProfile::default());
+        *self.active_band_profile.lock().await = "classic".to_string();
+        *self.band_resolution_checked.lock().await = None;
+        *self.passband_only.lock().await = false;
+        *self.band_passband_checked.lock().await = None;
+        *self.band_smoothing.lock().await = BandSmoothing::default();
+        *self.multiresolution.lock().await = None;
+        *self.analysis_method.lock().await = AnalysisMethod::Fft;
+        *self.analysis_source.lock().await = AnalysisSource::Filtered;
+        *self.goertzel_points.lock().await = DEFAULT_GOERTZEL_POINTS;
+        *self.gamma_preemphasis.lock().await = false;
+        *self.whitening.lock().await = None;
+        *self.dc_exclusion_bins.lock().await = DEFAULT_DC_EXCLUSION_BINS;
+        *self.zero_pad_factor.lock().await = 1;
+        *self.auto_gain.lock().await = None;
+        *self.analysis_enabled.lock().await = true;
+        *self.min_band_bins.lock().await = DEFAULT_MIN_BAND_BINS;
+        *self.erd_windows.lock().await = ErdWindows::default();
+        *self.test_markers.lock().await = TestMarkers::default();
+        *self.calm_score.lock().await = CalmScoreState::default();
+        *self.meditation_index.lock().await = MeditationIndexState::default();
+        *self.band_normalization.lock().await = BandNormalizationState::default();
+        *self.alpha_blocking.lock().await = None;
+        *self.blinks.lock().await = BlinkState::default();
+        *self.engagement_mode.lock().await = EngagementMode::PerChannel;
+        *self.target_phase_hz.lock().await = None;
+        *self.envelope_band.lock().await = None;
+        *self.aperiodic_range.lock().await = DEFAULT_APERIODIC_RANGE_HZ;
+        *self.entropy_range.lock().await = DEFAULT_ENTROPY_RANGE_HZ;
+        *self.muscle_artifact.lock().await = None;
+        *self.gate_bad_channels.lock().await = false;
+        self.gated_channels.lock().await.clear();
+        {
+            let mut history = self.frame_history.lock().await;
+            history.capacity = DEFAULT_FRAME_HISTORY;
+            history.frames.clear();
+        }
+        *self.variance_trend.lock().await = None;
+        *self.channel_groups.lock().await = None;
+        *self.score_channels.lock().await = None;
+        *self.xcorr.lock().await = None;
+        self.band_crossings.lock().await.clear();
+        *self.individualized_bands.lock().await = None;
+        self.triggers.lock().await.clear();
+        self.set_band_thresholds(app_handle, HashMap::new()).await?;
+        
+        // Emission
+        *self.band_format.lock().await = BandFormat::PerChannel;
+        *self.timestamp_source.lock().await = TimestampSource::Lsl;
+        *self.emit_lsl_timestamp.lock().await = false;
+        *self.fixed_emit_hz.lock().await = 0.0;
+        *self.sample_batch_size.lock().await = 1;
+        self.set_max_event_rate(DEFAULT_MAX_EVENT_RATE).await?;
+        *self.delta_encoder.lock().await = None;
+        *self.emit_raw_spectrum.lock().await = false;
+        *self.quiet_mode.lock().await = None;
+        *self.processing_chain.lock().await = None;
+        
+        // Connection
+        *self.overload_policy.lock().await = OverloadPolicy::None;
+        *self.pull_timeout.lock().await = DEFAULT_PULL_TIMEOUT_S;
+        self.set_pull_mode(PullMode::Poll).await;
+        *self.match_field.lock().await = MatchField::Auto;
+        *self.max_streams.lock().await = DEFAULT_MAX_STREAMS;
+        *self.reconnect_any_match.lock().await = false;
+        *self.auto_expand_channels.lock().await = false;
+        *self.max_reconnect_interval.lock().await = DEFAULT_MAX_RECONNECT_INTERVAL_S;
+        *self.rate_smoothing.lock().await = DEFAULT_RATE_SMOOTHING;
+        LSL_TASK_GRACE_MS.store(DEFAULT_LSL_TASK_GRACE_MS, Ordering::Relaxed);
+        
+        println!("🧹 [DEBUG] Configuration reset to defaults");
+        Ok(self.describe_processing_chain().await)
+    }
+
+    async fn capture_preset(&self) -> ConfigPreset {
+        let band_normalization = self.band_normalization.lock().await.clone();
+        ConfigPreset {
+            filter: self.filter_config.lock().await.clone(),
+            band_profile: self.band_profile.lock().await.edges(),
+            active_band_profile: self.active_band_profile.lock().await.clone(),
+            band_time_constants: self.band_smoothing.lock().await.time_constants.clone(),
+            analysis_method: *self.analysis_method.lock().await,
+            goertzel_points: *self.goertzel_points.lock().await,
+            analysis_source: *self.analysis_source.lock().await,
+            artifact_domain: *self.artifact_domain.lock().await,
+            band_format: *self.band_format.lock().await,
+            engagement_mode: *self.engagement_mode.lock().await,
+            band_normalization: band_normalization.mode,
+            band_normalization_per_band: band_normalization.per_band,
+            gamma_preemphasis: *self.gamma_preemphasis.lock().await,
+            whitening_bins: *self.whitening.lock().await,
+            dc_exclusion_bins: *self.dc_exclusion_bins.lock().await,
+            zero_pad_factor: *self.zero_pad_factor.lock().await,
+            passband_only: *self.passband_only.lock().await,
+            timestamp_source: *self.timestamp_source.lock().await,
+            timestamp_guard: *self.timestamp_guard.lock().await,
+            overload_policy: *self.overload_policy.lock().await,
+            match_field: *self.match_field.lock().await,
+            max_streams: *self.max_streams.lock().await,
+            pull_mode: *self.pull_mode.lock().await,
+            pull_timeout_s: *self.pull_timeout.lock().await,
+            reconnect_any_match: *self.reconnect_any_match.lock().await,
+            auto_expand_channels: *self.auto_expand_channels.lock().await,
+            max_reconnect_interval: *self.max_reconnect_interval.lock().await,
+            gap_interpolation: *self.gap_interpolation.lock().await,
+            settle_samples: *self.settle_samples.lock().await,
+            polarity_stage: *self.polarity_stage.lock().await,
+            channel_polarity: self.channel_polarity.lock().await.clone(),
+            display_gains: self.display_gains.lock().await.clone(),
+            channel_scales: self.channel_scales.lock().await.clone(),
+            channel_signal_types: self.channel_signal_types.lock().await.clone(),
+        }
+    }
+
+    // All or nothing: a preset that fails validation part way (e.g. a hand-edited file) puts the
+    // settings from before back. Callers hold the processor lock, so the processing loop never
+    // sees a half-applied preset.
+    async fn apply_preset(&self, preset: &ConfigPreset) -> Result<ProcessingChain, String> {
+        let previous = self.capture_preset().await;
+        if let Err(e) = self.try_apply_preset(preset).await {
+            if let Err(restore) = self.try_apply_preset(&previous).await {
+                eprintln!("❌ [DEBUG] Failed to restore the previous settings: {}", restore);
+            }
+            return Err(e);
+        }
+        Ok(self.describe_processing_chain().await)
+    }
+
+    async fn try_apply_preset(&self, preset: &ConfigPreset) -> Result<(), String> {
+        // Filters, rebuilt from the preset's config and channel types
+        let filter_rate = *self.filter_rate.lock().await;
+        preset.filter.validate(filter_rate)?;
+        *self.filter_config.lock().await = preset.filter.clone();
+        {
+            let mut signal_types = self.channel_signal_types.lock().await;
+            for (signal_type, preset_type) in signal_types.iter_mut().zip(preset.channel_signal_types.iter()) {
+                *signal_type = *preset_type;
+            }
+            if let Some(filters) = self.channel_filters.lock().await.as_mut() {
+                *filters = (0..filters.len())
+                    .map(|ch| {
+                        let signal_type = signal_types.get(ch).copied().unwrap_or(SignalType::Eeg);
+                        ChannelFilter::new(signal_type.filter_preset(&preset.filter, filter_rate), filter_rate)
+                    })
+                    .collect();
+            }
+        }
+        self.set_gap_interpolation(preset.gap_interpolation).await?;
+        self.set_settle_samples(preset.settle_samples).await;
+        *self.artifact_domain.lock().await = preset.artifact_domain;
+        *self.timestamp_guard.lock().await = preset.timestamp_guard;
+        self.set_polarity_stage(preset.polarity_stage).await;
+        
+        // Per-channel settings
+        for (value, preset_value) in self.channel_polarity.lock().await.iter_mut().zip(preset.channel_polarity.iter()) {
+            *value = *preset_value;
+        }
+        for (value, preset_value) in self.display_gains.lock().await.iter_mut().zip(preset.display_gains.iter()) {
+            *value = *preset_value;
+        }
+        for (value, preset_value) in self.channel_scales.lock().await.iter_mut().zip(preset.channel_scales.iter()) {
+            *value = *preset_value;
+        }
+        
+        // Bands and analysis
+        let profile = BandProfile::from_edges(preset.band_profile.clone())?;
+        self.named_band_profiles.lock().await.insert(preset.active_band_profile.clone(), profile.clone());
+        *self.band_profile.lock().await = profile;
+        *self.active_band_profile.lock().await = preset.active_band_profile.clone();
+        *self.band_resolution_checked.lock().await = None;
+        *self.band_passband_checked.lock().await = None;
+        *self.band_smoothing.lock().await = BandSmoothing::default();
+        for (band, seconds) in &preset.band_time_constants {
+            self.set_band_time_constant(band.clone(), *seconds).await?;
+        }
+        self.set_analysis_method(preset.analysis_method, Some(preset.goertzel_points)).await?;
+        self.set_analysis_source(preset.analysis_source).await;
+        *self.gamma_preemphasis.lock().await = preset.gamma_preemphasis;
+        self.set_whitening(preset.whitening_bins.is_some(), preset.whitening_bins.unwrap_or(1)).await?;
+        self.set_dc_exclusion_bins(preset.dc_exclusion_bins).await?;
+        self.set_zero_pad_factor(preset.zero_pad_factor).await?;
+        self.set_passband_only(preset.passband_only).await;
+        self.set_engagement_mode(preset.engagement_mode).await;
+        self.set_band_normalization(preset.band_normalization, None).await?;
+        for (band, mode) in &preset.band_normalization_per_band {
+            self.set_band_normalization(*mode, Some(band.clone())).await?;
+        }
+        
+        // Emission
+        *self.band_format.lock().await = preset.band_format;
+        *self.timestamp_source.lock().await = preset.timestamp_source;
+        
+        // Connection
+        *self.overload_policy.lock().await = preset.overload_policy;
+        *self.match_field.lock().await = preset.match_field;
+        self.set_max_streams(preset.max_streams).await?;
+        self.set_pull_mode(preset.pull_mode).await;
+        self.set_pull_timeout(preset.pull_timeout_s).await?;
+        self.set_reconnect_to_any_match(preset.reconnect_any_match).await;
+        self.set_auto_expand_channels(preset.auto_expand_channels).await;
+        self.set_max_reconnect_interval(preset.max_reconnect_interval).await?;
+        Ok(())
+    }
+
+    // Events the current settings can produce; data-dependent ones (e.g. erd_ers without markers,
+    // hemisphere_difference without mirrored electrodes) are listed whenever their stage runs
+    async fn list_active_events(&self) -> Vec<String> {
+        let mut events: Vec<&str> = Vec::new();
+        
+        if self.quiet_mode.lock().await.is_some() {
+            events.push("heartbeat");
+        } else {
+            let string_channels = self.lsl_connection.lock().await.string_channels;
+            if string_channels {
+                events.push("string_sample");
+            } else {
+                events.push(if *self.sample_batch_size.lock().await > 1 { "eeg_sample_batch" } else { "eeg_sample" });
+                events.push("filtered_eeg_sample");
+                if self.delta_encoder.lock().await.is_some() {
+                    events.push("eeg_sample_delta");
+                }
+            }
+            events.extend(["connection_status", "buffer_fill_progress", "processing_load", "processing_latency_ms", "loop_jitter"]);
+            if *self.settle_samples.lock().await > 0 {
+                events.push("settling");
+            }
+            if self.event_rate.lock().unwrap_or_else(std::sync::PoisonError::into_inner).per_second.is_some() {
+                events.push("events_dropped");
+            }
+            if self.processing_chain.lock().await.is_some() {
+                events.push("processing_chain");
+            }
+            if *self.auto_expand_channels.lock().await {
+                events.push("channel_layout");
+            }
+            if self.target_phase_hz.lock().await.is_some() {
+                events.push("target_phase");
+            }
+            if self.envelope_band.lock().await.is_some() {
+                events.push("band_envelope");
+            }
+            if self.variance_trend.lock().await.is_some() {
+                events.push("channel_variance_trend");
+            }
+            
+            if *self.analysis_enabled.lock().await && !string_channels {
+                events.extend(["frequency_bands", "eeg_frame", "band_resolution_warning", "band_passband_warning", "calm_score", "meditation_index"]);
+                events.extend(["engagement_index", "erd_ers"]);
+                events.extend(["hemisphere_difference", "regional_bands", "snr", "aperiodic", "spectral_entropy", "taf", "hjorth", "correlation_matrix"]);
+                if *self.emit_raw_spectrum.lock().await {
+                    events.push("raw_frequency_bands");
+                }
+                if self.muscle_artifact.lock().await.is_some() {
+                    events.push("muscle_artifact");
+                }
+                if *self.gate_bad_channels.lock().await {
+                    events.push("channel_gate");
+                }
+                if self.xcorr.lock().await.is_some() {
+                    events.push("xcorr_lag");
+                }
+                if !self.triggers.lock().await.is_empty() {
+                    events.push("trigger_fired");
+                }
+                if self.alpha_blocking.lock().await.is_some() {
+                    events.extend(["alpha_blocking_prompt", "alpha_blocking_result"]);
+                }
+                if self.blinks.lock().await.calibration.is_some() {
+                    events.extend(["blink_calibration", "blink_template"]);
+                }
+                if !self.custom_metrics.lock().await.is_empty() {
+                    events.push("custom_metric");
+                }
+                if self.adaptive_notch.lock().await.is_some() {
+                    events.push("adaptive_notch");
+                }
+                if *self.adaptive_filter_redesign.lock().await {
+                    events.push("filter_redesign");
+                }
+            }
+        }
+        
+        // Not muted by quiet mode
+        events.push("stream_warning");
+        if self.timed_session.lock().await.is_some() {
+            events.push("session_complete");
+        }
+        events.push("config_reset");
+        events.into_iter().map(str::to_string).collect()
+    }
+
+    async fn is_real_connection(&self) -> bool {
+        let connection = self.lsl_connection.lock().await;
+        connection.is_real_connection
+    }
+}
+
+#[tauri::command]
+async fn connect_to_lsl_stream(
+    stream_name: String,
+    app_handle: tauri::AppHandle,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<LSLStreamInfo, String> {
+    println!("🚀 [DEBUG] ===== TAURI COMMAND: connect_to_lsl_stream =====");
+    println!("🚀 [DEBUG] Called with stream_name: '{}'", stream_name);
+    println!("🚀 [DEBUG] Thread: {:?}", std::thread::current().id());
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    println!("🔒 [DEBUG] Acquired processor lock, calling connect_to_lsl...");
+    let result = processor_guard.connect_to_lsl(&stream_name).await;
+    
+    match &result {
+        Ok(info) => {
+            println!("✅ [DEBUG] ===== TAURI COMMAND SUCCESS =====");
+            println!("✅ [DEBUG] Stream name: {}", info.name);
+            println!("✅ [DEBUG] Channels: {}", info.channel_count);
+            println!("✅ [DEBUG] Sample rate: {}", info.sample_rate);
+            println!("✅ [DEBUG] Is connected: {}", info.is_connected);
+            println!("✅ [DEBUG] Metadata: {}", info.metadata);
+            
+            // Remember the stream for auto_connect_last, keeping the user's opt-in choice
+            let auto_connect = load_last_stream(&app_handle).ok().flatten().is_some_and(|last| last.auto_connect);
+            let last_stream = LastStream {
+                stream_name: stream_name.clone(),
+                source_id: info.source_id.clone(),
+                auto_connect,
+            };
+            if let Err(e) = save_last_stream(&app_handle, &last_stream) {
+                eprintln!("⚠️ [DEBUG] Could not persist last stream: {}", e);
+            }
+            
+            if info.sample_rate == IRREGULAR_RATE {
+                let warning = "Stream has an irregular sampling rate - spectral results use an estimated rate and are approximate";
+                if let Err(e) = app_handle.emit_all("stream_warning", warning) {
+                    eprintln!("❌ [DEBUG] Failed to emit stream warning: {}", e);
+                }
+            }
+        }
+        Err(e) => {
+            println!("❌ [DEBUG] ===== TAURI COMMAND FAILED =====");
+            println!("❌ [DEBUG] Error: {}", e);
+        }
+    }
+    
+    result
+}
+
+// For networks where multicast discovery is blocked. liblsl still has to reach the host: list it
+// under KnownPeers in lsl_api.cfg (or point LSLAPICFG at such a file) before the app starts, and
+// open the LSL ports (16571-16604 TCP/UDP by default) between the machines. `host` is the source
+// machine's hostname as LSL reports it; IP addresses work in KnownPeers but not here.
+#[tauri::command]
+async fn connect_by_address(
+    host: String,
+    name: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<LSLStreamInfo, String> {
+    println!("🚀 [DEBUG] Tauri command: connect_by_address called with host '{}', name '{}'", host, name);
+    let host = host.trim();
+    if host.is_empty() {
+        return Err("❌ Host must not be empty".to_string());
+    }
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.connect_to_lsl_on(&name, Some(host)).await
+}
+
+#[tauri::command]
+async fn set_auto_connect(enabled: bool, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let mut last_stream = load_last_stream(&app_handle)?.unwrap_or_default();
+    last_stream.auto_connect = enabled;
+    save_last_stream(&app_handle, &last_stream)?;
+    
+    println!("🔁 [DEBUG] Auto-connect on startup {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+#[tauri::command]
+async fn check_lsl_environment() -> Result<LslEnvReport, String> {
+    Ok(run_lsl_environment_check().await)
+}
+
+// Persisted; takes effect from the next launch
+#[tauri::command]
+async fn set_startup_lsl_check(enabled: bool, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let mut settings = load_startup_settings(&app_handle)?;
+    settings.check_lsl_environment = enabled;
+    save_startup_settings(&app_handle, &settings)?;
+    
+    println!("🩺 [DEBUG] LSL environment check on startup {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+// None when the startup check is off or hasn't finished
+#[tauri::command]
+async fn get_startup_lsl_report() -> Result<Option<LslEnvReport>, String> {
+    Ok(STARTUP_LSL_REPORT.lock().unwrap_or_else(std::sync::PoisonError::into_inner).clone())
+}
+
+// Called by the frontend on startup; reconnects to the last stream when auto-connect is on
+// and the stream is currently visible. Progress is reported through `connection_status`.
+#[tauri::command]
+async fn auto_connect_last(
+    app_handle: tauri::AppHandle,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Option<LSLStreamInfo>, String> {
+    let emit_status = |status: &str, stream_name: Option<&String>, message: String| {
+        let event = ConnectionStatus {
+            status: status.to_string(),
+            stream_name: stream_name.cloned(),
+            message,
+            effective_rate: None,
+        };
+        if let Err(e) = app_handle.emit_all("connection_status", &event) {
+            eprintln!("❌ [DEBUG] Failed to emit connection status: {}", e);
+        }
+    };
+    
+    let last_stream = match load_last_stream(&app_handle)? {
+        Some(last) if !last.stream_name.is_empty() => last,
+        _ => {
+            emit_status("no_saved_stream", None, "No previously connected stream".to_string());
+            return Ok(None);
+        }
+    };
+    let name = Some(&last_stream.stream_name);
+    
+    if !last_stream.auto_connect {
+        emit_status("disabled", name, "Auto-connect is turned off".to_string());
+        return Ok(None);
+    }
+    
+    let processor = processor.inner().clone();
+    let (match_field, max_streams) = {
+        let processor_guard = processor.lock().await;
+        let match_field = *processor_guard.match_field.lock().await;
+        let max_streams = *processor_guard.max_streams.lock().await;
+        (match_field, max_streams)
+    };
+    
+    // The resolve can take seconds; other commands keep the processor meanwhile
+    if !EEGProcessor::ping_stream(&last_stream.stream_name, match_field, max_streams).await {
+        emit_status("not_found", name, format!("Stream '{}' is not available", last_stream.stream_name));
+        return Ok(None);
+    }
+    
+    println!("🔁 [DEBUG] Auto-connecting to last stream '{}' ({})", last_stream.stream_name, last_stream.source_id);
+    emit_status("connecting", name, format!("Reconnecting to '{}'", last_stream.stream_name));
+    
+    let processor_guard = processor.lock().await;
+    match processor_guard.connect_to_lsl(&last_stream.stream_name).await {
+        Ok(info) => {
+            emit_status("connected", name, format!("Connected to '{}'", info.name));
+            Ok(Some(info))
+        }
+        Err(e) => {
+            emit_status("failed", name, e.clone());
+            Err(e)
+        }
+    }
+}
+
+// Feeds a CSV file or fifo (e.g. from a custom acquisition script) through the pipeline as
+// lines arrive; start_eeg_processing then runs on it exactly as on an LSL stream
+#[tauri::command]
+async fn connect_to_csv_stream(
+    source: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<LSLStreamInfo, String> {
+    println!("📄 [DEBUG] Tauri command: connect_to_csv_stream called with '{}'", source);
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.connect_to_csv_stream(&source).await
+}
+
+#[tauri::command]
+async fn disconnect_from_lsl(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    println!("🔌 [DEBUG] Tauri command: disconnect_from_lsl called");
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.disconnect_lsl().await;
+    
+    println!("✅ [DEBUG] Tauri command: disconnect_from_lsl completed");
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_current_stream_info(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Option<LSLStreamInfo>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    let info = processor_guard.get_stream_info().await;
+    println!("📊 [DEBUG] Tauri command: get_current_stream_info returning: {:?}", info);
+    
+    Ok(info)
+}
+
+#[tauri::command]
+async fn get_detected_device(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Option<DeviceProfile>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    Ok(processor_guard.get_detected_device().await)
+}
+
+#[tauri::command]
+async fn set_channel_filter(
+    index: usize,
+    highpass: f64,
+    lowpass: f64,
+    notch: Option<f64>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    println!("🔧 [DEBUG] Tauri command: set_channel_filter called for channel {}", index);
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    let (notch_q, median_despike, mains_method) = {
+        let config = processor_guard.filter_config.lock().await;
+        (config.notch_q, config.median_despike, config.mains_method)
+    };
+    processor_guard.set_channel_filter(
+        index,
+        ChannelFilterConfig { highpass, lowpass, notch, notch_q, median_despike, mains_method },
+    ).await
+}
+
+#[tauri::command]
+async fn set_channel_signal_type(
+    index: usize,
+    signal_type: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<ChannelFilterConfig, String> {
+    let signal_type: SignalType = signal_type.parse()?;
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_channel_signal_type(index, signal_type).await
+}
+
+#[tauri::command]
+async fn set_notch_q(
+    q: f64,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    println!("🔧 [DEBUG] Tauri command: set_notch_q called with q: {}", q);
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_notch_q(q).await
+}
+
+#[tauri::command]
+async fn set_mains_method(
+    method: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let method: MainsMethod = method.parse()?;
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_mains_method(method).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_mains_canceller_status(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<MainsCancellerStatus>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    Ok(processor_guard.get_mains_canceller_status().await)
+}
+
+#[tauri::command]
+async fn set_median_despike(
+    n: usize,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    println!("🔧 [DEBUG] Tauri command: set_median_despike called with n: {}", n);
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_median_despike(n).await
+}
+
+#[tauri::command]
+async fn set_replay_duration(
+    seconds: f64,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_replay_duration(seconds).await
+}
+
+#[tauri::command]
+async fn get_dual_snapshot(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<DualSnapshot, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    Ok(processor_guard.get_dual_snapshot().await)
+}
+
+#[tauri::command]
+async fn dump_replay_buffer(
+    path: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<usize, String> {
+    println!("💾 [DEBUG] Tauri command: dump_replay_buffer called with path: '{}'", path);
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.dump_replay_buffer(&path).await
+}
+
+// `range` is [min, max] in the units of `scale` (default "db"); sizes default to
+// DEFAULT_SPECTROGRAM_MAX_SIZE
+#[tauri::command]
+async fn render_spectrogram_png(
+    channel: usize,
+    path: String,
+    scale: Option<String>,
+    range: Option<(f32, f32)>,
+    max_width: Option<u32>,
+    max_height: Option<u32>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<SpectrogramImage, String> {
+    let scale: SpectrogramScale = scale.as_deref().unwrap_or("db").parse()?;
+    let (default_width, default_height) = DEFAULT_SPECTROGRAM_MAX_SIZE;
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.render_spectrogram_png(
+        channel,
+        &path,
+        scale,
+        range,
+        (max_width.unwrap_or(default_width), max_height.unwrap_or(default_height)),
+    ).await
+}
+
+#[tauri::command]
+async fn set_session_metadata(
+    metadata: HashMap<String, String>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_session_metadata(metadata).await
+}
+
+#[tauri::command]
+async fn set_event_logging(
+    enabled: bool,
+    capacity: Option<usize>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_event_logging(enabled, capacity).await
+}
+
+#[tauri::command]
+async fn get_event_log(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<EventLogEntry>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    Ok(processor_guard.get_event_log().await)
+}
+
+#[tauri::command]
+async fn set_max_event_rate(
+    per_second: f64,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_max_event_rate(per_second).await
+}
+
+#[tauri::command]
+async fn start_band_logging(
+    path: String,
+    compressed: Option<bool>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    println!("📝 [DEBUG] Tauri command: start_band_logging called with path: '{}'", path);
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.start_band_logging(&path, compressed.unwrap_or(false)).await
+}
+
+#[tauri::command]
+async fn stop_band_logging(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<usize, String> {
+    println!("📝 [DEBUG] Tauri command: stop_band_logging called");
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.stop_band_logging().await
+}
+
+// Raw and filtered samples to a CSV file until stop_recording. decimation (default 1) writes every
+// Nth sample, which is lossy for the raw signal; anti_alias (default true) averages each block of N
+// instead of dropping the samples between.
+#[tauri::command]
+async fn start_recording(
+    path: String,
+    decimation: Option<usize>,
+    anti_alias: Option<bool>,
+    compressed: Option<bool>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    println!("🎙️ [DEBUG] Tauri command: start_recording called with path: '{}'", path);
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.start_recording(&path, decimation.unwrap_or(1), anti_alias.unwrap_or(true), compressed.unwrap_or(false)).await
+}
+
+#[tauri::command]
+async fn stop_recording(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<usize, String> {
+    println!("🎙️ [DEBUG] Tauri command: stop_recording called");
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.stop_recording().await
+}
+
+#[tauri::command]
+async fn compare_sessions(
+    path_a: String,
+    path_b: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<SessionComparison, String> {
+    println!("📊 [DEBUG] Tauri command: compare_sessions called with '{}' and '{}'", path_a, path_b);
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.compare_sessions(&path_a, &path_b).await
+}
+
+#[tauri::command]
+async fn get_filter_coefficients(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<FilterCoeffs>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.get_filter_coefficients().await
+}
+
+#[tauri::command]
+async fn get_impulse_responses(
+    channel: Option<usize>,
+    length: Option<usize>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<ImpulseResponses, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.get_impulse_responses(channel.unwrap_or(0), length.unwrap_or(DEFAULT_IMPULSE_RESPONSE_LEN)).await
+}
+
+#[tauri::command]
+async fn ping_stream(
+    name: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<bool, String> {
+    let processor = processor.inner().clone();
+    let (match_field, max_streams) = {
+        let processor_guard = processor.lock().await;
+        let match_field = *processor_guard.match_field.lock().await;
+        let max_streams = *processor_guard.max_streams.lock().await;
+        (match_field, max_streams)
+    };
+    
+    Ok(EEGProcessor::ping_stream(&name, match_field, max_streams).await)
+}
+
+#[tauri::command]
+async fn probe_stream(
+    name: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<StreamProbe, String> {
+    println!("🔎 [DEBUG] Tauri command: probe_stream called with name: '{}'", name);
+    
+    let processor = processor.inner().clone();
+    let (match_field, max_streams) = {
+        let processor_guard = processor.lock().await;
+        let match_field = *processor_guard.match_field.lock().await;
+        let max_streams = *processor_guard.max_streams.lock().await;
+        (match_field, max_streams)
+    };
+    
+    EEGProcessor::probe_stream(&name, match_field, max_streams).await
+}
+
+#[tauri::command]
+async fn set_analysis_enabled(
+    enabled: bool,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_analysis_enabled(enabled).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn measure_now(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<FrequencyBands>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.measure_now().await
+}
+
+#[tauri::command]
+async fn set_delta_encoding(
+    enabled: bool,
+    quantization: Option<f32>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_delta_encoding(enabled, quantization).await
+}
+
+#[tauri::command]
+async fn set_max_reconnect_interval(
+    seconds: f32,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_max_reconnect_interval(seconds).await
+}
+
+#[tauri::command]
+async fn set_rate_smoothing(
+    alpha: f64,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_rate_smoothing(alpha).await
+}
+
+#[tauri::command]
+async fn set_max_streams(
+    n: usize,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_max_streams(n).await
+}
+
+#[tauri::command]
+async fn set_match_field(
+    field: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_match_field(&field).await
+}
+
+#[tauri::command]
+async fn run_filter_self_test(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<SelfTestReport, String> {
+    println!("🧪 [DEBUG] Tauri command: run_filter_self_test called");
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    Ok(processor_guard.run_filter_self_test().await)
+}
+
+#[tauri::command]
+async fn run_end_to_end_test() -> Result<E2EReport, String> {
+    println!("🧪 [DEBUG] Tauri command: run_end_to_end_test called");
+    
+    Ok(EEGProcessor::run_end_to_end_test().await)
+}
+
+#[tauri::command]
+async fn set_channel_order(
+    permutation: Vec<usize>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    println!("🔀 [DEBUG] Tauri command: set_channel_order called with {:?}", permutation);
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_channel_order(permutation).await
+}
+
+#[tauri::command]
+async fn set_eeg_channel_count(
+    count: usize,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    println!("🔧 [DEBUG] Tauri command: set_eeg_channel_count called with {}", count);
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_eeg_channel_count(count).await
+}
+
+#[tauri::command]
+async fn set_artifact_domain(
+    domain: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let domain: ArtifactDomain = domain.parse()?;
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    *processor_guard.artifact_domain.lock().await = domain;
+    println!("🧹 [DEBUG] Artifact domain set to {:?}", domain);
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_gamma_preemphasis(
+    enabled: bool,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    *processor_guard.gamma_preemphasis.lock().await = enabled;
+    println!("📈 [DEBUG] Gamma pre-emphasis {}", if enabled { "enabled (display only)" } else { "disabled" });
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_min_band_bins(
+    n: usize,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_min_band_bins(n).await
+}
+
+#[tauri::command]
+async fn set_passband_only(
+    enabled: bool,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_passband_only(enabled).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn suggest_fft_size(
+    target_resolution_hz: f32,
+    apply: Option<bool>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<usize, String> {
+    let processor = processor.inner().clone();
+    let mut processor_guard = processor.lock().await;
+    
+    processor_guard.suggest_fft_size(target_resolution_hz, apply.unwrap_or(false)).await
+}
+
+#[tauri::command]
+async fn set_buffer_size(
+    size: usize,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let mut processor_guard = processor.lock().await;
+    
+    processor_guard.set_buffer_size(size).await
+}
+
+#[tauri::command]
+async fn set_zero_pad_factor(
+    n: usize,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_zero_pad_factor(n).await
+}
+
+#[tauri::command]
+async fn set_multiresolution(
+    enabled: bool,
+    short_len: usize,
+    long_len: usize,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_multiresolution(enabled, short_len, long_len).await
+}
+
+#[tauri::command]
+async fn set_overload_policy(
+    policy: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let policy: OverloadPolicy = policy.parse()?;
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    *processor_guard.overload_policy.lock().await = policy;
+    println!("🏋️ [DEBUG] Overload policy set to {:?}", policy);
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_timestamp_guard(
+    guard: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let guard: TimestampGuard = guard.parse()?;
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    *processor_guard.timestamp_guard.lock().await = guard;
+    println!("⏱️ [DEBUG] Non-monotonic timestamps are now handled by {:?}", guard);
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_timestamp_stats(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<TimestampStats, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    Ok(processor_guard.get_timestamp_stats().await)
+}
+
+// With a name the profile is stored for set_channel_band_profile instead of replacing the global
+// one. Either way it is listed by list_band_profiles; an unnamed load is listed by its file stem.
+#[tauri::command]
+async fn load_band_profile(
+    path: String,
+    name: Option<String>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<String>, String> {
+    println!("🎼 [DEBUG] Tauri command: load_band_profile called with '{}'", path);
+    
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("❌ Failed to read band profile '{}': {}", path, e))?;
+    let profile = BandProfile::from_json(&contents)?;
+    let names = profile.names();
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    match name {
+        Some(name) => {
+            println!("🎼 [DEBUG] Band profile '{}' loaded: {:?}", name, profile.bands);
+            processor_guard.named_band_profiles.lock().await.insert(name, profile);
+        }
+        None => {
+            let name = std::path::Path::new(&path)
+                .file_stem()
+                .map_or_else(|| path.clone(), |stem| stem.to_string_lossy().into_owned());
+            println!("🎼 [DEBUG] Band profile '{}' loaded as the global profile: {:?}", name, profile.bands);
+            processor_guard.named_band_profiles.lock().await.insert(name.clone(), profile.clone());
+            *processor_guard.band_profile.lock().await = profile;
+            *processor_guard.active_band_profile.lock().await = name;
+        }
+    }
+    Ok(names)
+}
+
+#[tauri::command]
+async fn list_band_profiles(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<String>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    let mut names: Vec<String> = processor_guard.named_band_profiles.lock().await.keys().cloned().collect();
+    names.sort();
+    Ok(names)
+}
+
+// Name of the global profile; channels overridden by set_channel_band_profile are not reflected
+#[tauri::command]
+async fn get_active_band_profile(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<String, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    let name = processor_guard.active_band_profile.lock().await.clone();
+    Ok(name)
+}
+
+#[tauri::command]
+async fn set_channel_band_profile(
+    index: usize,
+    profile_name: Option<String>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_channel_band_profile(index, profile_name).await
+}
+
+#[tauri::command]
+async fn set_band_time_constant(
+    band: String,
+    seconds: f32,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_band_time_constant(band, seconds).await
+}
+
+#[tauri::command]
+async fn set_auto_gain(
+    enabled: bool,
+    target_rms: f32,
+    time_constant: f32,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_auto_gain(enabled, target_rms, time_constant).await
+}
+
+#[tauri::command]
+async fn set_whitening(
+    enabled: bool,
+    smoothing_bins: usize,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_whitening(enabled, smoothing_bins).await
+}
+
+#[tauri::command]
+async fn set_dc_exclusion_bins(
+    bins: usize,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_dc_exclusion_bins(bins).await
+}
+
+#[tauri::command]
+async fn set_analysis_method(
+    method: String,
+    points_per_band: Option<usize>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let method: AnalysisMethod = method.parse()?;
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_analysis_method(method, points_per_band).await
+}
+
+#[tauri::command]
+async fn set_analysis_source(
+    source: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let source: AnalysisSource = source.parse()?;
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_analysis_source(source).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_band_format(
+    format: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let format: BandFormat = format.parse()?;
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    *processor_guard.band_format.lock().await = format;
+    println!("📊 [DEBUG] Band format set to {:?}", format);
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_savgol(
+    window: usize,
+    order: usize,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_savgol(window, order).await
+}
+
+#[tauri::command]
+async fn measure_dc_offset(
+    seconds: f64,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<f32>, String> {
+    println!("📏 [DEBUG] Tauri command: measure_dc_offset called with {} s", seconds);
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.measure_dc_offset(seconds).await
+}
+
+#[tauri::command]
+async fn clear_dc_offset(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.clear_dc_offset().await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn enable_builtin_metric(
+    name: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let metric: Box<dyn EegMetric> = match name.to_lowercase().as_str() {
+        "hjorth" => Box::new(HjorthMetric),
+        other => return Err(format!("❌ Unknown built-in metric '{}': available metrics are \"hjorth\"", other)),
+    };
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.register_metric(metric).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_fixed_emit_hz(
+    hz: f32,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_fixed_emit_hz(hz).await
+}
+
+#[tauri::command]
+async fn set_sample_batch_size(
+    n: usize,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_sample_batch_size(n).await
+}
+
+#[tauri::command]
+async fn set_quiet_mode(
+    enabled: bool,
+    heartbeat_interval: Option<f64>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_quiet_mode(enabled, heartbeat_interval).await
+}
+
+#[tauri::command]
+async fn set_emit_processing_chain(
+    enabled: bool,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_emit_processing_chain(enabled).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn add_trigger(
+    metric: String,
+    channel: usize,
+    band: Option<String>,
+    op: String,
+    threshold: f32,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<u64, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    let known_bands = processor_guard.band_profile.lock().await.names();
+    let metric = TriggerMetric::parse(&metric, band.as_deref(), &known_bands)?;
+    let op: TriggerOp = op.parse()?;
+    
+    processor_guard.add_trigger(metric, channel, op, threshold).await
+}
+
+#[tauri::command]
+async fn remove_trigger(
+    id: u64,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.remove_trigger(id).await
+}
+
+#[tauri::command]
+async fn mark_event(
+    label: Option<String>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<f64, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.mark_event(label).await
+}
+
+#[tauri::command]
+async fn inject_test_marker(
+    code: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<f64, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.inject_test_marker(code).await
+}
+
+#[tauri::command]
+async fn set_auto_markers(
+    interval_ms: u64,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_auto_markers(interval_ms).await
+}
+
+#[tauri::command]
+async fn set_erd_windows(
+    baseline_s: f64,
+    activity_s: f64,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_erd_windows(baseline_s, activity_s).await
+}
+
+#[tauri::command]
+async fn set_display_gain(
+    index: usize,
+    gain: f32,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<f32>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_display_gain(index, gain).await
+}
+
+#[tauri::command]
+async fn auto_normalize_display(
+    seconds: f64,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<f32>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.auto_normalize_display(seconds).await
+}
+
+#[tauri::command]
+async fn set_channel_scale(
+    index: usize,
+    scale: f32,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<f32>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_channel_scale(index, scale).await
+}
+
+#[tauri::command]
+async fn set_channel_polarity(
+    index: usize,
+    inverted: bool,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<bool>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_channel_polarity(index, inverted).await
+}
+
+#[tauri::command]
+async fn set_polarity_stage(
+    stage: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let stage: PolarityStage = stage.parse()?;
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_polarity_stage(stage).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_display_gains(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<f32>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    let gains = processor_guard.display_gains.lock().await.clone();
+    Ok(gains)
+}
+
+// Replaces the whole set and saves it to band_thresholds.json in the app data directory
+#[tauri::command]
+async fn set_band_thresholds(
+    thresholds: HashMap<String, BandThreshold>,
+    app_handle: tauri::AppHandle,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_band_thresholds(&app_handle, thresholds).await
+}
+
+#[tauri::command]
+async fn get_band_thresholds(
+    app_handle: tauri::AppHandle,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<HashMap<String, BandThreshold>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    Ok(processor_guard.band_thresholds(&app_handle).await)
+}
+
+#[tauri::command]
+async fn set_score_weights(
+    weights: HashMap<String, f32>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_score_weights(weights).await
+}
+
+#[tauri::command]
+async fn set_band_normalization(
+    mode: String,
+    band: Option<String>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let mode: BandNormalization = mode.parse()?;
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_band_normalization(mode, band).await
+}
+
+#[tauri::command]
+async fn calibrate_band_baseline(
+    seconds: f64,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.calibrate_band_baseline(seconds).await
+}
+
+#[tauri::command]
+async fn calibrate_calm_score(
+    seconds: f64,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.calibrate_calm_score(seconds).await
+}
+
+// Clears the meditation index baseline, which was taken with the old weights
+#[tauri::command]
+async fn set_meditation_index_weights(
+    alpha: f32,
+    theta: f32,
+    beta: f32,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_meditation_index_weights(MeditationWeights { alpha, theta, beta }).await
+}
+
+#[tauri::command]
+async fn calibrate_meditation_index(
+    seconds: f64,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.calibrate_meditation_index(seconds).await
+}
+
+// Prompts and the result arrive as events; phase_seconds defaults to DEFAULT_ALPHA_BLOCKING_PHASE_S
+#[tauri::command]
+async fn run_alpha_blocking_test(
+    phase_seconds: Option<f64>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.run_alpha_blocking_test(phase_seconds.unwrap_or(DEFAULT_ALPHA_BLOCKING_PHASE_S)).await
+}
+
+// Progress arrives as `blink_calibration` events, the result as `blink_template`
+#[tauri::command]
+async fn calibrate_blinks(
+    count: usize,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.calibrate_blinks(count).await
+}
+
+// Needs the template of a finished calibrate_blinks
+#[tauri::command]
+async fn set_blink_correction(
+    enabled: bool,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_blink_correction(enabled).await
+}
+
+#[tauri::command]
+async fn set_pull_timeout(
+    seconds: f64,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_pull_timeout(seconds).await
+}
+
+#[tauri::command]
+async fn set_pull_mode(
+    mode: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let mode: PullMode = mode.parse()?;
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_pull_mode(mode).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_reconnect_to_any_match(
+    enabled: bool,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_reconnect_to_any_match(enabled).await;
+    Ok(())
+}
+
+// Emits `channel_layout` each time a wider sample grows the layout
+#[tauri::command]
+async fn set_auto_expand_channels(
+    enabled: bool,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_auto_expand_channels(enabled).await;
+    Ok(())
+}
+
+// Adds `raw_frequency_bands`, the band powers of the unfiltered buffers in the same layout as
+// `frequency_bands`, so the two can be compared to see what the bandpass and notch remove
+#[tauri::command]
+async fn set_emit_raw_spectrum(
+    enabled: bool,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    *processor_guard.emit_raw_spectrum.lock().await = enabled;
+    println!("📶 [DEBUG] Raw spectrum emission {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_aperiodic_range(
+    low_hz: f32,
+    high_hz: f32,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_aperiodic_range(low_hz, high_hz).await
+}
+
+#[tauri::command]
+async fn set_entropy_range(
+    low_hz: f32,
+    high_hz: f32,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_entropy_range(low_hz, high_hz).await
+}
+
+#[tauri::command]
+async fn get_dominant_frequency(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<f32, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    Ok(processor_guard.get_dominant_frequency().await)
+}
+
+#[tauri::command]
+async fn measure_thd(
+    fundamental_hz: f32,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<f32>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.measure_thd(fundamental_hz).await
+}
+
+// Also emitted as `itpc` for any other window showing it
+#[tauri::command]
+async fn compute_itpc(
+    label: Option<String>,
+    epoch_ms: Option<u64>,
+    app_handle: tauri::AppHandle,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Itpc, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    let itpc = processor_guard.compute_itpc(label, epoch_ms).await?;
+    if let Err(e) = app_handle.emit_all("itpc", &itpc) {
+        eprintln!("❌ [DEBUG] Failed to emit itpc: {}", e);
+    }
+    Ok(itpc)
+}
+
+#[tauri::command]
+async fn get_ssvep_response(
+    freqs: Vec<f32>,
+    harmonics: Option<usize>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<ChannelFreqResponse>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.get_ssvep_response(&freqs, harmonics).await
+}
+
+#[tauri::command]
+async fn set_muscle_artifact(
+    enabled: bool,
+    threshold: Option<f32>,
+    exclude_from_averages: Option<bool>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_muscle_artifact(enabled, threshold, exclude_from_averages).await
+}
+
+#[tauri::command]
+async fn set_frame_history(
+    length: usize,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_frame_history(length).await
+}
+
+// Nearest kept analysis cycle; None until the first one
+#[tauri::command]
+async fn get_frame_at(
+    timestamp: f64,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Option<CombinedFrame>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.get_frame_at(timestamp).await
+}
+
+#[tauri::command]
+async fn set_gate_bad_channels(
+    enabled: bool,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_gate_bad_channels(enabled).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_variance_trend(
+    enabled: bool,
+    window_s: Option<f64>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_variance_trend(enabled, window_s).await
+}
+
+#[tauri::command]
+async fn set_xcorr_pair(
+    pair: Option<(usize, usize)>,
+    max_lag_ms: f32,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_xcorr_pair(pair, max_lag_ms).await
+}
+
+#[tauri::command]
+async fn set_individualized_bands(
+    enabled: bool,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_individualized_bands(enabled).await
+}
+
+#[tauri::command]
+async fn set_engagement_mode(
+    mode: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let mode: EngagementMode = mode.parse()?;
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_engagement_mode(mode).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_aperiodic(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<Aperiodic>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    Ok(processor_guard.analyze_aperiodic().await)
+}
+
+#[tauri::command]
+async fn get_phase(
+    channel: usize,
+    freq: f32,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<f32, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.get_phase(channel, freq).await
+}
+
+// null stops the target_phase event
+#[tauri::command]
+async fn set_envelope_band(
+    band: Option<String>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_envelope_band(band).await
+}
+
+#[tauri::command]
+async fn set_target_phase(
+    freq: Option<f32>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_target_phase(freq).await
+}
+
+#[tauri::command]
+async fn get_channel_ranges(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<(f32, f32)>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    Ok(processor_guard.get_channel_ranges().await)
+}
+
+#[tauri::command]
+async fn get_channel_snr(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<ChannelSnr>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    Ok(processor_guard.last_snr().await)
+}
+
+#[tauri::command]
+async fn set_gap_interpolation(
+    max_samples: usize,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_gap_interpolation(max_samples).await
+}
+
+#[tauri::command]
+async fn set_settle_samples(
+    n: usize,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_settle_samples(n).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_adaptive_filter_redesign(
+    enabled: bool,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_adaptive_filter_redesign(enabled).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_adaptive_notch(
+    enabled: bool,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_adaptive_notch(enabled).await
+}
+
+#[tauri::command]
+async fn get_adaptive_notch(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Option<AdaptiveNotch>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    Ok(processor_guard.get_adaptive_notch().await)
+}
+
+#[tauri::command]
+async fn set_timestamp_source(
+    source: String,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let source: TimestampSource = source.parse()?;
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_timestamp_source(source).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn list_active_events(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<String>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    Ok(processor_guard.list_active_events().await)
+}
+
+// Emits `config_reset` with the resulting processing chain so views can reload their settings
+#[tauri::command]
+async fn reset_config(
+    app_handle: tauri::AppHandle,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    let chain = processor_guard.reset_config(&app_handle).await?;
+    app_handle.emit_all("config_reset", &chain)
+        .map_err(|e| format!("❌ Failed to emit config reset: {}", e))
+}
+
+// Overwrites a preset of the same name
+#[tauri::command]
+async fn save_preset(
+    name: String,
+    app_handle: tauri::AppHandle,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("❌ Preset name must not be empty".to_string());
+    }
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    let preset = processor_guard.capture_preset().await;
+    let mut presets = load_presets(&app_handle)?;
+    presets.insert(name.clone(), preset);
+    save_presets(&app_handle, &presets)?;
+    println!("💾 [DEBUG] Preset '{}' saved", name);
+    Ok(())
+}
+
+// Emits `config_reset` with the resulting processing chain, like reset_config, so views reload
+#[tauri::command]
+async fn load_preset(
+    name: String,
+    app_handle: tauri::AppHandle,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let presets = load_presets(&app_handle)?;
+    let preset = presets.get(&name).ok_or_else(|| format!("❌ Unknown preset '{}'", name))?;
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    let chain = processor_guard.apply_preset(preset).await?;
+    println!("💾 [DEBUG] Preset '{}' loaded", name);
+    app_handle.emit_all("config_reset", &chain)
+        .map_err(|e| format!("❌ Failed to emit config reset: {}", e))
+}
+
+#[tauri::command]
+async fn list_presets(app_handle: tauri::AppHandle) -> Result<Vec<String>, String> {
+    let mut names: Vec<String> = load_presets(&app_handle)?.into_keys().collect();
+    names.sort();
+    Ok(names)
+}
+
+#[tauri::command]
+async fn delete_preset(name: String, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let mut presets = load_presets(&app_handle)?;
+    if presets.remove(&name).is_none() {
+        return Err(format!("❌ Unknown preset '{}'", name));
+    }
+    save_presets(&app_handle, &presets)?;
+    println!("💾 [DEBUG] Preset '{}' deleted", name);
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_emit_lsl_timestamp(
+    enabled: bool,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_emit_lsl_timestamp(enabled).await;
+    Ok(())
+}
+
+// Needs the `websocket` feature; without it both commands return an error
+#[tauri::command]
+async fn start_websocket_server(
+    port: u16,
+    app_handle: tauri::AppHandle,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<u16, String> {
+    #[cfg(feature = "websocket")]
+    {
+        let processor = processor.inner().clone();
+        let processor_guard = processor.lock().await;
+        
+        processor_guard.start_websocket_server(&app_handle, port).await
+    }
+    #[cfg(not(feature = "websocket"))]
+    {
+        let _ = (port, app_handle, processor);
+        Err("❌ WebSocket output is not available: the app was built without the `websocket` feature".to_string())
+    }
+}
+
+#[tauri::command]
+async fn stop_websocket_server(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    #[cfg(feature = "websocket")]
+    {
+        let processor = processor.inner().clone();
+        let processor_guard = processor.lock().await;
+        
+        processor_guard.stop_websocket_server().await
+    }
+    #[cfg(not(feature = "websocket"))]
+    {
+        let _ = processor;
+        Err("❌ WebSocket output is not available: the app was built without the `websocket` feature".to_string())
+    }
+}
+
+// emit_all that drops the event in quiet mode, so the processing loop's emit sites stay as they are.
+// Events over set_max_event_rate's budget are dropped before the log; the rest also go to the
+// event log while set_event_logging is on.
+struct EventSink<'a> {
+    app_handle: &'a tauri::AppHandle,
+    muted: bool,
+    log: Arc<std::sync::Mutex<Option<EventLog>>>,
+    limiter: Arc<std::sync::Mutex<EventRateLimiter>>,
+}
+
+impl EventSink<'_> {
+    fn emit_all<S: Serialize + Clone>(&self, event: &str, payload: S) -> tauri::Result<()> {
+        if self.muted {
+            return Ok(());
+        }
+        self.emit_unmuted(event, payload)
+    }
+    
+    // For events that are sent in quiet mode too
+    fn emit_unmuted<S: Serialize + Clone>(&self, event: &str, payload: S) -> tauri::Result<()> {
+        if !self.limiter.lock().unwrap_or_else(std::sync::PoisonError::into_inner).admit(event) {
+            return Ok(());
+        }
+        self.send(event, payload)
+    }
+    
+    // Outside the rate budget, so the report can't be dropped itself
+    fn report_dropped_events(&self) -> tauri::Result<()> {
+        if self.muted {
+            return Ok(());
+        }
+        let report = self.limiter.lock().unwrap_or_else(std::sync::PoisonError::into_inner).take_report();
+        match report {
+            Some(report) => self.send("events_dropped", report),
+            None => Ok(()),
+        }
+    }
+    
+    fn send<S: Serialize + Clone>(&self, event: &str, payload: S) -> tauri::Result<()> {
+        if let Some(log) = self.log.lock().unwrap_or_else(std::sync::PoisonError::into_inner).as_mut() {
+            log.record(event, &payload);
+        }
+        self.app_handle.emit_all(event, payload)
+    }
+}
+
+// start_websocket_server: loopback only. Each client gets its own subscribe() receiver and
+// receives the frequency_bands and eeg_frame events as {"event", "payload"} JSON text messages,
+// muted in quiet mode like the Tauri events.
+#[cfg(feature = "websocket")]
+struct WebSocketServer {
+    port: u16,
+    task: tokio::task::JoinHandle<()>, // accept loop; aborting it drops every client task with it
+}
+
+#[cfg(feature = "websocket")]
+#[derive(Clone)]
+struct WebSocketOutputs {
+    band_format: Arc<Mutex<BandFormat>>,
+    band_thresholds: Arc<Mutex<Option<HashMap<String, BandThreshold>>>>,
+    quiet_mode: Arc<Mutex<Option<QuietMode>>>,
+}
+
+#[cfg(feature = "websocket")]
+async fn serve_websocket_client(stream: tokio::net::TcpStream, mut frames: broadcast::Receiver<ProcessedFrame>, outputs: WebSocketOutputs) {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message;
+    
+    let peer = stream.peer_addr().map_or_else(|_| "unknown".to_string(), |addr| addr.to_string());
+    let socket = match tokio_tungstenite::accept_async(stream).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            eprintln!("❌ [DEBUG] WebSocket handshake with {} failed: {}", peer, e);
+            return;
+        }
+    };
+    println!("🌐 [DEBUG] WebSocket client {} connected", peer);
+    let (mut sink, mut incoming) = socket.split();
+    
+    'client: loop {
+        tokio::select! {
+            frame = frames.recv() => {
+                let (timestamp, bands) = match frame {
+                    Ok(ProcessedFrame { raw, bands: Some(bands), .. }) => (raw.timestamp, bands),
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        println!("⚠️ [DEBUG] WebSocket client {} fell behind, {} frames skipped", peer, missed);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if outputs.quiet_mode.lock().await.is_some() {
+                    continue;
+                }
+                
+                let frequency_bands = match *outputs.band_format.lock().await {
+                    BandFormat::PerChannel => serde_json::to_value(&bands),
+                    BandFormat::Banded => serde_json::to_value(BandedFrequencyBands::from_bands(timestamp, &bands)),
+                };
+                let thresholds = outputs.band_thresholds.lock().await.clone().unwrap_or_default();
+                let frame = serde_json::to_value(EegFrame { timestamp, bands: &bands, thresholds: &thresholds });
+                
+                for (event, payload) in [("frequency_bands", frequency_bands), ("eeg_frame", frame)] {
+                    let payload = match payload {
+                        Ok(payload) => payload,
+                        Err(e) => {
+                            eprintln!("❌ [DEBUG] Failed to serialize {} for WebSocket: {}", event, e);
+                            continue;
+                        }
+                    };
+                    let message = serde_json::json!({ "event": event, "payload": payload }).to_string();
+                    if sink.send(Message::Text(message)).await.is_err() {
+                        break 'client;
+                    }
+                }
+            }
+            // Clients only listen; pings are answered by tungstenite while reading
+            message = incoming.next() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    println!("🌐 [DEBUG] WebSocket client {} disconnected", peer);
+}
+
+#[tauri::command]
+async fn start_eeg_processing(
+    app_handle: tauri::AppHandle,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    println!("🚀 [DEBUG] ===== TAURI COMMAND: start_eeg_processing =====");
+    
+    let processor = processor.inner().clone();
+    let app_handle = app_handle.clone();
+    
+    // A second start replaces the running loop instead of doubling every event
+    let (run, processing_run) = {
+        let processor_guard = processor.lock().await;
+        (processor_guard.begin_processing_run().await, processor_guard.processing_run.clone())
+    };
+    
+    // Fixed-cadence emitter: re-sends the newest display sample on its own timer, holding the last
+    // value through gaps, so the UI gets a steady stream even when samples arrive in bursts
+    let (fixed_emit_hz, latest_display_sample, quiet_mode, limiter) = {
+        let processor_guard = processor.lock().await;
+        (
+            processor_guard.fixed_emit_hz.clone(),
+            processor_guard.latest_display_sample.clone(),
+            processor_guard.quiet_mode.clone(),
+            processor_guard.event_rate.clone(),
+        )
+    };
+    let emit_handle = app_handle.clone();
+    let emitter_run = processing_run.clone();
+    tokio::spawn(async move {
+        let mut current_hz = 0.0f32;
+        let mut ticker = interval(Duration::from_millis(100));
+        
+        loop {
+            ticker.tick().await;
+            if *emitter_run.lock().await != run {
+                break;
+            }
+            
+            let hz = *fixed_emit_hz.lock().await;
+            if hz != current_hz {
+                current_hz = hz;
+                let period = if hz > 0.0 { Duration::from_secs_f32(1.0 / hz) } else { Duration::from_millis(100) };
+                ticker = interval(period);
+                ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+                continue;
+            }
+            if current_hz <= 0.0 || quiet_mode.lock().await.is_some() {
+                continue;
+            }
+            
+            let sample = latest_display_sample.lock().await.clone();
+            if let Some(sample) = sample {
+                if let Err(e) = emit_limited(&emit_handle, &limiter, "filtered_eeg_sample", &sample) {
+                    eprintln!("❌ [DEBUG] Failed to emit fixed-rate filtered EEG sample: {}", e);
+                }
+            }
+        }
+    });
+    
+    // Tauri transport: forwards the engine's frames as eeg_sample, frequency_bands and eeg_frame events
+    let (mut frames, display_gains, band_format, band_thresholds, quiet_mode, sample_batch_size, limiter) = {
+        let processor_guard = processor.lock().await;
+        processor_guard.band_thresholds(&app_handle).await;
+        (
+            processor_guard.subscribe(),
+            processor_guard.display_gains.clone(),
+            processor_guard.band_format.clone(),
+            processor_guard.band_thresholds.clone(),
+            processor_guard.quiet_mode.clone(),
+            processor_guard.sample_batch_size.clone(),
+            processor_guard.event_rate.clone(),
+        )
+    };
+    let emit_handle = app_handle.clone();
+    let forwarder_run = processing_run.clone();
+    tokio::spawn(async move {
+        let mut frame_count = 0u64;
+        let mut batch: Vec<EEGSample> = Vec::new();
+        
+        loop {
+            let frame = match frames.recv().await {
+                Ok(frame) => frame,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    println!("⚠️ [DEBUG] Event forwarder fell behind, {} frames skipped", missed);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if *forwarder_run.lock().await != run {
+                break;
+            }
+            frame_count += 1;
+            if quiet_mode.lock().await.is_some() {
+                continue;
+            }
+            
+            let batch_size = *sample_batch_size.lock().await;
+            // Going back to single events (or a smaller size) first sends what is pending
+            if !batch.is_empty() && batch.len() >= batch_size {
+                let pending = EEGSampleBatch { samples: std::mem::take(&mut batch) };
+                if let Err(e) = emit_limited(&emit_handle, &limiter, "eeg_sample_batch", &pending) {
+                    eprintln!("❌ [DEBUG] Failed to emit EEG sample batch: {}", e);
+                }
+            }
+            
+            // Emit raw EEG sample (every 2nd sample for performance; batches keep every sample)
+            if batch_size > 1 || frame_count.is_multiple_of(2) {
+                let mut display_raw = frame.raw.clone();
+                for (value, gain) in display_raw.channels.iter_mut().zip(display_gains.lock().await.iter()) {
+                    *value *= gain;
+                }
+                if batch_size > 1 {
+                    batch.push(display_raw);
+                    if batch.len() >= batch_size {
+                        let full = EEGSampleBatch { samples: std::mem::take(&mut batch) };
+                        if let Err(e) = emit_limited(&emit_handle, &limiter, "eeg_sample_batch", &full) {
+                            eprintln!("❌ [DEBUG] Failed to emit EEG sample batch: {}", e);
+                        }
+                    }
+                } else if let Err(e) = emit_limited(&emit_handle, &limiter, "eeg_sample", &display_raw) {
+                    eprintln!("❌ [DEBUG] Failed to emit raw EEG sample: {}", e);
+                }
+            }
+            
+            if let Some(bands) = frame.bands {
+                let emitted = match *band_format.lock().await {
+                    BandFormat::PerChannel => emit_limited(&emit_handle, &limiter, "frequency_bands", &bands),
+                    BandFormat::Banded => emit_limited(
+                        &emit_handle,
+                        &limiter,
+                        "frequency_bands",
+                        BandedFrequencyBands::from_bands(frame.raw.timestamp, &bands),
+                    ),
+                };
+                if let Err(e) = emitted {
+                    eprintln!("❌ [DEBUG] Failed to emit frequency bands: {}", e);
+                }
+                
+                let thresholds = band_thresholds.lock().await.clone().unwrap_or_default();
+                let frame = EegFrame { timestamp: frame.raw.timestamp, bands: &bands, thresholds: &thresholds };
+                if let Err(e) = emit_limited(&emit_handle, &limiter, "eeg_frame", &frame) {
+                    eprintln!("❌ [DEBUG] Failed to emit EEG frame: {}", e);
+                }
+            }
+        }
+    });
+    
+    tokio::spawn(async move {
+        println!("🔄 [DEBUG] EEG processing loop started");
+        
+        let mut interval = interval(Duration::from_millis(4)); // 250 Hz = 4ms intervals
+        let mut tick_policy = *processor.lock().await.overload_policy.lock().await;
+        interval.set_missed_tick_behavior(tick_policy.missed_tick_behavior());
+        let start_time = std::time::SystemTime::now();
+        let mut sample_count = 0u64;
+        let mut last_fft_time = 0u64;
+        let mut last_data_log = 0u64;
+        let mut load = 0.0f32;
+        let mut skipped_analyses = 0u64;
+        let mut last_load_emit = 0u64;
+        let mut last_fill_emit = 0u64;
+        let mut last_settle_emit = 0u64;
+        let (mut latency_sum_ms, mut latency_max_ms, mut latency_cycles) = (0.0f32, 0.0f32, 0u64);
+        let (mut jitter_sum_ms, mut jitter_max_ms, mut jitter_ticks) = (0.0f32, 0.0f32, 0u64);
+        let mut buffers_full = false;
+        let mut invalid_rate_warned = false;
+        let mut drain_pending = false;
+        
+        loop {
+            // Samples queued by a gap fill go through in the same tick, not one per tick behind
+            let scheduled = if drain_pending { None } else { Some(interval.tick().await) };
+            let jitter_ms = scheduled.map(|scheduled| scheduled.elapsed().as_secs_f32() * 1000.0);
+            drain_pending = false;
+            if SHUTTING_DOWN.load(Ordering::Relaxed) {
+                println!("👋 [DEBUG] EEG processing loop stopped for shutdown");
+                break;
+            }
+            if *processing_run.lock().await != run {
+                println!("⏹️ [DEBUG] EEG processing loop stopped");
+                break;
+            }
+            let tick_start = std::time::Instant::now();
+            
+            let elapsed = start_time.elapsed().unwrap_or_default();
+            let timestamp = elapsed.as_secs_f64();
+            sample_count += 1;
+            
+            let processor_guard = processor.lock().await;
+            let is_real_connection = processor_guard.is_real_connection().await;
+            // Quiet mode keeps processing, recording and analysis running; only the events go
+            let events = EventSink {
+                app_handle: &app_handle,
+                muted: processor_guard.is_quiet().await,
+                log: Arc::clone(&processor_guard.event_log),
+                limiter: Arc::clone(&processor_guard.event_rate),
+            };
+            
+            let overload_policy = *processor_guard.overload_policy.lock().await;
+            let overloaded = load > 0.9;
+            if overload_policy != tick_policy {
+                interval.set_missed_tick_behavior(overload_policy.missed_tick_behavior());
+                tick_policy = overload_policy;
+            }
+            
+            if is_real_connection {
+                if let Some(mut string_sample) = processor_guard.get_string_sample().await {
+                    if *processor_guard.timestamp_source.lock().await == TimestampSource::Local {
+                        string_sample.timestamp = timestamp;
+                    }
+                    if let Err(e) = events.emit_all("string_sample", &string_sample) {
+                        eprintln!("❌ [DEBUG] Failed to emit string sample: {}", e);
+                    }
+                }
+                
+                // Try to get REAL LSL sample
+                let lsl_sample = processor_guard.get_lsl_sample().await;
+                drain_pending = lsl_sample.is_some() && processor_guard.has_pending_samples().await;
+                if let Some(status) = processor_guard.take_reconnect_status().await {
+                    if let Err(e) = events.emit_all("connection_status", &status) {
+                        eprintln!("❌ [DEBUG] Failed to emit connection status: {}", e);
+                    }
+                }
+                if let Some(status) = processor_guard.take_rate_status().await {
+                    if let Err(e) = events.emit_all("connection_status", &status) {
+                        eprintln!("❌ [DEBUG] Failed to emit connection status: {}", e);
+                    }
+                }
+                if let Some(chain) = processor_guard.take_processing_chain_change().await {
+                    if let Err(e) = events.emit_all("processing_chain", &chain) {
+                        eprintln!("❌ [DEBUG] Failed to emit processing chain: {}", e);
+                    }
+                }
+                if let Some(layout) = processor_guard.take_layout_change().await {
+                    if let Err(e) = events.emit_all("channel_layout", &layout) {
+                        eprintln!("❌ [DEBUG] Failed to emit channel layout: {}", e);
+                    }
+                }
+                if let Some(mut lsl_sample) = lsl_sample {
+                    // Every event derived from this sample reuses its timestamp, so all share one clock
+                    if *processor_guard.timestamp_source.lock().await == TimestampSource::Local {
+                        lsl_sample.timestamp = timestamp;
+                    }
+                    processor_guard.apply_dc_offset(&mut lsl_sample).await;
+                    
+                    // Log data reception periodically
+                    let current_time_ms = (timestamp * 1000.0) as u64;
+                    if current_time_ms - last_data_log >= 5000 { // Every 5 seconds
+                        println!("📊 [DEBUG] Received real LSL sample: {} channels, timestamp: {}", 
+                                lsl_sample.channels.len(), lsl_sample.timestamp);
+                        last_data_log = current_time_ms;
+                    }
+                    
+                    // Apply real-time filters
+                    let mut filtered_sample = processor_guard.apply_real_time_filters(&lsl_sample).await;
+                    processor_guard.apply_polarity(&mut filtered_sample.channels, PolarityStage::AfterFilter).await;
+                    
+                    // While settling the sample has primed the filter state but goes no further
+                    if let Some(remaining) = processor_guard.settle_sample().await {
+                        if remaining == 0 || current_time_ms - last_settle_emit >= 100 {
+                            let total = *processor_guard.settle_samples.lock().await;
+                            if let Err(e) = events.emit_all("settling", SettlingStatus { remaining, total }) {
+                                eprintln!("❌ [DEBUG] Failed to emit settling status: {}", e);
+                            }
+                            last_settle_emit = current_time_ms;
+                        }
+                        continue;
+                    }
+                    
+                    // Update buffers for FFT analysis
+                    processor_guard.update_buffers(&lsl_sample, &filtered_sample).await;
+                    processor_guard.record_sample(&lsl_sample, &filtered_sample).await;
+                    let (blink_progress, blink_template) = processor_guard.update_blinks(lsl_sample.timestamp).await;
+                    if let Some(progress) = blink_progress {
+                        if let Err(e) = events.emit_all("blink_calibration", &progress) {
+                            eprintln!("❌ [DEBUG] Failed to emit blink calibration: {}", e);
+                        }
+                    }
+                    if let Some(template) = blink_template {
+                        if let Err(e) = events.emit_all("blink_template", &template) {
+                            eprintln!("❌ [DEBUG] Failed to emit blink template: {}", e);
+                        }
+                    }
+                    processor_guard.record_heartbeat_sample().await;
+                    processor_guard.record_session_sample().await;
+                    if let Some(trend) = processor_guard.update_variance_trend(&lsl_sample).await {
+                        if let Err(e) = events.emit_all("channel_variance_trend", &trend) {
+                            eprintln!("❌ [DEBUG] Failed to emit channel variance trend: {}", e);
+                        }
+                    }
+                    
+                    // Report warm-up progress every 100 ms until the buffers are full; a reconnect
+                    // empties them again and restarts the reports
+                    let fill = processor_guard.buffer_fill_fraction().await;
+                    if fill < 1.0 {
+                        buffers_full = false;
+                    }
+                    if !buffers_full && (fill >= 1.0 || current_time_ms - last_fill_emit >= 100) {
+                        if let Err(e) = events.emit_all("buffer_fill_progress", BufferFillProgress { fraction: fill }) {
+                            eprintln!("❌ [DEBUG] Failed to emit buffer fill progress: {}", e);
+                        }
+                        buffers_full = fill >= 1.0;
+                        last_fill_emit = current_time_ms;
+                    }
+                    
+                    // Emit filtered EEG sample (every 2nd sample for performance)
+                    let mut display_sample = processor_guard.smooth_for_display(&filtered_sample).await;
+                    if let Some(display_sample) = display_sample.as_mut() {
+                        processor_guard.apply_display_gain(&mut display_sample.channels).await;
+                    }
+                    if display_sample.is_some() {
+                        processor_guard.latest_display_sample.lock().await.clone_from(&display_sample);
+                    }
+                    // Continuous phase output at the display cadence rather than the 250 ms analysis one
+                    let display_tick = sample_count.is_multiple_of(2);
+                    if display_tick {
+                        if let Some(phase) = processor_guard.compute_target_phase(lsl_sample.timestamp).await {
+                            if let Err(e) = events.emit_all("target_phase", &phase) {
+                                eprintln!("❌ [DEBUG] Failed to emit target phase: {}", e);
+                            }
+                        }
+                        if let Some(envelope) = processor_guard.compute_band_envelope(lsl_sample.timestamp).await {
+                            if let Err(e) = events.emit_all("band_envelope", &envelope) {
+                                eprintln!("❌ [DEBUG] Failed to emit band envelope: {}", e);
+                            }
+                        }
                     }
                     
-                    // COMPREHENSIVE MATCHING: Try multiple strategies
-                    println!("🔍 [DEBUG] ===== STREAM MATCHING PROCESS =====");
-                    println!("🔍 [DEBUG] Looking for stream: '{}'", stream_name_clone);
+                    let fixed_rate = *processor_guard.fixed_emit_hz.lock().await > 0.0;
+                    if display_tick && !fixed_rate {
+                        if let Some(display_sample) = &display_sample {
+                            if let Err(e) = events.emit_all("filtered_eeg_sample", display_sample) {
+                                eprintln!("❌ [DEBUG] Failed to emit filtered EEG sample: {}", e);
+                            }
+                        }
+                    }
+                    if display_tick {
+                        if let Some(display_sample) = &display_sample {
+                            if let Some(delta) = processor_guard.encode_delta(display_sample).await {
+                                if let Err(e) = events.emit_all("eeg_sample_delta", &delta) {
+                                    eprintln!("❌ [DEBUG] Failed to emit EEG sample delta: {}", e);
+                                }
+                            }
+                        }
+                    }
                     
-                    let matching_stream = streams.iter().find(|stream| {
-                        let hostname = stream.hostname().to_lowercase();
-                        let source_id = stream.source_id().to_lowercase();
-                        let stream_type = stream.stream_type().to_lowercase();
-                        let uid = stream.uid().to_lowercase();
-                        let target = stream_name_clone.to_lowercase();
-                        
-                        println!("🔍 [DEBUG] Checking stream:");
-                        println!("🔍 [DEBUG]   hostname='{}' vs target='{}'", hostname, target);
-                        println!("🔍 [DEBUG]   source_id='{}' vs target='{}'", source_id, target);
-                        println!("🔍 [DEBUG]   uid='{}' vs target='{}'", uid, target);
-                        
-                        // Multiple matching strategies
-                        let exact_hostname = hostname == target;
-                        let exact_source = source_id == target;
-                        let exact_uid = uid == target;
-                        let contains_hostname = hostname.contains(&target);
-                        let contains_source = source_id.contains(&target);
-                        let unicorn_123 = target == "123" && (hostname == "123" || source_id.contains("unicorn") || uid.contains("unicorn"));
-                        let unicorn_match = target.contains("unicorn") && (hostname.contains("unicorn") || source_id.contains("unicorn"));
-                        
-                        println!("🔍 [DEBUG]   exact_hostname: {}", exact_hostname);
-                        println!("🔍 [DEBUG]   exact_source: {}", exact_source);
-                        println!("🔍 [DEBUG]   exact_uid: {}", exact_uid);
-                        println!("🔍 [DEBUG]   contains_hostname: {}", contains_hostname);
-                        println!("🔍 [DEBUG]   contains_source: {}", contains_source);
-                        println!("🔍 [DEBUG]   unicorn_123: {}", unicorn_123);
-                        println!("🔍 [DEBUG]   unicorn_match: {}", unicorn_match);
-                        
-                        let is_match = exact_hostname || exact_source || exact_uid || contains_hostname || contains_source || unicorn_123 || unicorn_match;
-                        println!("🔍 [DEBUG]   FINAL MATCH RESULT: {}", is_match);
-                        
-                        is_match
-                    });
+                    // Analyze frequency bands every 250ms
+                    let current_time_ms = (timestamp * 1000.0) as u64;
+                    let analysis_period_ms = match overload_policy {
+                        OverloadPolicy::DropAnalysis if overloaded => 1000,
+                        _ => 250,
+                    };
+                    let analysis_due = current_time_ms - last_fft_time >= analysis_period_ms;
+                    let analysis_enabled = *processor_guard.analysis_enabled.lock().await;
+                    let mut frame_bands = None;
                     
-                    if let Some(stream_info) = matching_stream {
-                        println!("✅ [DEBUG] ===== FOUND MATCHING STREAM =====");
-                        println!("✅ [DEBUG] Matched stream: '{}'", stream_info.hostname());
-                        println!("✅ [DEBUG] Stream type: '{}'", stream_info.stream_type());
-                        println!("✅ [DEBUG] Source ID: '{}'", stream_info.source_id());
-                        println!("✅ [DEBUG] Channels: {}", stream_info.channel_count());
-                        println!("✅ [DEBUG] Sample rate: {:.1} Hz", stream_info.nominal_srate());
+                    // Analysis needs a usable rate; warn once per invalid spell instead of emitting garbage
+                    let analysis_rate = processor_guard.analysis_rate().await;
+                    let rate_ok = valid_rate(analysis_rate);
+                    if analysis_due && analysis_enabled && !rate_ok && !invalid_rate_warned {
+                        let warning = format!(
+                            "Sample rate {} Hz is not usable - frequency analysis paused until a valid rate is known",
+                            analysis_rate
+                        );
+                        println!("⚠️ [DEBUG] {}", warning);
+                        if let Err(e) = events.emit_all("stream_warning", &warning) {
+                            eprintln!("❌ [DEBUG] Failed to emit stream warning: {}", e);
+                        }
+                        invalid_rate_warned = true;
+                    }
+                    if rate_ok {
+                        invalid_rate_warned = false;
+                    }
+                    
+                    if analysis_due && (!analysis_enabled || !rate_ok) {
+                        last_fft_time = current_time_ms;
+                    } else if analysis_due && overloaded && overload_policy == OverloadPolicy::DropOldest {
+                        skipped_analyses += 1;
+                        last_fft_time = current_time_ms;
+                    } else if analysis_due {
+                        let bands = processor_guard.analyze_frequency_bands(lsl_sample.timestamp).await;
                         
-                        let channel_count = stream_info.channel_count() as usize;
+                        let muscle_artifact = processor_guard.analyze_muscle_artifact(lsl_sample.timestamp).await;
+                        if let Some(artifact) = &muscle_artifact {
+                            if let Err(e) = events.emit_all("muscle_artifact", artifact) {
+                                eprintln!("❌ [DEBUG] Failed to emit muscle artifact: {}", e);
+                            }
+                        }
                         
-                        // Extract metadata
-                        let stream_type = stream_info.stream_type().to_string();
-                        let source_id = stream_info.source_id().to_string();
+                        if let Some(gate) = processor_guard.evaluate_channel_gate(lsl_sample.timestamp).await {
+                            if let Err(e) = events.emit_all("channel_gate", &gate) {
+                                eprintln!("❌ [DEBUG] Failed to emit channel gate: {}", e);
+                            }
+                        }
                         
-                        // Extract channel names
-                        let channel_names = Self::extract_real_channel_names_sync(stream_info, channel_count);
+                        // Pre-filter counterpart, only at the analysis cadence to bound the extra cost
+                        if let Some(raw_bands) = processor_guard.analyze_raw_frequency_bands(lsl_sample.timestamp).await {
+                            let emitted = match *processor_guard.band_format.lock().await {
+                                BandFormat::PerChannel => events.emit_all("raw_frequency_bands", &raw_bands),
+                                BandFormat::Banded => events.emit_all(
+                                    "raw_frequency_bands",
+                                    BandedFrequencyBands::from_bands(lsl_sample.timestamp, &raw_bands),
+                                ),
+                            };
+                            if let Err(e) = emitted {
+                                eprintln!("❌ [DEBUG] Failed to emit raw frequency bands: {}", e);
+                            }
+                        }
                         
-                        // Extract device info
-                        let (manufacturer, device_model) = Self::extract_device_info_sync(stream_info);
+                        for fired in processor_guard.evaluate_triggers(lsl_sample.timestamp, &bands).await {
+                            if let Err(e) = events.emit_all("trigger_fired", &fired) {
+                                eprintln!("❌ [DEBUG] Failed to emit trigger: {}", e);
+                            }
+                        }
                         
-                        // Create metadata
-                        let metadata = format!(
-                            "🔴 REAL LSL DATA - Type: {} | Source: {} | Channels: {} | Rate: {:.1} Hz | Manufacturer: {} | Model: {}",
-                            stream_type,
-                            source_id,
-                            channel_count,
-                            stream_info.nominal_srate(),
-                            manufacturer,
-                            device_model
-                        );
-
-                        // CRITICAL: Test connection by creating inlet with extended timeout
-                        println!("🔗 [DEBUG] ===== TESTING LSL CONNECTION =====");
-                        println!("🔗 [DEBUG] Creating StreamInlet with 360 buffer, 1 chunk, true recover...");
+                        let calm_score = processor_guard.compute_calm_score(lsl_sample.timestamp, &bands).await;
+                        if let Some(calm_score) = &calm_score {
+                            if let Err(e) = events.emit_all("calm_score", calm_score) {
+                                eprintln!("❌ [DEBUG] Failed to emit calm score: {}", e);
+                            }
+                        }
                         
-                        match StreamInlet::new(stream_info, 360, 1, true) {
-                            Ok(inlet) => {
-                                println!("✅ [DEBUG] StreamInlet created successfully!");
-                                
-                                // Test data pull with longer timeout
-                                println!("📊 [DEBUG] Testing data pull with 2 second timeout...");
-                                match <StreamInlet as Pullable<f32>>::pull_sample(&inlet, 2.0) {
-                                    Ok((sample, timestamp)) => {
-                                        println!("✅ [DEBUG] SUCCESS! Pulled test sample:");
-                                        println!("✅ [DEBUG]   Sample length: {} channels", sample.len());
-                                        println!("✅ [DEBUG]   Timestamp: {}", timestamp);
-                                        println!("✅ [DEBUG]   First few values: {:?}", &sample[..sample.len().min(5)]);
-                                    }
-                                    Err(e) => {
-                                        println!("⚠️ [DEBUG] No immediate data available (this can be normal): {}", e);
-                                        println!("⚠️ [DEBUG] Will proceed anyway as inlet was created successfully");
-                                    }
-                                }
-                                
-                                let info = LSLStreamInfo {
-                                    name: stream_info.hostname().to_string(),
-                                    channel_count: stream_info.channel_count(),
-                                    sample_rate: stream_info.nominal_srate(),
-                                    is_connected: true, // REAL connection
-                                    metadata,
-                                    stream_type,
-                                    source_id,
-                                    channel_names,
-                                    manufacturer,
-                                    device_model,
-                                };
-                                
-                                println!("✅ [DEBUG] ===== LSL CONNECTION SUCCESSFUL =====");
-                                println!("✅ [DEBUG] Stream info created: {:?}", info);
-                                Ok((info, channel_count, true))
+                        if let Some(index) = processor_guard.compute_meditation_index(lsl_sample.timestamp, &bands).await {
+                            if let Err(e) = events.emit_all("meditation_index", &index) {
+                                eprintln!("❌ [DEBUG] Failed to emit meditation index: {}", e);
                             }
-                            Err(e) => {
-                                println!("❌ [DEBUG] ===== FAILED TO CREATE INLET =====");
-                                println!("❌ [DEBUG] Error: {}", e);
-                                println!("❌ [DEBUG] This could mean:");
-                                println!("❌ [DEBUG] 1. Stream exists but is not accessible");
-                                println!("❌ [DEBUG] 2. Another application is using the stream");
-                                println!("❌ [DEBUG] 3. Network connectivity issues");
-                                Err(format!("❌ Failed to create inlet for LSL stream '{}': {}", stream_name_clone, e))
+                        }
+                        
+                        let engagement = processor_guard.compute_engagement_index(lsl_sample.timestamp, &bands).await;
+                        if let Some(engagement) = &engagement {
+                            if let Err(e) = events.emit_all("engagement_index", engagement) {
+                                eprintln!("❌ [DEBUG] Failed to emit engagement index: {}", e);
                             }
                         }
-                    } else {
-                        let available_names: Vec<String> = streams.iter()
-                            .map(|s| format!("'{}' (type: {}, source: {})", 
-                                           s.hostname(), s.stream_type(), s.source_id()))
-                            .collect();
                         
-                        println!("❌ [DEBUG] ===== NO MATCHING STREAM FOUND =====");
-                        println!("❌ [DEBUG] Target: '{}'", stream_name_clone);
-                        println!("❌ [DEBUG] Available streams:");
-                        for (i, name) in available_names.iter().enumerate() {
-                            println!("❌ [DEBUG]   {}: {}", i + 1, name);
+                        let (prompt, result) = processor_guard.update_alpha_blocking(lsl_sample.timestamp, &bands).await;
+                        if let Some(prompt) = prompt {
+                            if let Err(e) = events.emit_all("alpha_blocking_prompt", &prompt) {
+                                eprintln!("❌ [DEBUG] Failed to emit alpha blocking prompt: {}", e);
+                            }
+                        }
+                        if let Some(result) = result {
+                            if let Err(e) = events.emit_all("alpha_blocking_result", &result) {
+                                eprintln!("❌ [DEBUG] Failed to emit alpha blocking result: {}", e);
+                            }
                         }
                         
-                        Err(format!("❌ No LSL stream found with name: '{}'. Available streams: {}", 
-                                   stream_name_clone, 
-                                   available_names.join(", ")))
+                        let erd_ers = processor_guard.evaluate_erd_ers(lsl_sample.timestamp, &bands).await;
+                        if !erd_ers.is_empty() {
+                            if let Err(e) = events.emit_all("erd_ers", &erd_ers) {
+                                eprintln!("❌ [DEBUG] Failed to emit ERD/ERS: {}", e);
+                            }
+                        }
+                        
+                        for warning in processor_guard.check_band_resolution().await {
+                            if let Err(e) = events.emit_all("band_resolution_warning", &warning) {
+                                eprintln!("❌ [DEBUG] Failed to emit band resolution warning: {}", e);
+                            }
+                        }
+                        
+                        for warning in processor_guard.check_band_passband().await {
+                            if let Err(e) = events.emit_all("band_passband_warning", &warning) {
+                                eprintln!("❌ [DEBUG] Failed to emit band passband warning: {}", e);
+                            }
+                        }
+                        
+                        if let Some(redesign) = processor_guard.check_filter_redesign().await {
+                            if let Err(e) = events.emit_all("filter_redesign", &redesign) {
+                                eprintln!("❌ [DEBUG] Failed to emit filter redesign: {}", e);
+                            }
+                        }
+                        
+                        if let Some(notch) = processor_guard.track_mains_frequency().await {
+                            if let Err(e) = events.emit_all("adaptive_notch", &notch) {
+                                eprintln!("❌ [DEBUG] Failed to emit adaptive notch: {}", e);
+                            }
+                        }
+                        
+                        if let Some(difference) = processor_guard.analyze_hemisphere_difference(lsl_sample.timestamp, &bands).await {
+                            if let Err(e) = events.emit_all("hemisphere_difference", &difference) {
+                                eprintln!("❌ [DEBUG] Failed to emit hemisphere difference: {}", e);
+                            }
+                        }
+                        if let Some(regional) = processor_guard.analyze_regional_bands(lsl_sample.timestamp, &bands).await {
+                            if let Err(e) = events.emit_all("regional_bands", &regional) {
+                                eprintln!("❌ [DEBUG] Failed to emit regional bands: {}", e);
+                            }
+                        }
+                        
+                        let snr = processor_guard.last_snr().await;
+                        let averaged = processor_guard.bands_for_averages(&bands, muscle_artifact.as_ref()).await;
+                        processor_guard.record_heartbeat_analysis(&averaged, &snr).await;
+                        processor_guard.record_session_analysis(&averaged).await;
+                        if let Err(e) = events.emit_all("snr", &snr) {
+                            eprintln!("❌ [DEBUG] Failed to emit SNR: {}", e);
+                        }
+                        
+                        let aperiodic = processor_guard.analyze_aperiodic().await;
+                        if let Err(e) = events.emit_all("aperiodic", &aperiodic) {
+                            eprintln!("❌ [DEBUG] Failed to emit aperiodic fit: {}", e);
+                        }
+                        
+                        let entropy = processor_guard.analyze_spectral_entropy().await;
+                        if let Err(e) = events.emit_all("spectral_entropy", &entropy) {
+                            eprintln!("❌ [DEBUG] Failed to emit spectral entropy: {}", e);
+                        }
+                        
+                        let taf = processor_guard.analyze_taf().await;
+                        if let Err(e) = events.emit_all("taf", &taf) {
+                            eprintln!("❌ [DEBUG] Failed to emit transition frequency: {}", e);
+                        }
+                        
+                        let hjorth = processor_guard.analyze_hjorth(lsl_sample.timestamp).await;
+                        if let Err(e) = events.emit_all("hjorth", &hjorth) {
+                            eprintln!("❌ [DEBUG] Failed to emit Hjorth parameters: {}", e);
+                        }
+                        
+                        if let Some(matrix) = processor_guard.analyze_correlation(lsl_sample.timestamp).await {
+                            if let Err(e) = events.emit_all("correlation_matrix", &matrix) {
+                                eprintln!("❌ [DEBUG] Failed to emit correlation matrix: {}", e);
+                            }
+                        }
+                        
+                        if let Some(xcorr) = processor_guard.analyze_xcorr_lag(lsl_sample.timestamp).await {
+                            if let Err(e) = events.emit_all("xcorr_lag", &xcorr) {
+                                eprintln!("❌ [DEBUG] Failed to emit cross-correlation lag: {}", e);
+                            }
+                        }
+                        
+                        let custom_metrics = processor_guard.compute_custom_metrics(lsl_sample.timestamp).await;
+                        if !custom_metrics.is_empty() {
+                            if let Err(e) = events.emit_all("custom_metric", &custom_metrics) {
+                                eprintln!("❌ [DEBUG] Failed to emit custom metrics: {}", e);
+                            }
+                        }
+                        
+                        let excluded_channels = processor_guard.gated_channels.lock().await.clone();
+                        processor_guard.frame_history.lock().await.push(CombinedFrame {
+                            timestamp: lsl_sample.timestamp,
+                            raw: lsl_sample.channels.clone(),
+                            filtered: filtered_sample.channels.clone(),
+                            bands: bands.clone(),
+                            calm_score,
+                            engagement,
+                            snr,
+                            excluded_channels,
+                        });
+                        frame_bands = Some(processor_guard.band_normalization.lock().await.apply(lsl_sample.timestamp, &bands));
+                        last_fft_time = current_time_ms;
                     }
+                    
+                    processor_guard.publish(ProcessedFrame {
+                        raw: lsl_sample,
+                        filtered: filtered_sample,
+                        bands: frame_bands,
+                    });
+                    
+                    let cycle_ms = tick_start.elapsed().as_secs_f32() * 1000.0;
+                    latency_sum_ms += cycle_ms;
+                    latency_max_ms = latency_max_ms.max(cycle_ms);
+                    latency_cycles += 1;
+                } else {
+                    // No real data available - this is normal, just continue
+                    // Don't log this as it would spam the console
                 }
-                Err(e) => {
-                    println!("❌ [DEBUG] ===== LSL STREAM RESOLUTION FAILED =====");
-                    println!("❌ [DEBUG] Error: {}", e);
-                    println!("❌ [DEBUG] This usually means:");
-                    println!("❌ [DEBUG] 1. No LSL applications are running");
-                    println!("❌ [DEBUG] 2. LSL library is not properly installed");
-                    println!("❌ [DEBUG] 3. Network/firewall blocking LSL multicast");
-                    println!("❌ [DEBUG] 4. UnicornLSL.exe is not started");
-                    Err(format!("❌ Failed to resolve LSL streams: {}. Make sure UnicornLSL.exe is running and broadcasting stream '{}'.", e, stream_name_clone))
+            } else {
+                // No real connection - should not happen if we reach this point
+                println!("⚠️ [DEBUG] EEG processing running but no real connection available");
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            
+            if let Some(heartbeat) = processor_guard.take_heartbeat(timestamp).await {
+                if let Err(e) = events.emit_unmuted("heartbeat", &heartbeat) {
+                    eprintln!("❌ [DEBUG] Failed to emit heartbeat: {}", e);
                 }
             }
-        }).await;
+            
+            // Sent even in quiet mode: it ends the session
+            if let Some(summary) = processor_guard.take_session_complete(timestamp).await {
+                if let Err(e) = events.emit_unmuted("session_complete", &summary) {
+                    eprintln!("❌ [DEBUG] Failed to emit session summary: {}", e);
+                }
+                processor_guard.stop_processing().await;
+                println!("⏱️ [DEBUG] Timed session complete after {:.1} s, processing stopped", summary.duration_s);
+                break;
+            }
+            
+            drop(processor_guard);
+            
+            if is_real_connection {
+                let busy = tick_start.elapsed().as_secs_f32() / 0.004;
+                load = 0.95 * load + 0.05 * busy;
+                if let Some(jitter_ms) = jitter_ms {
+                    jitter_sum_ms += jitter_ms;
+                    jitter_max_ms = jitter_max_ms.max(jitter_ms);
+                    jitter_ticks += 1;
+                }
+                
+                let current_time_ms = (timestamp * 1000.0) as u64;
+                if current_time_ms - last_load_emit >= 1000 {
+                    let report = ProcessingLoad { load, overloaded: load > 0.9, policy: overload_policy, skipped_analyses };
+                    if let Err(e) = events.emit_all("processing_load", &report) {
+                        eprintln!("❌ [DEBUG] Failed to emit processing load: {}", e);
+                    }
+                    
+                    if latency_cycles > 0 {
+                        let latency = ProcessingLatency {
+                            average: latency_sum_ms / latency_cycles as f32,
+                            max: latency_max_ms,
+                            cycles: latency_cycles,
+                            overloaded: report.overloaded,
+                            policy: overload_policy,
+                        };
+                        if let Err(e) = events.emit_all("processing_latency_ms", &latency) {
+                            eprintln!("❌ [DEBUG] Failed to emit processing latency: {}", e);
+                        }
+                        (latency_sum_ms, latency_max_ms, latency_cycles) = (0.0, 0.0, 0);
+                    }
+                    
+                    let jitter = LoopJitter {
+                        mean_ms: jitter_sum_ms / jitter_ticks.max(1) as f32,
+                        max_ms: jitter_max_ms,
+                        ticks: jitter_ticks,
+                    };
+                    if let Err(e) = events.emit_all("loop_jitter", &jitter) {
+                        eprintln!("❌ [DEBUG] Failed to emit loop jitter: {}", e);
+                    }
+                    if let Err(e) = events.report_dropped_events() {
+                        eprintln!("❌ [DEBUG] Failed to emit dropped event counts: {}", e);
+                    }
+                    (jitter_sum_ms, jitter_max_ms, jitter_ticks) = (0.0, 0.0, 0);
+                    last_load_emit = current_time_ms;
+                }
+            }
+        }
+    });
+    
+    println!("✅ [DEBUG] Tauri command: start_eeg_processing completed (background task started)");
+    Ok(())
+}
+
+#[tauri::command]
+fn validate_montage(names: Vec<String>) -> Vec<MontageIssue> {
+    check_montage(&names)
+}
+
+#[tauri::command]
+async fn load_montage(
+    names: Vec<String>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<String>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.load_montage(names).await
+}
+
+#[tauri::command]
+async fn get_topomap_layout(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<Vec<TopoPoint>, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.get_topomap_layout().await
+}
+
+#[tauri::command]
+async fn set_channel_groups(
+    groups: HashMap<String, Vec<usize>>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_channel_groups(groups).await
+}
+
+// Channels feeding calm_score and meditation_index; an empty list restores the default
+// (parietal and occipital channels when the montage names them, otherwise all)
+#[tauri::command]
+async fn set_score_channels(
+    indices: Vec<usize>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_score_channels(indices).await
+}
+
+#[tauri::command]
+async fn capture_debug_snapshot(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<String, String> {
+    println!("🐞 [DEBUG] Tauri command: capture_debug_snapshot called");
+    
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.capture_debug_snapshot().await
+}
 
-        println!("🔄 [DEBUG] ===== PROCESSING BLOCKING TASK RESULT =====");
-        match result {
-            Ok(Ok((info, channel_count, is_real))) => {
-                println!("✅ [DEBUG] Blocking task succeeded, updating processor state...");
-                
-                // Update connection state
-                let mut connection = self.lsl_connection.lock().await;
-                connection.stream_info = Some(info.clone());
-                connection.channel_count = channel_count;
-                connection.is_real_connection = is_real;
-                connection.stream_name = Some(stream_name.to_string());
-                
-                // Update buffers
-                *self.channel_buffers.lock().await = vec![Vec::new(); channel_count];
-                *self.filtered_buffers.lock().await = vec![Vec::new(); channel_count];
-                
-                // Initialize filters for real-time processing
-                *self.bandpass_filter.lock().await = Some(ButterworthFilter::new(4, channel_count));
-                *self.notch_filter.lock().await = Some(NotchFilter::new(channel_count));
-                
-                println!("✅ [DEBUG] EEG processor state updated successfully");
-                println!("✅ [DEBUG] ===== LSL CONNECTION COMPLETE =====");
-                Ok(info)
-            }
-            Ok(Err(e)) => {
-                println!("❌ [DEBUG] Blocking task returned error: {}", e);
-                Err(e)
+#[tauri::command]
+fn get_task_stats() -> TaskStats {
+    task_stats()
+}
+
+#[tauri::command]
+fn set_lsl_task_grace(seconds: f64) -> Result<(), String> {
+    if !(0.5..=60.0).contains(&seconds) {
+        return Err(format!("❌ Invalid LSL task grace {} s: must be between 0.5 and 60 seconds", seconds));
+    }
+    
+    LSL_TASK_GRACE_MS.store((seconds * 1000.0) as u64, Ordering::Relaxed);
+    println!("⏳ [DEBUG] LSL tasks are abandoned {} s after their own timeouts", seconds);
+    Ok(())
+}
+
+// Starts processing like start_eeg_processing and stops it after `seconds` with a
+// `session_complete` summary; with_quote adds a meditation quote to it
+#[tauri::command]
+async fn start_timed_session(
+    seconds: f64,
+    with_quote: Option<bool>,
+    app_handle: tauri::AppHandle,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    {
+        let processor_guard = processor.inner().lock().await;
+        processor_guard.set_timed_session(seconds, with_quote.unwrap_or(false)).await?;
+    }
+    
+    start_eeg_processing(app_handle, processor).await
+}
+
+// Ends the processing loop (and a timed session, without session_complete); the connection stays open
+#[tauri::command]
+async fn stop_eeg_processing(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.stop_processing().await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_band_crossing_threshold(
+    band: String,
+    threshold: Option<f32>,
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<(), String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    processor_guard.set_band_crossing_threshold(band, threshold).await
+}
+
+#[tauri::command]
+async fn get_session_stats(
+    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
+) -> Result<SessionStats, String> {
+    let processor = processor.inner().clone();
+    let processor_guard = processor.lock().await;
+    
+    Ok(processor_guard.get_session_stats().await)
+}
+
+// Each quote with the meditation tier it is picked for
+const MEDITATION_QUOTES: &[(MeditationTier, &str)] = &[
+    (MeditationTier::Steady, "The mind is everything. What you think you become. - Buddha"),
+    (MeditationTier::Settling, "Peace comes from within. Do not seek it without. - Buddha"),
+    (MeditationTier::Steady, "Meditation is not evasion; it is a serene encounter with reality. - Thich Nhat Hanh"),
+    (MeditationTier::Deep, "In the depth of winter, I finally learned that there was in me an invincible summer. - Albert Camus"),
+    (MeditationTier::Settling, "Breathe in peace, breathe out stress. - Anonymous"),
+    (MeditationTier::Steady, "The present moment is the only time over which we have dominion. - Thich Nhat Hanh"),
+    (MeditationTier::Settling, "Your calm mind is the ultimate weapon against your challenges. - Bryant McGill"),
+    (MeditationTier::Deep, "Meditation is a way for nourishing and blossoming the divinity within you. - Amit Ray"),
+];
+
+// A random quote of the tier; None draws from all of them
+fn meditation_quote(tier: Option<MeditationTier>) -> String {
+    let quotes: Vec<&str> = MEDITATION_QUOTES.iter()
+        .filter(|(quote_tier, _)| tier.is_none_or(|tier| *quote_tier == tier))
+        .map(|(_, quote)| *quote)
+        .collect();
+    with_rng(|rng| quotes.choose(rng).copied().unwrap_or(MEDITATION_QUOTES[0].1).to_string())
+}
+
+#[tauri::command]
+fn get_meditation_quote() -> String {
+    meditation_quote(None)
+}
+
+// Some(seed) restarts the shared RNG from that seed; None returns to entropy (the default)
+#[tauri::command]
+fn set_rng_seed(seed: Option<u64>) {
+    println!("🎲 [DEBUG] RNG {}", seed.map_or("seeded from entropy".to_string(), |s| format!("seed set to {}", s)));
+    *SEEDED_RNG.lock().unwrap_or_else(std::sync::PoisonError::into_inner) = seed.map(rand::rngs::StdRng::seed_from_u64);
+}
+
+fn main() {
+    println!("🚀 [DEBUG] ===== STARTING TAURI APPLICATION =====");
+    println!("🚀 [DEBUG] Initializing EEG processor...");
+    
+    let processor = Arc::new(Mutex::new(EEGProcessor::new()));
+    
+    println!("🚀 [DEBUG] Building Tauri app with invoke handlers...");
+    tauri::Builder::default()
+        .manage(processor)
+        .setup(|app| {
+            let app_handle = app.handle();
+            let enabled = load_startup_settings(&app_handle).map_or(true, |settings| settings.check_lsl_environment);
+            if enabled {
+                tauri::async_runtime::spawn(async move {
+                    let report = run_lsl_environment_check().await;
+                    *STARTUP_LSL_REPORT.lock().unwrap_or_else(std::sync::PoisonError::into_inner) = Some(report.clone());
+                    if let Err(e) = app_handle.emit_all("lsl_environment", &report) {
+                        eprintln!("❌ [DEBUG] Failed to emit LSL environment report: {}", e);
+                    }
+                });
             }
-            Err(e) => {
-                println!("❌ [DEBUG] Blocking task execution failed: {}", e);
-                Err(format!("❌ Task execution failed: {}", e))
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            connect_to_lsl_stream,
+            disconnect_from_lsl,
+            connect_to_csv_stream,
+            connect_by_address,
+            set_auto_connect,
+            auto_connect_last,
+            check_lsl_environment,
+            set_startup_lsl_check,
+            get_startup_lsl_report,
+            get_current_stream_info,
+            get_detected_device,
+            ping_stream,
+            probe_stream,
+            set_match_field,
+            set_max_streams,
+            set_delta_encoding,
+            set_analysis_enabled,
+            measure_now,
+            set_max_reconnect_interval,
+            set_rate_smoothing,
+            set_timestamp_guard,
+            get_timestamp_stats,
+            start_eeg_processing,
+            start_timed_session,
+            stop_eeg_processing,
+            set_band_crossing_threshold,
+            get_session_stats,
+            set_channel_filter,
+            set_channel_signal_type,
+            set_notch_q,
+            set_mains_method,
+            get_mains_canceller_status,
+            set_median_despike,
+            set_adaptive_filter_redesign,
+            set_adaptive_notch,
+            get_adaptive_notch,
+            set_settle_samples,
+            set_gap_interpolation,
+            set_timestamp_source,
+            set_emit_lsl_timestamp,
+            start_websocket_server,
+            stop_websocket_server,
+            reset_config,
+            save_preset,
+            load_preset,
+            list_presets,
+            delete_preset,
+            list_active_events,
+            set_fixed_emit_hz,
+            set_emit_processing_chain,
+            set_sample_batch_size,
+            set_quiet_mode,
+            set_pull_timeout,
+            set_pull_mode,
+            set_reconnect_to_any_match,
+            set_auto_expand_channels,
+            set_display_gain,
+            get_display_gains,
+            auto_normalize_display,
+            set_channel_scale,
+            set_channel_polarity,
+            set_polarity_stage,
+            set_channel_order,
+            set_eeg_channel_count,
+            set_band_format,
+            set_artifact_domain,
+            set_gamma_preemphasis,
+            set_multiresolution,
+            suggest_fft_size,
+            set_min_band_bins,
+            set_passband_only,
+            set_buffer_size,
+            set_zero_pad_factor,
+            set_whitening,
+            set_dc_exclusion_bins,
+            set_analysis_method,
+            set_analysis_source,
+            set_auto_gain,
+            set_band_time_constant,
+            set_overload_policy,
+            load_band_profile,
+            set_channel_band_profile,
+            list_band_profiles,
+            get_active_band_profile,
+            set_savgol,
+            measure_dc_offset,
+            clear_dc_offset,
+            enable_builtin_metric,
+            add_trigger,
+            remove_trigger,
+            mark_event,
+            inject_test_marker,
+            set_auto_markers,
+            compute_itpc,
+            set_erd_windows,
+            set_band_thresholds,
+            get_band_thresholds,
+            set_score_weights,
+            calibrate_calm_score,
+            set_meditation_index_weights,
+            calibrate_meditation_index,
+            set_band_normalization,
+            calibrate_band_baseline,
+            run_alpha_blocking_test,
+            calibrate_blinks,
+            set_blink_correction,
+            set_replay_duration,
+            get_dual_snapshot,
+            dump_replay_buffer,
+            render_spectrogram_png,
+            set_session_metadata,
+            set_event_logging,
+            get_event_log,
+            set_max_event_rate,
+            start_band_logging,
+            stop_band_logging,
+            start_recording,
+            stop_recording,
+            compare_sessions,
+            get_filter_coefficients,
+            get_impulse_responses,
+            get_channel_snr,
+            get_channel_ranges,
+            get_phase,
+            set_aperiodic_range,
+            set_entropy_range,
+            get_dominant_frequency,
+            measure_thd,
+            get_ssvep_response,
+            set_muscle_artifact,
+            set_gate_bad_channels,
+            set_frame_history,
+            get_frame_at,
+            set_variance_trend,
+            set_xcorr_pair,
+            set_individualized_bands,
+            set_engagement_mode,
+            set_emit_raw_spectrum,
+            get_aperiodic,
+            set_target_phase,
+            set_envelope_band,
+            run_filter_self_test,
+            run_end_to_end_test,
+            get_task_stats,
+            set_lsl_task_grace,
+            capture_debug_snapshot,
+            validate_montage,
+            load_montage,
+            get_topomap_layout,
+            set_channel_groups,
+            set_score_channels,
+            get_meditation_quote,
+            set_rng_seed
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|_app_handle, event| {
+            if matches!(event, tauri::RunEvent::ExitRequested { .. } | tauri::RunEvent::Exit) {
+                begin_shutdown();
             }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_pair(timestamp: f64, channels: Vec<f32>) -> (EEGSample, FilteredEEGSample) {
+        let sample = EEGSample { timestamp, sample_index: 0, channels: channels.clone(), interpolated: false, lsl_timestamp: None };
+        let filtered = FilteredEEGSample { timestamp, sample_index: 0, channels, interpolated: false, lsl_timestamp: None };
+        (sample, filtered)
+    }
+
+    fn stream_info(names: &[&str], sample_rate: f64) -> LSLStreamInfo {
+        LSLStreamInfo {
+            name: "test".to_string(),
+            channel_count: names.len() as i32,
+            sample_rate,
+            is_connected: true,
+            metadata: String::new(),
+            stream_type: "EEG".to_string(),
+            source_id: String::new(),
+            channel_names: names.iter().map(|name| name.to_string()).collect(),
+            manufacturer: String::new(),
+            device_model: String::new(),
+            channel_format: "float32".to_string(),
+            units: vec![String::new(); names.len()],
         }
     }
 
-    fn extract_real_channel_names_sync(stream_info: &StreamInfo, channel_count: usize) -> Vec<String> {
-        println!("🔍 [DEBUG] Extracting channel names from LSL stream...");
-        
-        let mut channel_names = Vec::new();
+    #[tokio::test]
+    async fn channel_buffers_follow_the_channel_count() {
+        let processor = EEGProcessor::new();
+        let names = ["Fp1", "Fp2", "C3", "C4", "P3", "P4", "O1", "O2"];
+        processor.apply_connection(&stream_info(&names[..4], 250.0), 4, true, "test", None).await;
+        let (sample, filtered) = sample_pair(0.0, vec![1.0; 4]);
+        processor.update_buffers(&sample, &filtered).await;
+        assert_eq!(processor.channel_buffers.lock().await.len(), 4);
+        assert!(processor.filtered_buffers.lock().await.iter().all(|buffer| buffer.len() == 1));
         
-        // Try to detect device type and use known layouts
-        let source_id = stream_info.source_id().to_lowercase();
-        let stream_name = stream_info.hostname().to_lowercase();
+        // A reconnect to a wider stream starts with empty buffers of the new count
+        processor.apply_connection(&stream_info(&names, 250.0), 8, true, "test", None).await;
+        assert_eq!(processor.channel_buffers.lock().await.len(), 8);
+        assert_eq!(processor.filtered_buffers.lock().await.len(), 8);
+        assert!(processor.channel_buffers.lock().await.iter().all(Vec::is_empty));
         
-        println!("🔍 [DEBUG] Device detection - Source ID: '{}', Stream Name: '{}'", source_id, stream_name);
+        // Disconnecting goes back to the default count
+        processor.apply_connection(&stream_info(&names[..2], 250.0), 2, true, "test", None).await;
+        processor.disconnect_lsl().await;
+        assert_eq!(processor.channel_buffers.lock().await.len(), 8);
+        assert_eq!(processor.filtered_buffers.lock().await.len(), 8);
         
-        // Unicorn Hybrid Black specific channel layout
-        if source_id.contains("unicorn") || stream_name.contains("unicorn") || stream_name == "123" {
-            println!("🦄 [DEBUG] Detected Unicorn Hybrid Black device");
-            let unicorn_channels = vec![
-                "Fz", "C3", "Cz", "C4", "Pz", "PO7", "Oz", "PO8",
-                "ACC_X", "ACC_Y", "ACC_Z", "GYR_X", "GYR_Y", "GYR_Z", 
-                "Battery", "Counter", "Validation"
-            ];
-            
-            for i in 0..channel_count.min(unicorn_channels.len()) {
-                channel_names.push(unicorn_channels[i].to_string());
+        // A sample wider than the buffers grows them instead of being cut off
+        let (sample, filtered) = sample_pair(0.004, vec![2.0; 10]);
+        processor.update_buffers(&sample, &filtered).await;
+        assert_eq!(processor.channel_buffers.lock().await.len(), 10);
+        assert_eq!(processor.filtered_buffers.lock().await[9], vec![2.0]);
+    }
+
+    // Output/input RMS of a sinusoid once the filter has settled
+    fn steady_gain(mut process: impl FnMut(f32) -> f32, frequency: f64, sample_rate: f64) -> f64 {
+        let n = (sample_rate * 20.0) as usize;
+        let (mut input, mut output) = (0.0, 0.0);
+        for i in 0..n {
+            let x = (2.0 * std::f64::consts::PI * frequency * i as f64 / sample_rate).sin() as f32;
+            let y = process(x);
+            if i >= n / 2 {
+                input += (x as f64).powi(2);
+                output += (y as f64).powi(2);
             }
         }
-        // OpenBCI detection
-        else if source_id.contains("openbci") || stream_name.contains("openbci") {
-            println!("🧠 [DEBUG] Detected OpenBCI device");
-            let openbci_8ch = vec!["Fp1", "Fp2", "C3", "C4", "P7", "P8", "O1", "O2"];
-            let openbci_16ch = vec![
-                "Fp1", "Fp2", "F7", "F3", "F4", "F8", "C3", "Cz", 
-                "C4", "T7", "T8", "P7", "P3", "Pz", "P4", "P8"
-            ];
-            
-            let channels = if channel_count <= 8 { &openbci_8ch } else { &openbci_16ch };
-            for i in 0..channel_count.min(channels.len()) {
-                channel_names.push(channels[i].to_string());
-            }
+        (output / input).sqrt()
+    }
+
+    #[test]
+    fn higher_notch_q_narrows_the_stopband() {
+        let gain = |q: f64, frequency: f64| {
+            let mut notch = NotchFilter::new(50.0, q, 250.0, 1);
+            steady_gain(|x| notch.process_sample(0, x), frequency, 250.0)
+        };
+        for q in [5.0, 30.0] {
+            assert!(gain(q, 50.0) < 0.05, "Q {} leaves mains at {}", q, gain(q, 50.0));
         }
-        // Emotiv detection
-        else if source_id.contains("emotiv") || stream_name.contains("emotiv") {
-            println!("🎭 [DEBUG] Detected Emotiv device");
-            let emotiv_channels = vec![
-                "AF3", "F7", "F3", "FC5", "T7", "P7", "O1", "O2", 
-                "P8", "T8", "FC6", "F4", "F8", "AF4"
-            ];
-            
-            for i in 0..channel_count.min(emotiv_channels.len()) {
-                channel_names.push(emotiv_channels[i].to_string());
-            }
+        // 3 Hz off mains is inside the Q=5 stopband (10 Hz wide, gain 3 / sqrt(3² + 5²) ≈ 0.51)
+        // but outside the Q=30 one (1.7 Hz)
+        for frequency in [47.0, 53.0] {
+            assert!(gain(5.0, frequency) < 0.6);
+            assert!(gain(30.0, frequency) > 0.9);
         }
-        // Generic fallback
-        else {
-            println!("❓ [DEBUG] Unknown device, using generic channel names");
-            for i in 0..channel_count {
-                channel_names.push(format!("Ch{}", i + 1));
-            }
+        // The -3 dB points lie frequency / (2Q) either side of mains
+        for frequency in [45.0, 55.0] {
+            assert!((gain(5.0, frequency) - std::f64::consts::FRAC_1_SQRT_2).abs() < 0.1);
         }
+    }
+
+    #[test]
+    fn unstable_filter_designs_are_rejected() {
+        assert_eq!(unstable_pole(&[Complex::new(0.5, 0.5), Complex::new(1.0, 0.0)]), Some(1.0));
+        assert_eq!(unstable_pole(&[Complex::new(0.0, 1.2)]), Some(1.2));
+        assert_eq!(unstable_pole(&[Complex::new(f64::NAN, 0.0)]), Some(f64::INFINITY));
+        assert_eq!(unstable_pole(&[Complex::new(0.9, 0.0), Complex::new(-0.3, 0.4)]), None);
         
-        // Ensure we have the right number of channels
-        while channel_names.len() < channel_count {
-            channel_names.push(format!("Ch{}", channel_names.len() + 1));
+        // The default 1-40 Hz Butterworth at 250 Hz is stable
+        let config = ChannelFilterConfig::default();
+        assert!(unstable_pole(&ButterworthFilter::bandpass_poles(4, config.highpass, config.lowpass, 250.0)).is_none());
+        assert!(config.validate(250.0).is_ok());
+        
+        // A highpass squeezed against 0 Hz is in range but rounds its poles onto the unit circle
+        let squeezed = ChannelFilterConfig { highpass: 1e-15, ..ChannelFilterConfig::default() };
+        let error = squeezed.validate(250.0).unwrap_err();
+        assert!(error.contains("Unstable bandpass"), "{}", error);
+    }
+
+    fn write_fixture(name: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!("tamara_test_{}_{}.csv", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+    
+    fn read_fixture(name: &str, contents: &str) -> Recording {
+        let path = write_fixture(name, contents);
+        let recording = read_recording(&path);
+        std::fs::remove_file(&path).unwrap();
+        recording.unwrap()
+    }
+    
+    #[test]
+    fn csv_recordings_read_in_every_export_format() {
+        let plain = "timestamp,Fp1,Fp2\n0.000,1.0,2.0\n0.004,3.0,4.0\n0.008,5.0,6.0\n";
+        let recording = read_fixture("plain", plain);
+        assert_eq!(recording.names, vec!["Fp1", "Fp2"]);
+        assert_eq!(recording.channels, vec![vec![1.0, 3.0, 5.0], vec![2.0, 4.0, 6.0]]);
+        assert!((recording.rate.unwrap() - 250.0).abs() < 0.01);
+        assert_eq!(recording.skipped_rows, 0);
+        
+        let variants = [
+            ("bom", "\u{feff}timestamp,Fp1,Fp2\n0.000,1.0,2.0\n0.004,3.0,4.0\n0.008,5.0,6.0\n".to_string()),
+            ("semicolon", "timestamp;Fp1;Fp2\n0.000;1.0;2.0\n0.004;3.0;4.0\n0.008;5.0;6.0\n".to_string()),
+            ("crlf", plain.replace('\n', "\r\n")),
+            ("quoted", "\"timestamp\",\"Fp1\",\"Fp2\"\n0.000,\"1.0\",2.0\n0.004,3.0,4.0\n0.008,5.0,\"6.0\"\n".to_string()),
+        ];
+        for (name, contents) in variants {
+            let variant = read_fixture(name, &contents);
+            assert_eq!(variant.names, recording.names, "{}", name);
+            assert_eq!(variant.channels, recording.channels, "{}", name);
+            assert_eq!(variant.rate, recording.rate, "{}", name);
         }
+    }
+    
+    #[test]
+    fn csv_recording_metadata_and_damaged_rows() {
+        let contents = "# recording_started: 2024-01-01T00:00:00Z\n# note without a value\n# config: {\"filter_rate\": 500.0}\n\n\
+                        Fp1,Fp2\n1.0,2.0\n1.5\n3.0,oops\n5.0,6.0\n";
+        let recording = read_fixture("metadata", contents);
+        assert_eq!(recording.metadata.len(), 2);
+        assert_eq!(recording.metadata[0], ("recording_started".to_string(), "2024-01-01T00:00:00Z".to_string()));
+        assert_eq!(recording.header_rate(), Some(500.0));
         
-        // Truncate if we have too many
-        channel_names.truncate(channel_count);
+        // No timestamp column: every column is a channel and the rate comes from the header
+        assert_eq!(recording.names, vec!["Fp1", "Fp2"]);
+        assert_eq!(recording.rate, None);
+        assert_eq!(recording.channels, vec![vec![1.0, 5.0], vec![2.0, 6.0]]);
+        assert_eq!(recording.skipped_rows, 2);
+    }
+    
+    #[test]
+    fn csv_header_detection() {
+        let layout = CsvLayout::parse("\u{feff}\"Time\"; \"C3\" ;C4").unwrap();
+        assert!(layout.timestamp_column);
+        assert_eq!(layout.delimiter, ';');
+        assert_eq!(layout.header, vec!["Time", "C3", "C4"]);
+        assert_eq!(layout.parse_row("1.5;\"2,5\";3", &[1, 2]), Err("invalid value '2,5'".to_string()));
+        assert_eq!(layout.parse_row("1.5;2;3", &[1, 2]), Ok((Some(1.5), vec![2.0, 3.0])));
         
-        println!("✅ [DEBUG] Final channel names: {:?}", channel_names);
-        channel_names
+        let dump = CsvLayout::parse("timestamp,raw_Fp1,filtered_Fp1").unwrap();
+        assert_eq!(dump.channels("filtered_"), (vec![2], vec!["Fp1".to_string()]));
+        assert!(CsvLayout::parse("timestamp").is_err());
+        assert!(CsvLayout::parse("Fp1,,Fp2").is_err());
     }
 
-    fn extract_device_info_sync(stream_info: &StreamInfo) -> (String, String) {
-        let source_id = stream_info.source_id().to_lowercase();
-        let stream_name = stream_info.hostname().to_lowercase();
+    fn raw_sample(timestamp: f64, channels: Vec<f32>) -> EEGSample {
+        EEGSample { timestamp, sample_index: 0, channels, interpolated: false, lsl_timestamp: None }
+    }
+    
+    #[tokio::test]
+    async fn gaps_are_interpolated_up_to_the_limit() {
+        let previous = raw_sample(1.0, vec![0.0, 10.0]);
         
-        // Detect device type from source ID or stream name
-        if source_id.contains("unicorn") || stream_name.contains("unicorn") || stream_name == "123" {
-            ("g.tec medical engineering GmbH".to_string(), "Unicorn Hybrid Black".to_string())
-        } else if source_id.contains("openbci") || stream_name.contains("openbci") {
-            ("OpenBCI".to_string(), "Cyton Board".to_string())
-        } else if source_id.contains("emotiv") || stream_name.contains("emotiv") {
-            ("Emotiv Inc.".to_string(), "EPOC+".to_string())
-        } else if source_id.contains("neurosky") || stream_name.contains("neurosky") {
-            ("NeuroSky".to_string(), "MindWave".to_string())
-        } else if source_id.contains("muse") || stream_name.contains("muse") {
-            ("InteraXon".to_string(), "Muse Headband".to_string())
-        } else {
-            ("Unknown Manufacturer".to_string(), "EEG Device".to_string())
+        // Three missing samples at 100 Hz: evenly spaced on the line between the two ends
+        let filled = EEGProcessor::fill_gap(&previous, &raw_sample(1.04, vec![4.0, 6.0]), 100.0, 5);
+        assert_eq!(filled.len(), 3);
+        for (k, sample) in filled.iter().enumerate() {
+            let t = (k + 1) as f32 / 4.0;
+            assert!((sample.timestamp - (1.0 + 0.01 * (k + 1) as f64)).abs() < 1e-9);
+            assert!((sample.channels[0] - 4.0 * t).abs() < 1e-5);
+            assert!((sample.channels[1] - (10.0 - 4.0 * t)).abs() < 1e-5);
+            assert!(sample.interpolated);
         }
+        
+        // Consecutive samples, a gap past the limit and a channel count change are left alone
+        assert!(EEGProcessor::fill_gap(&previous, &raw_sample(1.01, vec![1.0, 9.0]), 100.0, 5).is_empty());
+        assert!(EEGProcessor::fill_gap(&previous, &raw_sample(1.07, vec![1.0, 9.0]), 100.0, 5).is_empty());
+        assert_eq!(EEGProcessor::fill_gap(&previous, &raw_sample(1.06, vec![1.0, 9.0]), 100.0, 5).len(), 5);
+        assert!(EEGProcessor::fill_gap(&previous, &raw_sample(1.04, vec![1.0]), 100.0, 5).is_empty());
+        assert!(EEGProcessor::fill_gap(&previous, &raw_sample(1.04, vec![1.0, 9.0]), 100.0, 0).is_empty());
+        
+        let processor = EEGProcessor::new();
+        assert!(processor.set_gap_interpolation(MAX_GAP_INTERPOLATION).await.is_ok());
+        assert!(processor.set_gap_interpolation(MAX_GAP_INTERPOLATION + 1).await.is_err());
     }
 
-    async fn disconnect_lsl(&self) {
-        println!("🔌 [DEBUG] Disconnecting from LSL stream");
-        let mut connection = self.lsl_connection.lock().await;
-        connection.stream_info = None;
-        connection.channel_count = 8;
-        connection.is_real_connection = false;
-        connection.stream_name = None;
+    #[tokio::test]
+    async fn a_filled_gap_keeps_the_pulled_sample_count() {
+        let processor = EEGProcessor::new();
+        processor.lsl_connection.lock().await.channel_count = 2;
+        processor.set_gap_interpolation(5).await.unwrap();
         
-        *self.bandpass_filter.lock().await = None;
-        *self.notch_filter.lock().await = None;
-        println!("✅ [DEBUG] LSL disconnection complete");
+        // Two samples lost at 250 Hz between the first and second pull
+        let first = processor.accept_sample(raw_sample(1.0, vec![0.0, 0.0]), None, false).await.unwrap();
+        assert_eq!(first.sample_index, 0);
+        let next = processor.accept_sample(raw_sample(1.012, vec![3.0, 3.0]), None, false).await.unwrap();
+        assert!(next.interpolated);
+        assert!(processor.has_pending_samples().await);
+        
+        let mut samples = vec![next];
+        samples.extend(processor.lsl_connection.lock().await.pending_samples.drain(..));
+        assert!(!processor.has_pending_samples().await);
+        let summary: Vec<(u64, bool, f32)> = samples.iter().map(|s| (s.sample_index, s.interpolated, s.channels[0])).collect();
+        assert_eq!(summary, vec![(0, true, 1.0), (0, true, 2.0), (1, false, 3.0)]);
+        
+        // The next pull continues the count of pulled samples
+        let after = processor.accept_sample(raw_sample(1.016, vec![4.0, 4.0]), None, false).await.unwrap();
+        assert_eq!((after.sample_index, after.interpolated), (2, false));
     }
 
-    // Create new inlet each time to avoid threading issues
-    async fn get_lsl_sample(&self) -> Option<EEGSample> {
-        let connection = self.lsl_connection.lock().await;
+    #[test]
+    fn mains_canceller_removes_the_interference_once_converged() {
+        let rate = 250.0;
+        let mut canceller = MainsCanceller::new(50.0, rate);
+        let mains = |n: usize| 20.0 * (2.0 * std::f64::consts::PI * 50.0 * n as f64 / rate + 0.7).sin();
+        let alpha = |n: usize| 5.0 * (2.0 * std::f64::consts::PI * 10.0 * n as f64 / rate).sin();
         
-        // Only try to get real data if we have a real connection
-        if !connection.is_real_connection {
-            return None;
-        }
+        let residual: Vec<f64> = (0..5000)
+            .map(|n| canceller.process((mains(n) + alpha(n)) as f32) as f64 - alpha(n))
+            .collect();
+        assert!(canceller.converged());
+        assert!((canceller.amplitude() - 20.0).abs() < 1.0, "amplitude {}", canceller.amplitude());
         
-        let stream_name = connection.stream_name.clone()?;
-        let channel_count = connection.channel_count;
-        drop(connection); // Release lock before blocking operation
+        // The interference (200 µV² mean power) is down by more than 30 dB; the 10 Hz rhythm stays
+        let power = |samples: &[f64]| samples.iter().map(|x| x * x).sum::<f64>() / samples.len() as f64;
+        let before = power(&(0..1000).map(mains).collect::<Vec<f64>>());
+        let after = power(&residual[4000..]);
+        assert!(after < before * 1e-3, "residual power {} against {}", after, before);
+        assert!(power(&residual[..50]) > after);
+    }
+
+    fn tone(frequency: f64, amplitude: f64, rate: f64, len: usize) -> Vec<f32> {
+        (0..len).map(|n| (amplitude * (2.0 * std::f64::consts::PI * frequency * n as f64 / rate).sin()) as f32).collect()
+    }
+    
+    #[tokio::test]
+    async fn adaptive_notch_follows_drifting_mains() {
+        let processor = EEGProcessor::new();
+        assert!(processor.track_mains_frequency().await.is_none());
+        processor.set_adaptive_notch(true).await.unwrap();
         
-        // Use blocking task for LSL operations - create fresh inlet each time
-        let result = tokio::task::spawn_blocking(move || {
-            match resolve_streams(0.1) {
-                Ok(streams) => {
-                    let matching_stream = streams.iter()
-                        .find(|stream| {
-                            let hostname = stream.hostname().to_lowercase();
-                            let source_id = stream.source_id().to_lowercase();
-                            let target = stream_name.to_lowercase();
-                            
-                            hostname == target || 
-                            source_id.contains(&target) || 
-                            hostname.contains(&target) ||
-                            (target == "123" && (hostname == "123" || source_id.contains("unicorn")))
-                        });
-                    
-                    if let Some(stream_info) = matching_stream {
-                        match StreamInlet::new(stream_info, 360, 1, true) {
-                            Ok(inlet) => {
-                                // Pull sample with very short timeout
-                                match <StreamInlet as Pullable<f32>>::pull_sample(&inlet, 0.001) {
-                                    Ok((sample, timestamp)) => {
-                                        let mut channels = vec![0.0f32; channel_count];
-                                        for (i, &value) in sample.iter().enumerate().take(channel_count) {
-                                            channels[i] = value;
-                                        }
-                                        
-                                        Some(EEGSample {
-                                            timestamp,
-                                            channels,
-                                        })
-                                    }
-                                    Err(_) => None, // No data available right now
-                                }
-                            }
-                            Err(_) => None,
-                        }
-                    } else {
-                        None
-                    }
-                }
-                Err(_) => None,
-            }
-        }).await;
+        // Mains drifts from 50 to 51 Hz over 20 analysis cycles, then stays there
+        let mut retunes = 0;
+        for cycle in 0..40 {
+            let frequency = 50.0 + (cycle as f64 / 20.0).min(1.0);
+            *processor.channel_buffers.lock().await = vec![tone(frequency, 20.0, 250.0, processor.buffer_size); 2];
+            retunes += usize::from(processor.track_mains_frequency().await.is_some());
+        }
+        let notch = processor.get_adaptive_notch().await.unwrap();
+        assert_eq!(notch.nominal, 50.0);
+        assert!((notch.tracked - 51.0).abs() < 0.05, "tracked {} Hz", notch.tracked);
+        assert!((notch.frequency - 51.0).abs() < ADAPTIVE_NOTCH_STEP_HZ + 0.05, "notch at {} Hz", notch.frequency);
+        assert!(retunes >= 10, "{} retunes", retunes);
         
-        result.unwrap_or(None)
+        // Without a peak above the floor the estimate holds where it was
+        *processor.channel_buffers.lock().await = vec![vec![0.0; processor.buffer_size]; 2];
+        assert!(processor.track_mains_frequency().await.is_none());
+        assert_eq!(processor.get_adaptive_notch().await.unwrap().tracked, notch.tracked);
     }
 
-    async fn apply_real_time_filters(&self, sample: &EEGSample) -> FilteredEEGSample {
-        let mut bandpass_guard = self.bandpass_filter.lock().await;
-        let mut notch_guard = self.notch_filter.lock().await;
+    #[test]
+    fn median_filter_removes_spikes_but_keeps_edges() {
+        let mut filter = MedianFilter::new(5);
+        let mut input = [0.0f32; 40];
+        input[10] = 500.0;
+        input[20..].fill(1.0);
+        let output: Vec<f32> = input.iter().map(|&x| filter.process(x)).collect();
         
-        if let (Some(bandpass), Some(notch)) = (bandpass_guard.as_mut(), notch_guard.as_mut()) {
-            // Apply bandpass filter (1-40 Hz)
-            let bandpass_output = bandpass.process(&sample.channels);
-            
-            // Apply notch filter (50 Hz)
-            let notch_output = notch.process(&bandpass_output);
-            
-            // Artifact removal - clip extreme values (>300 µV)
-            let mut filtered_channels = notch_output;
-            for channel_data in filtered_channels.iter_mut() {
-                if channel_data.abs() > 300.0 {
-                    *channel_data = channel_data.signum() * 300.0;
-                }
-            }
-            
-            FilteredEEGSample {
-                timestamp: sample.timestamp,
-                channels: filtered_channels,
-            }
-        } else {
-            // Fallback: simple filtering if filters not initialized
-            let mut filtered_channels = sample.channels.clone();
-            for channel_data in filtered_channels.iter_mut() {
-                if channel_data.abs() > 300.0 {
-                    *channel_data = channel_data.signum() * 300.0;
-                }
-                *channel_data *= 0.95; // Simple high-pass
-            }
-            
-            FilteredEEGSample {
-                timestamp: sample.timestamp,
-                channels: filtered_channels,
-            }
+        // The spike is gone; the step stays a clean step, two samples (half the window) late
+        assert!(output[..22].iter().all(|&y| y == 0.0), "{:?}", &output[..22]);
+        assert!(output[22..].iter().all(|&y| y == 1.0), "{:?}", &output[22..]);
+        
+        assert!(MedianFilter::validate_len(1).is_ok());
+        assert!(MedianFilter::validate_len(MAX_MEDIAN_DESPIKE).is_ok());
+        assert!(MedianFilter::validate_len(4).is_err());
+        assert!(MedianFilter::validate_len(0).is_err());
+    }
+
+    #[test]
+    fn median_filter_keeps_a_sine_through_spikes() {
+        let (rate, amplitude) = (250.0, 20.0);
+        let mut input = tone(10.0, amplitude, rate, 500);
+        for n in (7..input.len()).step_by(50) {
+            input[n] += 500.0;
+        }
+        let mut filter = MedianFilter::new(5);
+        let output: Vec<f32> = input.iter().map(|&x| filter.process(x)).collect();
+        
+        // Every spike is gone and the sine, two samples late, loses no more than 5% at its crests
+        let delayed = tone(10.0, amplitude, rate, 498);
+        for (n, (&y, &x)) in output[50..].iter().zip(&delayed[48..]).enumerate() {
+            assert!((y - x).abs() < 0.05 * amplitude as f32, "sample {}: {} vs {}", n + 50, y, x);
         }
+        let peak = output[50..].iter().fold(0.0f32, |m, &y| m.max(y.abs()));
+        assert!(peak > 0.95 * amplitude as f32 && peak <= amplitude as f32, "peak {}", peak);
     }
 
-    async fn update_buffers(&self, sample: &EEGSample, filtered_sample: &FilteredEEGSample) {
-        let mut raw_buffers = self.channel_buffers.lock().await;
-        let mut filtered_buffers = self.filtered_buffers.lock().await;
+    #[tokio::test]
+    async fn thd_of_a_sine_with_a_known_third_harmonic() {
+        let processor = EEGProcessor::new();
+        let (rate, size) = (processor.sample_rate as f64, processor.buffer_size);
+        // On a bin centre, so each Hann peak lies entirely within ±THD_PEAK_BINS
+        let fundamental = 20.0 * rate / size as f64;
+        let distorted: Vec<f32> = tone(fundamental, 10.0, rate, size).iter()
+            .zip(tone(3.0 * fundamental, 2.0, rate, size))
+            .map(|(a, b)| a + b)
+            .collect();
+        *processor.channel_buffers.lock().await = vec![
+            distorted,
+            tone(fundamental, 10.0, rate, size),
+            vec![0.0; size],
+            vec![0.0; 10], // window not filled yet
+        ];
         
-        for (i, (&raw_value, &filtered_value)) in sample.channels.iter()
-            .zip(filtered_sample.channels.iter()).enumerate() {
-            
-            if i < raw_buffers.len() {
-                raw_buffers[i].push(raw_value);
-                if raw_buffers[i].len() > self.buffer_size {
-                    raw_buffers[i].remove(0);
-                }
-            }
-            
-            if i < filtered_buffers.len() {
-                filtered_buffers[i].push(filtered_value);
-                if filtered_buffers[i].len() > self.buffer_size {
-                    filtered_buffers[i].remove(0);
-                }
-            }
-        }
+        // (2 / 10)² of the power sits in the harmonic
+        let thd = processor.measure_thd(fundamental as f32).await.unwrap();
+        assert!((thd[0] - 0.04).abs() < 1e-4, "THD {}", thd[0]);
+        assert!(thd[1] < 1e-6, "THD {}", thd[1]);
+        assert!(thd[2].is_nan() && thd[3].is_nan());
+        
+        assert!(processor.measure_thd(1.0).await.is_err());
+        assert!(processor.measure_thd(70.0).await.is_err());
     }
 
-    async fn analyze_frequency_bands(&self, timestamp: f64) -> Vec<FrequencyBands> {
-        let buffers = self.filtered_buffers.lock().await;
-        let mut results = Vec::new();
+    // Deterministic white noise in [-1, 1)
+    fn noise(len: usize, seed: u64) -> Vec<f64> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (state >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn xcorr_peak_finds_the_lag_and_sign_of_a_delayed_copy() {
+        let a = noise(600, 1);
+        let delayed: Vec<f64> = (0..500).map(|t| a[t + 100 - 7]).collect();
+        let a = &a[100..];
         
-        for (channel_idx, buffer) in buffers.iter().enumerate() {
-            if buffer.len() < self.buffer_size {
-                continue;
-            }
-            
-            // Perform FFT
-            let mut planner = FftPlanner::new();
-            let fft = planner.plan_fft_forward(self.buffer_size);
-            
-            let mut buffer_complex: Vec<Complex<f32>> = buffer
-                .iter()
-                .map(|&x| Complex::new(x, 0.0))
-                .collect();
-            
-            fft.process(&mut buffer_complex);
-            
-            // Calculate power in frequency bands
-            let freq_resolution = self.sample_rate / self.buffer_size as f32;
-            let mut alpha_power = 0.0;
-            let mut beta_power = 0.0;
-            let mut theta_power = 0.0;
-            let mut delta_power = 0.0;
-            let mut gamma_power = 0.0;
-            
-            for (i, complex) in buffer_complex.iter().enumerate() {
-                let freq = i as f32 * freq_resolution;
-                let power = complex.norm_sqr();
-                
-                match freq {
-                    f if f >= 0.5 && f < 4.0 => delta_power += power,
-                    f if f >= 4.0 && f < 8.0 => theta_power += power,
-                    f if f >= 8.0 && f < 12.0 => alpha_power += power,
-                    f if f >= 13.0 && f < 30.0 => beta_power += power,
-                    f if f >= 30.0 && f < 100.0 => gamma_power += power,
-                    _ => {}
-                }
+        let (lag, peak) = xcorr_peak(a, &delayed, 20).unwrap();
+        assert_eq!(lag, 7);
+        assert!(peak > 0.9, "peak {}", peak);
+        
+        let inverted: Vec<f64> = a.iter().map(|v| -v).collect();
+        let (lag, peak) = xcorr_peak(&inverted, &delayed, 20).unwrap();
+        assert_eq!(lag, 7);
+        assert!(peak < -0.9, "peak {}", peak);
+        
+        // b leading a is a negative lag; one outside the search window isn't found
+        let (lag, _) = xcorr_peak(&delayed, a, 20).unwrap();
+        assert_eq!(lag, -7);
+        assert_ne!(xcorr_peak(a, &delayed, 5).unwrap().0, 7);
+        assert!(xcorr_peak(a, &[3.0; 500], 20).is_none());
+    }
+
+    #[test]
+    fn ssvep_peak_against_its_neighbours() {
+        let mut power = vec![1.0f32; 40];
+        power[20] = 30.0;
+        let (peak, snr) = ssvep_peak(&power, 20);
+        assert_eq!(peak, 32.0);
+        assert!((snr - 32.0 / 3.0).abs() < 1e-5);
+        assert!(ssvep_peak(&[0.0; 40], 20).1.is_nan());
+    }
+    
+    #[tokio::test]
+    async fn ssvep_response_peaks_at_the_stimulation_frequency() {
+        let processor = EEGProcessor::new();
+        let (rate, size) = (processor.sample_rate as f64, processor.buffer_size);
+        let resolution = rate / size as f64;
+        let (stimulus, other) = (24.0 * resolution, 31.0 * resolution);
+        let window: Vec<f32> = tone(stimulus, 5.0, rate, size).iter()
+            .zip(noise(size, 3))
+            .map(|(s, n)| s + 0.5 * n as f32)
+            .collect();
+        *processor.channel_buffers.lock().await = vec![window];
+        
+        let responses = processor.get_ssvep_response(&[stimulus as f32, other as f32, 50.0], None).await.unwrap();
+        let [target, off, high] = &responses[0].responses[..] else { panic!("{:?}", responses) };
+        assert!(target.harmonics[0].power > 100.0 * off.harmonics[0].power);
+        assert!(target.harmonics[0].snr > 100.0, "SNR {}", target.harmonics[0].snr);
+        assert!(off.harmonics[0].snr < 10.0, "SNR {}", off.harmonics[0].snr);
+        assert_eq!(target.harmonics.len(), DEFAULT_SSVEP_HARMONICS);
+        // 150 Hz is past Nyquist
+        assert_eq!(high.harmonics.iter().map(|h| h.harmonic).collect::<Vec<_>>(), vec![1, 2]);
+        
+        assert!(processor.get_ssvep_response(&[], None).await.is_err());
+        assert!(processor.get_ssvep_response(&[200.0], None).await.is_err());
+        assert!(processor.get_ssvep_response(&[12.0], Some(MAX_SSVEP_HARMONICS + 1)).await.is_err());
+    }
+
+    fn two_pass_variance(values: &[f64]) -> f64 {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (values.len() - 1) as f64
+    }
+    
+    #[test]
+    fn variance_trend_matches_the_two_pass_variance() {
+        // A large offset is where a naive sum-of-squares variance loses its digits
+        let values: Vec<f64> = noise(700, 5).iter().map(|n| 1000.0 + 3.0 * n).collect();
+        let mut whole = Welford::default();
+        let (mut first, mut second) = (Welford::default(), Welford::default());
+        for (i, &v) in values.iter().enumerate() {
+            whole.push(v);
+            if i < 250 { first.push(v) } else { second.push(v) }
+        }
+        let expected = two_pass_variance(&values);
+        assert!((whole.variance() - expected).abs() < 1e-9 * expected);
+        assert!((first.merge(&second).variance() - expected).abs() < 1e-9 * expected);
+        assert!(Welford::default().variance().is_nan());
+        
+        // 100 Hz into a 3 s window: the report at 6 s covers the three blocks before it
+        let mut trend = VarianceTrend::new(3.0);
+        let mut last = None;
+        for (i, &v) in values[..601].iter().enumerate() {
+            if let Some(report) = trend.push(i as f64 / 100.0, &[v as f32, 2.0 * v as f32]) {
+                last = Some((i, report));
             }
-            
-            results.push(FrequencyBands {
-                timestamp,
-                channel: channel_idx,
-                alpha: alpha_power.sqrt(),
-                beta: beta_power.sqrt(),
-                theta: theta_power.sqrt(),
-                delta: delta_power.sqrt(),
-                gamma: gamma_power.sqrt(),
-            });
         }
+        let (i, report) = last.unwrap();
+        assert_eq!(i, 600);
+        assert_eq!(report.window_s, 3.0);
+        assert_eq!(report.samples, 300);
+        let window: Vec<f64> = values[300..600].iter().map(|&v| v as f32 as f64).collect();
+        let expected = two_pass_variance(&window);
+        assert!((report.variance[0] as f64 - expected).abs() < 1e-3 * expected, "{} vs {}", report.variance[0], expected);
+        assert!((report.variance[1] as f64 - 4.0 * expected).abs() < 4e-3 * expected);
+    }
+
+    #[test]
+    fn hjorth_parameters_of_a_pure_sine() {
+        // 10 Hz at 250 Hz, 20 whole periods. For A·sin(ωn) the first difference is a sinusoid of
+        // amplitude 2A·sin(ω/2), so mobility is 2·sin(ω/2) per sample and complexity is 1.
+        let omega = 2.0 * std::f64::consts::PI * 10.0 / 250.0;
+        let (activity, mobility, complexity) = hjorth_parameters(&tone(10.0, 4.0, 250.0, 500));
+        assert!((activity - 8.0).abs() < 1e-3, "activity {}", activity);
+        assert!((mobility - 2.0 * (omega / 2.0).sin()).abs() < 1e-3, "mobility {}", mobility);
+        assert!((complexity - 1.0).abs() < 1e-2, "complexity {}", complexity);
         
-        results
+        // Twice the frequency, twice the mobility (nearly: sin(ω) / sin(ω/2) = 2·cos(ω/2))
+        let (_, doubled, _) = hjorth_parameters(&tone(20.0, 4.0, 250.0, 500));
+        assert!((doubled / mobility - 2.0 * (omega / 2.0).cos()).abs() < 1e-2);
+        assert_eq!(hjorth_parameters(&[3.0; 100]), (0.0, 0.0, 0.0));
     }
 
-    async fn get_stream_info(&self) -> Option<LSLStreamInfo> {
-        let connection = self.lsl_connection.lock().await;
-        connection.stream_info.clone()
+    #[test]
+    fn whitening_flattens_a_one_over_f_spectrum() {
+        let mut spectrum: Vec<f32> = (0..257).map(|i| 1.0 / (i as f32).max(1.0)).collect();
+        spectrum[100] *= 10.0;
+        EEGProcessor::whiten(&mut spectrum, 4);
+        
+        // Away from the edges and the peak every bin is within a few percent of 1 (from 12:1)
+        let flat = spectrum[20..240].iter().enumerate().filter(|&(i, _)| (i + 20).abs_diff(100) > 4).map(|(_, &p)| p);
+        let (min, max) = flat.fold((f32::INFINITY, 0.0f32), |(min, max), p| (min.min(p), max.max(p)));
+        assert!(min > 0.97 && max < 1.03, "whitened range {}..{}", min, max);
+        // The oscillatory peak stands out of the flattened background: 10 / ((8 + 10) / 9)
+        assert!((spectrum[100] - 5.0).abs() < 0.05, "peak {}", spectrum[100]);
+        
+        let mut silent = vec![0.0f32; 64];
+        EEGProcessor::whiten(&mut silent, 4);
+        assert!(silent.iter().all(|&p| p == 0.0));
+    }
+    
+    #[test]
+    fn whitening_makes_an_alpha_peak_more_prominent() {
+        // 512 bins at 250 Hz: a 10 Hz bump on a 1/f background, which still leaves more power per
+        // bin in the neighbouring theta band than in alpha
+        let resolution = 250.0 / 512.0;
+        let mut spectrum: Vec<f32> = (0..257)
+            .map(|i| {
+                let f = (i as f32 * resolution).max(resolution);
+                (1.0 + 0.8 * (-(f - 10.0).powi(2)).exp()) / f
+            })
+            .collect();
+        let mean = |spectrum: &[f32], low: f32, high: f32| {
+            let bins: Vec<f32> = (0..spectrum.len())
+                .filter(|&i| (low..high).contains(&(i as f32 * resolution)))
+                .map(|i| spectrum[i])
+                .collect();
+            bins.iter().sum::<f32>() / bins.len() as f32
+        };
+        let prominence = |spectrum: &[f32]| mean(spectrum, 8.0, 13.0) / mean(spectrum, 4.0, 8.0);
+        
+        let before = prominence(&spectrum);
+        EEGProcessor::whiten(&mut spectrum, 8);
+        let after = prominence(&spectrum);
+        assert!(before < 0.8, "alpha to theta {} before whitening", before);
+        assert!(after > 1.3, "alpha to theta {} after whitening", after);
     }
 
-    async fn is_real_connection(&self) -> bool {
-        let connection = self.lsl_connection.lock().await;
-        connection.is_real_connection
+    #[tokio::test]
+    async fn snr_of_a_tone_in_white_noise() {
+        let processor = EEGProcessor::new();
+        let (rate, size) = (processor.sample_rate as f64, processor.buffer_size);
+        let resolution = rate / size as f64;
+        // White noise puts the same expected power, N·σ², in each of the 256 bins above DC; 79 of
+        // them lie in 1-40 Hz. A bin-centred tone of amplitude A adds (N·A/2)² to one in-band bin.
+        let (amplitude, scale) = (10.0, 8.0);
+        let variance = scale * scale / 3.0; // uniform in [-scale, scale)
+        let window = |seed: u64, amplitude: f64| -> Vec<f32> {
+            tone(20.0 * resolution, amplitude, rate, size).iter()
+                .zip(noise(size, seed))
+                .map(|(s, n)| s + (scale * n) as f32)
+                .collect()
+        };
+        // A single noise draw scatters by about a dB, so average four of each
+        let mut buffers: Vec<Vec<f32>> = (0..8).map(|ch| window(ch + 1, if ch < 4 { amplitude } else { 0.0 })).collect();
+        buffers.push(vec![0.0; 100]);
+        *processor.filtered_buffers.lock().await = buffers;
+        processor.analyze_frequency_bands(0.0).await;
+        
+        let snr = processor.last_snr().await;
+        assert_eq!(snr.iter().map(|s| s.channel).collect::<Vec<_>>(), (0..8).collect::<Vec<_>>());
+        let mean_db = |channels: &[ChannelSnr]| channels.iter().map(|s| s.db as f64).sum::<f64>() / channels.len() as f64;
+        let n = size as f64;
+        let expected = |tone_power: f64| 10.0 * ((tone_power + 79.0 * n * variance) / (177.0 * n * variance)).log10();
+        let (with_tone, noise_only) = (expected((n * amplitude / 2.0).powi(2)), expected(0.0));
+        assert!((mean_db(&snr[..4]) - with_tone).abs() < 0.75, "SNR {} dB, expected {:.2}", mean_db(&snr[..4]), with_tone);
+        assert!((mean_db(&snr[4..]) - noise_only).abs() < 0.75, "SNR {} dB, expected {:.2}", mean_db(&snr[4..]), noise_only);
     }
-}
 
-#[tauri::command]
-async fn connect_to_lsl_stream(
-    stream_name: String,
-    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
-) -> Result<LSLStreamInfo, String> {
-    println!("🚀 [DEBUG] ===== TAURI COMMAND: connect_to_lsl_stream =====");
-    println!("🚀 [DEBUG] Called with stream_name: '{}'", stream_name);
-    println!("🚀 [DEBUG] Thread: {:?}", std::thread::current().id());
-    
-    let processor = processor.inner().clone();
-    let processor_guard = processor.lock().await;
+    // Real window whose spectrum follows `magnitude(bin)`, bins 1..N/2. Neighbouring bins
+    // alternate in sign, so the Hann taper's ±1-bin smoothing keeps a smooth spectrum smooth
+    // instead of cancelling it.
+    fn shaped_window(size: usize, magnitude: impl Fn(usize) -> f64) -> Vec<f32> {
+        (0..size)
+            .map(|n| {
+                (1..size / 2)
+                    .map(|bin| {
+                        let phase = 2.0 * std::f64::consts::PI * (bin * n) as f64 / size as f64;
+                        magnitude(bin) * (phase + std::f64::consts::PI * bin as f64).cos()
+                    })
+                    .sum::<f64>() as f32
+            })
+            .collect()
+    }
     
-    println!("🔒 [DEBUG] Acquired processor lock, calling connect_to_lsl...");
-    let result = processor_guard.connect_to_lsl(&stream_name).await;
+    #[tokio::test]
+    async fn aperiodic_fit_recovers_the_exponent() {
+        let processor = EEGProcessor::new();
+        let size = processor.buffer_size;
+        // Power ∝ 1/f^k: amplitude ∝ f^(-k/2)
+        *processor.channel_buffers.lock().await = [1.0, 2.0]
+            .map(|k: f64| shaped_window(size, |bin| 100.0 * (bin as f64).powf(-k / 2.0)))
+            .to_vec();
+        
+        let fits = processor.analyze_aperiodic().await;
+        assert_eq!(fits.len(), 2);
+        assert!((fits[0].slope + 1.0).abs() < 0.05, "slope {}", fits[0].slope);
+        assert!((fits[1].slope + 2.0).abs() < 0.05, "slope {}", fits[1].slope);
+        
+        assert!(processor.set_aperiodic_range(0.0, 40.0).await.is_err());
+        assert!(processor.set_aperiodic_range(30.0, 20.0).await.is_err());
+        // A range narrower than two bins has nothing to fit
+        processor.set_aperiodic_range(10.0, 10.1).await.unwrap();
+        assert!(processor.analyze_aperiodic().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn spectral_entropy_spans_flat_to_single_bin() {
+        assert_eq!(normalized_entropy(&[2.0; 64]), Some(1.0));
+        let mut single = vec![0.0; 64];
+        single[10] = 5.0;
+        assert_eq!(normalized_entropy(&single), Some(0.0));
+        assert_eq!(normalized_entropy(&[1.0]), None);
+        
+        // Through the spectrum: white-like is near 1, a pure tone far below it
+        let processor = EEGProcessor::new();
+        let (rate, size) = (processor.sample_rate as f64, processor.buffer_size);
+        *processor.channel_buffers.lock().await = vec![
+            shaped_window(size, |_| 1.0),
+            tone(20.0 * rate / size as f64, 10.0, rate, size),
+        ];
+        let entropy = processor.analyze_spectral_entropy().await;
+        assert!(entropy[0].value > 0.99, "flat {}", entropy[0].value);
+        assert!(entropy[1].value < 0.25, "tone {}", entropy[1].value);
+        
+        assert!(processor.set_entropy_range(-1.0, 40.0).await.is_err());
+        assert!(processor.set_entropy_range(40.0, 40.0).await.is_err());
+        assert!(processor.set_entropy_range(1.0, f32::INFINITY).await.is_err());
+        assert!(processor.set_entropy_range(0.0, 20.0).await.is_ok());
+    }
+
+    fn channel_bands(channel: usize, values: &[(&str, f32)]) -> FrequencyBands {
+        FrequencyBands {
+            timestamp: 0.0,
+            channel,
+            bands: values.iter().map(|&(name, value)| (name.to_string(), value)).collect(),
+            unavailable: Vec::new(),
+        }
+    }
     
-    match &result {
-        Ok(info) => {
-            println!("✅ [DEBUG] ===== TAURI COMMAND SUCCESS =====");
-            println!("✅ [DEBUG] Stream name: {}", info.name);
-            println!("✅ [DEBUG] Channels: {}", info.channel_count);
-            println!("✅ [DEBUG] Sample rate: {}", info.sample_rate);
-            println!("✅ [DEBUG] Is connected: {}", info.is_connected);
-            println!("✅ [DEBUG] Metadata: {}", info.metadata);
+    #[tokio::test]
+    async fn compressed_band_log_decodes_to_the_plain_log() {
+        use std::io::Read;
+        let processor = EEGProcessor::new();
+        let cycle = [
+            channel_bands(0, &[("delta", 1.0), ("theta", 2.0), ("alpha", 3.0), ("beta", 4.0), ("gamma", 0.5)]),
+            channel_bands(1, &[("delta", 1.5), ("theta", 3.0), ("alpha", 2.0), ("beta", 0.0)]),
+        ];
+        let write_log = |name: &str, compressed: bool| {
+            let path = std::env::temp_dir().join(format!("tamara_test_{}_{}.csv", std::process::id(), name));
+            let path = path.to_string_lossy().into_owned();
+            let processor = &processor;
+            let cycle = &cycle;
+            async move {
+                processor.start_band_logging(&path, compressed).await.unwrap();
+                processor.log_bands(cycle).await;
+                processor.band_log.lock().await.as_mut().unwrap().writer.flush().unwrap();
+                processor.log_bands(cycle).await;
+                assert_eq!(processor.stop_band_logging().await, Ok(4));
+                let path = if compressed { format!("{}.gz", path) } else { path };
+                let bytes = std::fs::read(&path).unwrap();
+                std::fs::remove_file(&path).unwrap();
+                bytes
+            }
+        };
+        
+        let plain = String::from_utf8(write_log("band_log_plain", false).await).unwrap();
+        let compressed = write_log("band_log_gzip", true).await;
+        assert_eq!(&compressed[..2], &[0x1f, 0x8b]);
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(compressed.as_slice()).read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, plain);
+        
+        let rows: Vec<&str> = plain.lines().filter(|line| !line.starts_with('#')).collect();
+        assert_eq!(rows, vec![
+            "timestamp,channel,delta,theta,alpha,beta,gamma,theta_beta_ratio",
+            "0.000000,0,1,2,3,4,0.5,0.5",
+            "0.000000,1,1.5,3,2,0,,",
+            "0.000000,0,1,2,3,4,0.5,0.5",
+            "0.000000,1,1.5,3,2,0,,",
+        ]);
+    }
+
+    #[tokio::test]
+    async fn engagement_index_is_beta_over_alpha_plus_theta() {
+        let bands = [
+            channel_bands(0, &[("beta", 6.0), ("alpha", 1.0), ("theta", 2.0)]),
+            channel_bands(1, &[("beta", 3.0), ("alpha", 4.0), ("theta", 2.0), ("gamma", 9.0)]),
+            channel_bands(2, &[("beta", 2.0), ("alpha", 0.0), ("theta", 0.0)]),
+            channel_bands(3, &[("beta", 2.0), ("alpha", 1.0)]),
+        ];
+        assert_eq!(engagement_index(&bands[0]), Some(2.0));
+        assert_eq!(engagement_index(&bands[1]), Some(0.5));
+        // A zero denominator is floored instead of dividing by zero
+        assert_eq!(engagement_index(&bands[2]), Some(2.0 / ENGAGEMENT_POWER_FLOOR));
+        assert_eq!(engagement_index(&bands[3]), None);
+        
+        let processor = EEGProcessor::new();
+        let index = processor.compute_engagement_index(1.0, &bands[..2]).await.unwrap();
+        let values: Vec<(usize, f32)> = index.channels.unwrap().iter().map(|c| (c.channel, c.value)).collect();
+        assert_eq!(values, vec![(0, 2.0), (1, 0.5)]);
+        assert_eq!(index.average, None);
+        
+        processor.set_engagement_mode("AVERAGE".parse().unwrap()).await;
+        let index = processor.compute_engagement_index(1.0, &bands[..2]).await.unwrap();
+        assert_eq!((index.channels.is_none(), index.average), (true, Some(1.25)));
+        assert!(processor.compute_engagement_index(1.0, &bands[3..]).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn dc_exclusion_keeps_the_lowest_bins_out_of_band_power() {
+        let processor = EEGProcessor::new();
+        let (rate, size) = (processor.sample_rate, processor.buffer_size);
+        let resolution = rate as f64 / size as f64;
+        // A 10 µV offset, a unit tone on bin 2 (delta) and one on bin 20 (alpha). Unwindowed and
+        // bin-centred, each tone is N/2 in its bin and the offset N·10 in bin 0.
+        let window: Vec<f32> = tone(2.0 * resolution, 1.0, rate as f64, size).iter()
+            .zip(tone(20.0 * resolution, 1.0, rate as f64, size))
+            .map(|(a, b)| 10.0 + a + b)
+            .collect();
+        let profile = BandProfile::from_json(r#"{"delta": [0, 4], "alpha": [8, 13]}"#).unwrap();
+        let fft = processor.band_fft_plan.as_ref();
+        let bands = |excluded_bins: usize| {
+            let options = BandOptions { excluded_bins, ..BandOptions::default() };
+            EEGProcessor::compute_channel_bands(fft, &window, &mut fft.make_scratch_vec(), resolution as f32, &profile, options).0
+        };
+        
+        let n = size as f32;
+        let close = |a: f32, b: f32| (a - b).abs() <= 1e-3 * b.max(1.0);
+        let with_dc = bands(0);
+        assert!(close(with_dc["delta"], (100.0 * n * n + n * n / 4.0).sqrt()), "{:?}", with_dc);
+        let without_dc = bands(1);
+        assert!(close(without_dc["delta"], n / 2.0), "{:?}", without_dc);
+        assert!(bands(3)["delta"] < 1e-2 * n, "{:?}", bands(3));
+        for excluded in 0..=3 {
+            assert!(close(bands(excluded)["alpha"], n / 2.0));
+            assert_eq!(EEGProcessor::band_bin_count(0.0, 4.0, rate, size, excluded), 9 - excluded);
         }
-        Err(e) => {
-            println!("❌ [DEBUG] ===== TAURI COMMAND FAILED =====");
-            println!("❌ [DEBUG] Error: {}", e);
+        
+        assert!(processor.set_dc_exclusion_bins(MAX_DC_EXCLUSION_BINS).await.is_ok());
+        assert!(processor.set_dc_exclusion_bins(MAX_DC_EXCLUSION_BINS + 1).await.is_err());
+        assert_eq!(*processor.dc_exclusion_bins.lock().await, MAX_DC_EXCLUSION_BINS);
+    }
+
+    #[test]
+    fn real_fft_band_powers_match_the_complex_fft() {
+        let (rate, size) = (250.0f32, 512);
+        let resolution = rate / size as f32;
+        let window: Vec<f32> = noise(size, 7).iter().map(|&x| (20.0 * x) as f32).collect();
+        let mut edges = BandProfile::default().edges();
+        edges.insert("past_nyquist".to_string(), [100.0, 200.0]);
+        let profile = BandProfile::from_edges(edges).unwrap();
+        
+        // Reference: the full complex spectrum, unwindowed, summed over [low, high)
+        let mut spectrum: Vec<Complex<f32>> = window.iter().map(|&x| Complex::new(x, 0.0)).collect();
+        FftPlanner::new().plan_fft_forward(size).process(&mut spectrum);
+        let reference = |band: &BandDefinition, bins: usize| -> f32 {
+            spectrum[..bins].iter().enumerate()
+                .filter(|&(i, _)| (band.low..band.high).contains(&(i as f32 * resolution)))
+                .map(|(_, c)| c.norm_sqr())
+                .sum::<f32>()
+                .sqrt()
+        };
+        
+        let fft = RealFftPlanner::new().plan_fft_forward(size);
+        let (bands, _) = EEGProcessor::compute_channel_bands(
+            fft.as_ref(), &window, &mut fft.make_scratch_vec(), resolution, &profile, BandOptions::default(),
+        );
+        for band in &profile.bands {
+            let expected = reference(band, size / 2 + 1);
+            assert!((bands[&band.name] - expected).abs() <= 1e-4 * expected, "{}: {} vs {}", band.name, bands[&band.name], expected);
         }
+        // The complex spectrum mirrors above Nyquist; the real FFT counts a band only up to it
+        let past_nyquist = profile.bands.iter().find(|band| band.name == "past_nyquist").unwrap();
+        assert!(reference(past_nyquist, size) > 1.3 * bands["past_nyquist"]);
     }
-    
-    result
-}
 
-#[tauri::command]
-async fn disconnect_from_lsl(
-    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
-) -> Result<(), String> {
-    println!("🔌 [DEBUG] Tauri command: disconnect_from_lsl called");
-    
-    let processor = processor.inner().clone();
-    let processor_guard = processor.lock().await;
-    
-    processor_guard.disconnect_lsl().await;
+    // Stream info as a connect would leave it, without a real inlet
+    async fn attach_stream(processor: &EEGProcessor, names: &[&str]) {
+        let mut connection = processor.lsl_connection.lock().await;
+        connection.channel_count = names.len();
+        connection.stream_info = Some(stream_info(names, processor.sample_rate as f64));
+    }
     
-    println!("✅ [DEBUG] Tauri command: disconnect_from_lsl completed");
-    Ok(())
-}
+    #[tokio::test]
+    async fn channel_groups_validate_and_average() {
+        let processor = EEGProcessor::new();
+        attach_stream(&processor, &["Fp1", "Fp2", "C3", "O1", "O2", "AUX"]).await;
+        let groups = |entries: &[(&str, &[usize])]| -> HashMap<String, Vec<usize>> {
+            entries.iter().map(|&(name, channels)| (name.to_string(), channels.to_vec())).collect()
+        };
+        assert!(processor.set_channel_groups(groups(&[("left", &[0, 6])])).await.is_err());
+        assert!(processor.set_channel_groups(groups(&[("left", &[0, 2, 0])])).await.is_err());
+        assert!(processor.set_channel_groups(groups(&[("left", &[])])).await.is_err());
+        assert!(processor.set_channel_groups(groups(&[(" ", &[0])])).await.is_err());
+        assert!(processor.channel_groups.lock().await.is_none());
+        
+        let bands = [
+            channel_bands(0, &[("alpha", 2.0), ("beta", 1.0)]),
+            channel_bands(2, &[("alpha", 4.0)]),
+            channel_bands(3, &[("alpha", 6.0), ("beta", 3.0)]),
+            channel_bands(4, &[("alpha", 10.0), ("beta", 5.0)]),
+        ];
+        // Default groups: the 10-10 regions present in the montage
+        let regional = processor.analyze_regional_bands(1.0, &bands).await.unwrap();
+        let names: Vec<&str> = regional.regions.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["frontal", "central", "occipital"]);
+        assert_eq!(regional.regions[2].bands["alpha"], 8.0);
+        
+        // A band missing on one member is averaged over the members that have it
+        processor.set_channel_groups(groups(&[("left", &[0, 2, 3]), ("aux", &[5])])).await.unwrap();
+        let regional = processor.analyze_regional_bands(1.0, &bands).await.unwrap();
+        assert_eq!(regional.regions.len(), 1);
+        let left = &regional.regions[0];
+        assert_eq!((left.name.as_str(), left.channels.clone()), ("left", vec![0, 2, 3]));
+        assert_eq!((left.bands["alpha"], left.bands["beta"]), (4.0, 2.0));
+        
+        processor.set_channel_groups(HashMap::new()).await.unwrap();
+        assert!(processor.channel_groups.lock().await.is_none());
+    }
 
-#[tauri::command]
-async fn get_current_stream_info(
-    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
-) -> Result<Option<LSLStreamInfo>, String> {
-    let processor = processor.inner().clone();
-    let processor_guard = processor.lock().await;
-    
-    let info = processor_guard.get_stream_info().await;
-    println!("📊 [DEBUG] Tauri command: get_current_stream_info returning: {:?}", info);
-    
-    Ok(info)
-}
+    #[tokio::test]
+    async fn itpc_separates_phase_locked_from_random_phase() {
+        let processor = EEGProcessor::new();
+        let rate = processor.sample_rate as f64;
+        // 30 s at 250 Hz fills the replay buffer exactly. Channel 0 is a 10 Hz rhythm whose phase
+        // is the same one second apart, channel 1 noise; a "stim" marker every second.
+        for (n, random) in noise(7500, 7).into_iter().enumerate() {
+            let t = n as f64 / rate;
+            let locked = (2.0 * std::f64::consts::PI * 10.0 * t).sin() as f32;
+            let (sample, filtered) = sample_pair(t, vec![locked, random as f32]);
+            processor.update_buffers(&sample, &filtered).await;
+            if n % 250 == 0 {
+                processor.mark_event(Some("stim".to_string())).await.unwrap();
+            }
+            if n == 1000 {
+                processor.mark_event(None).await.unwrap();
+            }
+        }
+        // On the newest sample: no complete epoch after it
+        processor.mark_event(Some("stim".to_string())).await.unwrap();
+        
+        let itpc = processor.compute_itpc(Some("stim".to_string()), Some(1000)).await.unwrap();
+        assert_eq!((itpc.epochs, itpc.skipped_markers), (30, 1));
+        let bin = itpc.frequencies.iter().position(|&f| f == 10.0).unwrap();
+        assert!(itpc.channels[0].itpc[bin] > 0.99, "locked {}", itpc.channels[0].itpc[bin]);
+        // ~1/sqrt(30) = 0.18 on average for random phase
+        let random_mean = itpc.channels[1].itpc[1..].iter().sum::<f32>() / (itpc.frequencies.len() - 1) as f32;
+        assert!(random_mean < 0.3, "random {}", random_mean);
+        
+        assert_eq!(processor.compute_itpc(None, Some(1000)).await.unwrap().epochs, 31);
+        assert!(processor.compute_itpc(Some("other".to_string()), Some(1000)).await.is_err());
+        assert!(processor.compute_itpc(None, Some(MAX_ITPC_EPOCH_MS + 1)).await.is_err());
+    }
 
-#[tauri::command]
-async fn start_eeg_processing(
-    app_handle: tauri::AppHandle,
-    processor: State<'_, Arc<Mutex<EEGProcessor>>>,
-) -> Result<(), String> {
-    println!("🚀 [DEBUG] ===== TAURI COMMAND: start_eeg_processing =====");
-    
-    let processor = processor.inner().clone();
-    let app_handle = app_handle.clone();
+    #[test]
+    fn db_baseline_is_ten_log_power_over_baseline() {
+        let db = |amplitude: f32, mean: f32| normalize_band_value(BandNormalization::DbBaseline, amplitude, 0.0, Some((mean, 1.0)));
+        assert_eq!(db(2.0, 4.0), 0.0);
+        assert!((db(4.0, 4.0) - 10.0 * 4.0f32.log10()).abs() < 1e-5);
+        assert!((db(1.0, 2.0) + 10.0 * 2.0f32.log10()).abs() < 1e-5);
+        // A silent band or baseline is floored rather than -inf / +inf
+        assert!(db(0.0, 4.0).is_finite() && db(2.0, 0.0).is_finite());
+        assert!(normalize_band_value(BandNormalization::DbBaseline, 2.0, 0.0, None).is_nan());
+        
+        assert_eq!("DB_Baseline".parse::<BandNormalization>(), Ok(BandNormalization::DbBaseline));
+        assert_eq!(" zscore".parse::<BandNormalization>().ok(), None);
+    }
     
-    tokio::spawn(async move {
-        println!("🔄 [DEBUG] EEG processing loop started");
+    #[tokio::test]
+    async fn band_normalization_per_band_override() {
+        let processor = EEGProcessor::new();
+        processor.calibrate_band_baseline(5.0).await.unwrap();
+        let cycle = |timestamp: f64, alpha: f32, beta: f32| {
+            vec![FrequencyBands { timestamp, ..channel_bands(0, &[("alpha", alpha), ("beta", beta), ("theta", 1.0)]) }]
+        };
+        // Calibration: alpha power 4, beta power 1
+        for t in 0..=5 {
+            processor.band_normalization.lock().await.apply(t as f64, &cycle(t as f64, 2.0, 1.0));
+        }
+        assert!(processor.band_normalization.lock().await.calibration.is_none());
         
-        let mut interval = interval(Duration::from_millis(4)); // 250 Hz = 4ms intervals
-        let start_time = std::time::SystemTime::now();
-        let mut sample_count = 0u64;
-        let mut last_fft_time = 0u64;
-        let mut last_data_log = 0u64;
+        processor.set_band_normalization("db_baseline".parse().unwrap(), None).await.unwrap();
+        processor.set_band_normalization(BandNormalization::Absolute, Some("beta".to_string())).await.unwrap();
+        processor.set_band_normalization(BandNormalization::Relative, Some("theta".to_string())).await.unwrap();
+        assert!(processor.set_band_normalization(BandNormalization::Zscore, Some("mu".to_string())).await.is_err());
         
-        loop {
-            interval.tick().await;
-            
-            let elapsed = start_time.elapsed().unwrap_or_default();
-            let timestamp = elapsed.as_secs_f64();
-            sample_count += 1;
-            
-            let processor_guard = processor.lock().await;
-            let is_real_connection = processor_guard.is_real_connection().await;
-            
-            if is_real_connection {
-                // Try to get REAL LSL sample
-                if let Some(lsl_sample) = processor_guard.get_lsl_sample().await {
-                    // Log data reception periodically
-                    let current_time_ms = (timestamp * 1000.0) as u64;
-                    if current_time_ms - last_data_log >= 5000 { // Every 5 seconds
-                        println!("📊 [DEBUG] Received real LSL sample: {} channels, timestamp: {}", 
-                                lsl_sample.channels.len(), lsl_sample.timestamp);
-                        last_data_log = current_time_ms;
-                    }
-                    
-                    // Apply real-time filters
-                    let filtered_sample = processor_guard.apply_real_time_filters(&lsl_sample).await;
-                    
-                    // Update buffers for FFT analysis
-                    processor_guard.update_buffers(&lsl_sample, &filtered_sample).await;
-                    
-                    // Emit raw EEG sample (every 2nd sample for performance)
-                    if sample_count % 2 == 0 {
-                        if let Err(e) = app_handle.emit_all("eeg_sample", &lsl_sample) {
-                            eprintln!("❌ [DEBUG] Failed to emit raw EEG sample: {}", e);
-                        }
-                    }
-                    
-                    // Emit filtered EEG sample (every 2nd sample for performance)
-                    if sample_count % 2 == 0 {
-                        if let Err(e) = app_handle.emit_all("filtered_eeg_sample", &filtered_sample) {
-                            eprintln!("❌ [DEBUG] Failed to emit filtered EEG sample: {}", e);
-                        }
-                    }
-                    
-                    // Analyze frequency bands every 250ms
-                    let current_time_ms = (timestamp * 1000.0) as u64;
-                    if current_time_ms - last_fft_time >= 250 {
-                        let bands = processor_guard.analyze_frequency_bands(timestamp).await;
-                        if let Err(e) = app_handle.emit_all("frequency_bands", &bands) {
-                            eprintln!("❌ [DEBUG] Failed to emit frequency bands: {}", e);
-                        }
-                        last_fft_time = current_time_ms;
-                    }
-                } else {
-                    // No real data available - this is normal, just continue
-                    // Don't log this as it would spam the console
-                }
-            } else {
-                // No real connection - should not happen if we reach this point
-                println!("⚠️ [DEBUG] EEG processing running but no real connection available");
-                tokio::time::sleep(Duration::from_millis(100)).await;
+        let bands = processor.band_normalization.lock().await.apply(6.0, &cycle(6.0, 4.0, 3.0));
+        let values = &bands[0].bands;
+        assert!((values["alpha"] - 10.0 * 4.0f32.log10()).abs() < 1e-4, "{:?}", values);
+        assert_eq!(values["beta"], 3.0);
+        assert!((values["theta"] - 1.0 / 26.0).abs() < 1e-6);
+        
+        // Setting every band drops the overrides
+        processor.set_band_normalization(BandNormalization::DbBaseline, None).await.unwrap();
+        let bands = processor.band_normalization.lock().await.apply(7.0, &cycle(7.0, 2.0, 2.0));
+        assert_eq!(bands[0].bands["alpha"], 0.0);
+        assert!((bands[0].bands["beta"] - 10.0 * 4.0f32.log10()).abs() < 1e-4);
+    }
+
+    #[tokio::test]
+    async fn meditation_index_and_quote_tiers() {
+        let bands = channel_bands(0, &[("alpha", 3.0), ("theta", 1.0), ("beta", 2.0)]);
+        assert_eq!(meditation_index(&bands, MeditationWeights::default()), Some(2.0));
+        assert_eq!(meditation_index(&bands, MeditationWeights { alpha: 2.0, theta: 0.0, beta: 0.5 }), Some(6.0));
+        assert_eq!(meditation_index(&channel_bands(0, &[("alpha", 3.0), ("beta", 2.0)]), MeditationWeights::default()), None);
+        
+        assert_eq!(MeditationTier::from_normalized(0.5), MeditationTier::Settling);
+        assert_eq!(MeditationTier::from_normalized(MEDITATION_STEADY_RATIO), MeditationTier::Steady);
+        assert_eq!(MeditationTier::from_normalized(MEDITATION_DEEP_RATIO - 0.01), MeditationTier::Steady);
+        assert_eq!(MeditationTier::from_normalized(MEDITATION_DEEP_RATIO), MeditationTier::Deep);
+        for tier in [MeditationTier::Settling, MeditationTier::Steady, MeditationTier::Deep] {
+            for _ in 0..20 {
+                let quote = meditation_quote(Some(tier));
+                assert!(MEDITATION_QUOTES.iter().any(|&(t, q)| t == tier && q == quote), "{:?}: {}", tier, quote);
             }
-            
-            drop(processor_guard);
         }
-    });
-    
-    println!("✅ [DEBUG] Tauri command: start_eeg_processing completed (background task started)");
-    Ok(())
-}
+        
+        // Baseline index 2 (channel mean of 1 and 3); 3 is then 1.5 times it, a deep tier
+        let processor = EEGProcessor::new();
+        let cycle = |alpha: f32| [
+            channel_bands(0, &[("alpha", alpha - 1.0), ("theta", 0.0), ("beta", 1.0)]),
+            channel_bands(1, &[("alpha", alpha + 1.0), ("theta", 0.0), ("beta", 1.0)]),
+        ];
+        let before = processor.compute_meditation_index(0.0, &cycle(2.0)).await.unwrap();
+        assert_eq!((before.index, before.normalized, before.tier, before.calibrating), (2.0, None, None, false));
+        processor.calibrate_meditation_index(5.0).await.unwrap();
+        for t in 0..5 {
+            assert!(processor.compute_meditation_index(t as f64, &cycle(2.0)).await.unwrap().calibrating);
+        }
+        assert!(!processor.compute_meditation_index(5.0, &cycle(2.0)).await.unwrap().calibrating);
+        let index = processor.compute_meditation_index(6.0, &cycle(3.0)).await.unwrap();
+        assert_eq!((index.normalized, index.tier), (Some(1.5), Some(MeditationTier::Deep)));
+        assert_eq!(processor.session_meditation_tier(&cycle(1.9)).await, Some(MeditationTier::Steady));
+        
+        assert!(processor.set_meditation_index_weights(MeditationWeights { alpha: 1.0, theta: 1.0, beta: 0.0 }).await.is_err());
+        assert!(processor.set_meditation_index_weights(MeditationWeights { alpha: -1.0, theta: 1.0, beta: 1.0 }).await.is_err());
+    }
 
-#[tauri::command]
-fn get_meditation_quote() -> String {
-    let quotes = vec![
-        "The mind is everything. What you think you become. - Buddha",
-        "Peace comes from within. Do not seek it without. - Buddha",
-        "Meditation is not evasion; it is a serene encounter with reality. - Thich Nhat Hanh",
-        "In the depth of winter, I finally learned that there was in me an invincible summer. - Albert Camus",
-        "Breathe in peace, breathe out stress. - Anonymous",
-        "The present moment is the only time over which we have dominion. - Thich Nhat Hanh",
-        "Your calm mind is the ultimate weapon against your challenges. - Bryant McGill",
-        "Meditation is a way for nourishing and blossoming the divinity within you. - Amit Ray",
-    ];
-    
-    let mut rng = rand::rngs::StdRng::from_entropy();
-    quotes.choose(&mut rng).unwrap_or(&quotes[0]).to_string()
+    #[tokio::test]
+    async fn calibrated_blink_template_removes_a_blink() {
+        let processor = EEGProcessor::new();
+        assert!(processor.calibrate_blinks(3).await.is_err());
+        attach_stream(&processor, &["Fp1", "Fp2", "O1"]).await;
+        processor.resize_channel_buffers(3).await;
+        assert!(processor.set_blink_correction(true).await.is_err());
+        processor.calibrate_blinks(3).await.unwrap();
+        
+        // 150 µV calibration blinks at 1, 2 and 3 s, a 200 µV one at 5 s once correcting; each
+        // is a Gaussian of σ 50 ms, at 30% on O1. A little noise underneath.
+        let rate = 250.0;
+        let blink = |t: f64, centre: f64| (-0.5 * ((t - centre) / 0.05).powi(2)).exp();
+        let amplitude = |t: f64| if t < 4.0 { 150.0 } else { 200.0 };
+        let background = noise(1600, 11);
+        let mut template = None;
+        for (n, &noise) in background.iter().enumerate() {
+            let t = n as f64 / rate;
+            let deflection = [1.0, 2.0, 3.0, 5.0].iter().map(|&centre| amplitude(t) * blink(t, centre)).sum::<f64>();
+            let channels = [1.0, 1.0, 0.3].map(|weight| (weight * deflection + 2.0 * noise) as f32).to_vec();
+            let (sample, filtered) = sample_pair(t, channels);
+            processor.update_buffers(&sample, &filtered).await;
+            let (_, built) = processor.update_blinks(t).await;
+            if let Some(built) = built {
+                template = Some(built);
+                processor.set_blink_correction(true).await.unwrap();
+            }
+        }
+        let template = template.unwrap();
+        assert_eq!((template.blinks, template.detection_channels.clone()), (3, vec![0, 1]));
+        let peak = (BLINK_PRE_S * rate).round() as usize;
+        assert!((template.channels[0][peak] - 150.0).abs() < 5.0, "template peak {}", template.channels[0][peak]);
+        assert!((template.channels[2][peak] - 45.0).abs() < 5.0);
+        assert_eq!(processor.blinks.lock().await.corrected, 1);
+        
+        // What is left of the 5 s blink is about the noise level on every channel
+        let buffers = processor.filtered_buffers.lock().await;
+        let newest = background.len() - 1;
+        let start = newest + 1 - buffers[0].len();
+        for (channel, buffer) in buffers.iter().enumerate() {
+            let residual = buffer.iter().zip(&background[start..])
+                .map(|(&v, &noise)| (v as f64 - 2.0 * noise).abs())
+                .fold(0.0, f64::max);
+            assert!(residual < 10.0, "channel {} residual {} µV", channel, residual);
+        }
+    }
 }
-
-fn main() {
-    println!("🚀 [DEBUG] ===== STARTING TAURI APPLICATION =====");
-    println!("🚀 [DEBUG] Initializing EEG processor...");
-    
-    let processor = Arc::new(Mutex::new(EEGProcessor::new()));
-    
-    println!("🚀 [DEBUG] Building Tauri app with invoke handlers...");
-    tauri::Builder::default()
-        .manage(processor)
-        .invoke_handler(tauri::generate_handler![
-            connect_to_lsl_stream,
-            disconnect_from_lsl,
-            get_current_stream_info,
-            start_eeg_processing,
-            get_meditation_quote
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
\ No newline at end of file